@@ -1,3 +1,12 @@
 fn main() {
+    // Short commit hash for support reports; "unknown" outside a git checkout
+    let hash = std::process::Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .unwrap_or_else(|| "unknown".into());
+    println!("cargo:rustc-env=CLAPP_BUILD_HASH={}", hash);
     tauri_build::build()
 }
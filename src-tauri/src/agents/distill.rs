@@ -0,0 +1,194 @@
+//! New agents distilled from an existing conversation.
+
+use crate::*;
+
+// ─── Agents from sessions ─────────────────────────────────────────────────────
+
+/// Only the end of a longer conversation is sent for distillation.
+pub(crate) const DISTILL_TRANSCRIPT_MAX_CHARS: usize = 60_000;
+
+pub(crate) const DISTILL_PROMPT: &str = "Below is a conversation between a user and you. \
+The user wants to turn what was learned in it into a new, dedicated assistant. \
+Reply with only a JSON object, no other text, of the form \
+{\"systemPrompt\": \"...\", \"memoryNotes\": \"...\"}. \
+systemPrompt: instructions for the new assistant, written to it in the second person, \
+covering its role, the user's preferences and the approach that worked. \
+memoryNotes: Markdown bullet points of durable facts, decisions and context worth remembering.";
+
+/// A distilled agent waiting for the user to apply or cancel it. Nothing is on disk yet.
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AgentDraft {
+    pub(crate) draft_id: String,
+    pub(crate) agent_id: String,
+    pub(crate) name: String,
+    pub(crate) instructions: String,
+    pub(crate) memory_notes: String,
+    pub(crate) source: SourceSession,
+}
+
+/// "Rust Reviewer!" → "rust-reviewer"
+pub(crate) fn agent_id_from_name(name: &str) -> String {
+    let slug: String = name.trim().to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let slug = slug.split('-').filter(|p| !p.is_empty()).collect::<Vec<_>>().join("-");
+    slug.chars().take(64).collect::<String>().trim_end_matches('-').to_string()
+}
+
+pub(crate) fn session_transcript(agent_id: &str, session_key: &str) -> String {
+    let transcript = read_history(agent_id).iter()
+        .filter(|r| r.session_key == session_key && !r.superseded)
+        .map(|r| format!("{}: {}", if r.role == "user" { "User" } else { "Assistant" }, r.text.trim()))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let skip = transcript.chars().count().saturating_sub(DISTILL_TRANSCRIPT_MAX_CHARS);
+    transcript.chars().skip(skip).collect()
+}
+
+/// Takes the outermost JSON object in the reply, in case the model wrapped it in prose or a code fence.
+pub(crate) fn parse_distilled(reply: &str) -> Result<(String, String), AppError> {
+    let invalid = || AppError::Other("The agent did not return a usable draft; try again".into());
+    let (start, end) = (reply.find('{').ok_or_else(invalid)?, reply.rfind('}').ok_or_else(invalid)?);
+    let v: serde_json::Value = serde_json::from_str(reply.get(start..=end).ok_or_else(invalid)?)
+        .map_err(|_| invalid())?;
+    let instructions = v["systemPrompt"].as_str().unwrap_or("").trim().to_string();
+    if instructions.is_empty() {
+        return Err(invalid());
+    }
+    Ok((instructions, v["memoryNotes"].as_str().unwrap_or("").trim().to_string()))
+}
+
+/// Writes the agent, or nothing: on any failure the directories it created are removed again.
+pub(crate) fn write_distilled_agent(draft: &AgentDraft) -> Result<(), AppError> {
+    if agent_exists(&draft.agent_id) {
+        return Err(AppError::AlreadyExists(format!("agent {}", draft.agent_id)));
+    }
+    ensure_agent_slot()?;
+    let workspace = openclaw_dir().join(format!("workspace-{}", draft.agent_id));
+    let agent_root = openclaw_agents_root().join(&draft.agent_id);
+    // Only what this call creates is removed again; folders that were there stay
+    let created_agent_root = !agent_root.exists();
+    let created_workspace = !workspace.exists();
+    let result = (|| -> Result<(), AppError> {
+        fs::create_dir_all(agent_dir(&draft.agent_id))?;
+        // Same provider and key as the agent the conversation was with
+        fs::copy(
+            agent_dir(&draft.source.agent_id).join("auth-profiles.json"),
+            agent_dir(&draft.agent_id).join("auth-profiles.json"),
+        )?;
+        fs::create_dir_all(&workspace)?;
+        if !draft.memory_notes.is_empty() {
+            fs::write(workspace.join("MEMORY.md"), format!("{}\n", draft.memory_notes))?;
+        }
+        let source = read_agent_config(&draft.source.agent_id);
+        save_agent_config(&draft.agent_id, &AgentConfig {
+            name: draft.name.clone(),
+            instructions: draft.instructions.clone(),
+            model: source.model,
+            temperature: source.temperature,
+            workspace: Some(workspace.to_string_lossy().into_owned()),
+            source_session: Some(draft.source.clone()),
+            ..Default::default()
+        }, &FileBase::default())?;
+        Ok(())
+    })();
+    if result.is_err() {
+        if created_agent_root {
+            fs::remove_dir_all(&agent_root).ok();
+        }
+        if created_workspace {
+            fs::remove_dir_all(&workspace).ok();
+        }
+    }
+    result
+}
+
+/// First phase: asks the agent the session was with to distill it. Nothing is
+/// written; the draft is kept until it is applied or cancelled.
+#[tauri::command]
+pub(crate) async fn preview_agent_from_session(
+    app: tauri::AppHandle,
+    session_key: String,
+    name: String,
+) -> Result<AgentDraft, AppError> {
+    ensure_writable()?;
+    let name = name.trim().to_string();
+    let agent_id = agent_id_from_name(&name);
+    validate_agent_id(&agent_id)?;
+    let (key, id) = (session_key.clone(), agent_id.clone());
+    let (owner, transcript) = run_storage_io(&app, move || -> Result<(String, String), AppError> {
+        if agent_exists(&id) {
+            return Err(AppError::AlreadyExists(format!("agent {}", id)));
+        }
+        let owner = find_session_owner(&key).ok_or_else(|| AppError::NotFound(format!("session {}", key)))?;
+        let transcript = session_transcript(&owner, &key);
+        Ok((owner, transcript))
+    }).await??;
+    if transcript.trim().is_empty() {
+        return Err(AppError::InvalidInput(format!("Session {} has no messages", session_key)));
+    }
+
+    // A throwaway session, so the request doesn't become part of the conversation it summarizes
+    let distill_session = format!("clapp-distill-{}", now_ms());
+    let message = format!("{}\n\n---\n\n{}", DISTILL_PROMPT, transcript);
+    let in_flight = InFlightCall::start(&app, &owner);
+    let raw = execute_gateway_call(&app, &in_flight, &message, &distill_session, false, None, None).await?;
+    drop(in_flight);
+    if let Some(e) = serde_json::from_str::<serde_json::Value>(&raw).ok().and_then(|v| v.get("error").filter(|e| !e.is_null()).cloned()) {
+        return Err(AppError::Other(format!("Distillation failed: {}", e)));
+    }
+    let (instructions, memory_notes) = parse_distilled(&reply_text(&raw))?;
+    validate_instructions(&instructions)?;
+
+    let draft = AgentDraft {
+        draft_id: format!("draft-{}", now_ms()),
+        agent_id,
+        name,
+        instructions,
+        memory_notes,
+        source: SourceSession { agent_id: owner, session_key },
+    };
+    app.state::<AppState>().agent_drafts.lock().unwrap().insert(draft.draft_id.clone(), draft.clone());
+    Ok(draft)
+}
+
+/// Second phase: creates the agent from the draft, with the user's edits if any.
+#[tauri::command]
+pub(crate) async fn apply_agent_from_session(
+    app: tauri::AppHandle,
+    draft_id: String,
+    instructions: Option<String>,
+    memory_notes: Option<String>,
+) -> Result<String, AppError> {
+    ensure_writable()?;
+    let mut draft = app.state::<AppState>().agent_drafts.lock().unwrap().get(&draft_id).cloned()
+        .ok_or_else(|| AppError::NotFound(format!("draft {}", draft_id)))?;
+    if let Some(i) = instructions {
+        draft.instructions = i.trim().to_string();
+    }
+    if let Some(m) = memory_notes {
+        draft.memory_notes = m.trim().to_string();
+    }
+    if draft.instructions.is_empty() {
+        return Err(AppError::InvalidInput("Instructions are empty".into()));
+    }
+    validate_instructions(&draft.instructions)?;
+
+    let agent = draft.clone();
+    run_storage_io(&app, move || write_distilled_agent(&agent)).await??;
+    app.state::<AppState>().agent_drafts.lock().unwrap().remove(&draft_id);
+    audit("agent_created_from_session", serde_json::json!({
+        "agentId": draft.agent_id,
+        "sourceAgentId": draft.source.agent_id,
+        "sessionKey": draft.source.session_key,
+    }));
+    Ok(draft.agent_id)
+}
+
+/// Drops a draft. Returns false if it was already applied or cancelled.
+#[tauri::command]
+pub(crate) fn cancel_agent_from_session(state: tauri::State<AppState>, draft_id: String) -> bool {
+    state.agent_drafts.lock().unwrap().remove(&draft_id).is_some()
+}
@@ -0,0 +1,197 @@
+//! Per-agent environment variables for tools, e.g. a token only one agent needs.
+
+use crate::*;
+
+// ─── Agent environment ────────────────────────────────────────────────────────
+
+pub(crate) const AGENT_ENV_MAX_VARS: usize = 64;
+pub(crate) const AGENT_ENV_MAX_VALUE_LEN: usize = 8 * 1024;
+
+/// How the variables reach the agent's tools.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AgentEnvInfo {
+    /// Variable names with masked values
+    pub(crate) vars: std::collections::BTreeMap<String, String>,
+    /// "call-params-file": sent as `env` in the params of each call to this agent,
+    /// through a private file; "unavailable": the OpenClaw CLI can't take such a file
+    pub(crate) mechanism: &'static str,
+    pub(crate) detail: &'static str,
+}
+
+pub(crate) const AGENT_ENV_MECHANISM: &str = "call-params-file";
+pub(crate) const AGENT_ENV_DETAIL: &str = "OpenClaw's config has no per-agent environment Clapp can write, so the variables \
+are sent as `env` with each call to this agent and never set on the gateway process. The params go to the CLI \
+in a file only you can read, never on its command line. A gateway that does not know the field may refuse those calls.";
+pub(crate) const AGENT_ENV_UNAVAILABLE: &str = "unavailable";
+pub(crate) const AGENT_ENV_UNAVAILABLE_DETAIL: &str = "This OpenClaw CLI can only take call params on its command line, \
+where other programs could read them, so calls to agents with variables are refused. Update OpenClaw.";
+
+/// The `gateway call` option that reads params from a file.
+pub(crate) const PARAMS_FILE_FLAG: &str = "--params-file";
+
+/// Kept with Clapp's data rather than in the agent folder, so exports and the
+/// trash never carry them.
+pub(crate) fn agent_env_path(agent_id: &str) -> PathBuf {
+    clapp_dir().join("agent-env").join(format!("{}.json", agent_id))
+}
+
+pub(crate) fn read_agent_env(agent_id: &str) -> std::collections::BTreeMap<String, String> {
+    fs::read_to_string(agent_env_path(agent_id)).ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+pub(crate) fn validate_env_name(name: &str) -> Result<(), AppError> {
+    let mut chars = name.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(AppError::InvalidInput(format!(
+            "'{}' is not a valid variable name: letters, digits and '_', not starting with a digit", name
+        )));
+    }
+    Ok(())
+}
+
+/// Deletes the agent's variables; part of deleting the agent.
+pub(crate) fn purge_agent_env(agent_id: &str) {
+    if fs::remove_file(agent_env_path(agent_id)).is_ok() {
+        audit("agent_env_purged", serde_json::json!({ "agentId": agent_id }));
+    }
+}
+
+/// Teaches redaction every stored value, so none shows up in logs or forwarded output.
+/// Also narrows files written by builds that used default permissions.
+pub(crate) fn learn_agent_env_secrets() {
+    let Ok(entries) = fs::read_dir(clapp_dir().join("agent-env")) else { return };
+    for entry in entries.flatten() {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(entry.path(), fs::Permissions::from_mode(0o600)).ok();
+        }
+        let vars: std::collections::BTreeMap<String, String> = fs::read_to_string(entry.path()).ok()
+            .and_then(|c| serde_json::from_str(&c).ok())
+            .unwrap_or_default();
+        learn_secrets(vars.values());
+    }
+    // Params files left behind by a crash still hold values
+    if let Ok(stale) = fs::read_dir(call_params_dir()) {
+        for entry in stale.flatten() {
+            fs::remove_file(entry.path()).ok();
+        }
+    }
+}
+
+/// Whether `gateway call` can read its params from a file. Asked once per run;
+/// fixture mode never sends anything anywhere, so it always can.
+pub(crate) async fn params_file_supported(app: &tauri::AppHandle) -> bool {
+    if fixture_mode() {
+        return true;
+    }
+    if let Some(known) = *app.state::<AppState>().params_file_support.lock().unwrap() {
+        return known;
+    }
+    let supported = run_openclaw(app, &["gateway", "call", "--help"]).await
+        .is_ok_and(|(out, err)| [out, err].iter().any(|o| String::from_utf8_lossy(o).contains(PARAMS_FILE_FLAG)));
+    *app.state::<AppState>().params_file_support.lock().unwrap() = Some(supported);
+    supported
+}
+
+pub(crate) fn call_params_dir() -> PathBuf {
+    clapp_dir().join("call-params")
+}
+
+/// Params of one call, in a private file removed when this is dropped.
+pub(crate) struct ParamsFile(PathBuf);
+
+impl ParamsFile {
+    pub(crate) fn write(params: &str) -> std::io::Result<Self> {
+        let path = call_params_dir().join(format!("{}-{}.json", now_ms(), next_seq()));
+        write_private(&path, params.as_bytes())?;
+        Ok(Self(path))
+    }
+
+    pub(crate) fn path(&self) -> String {
+        self.0.to_string_lossy().into_owned()
+    }
+}
+
+impl Drop for ParamsFile {
+    fn drop(&mut self) {
+        fs::remove_file(&self.0).ok();
+    }
+}
+
+/// `extra` with the agent's variables added as `env`. Anything but an object
+/// or nothing is passed through for `to_wire` to refuse.
+pub(crate) fn with_agent_env(
+    vars: &std::collections::BTreeMap<String, String>,
+    extra: Option<&serde_json::Value>,
+) -> Option<serde_json::Value> {
+    if vars.is_empty() {
+        return extra.cloned();
+    }
+    match extra {
+        Some(serde_json::Value::Object(obj)) => {
+            let mut merged = obj.clone();
+            merged.insert("env".into(), serde_json::json!(vars));
+            Some(serde_json::Value::Object(merged))
+        }
+        None | Some(serde_json::Value::Null) => Some(serde_json::json!({ "env": vars })),
+        Some(other) => Some(other.clone()),
+    }
+}
+
+/// Replaces all of the agent's variables; an empty map removes them.
+#[tauri::command]
+pub(crate) async fn set_agent_env(
+    app: tauri::AppHandle,
+    agent_id: String,
+    vars: std::collections::BTreeMap<String, String>,
+) -> Result<(), AppError> {
+    ensure_writable()?;
+    validate_agent_id(&agent_id)?;
+    if vars.len() > AGENT_ENV_MAX_VARS {
+        return Err(AppError::InvalidInput(format!("At most {} variables per agent", AGENT_ENV_MAX_VARS)));
+    }
+    for (name, value) in &vars {
+        validate_env_name(name)?;
+        if value.len() > AGENT_ENV_MAX_VALUE_LEN || value.contains('\0') {
+            return Err(AppError::InvalidInput(format!("The value of {} is too long or not text", name)));
+        }
+    }
+    run_storage_io(&app, move || -> Result<(), AppError> {
+        if !agent_exists(&agent_id) {
+            return Err(AppError::NotFound(format!("agent {}", agent_id)));
+        }
+        let path = agent_env_path(&agent_id);
+        if vars.is_empty() {
+            fs::remove_file(&path).ok();
+        } else {
+            write_private(&path, serde_json::to_string_pretty(&vars)?.as_bytes())?;
+            learn_secrets(vars.values());
+        }
+        let names: Vec<&String> = vars.keys().collect();
+        audit("agent_env_set", serde_json::json!({ "agentId": agent_id, "names": names }));
+        Ok(())
+    }).await?
+}
+
+/// Names only; values come back as `REDACTED`.
+#[tauri::command]
+pub(crate) async fn get_agent_env(app: tauri::AppHandle, agent_id: String) -> Result<AgentEnvInfo, AppError> {
+    validate_agent_id(&agent_id)?;
+    let vars = run_storage_io(&app, move || read_agent_env(&agent_id)).await?;
+    let (mechanism, detail) = if params_file_supported(&app).await {
+        (AGENT_ENV_MECHANISM, AGENT_ENV_DETAIL)
+    } else {
+        (AGENT_ENV_UNAVAILABLE, AGENT_ENV_UNAVAILABLE_DETAIL)
+    };
+    Ok(AgentEnvInfo {
+        vars: vars.into_keys().map(|name| (name, REDACTED.to_string())).collect(),
+        mechanism,
+        detail,
+    })
+}
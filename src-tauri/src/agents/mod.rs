@@ -0,0 +1,540 @@
+//! Agents: their config files, creation and lookup.
+
+pub(crate) mod distill;
+pub(crate) mod env;
+pub(crate) mod skills;
+pub(crate) mod snapshots;
+pub(crate) mod templates;
+pub(crate) mod transfer;
+
+use crate::*;
+
+// ─── Agent config (agent.json) ────────────────────────────────────────────────
+
+#[derive(serde::Serialize, serde::Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AgentConfig {
+    #[serde(default)]
+    pub(crate) name: String,
+    #[serde(default)]
+    pub(crate) instructions: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) context_window: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) temperature: Option<f64>,
+    /// Tools enabled for the agent, e.g. "exec" or "write"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) tools: Vec<String>,
+    /// Defaults to ~/.openclaw/workspace
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) workspace: Option<String>,
+    #[serde(default)]
+    pub(crate) session_mode: SessionMode,
+    /// Ephemeral agents only: don't keep local history at all
+    #[serde(default)]
+    pub(crate) skip_history: bool,
+    /// Archived agents are kept on disk but skipped by bulk operations
+    #[serde(default)]
+    pub(crate) archived: bool,
+    /// The conversation this agent was distilled from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) source_session: Option<SourceSession>,
+    /// Fields OpenClaw or the user added that Clapp doesn't know, written back as they were
+    #[serde(flatten)]
+    pub(crate) extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SourceSession {
+    pub(crate) agent_id: String,
+    pub(crate) session_key: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum SessionMode {
+    #[default]
+    Persistent,
+    Ephemeral,
+}
+
+/// How "main", the agent OpenClaw falls back to, may be changed.
+#[derive(serde::Serialize, serde::Deserialize, Default, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum MainAgentPolicy {
+    /// Name and instructions only change through `set_main_agent_identity`; credentials still sync
+    Locked,
+    /// Mirroring copies the synced agent's identity onto main
+    #[default]
+    FollowSelected,
+    /// Main is edited like any agent, but mirroring only copies credentials
+    Independent,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AgentSummary {
+    pub(crate) id: String,
+    pub(crate) name: String,
+    pub(crate) session_mode: SessionMode,
+    pub(crate) is_main: bool,
+    /// Only on main
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) main_policy: Option<MainAgentPolicy>,
+}
+
+pub(crate) fn get_all_agent_ids() -> Vec<String> {
+    let mut ids: Vec<String> = fs::read_dir(openclaw_agents_root())
+        .map(|entries| {
+            entries.flatten()
+                .map(|e| e.file_name().to_string_lossy().into_owned())
+                .filter(|id| agent_exists(id))
+                .collect()
+        })
+        .unwrap_or_default();
+    ids.sort();
+    ids
+}
+
+#[tauri::command]
+pub(crate) async fn list_agents(app: tauri::AppHandle) -> Result<Vec<AgentSummary>, AppError> {
+    let policy = load_config().main_agent_policy;
+    run_storage_io(&app, move || {
+        get_all_agent_ids()
+            .into_iter()
+            .map(|id| {
+                let config = read_agent_config(&id);
+                let is_main = id == "main";
+                AgentSummary { id, name: config.name, session_mode: config.session_mode, is_main, main_policy: is_main.then_some(policy) }
+            })
+            .collect()
+    }).await
+}
+
+/// agent.json plus what lives next to it; only `config` is ever written back.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AgentConfigView {
+    #[serde(flatten)]
+    pub(crate) config: AgentConfig,
+    pub(crate) skills: Vec<skills::SkillInfo>,
+}
+
+#[tauri::command]
+pub(crate) async fn get_agent_config(app: tauri::AppHandle, agent_id: Option<String>) -> Result<AgentConfigView, AppError> {
+    let agent_id = resolve_agent_id(agent_id);
+    validate_agent_id(&agent_id)?;
+    run_storage_io(&app, move || {
+        if !agent_exists(&agent_id) {
+            return Err(AppError::NotFound(format!("agent {}", agent_id)));
+        }
+        Ok(AgentConfigView { config: read_agent_config(&agent_id), skills: skills::list_skills(&agent_id) })
+    }).await?
+}
+
+pub(crate) fn resolve_agent_id(agent_id: Option<String>) -> String {
+    agent_id.unwrap_or_else(|| load_config().default_agent_id)
+}
+
+#[tauri::command]
+pub(crate) fn get_default_agent_id() -> Result<String, AppError> {
+    Ok(load_config().default_agent_id)
+}
+
+#[tauri::command]
+pub(crate) async fn set_default_agent_id(app: tauri::AppHandle, id: String) -> Result<(), AppError> {
+    ensure_writable()?;
+    validate_agent_id(&id)?;
+    let check = id.clone();
+    if !run_storage_io(&app, move || agent_exists(&check)).await? {
+        return Err(AppError::NotFound(format!("agent {}", id)));
+    }
+    let mut config = load_config();
+    config.default_agent_id = id;
+    save_config(&config)?;
+    Ok(())
+}
+
+/// Switching modes never touches existing sessions or history.
+#[tauri::command]
+pub(crate) async fn set_agent_session_mode(
+    app: tauri::AppHandle,
+    agent_id: String,
+    mode: SessionMode,
+    skip_history: Option<bool>,
+) -> Result<(), AppError> {
+    ensure_writable()?;
+    validate_agent_id(&agent_id)?;
+    run_storage_io(&app, move || {
+        let (mut config, base) = load_agent_config(&agent_id);
+        config.session_mode = mode;
+        config.skip_history = mode == SessionMode::Ephemeral && skip_history.unwrap_or(config.skip_history);
+        save_agent_config(&agent_id, &config, &base)
+    }).await??;
+    Ok(())
+}
+
+pub(crate) fn agent_config_path(agent_id: &str) -> PathBuf {
+    agent_dir(agent_id).join("agent.json")
+}
+
+pub(crate) fn read_agent_config(agent_id: &str) -> AgentConfig {
+    load_agent_config(agent_id).0
+}
+
+/// agent.json and the base a write of it has to pass.
+pub(crate) fn load_agent_config(agent_id: &str) -> (AgentConfig, FileBase) {
+    let (content, base) = read_guarded(&agent_config_path(agent_id)).unwrap_or_default();
+    (serde_json::from_str(&content).unwrap_or_default(), base)
+}
+
+/// `MainAgentLocked` when `policy` forbids going from `current` to `next`:
+/// a locked main keeps its name and instructions.
+pub(crate) fn check_main_agent_policy(
+    agent_id: &str,
+    policy: MainAgentPolicy,
+    current: &AgentConfig,
+    next: &AgentConfig,
+) -> Result<(), AppError> {
+    let changes_identity = current.name != next.name || current.instructions != next.instructions;
+    if agent_id == "main" && changes_identity && policy == MainAgentPolicy::Locked {
+        return Err(AppError::MainAgentLocked);
+    }
+    Ok(())
+}
+
+/// Refuses to change main's name or instructions while it is locked.
+pub(crate) fn save_agent_config(agent_id: &str, config: &AgentConfig, base: &FileBase) -> Result<(), String> {
+    save_agent_config_under(load_config().main_agent_policy, agent_id, config, base)
+}
+
+pub(crate) fn save_agent_config_under(
+    policy: MainAgentPolicy,
+    agent_id: &str,
+    config: &AgentConfig,
+    base: &FileBase,
+) -> Result<(), String> {
+    check_main_agent_policy(agent_id, policy, &read_agent_config(agent_id), config).map_err(|e| e.to_string())?;
+    store_agent_config(agent_id, config, base)
+}
+
+/// Writes agent.json without the main agent policy; only `set_main_agent_identity` may.
+pub(crate) fn store_agent_config(agent_id: &str, config: &AgentConfig, base: &FileBase) -> Result<(), String> {
+    fs::create_dir_all(agent_dir(agent_id)).map_err(|e| e.to_string())?;
+    write_guarded(&agent_config_path(agent_id), base, &serde_json::to_string_pretty(config).unwrap())
+        .map_err(|e| e.to_string())
+}
+
+pub(crate) fn write_agent_config(agent_id: &str, name: &str, system_prompt: &str) -> Result<(), String> {
+    write_agent_config_under(load_config().main_agent_policy, agent_id, name, system_prompt)
+}
+
+pub(crate) fn write_agent_config_under(policy: MainAgentPolicy, agent_id: &str, name: &str, system_prompt: &str) -> Result<(), String> {
+    // Keep hints like contextWindow that were set separately
+    let (mut config, base) = load_agent_config(agent_id);
+    config.name = name.to_string();
+    config.instructions = system_prompt.to_string();
+    save_agent_config_under(policy, agent_id, &config, &base)
+}
+
+// ─── Main agent ───────────────────────────────────────────────────────────────
+
+/// Snapshot of main's agent.json taken once, before the policy first applied.
+pub(crate) const MAIN_POLICY_BACKUP_TAG: &str = "pre-main-policy";
+
+/// One-time: keeps main's identity as it was before anything could enforce a policy on it.
+pub(crate) fn backup_main_before_policy() {
+    if !agent_exists("main") || agents::snapshots::agent_snapshot_path("main", MAIN_POLICY_BACKUP_TAG).exists() {
+        return;
+    }
+    match agents::snapshots::write_agent_snapshot("main", MAIN_POLICY_BACKUP_TAG) {
+        Ok(()) => audit("main_agent_backup", serde_json::json!({ "tag": MAIN_POLICY_BACKUP_TAG })),
+        Err(e) => eprintln!("[AGENTS ERR] backup of main before policy: {}", e),
+    }
+}
+
+/// The one way to rename main or change its instructions while it is locked.
+#[tauri::command]
+pub(crate) async fn set_main_agent_identity(app: tauri::AppHandle, name: String, prompt: String) -> Result<(), AppError> {
+    ensure_writable()?;
+    if name.trim().is_empty() {
+        return Err(AppError::InvalidInput("Name is empty".into()));
+    }
+    run_storage_io(&app, move || {
+        if !agent_exists("main") {
+            return Err(AppError::NotFound("agent main".into()));
+        }
+        let (mut config, base) = load_agent_config("main");
+        config.name = name.trim().to_string();
+        config.instructions = prompt;
+        store_agent_config("main", &config, &base)?;
+        audit("main_agent_identity", serde_json::json!({ "name": config.name }));
+        Ok(())
+    }).await??;
+    app.state::<AppState>().prompt_cache.invalidate_agent("main");
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) fn set_main_agent_policy(policy: MainAgentPolicy) -> Result<(), AppError> {
+    ensure_writable()?;
+    let mut config = load_config();
+    config.main_agent_policy = policy;
+    save_config(&config)?;
+    audit("main_agent_policy", serde_json::json!({ "policy": policy }));
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) async fn set_agent_context_window(app: tauri::AppHandle, agent_id: String, tokens: u64) -> Result<(), AppError> {
+    ensure_writable()?;
+    validate_agent_id(&agent_id)?;
+    if !(1_000..=200_000).contains(&tokens) {
+        return Err(AppError::InvalidInput(
+            "Context window must be between 1000 and 200000 tokens".into(),
+        ));
+    }
+    run_storage_io(&app, move || {
+        if !agent_exists(&agent_id) {
+            return Err(AppError::NotFound(format!("agent {}", agent_id)));
+        }
+        let (mut config, base) = load_agent_config(&agent_id);
+        config.context_window = Some(tokens);
+        Ok(save_agent_config(&agent_id, &config, &base)?)
+    }).await??;
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) async fn sync_agent_auth(
+    app: tauri::AppHandle,
+    agent_id: String,
+    api_key: String,
+    agent_name: String,
+    system_prompt: String,
+    provider: String,
+    base_url: Option<String>,
+) -> Result<(), AppError> {
+    ensure_writable()?;
+    validate_agent_id(&agent_id)?;
+    // Ollama doesn't require a key, others do
+    if provider != "ollama" && api_key.trim().is_empty() {
+        return Err(AppError::InvalidInput("API key is empty".into()));
+    }
+    let config = load_config();
+    if config.api_key_check_on_sync {
+        test_api_key(app.clone(), api_key.clone(), Some(provider.clone()), base_url.clone()).await?;
+    } else if provider != "ollama" {
+        eprintln!("[AUTH WARN] {}: key saved without checking it with {} (api_key_check_on_sync is off)", agent_id, provider);
+    }
+    let (mirror, policy) = (config.mirror_to_main, config.main_agent_policy);
+    let changed = run_storage_io(&app, move || -> Result<Vec<String>, String> {
+        let mut targets = vec![agent_id.as_str()];
+        if mirror && agent_id != "main" {
+            targets.push("main");
+        }
+        // Cached answers were produced under the old instructions
+        let changed = targets.iter()
+            .filter(|id| read_agent_config(id).instructions != system_prompt)
+            .map(|id| id.to_string())
+            .collect();
+        let url = base_url.as_deref();
+        for id in targets {
+            // A mirrored main only takes the identity when it follows the synced agent.
+            // Identity first, so a locked main refuses before its credentials change.
+            let mirrored_main = id == "main" && agent_id != "main";
+            if !mirrored_main || policy == MainAgentPolicy::FollowSelected {
+                write_agent_config(id, &agent_name, &system_prompt)?;
+            }
+            write_auth_profile(id, &api_key, &provider, url, OPENCLAW_AUTH_VERSION)?;
+        }
+        Ok(changed)
+    }).await??;
+    let app_state = app.state::<AppState>();
+    let cache = &app_state.prompt_cache;
+    for id in changed {
+        cache.invalidate_agent(&id);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) fn set_mirror_to_main(enabled: bool) -> Result<(), AppError> {
+    ensure_writable()?;
+    let mut config = load_config();
+    config.mirror_to_main = enabled;
+    save_config(&config)?;
+    Ok(())
+}
+
+// ─── Agent creation ───────────────────────────────────────────────────────────
+
+#[derive(serde::Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct NewAgent {
+    pub(crate) id: String,
+    pub(crate) name: String,
+    #[serde(default)]
+    pub(crate) system_prompt: String,
+    #[serde(default)]
+    pub(crate) api_key: String,
+    #[serde(default = "default_provider")]
+    pub(crate) provider: String,
+    #[serde(default)]
+    pub(crate) base_url: Option<String>,
+    #[serde(default)]
+    pub(crate) model: Option<String>,
+    #[serde(default)]
+    pub(crate) temperature: Option<f64>,
+}
+
+pub(crate) fn default_provider() -> String {
+    "anthropic".into()
+}
+
+/// Agent ids become directory names under ~/.openclaw/agents.
+pub(crate) fn validate_agent_id(id: &str) -> Result<(), AppError> {
+    let ok = !id.is_empty()
+        && id.len() <= 64
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if ok {
+        Ok(())
+    } else {
+        Err(AppError::InvalidInput(format!(
+            "Agent id '{}' must be 1-64 characters of letters, digits, '-' or '_'", id
+        )))
+    }
+}
+
+/// Instructions are sent with every call, so they have to leave room in the context window.
+pub(crate) const MAX_INSTRUCTIONS_CHARS: usize = 32_000;
+
+pub(crate) fn validate_instructions(instructions: &str) -> Result<(), AppError> {
+    let len = instructions.chars().count();
+    if len > MAX_INSTRUCTIONS_CHARS {
+        return Err(AppError::InvalidInput(format!(
+            "Instructions are {} characters, over the {} character limit", len, MAX_INSTRUCTIONS_CHARS
+        )));
+    }
+    Ok(())
+}
+
+pub(crate) fn agent_exists(agent_id: &str) -> bool {
+    agent_config_path(agent_id).exists()
+}
+
+/// Refuses a new agent once `max_agents` exist.
+pub(crate) fn ensure_agent_slot() -> Result<(), AppError> {
+    match load_config().max_agents {
+        Some(limit) if get_all_agent_ids().len() >= limit => Err(AppError::AgentLimitReached { limit }),
+        _ => Ok(()),
+    }
+}
+
+pub(crate) fn create_agent_files(agent: &NewAgent) -> Result<(), AppError> {
+    validate_agent_id(&agent.id)?;
+    if agent_exists(&agent.id) {
+        return Err(AppError::AlreadyExists(format!("agent {}", agent.id)));
+    }
+    if agent.provider != "ollama" && agent.api_key.trim().is_empty() {
+        return Err(AppError::InvalidInput("API key is empty".into()));
+    }
+    validate_instructions(&agent.system_prompt)?;
+    write_auth_profile(&agent.id, &agent.api_key, &agent.provider, agent.base_url.as_deref(), OPENCLAW_AUTH_VERSION)?;
+    save_agent_config(&agent.id, &AgentConfig {
+        name: agent.name.clone(),
+        instructions: agent.system_prompt.clone(),
+        model: agent.model.clone(),
+        temperature: agent.temperature,
+        ..Default::default()
+    }, &FileBase::default())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) async fn create_agent(app: tauri::AppHandle, agent: NewAgent) -> Result<(), AppError> {
+    ensure_writable()?;
+    run_storage_io(&app, move || {
+        ensure_agent_slot()?;
+        create_agent_files(&agent)
+    }).await?
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Held by tests that write main's agent.json, which the whole test run shares.
+    pub(crate) static MAIN_AGENT_LOCK: Mutex<()> = Mutex::new(());
+
+    pub(crate) fn identity(name: &str, instructions: &str) -> AgentConfig {
+        AgentConfig { name: name.into(), instructions: instructions.into(), ..Default::default() }
+    }
+
+    #[test]
+    fn only_a_locked_main_refuses_identity_changes() {
+        let current = identity("Main", "Be brief.");
+        let cases = [
+            (MainAgentPolicy::Locked, "main", identity("Other", "Be brief."), false),
+            (MainAgentPolicy::Locked, "main", identity("Main", "Be verbose."), false),
+            (MainAgentPolicy::Locked, "main", AgentConfig { context_window: Some(8_000), ..current.clone() }, true),
+            (MainAgentPolicy::Locked, "helper", identity("Other", "Be verbose."), true),
+            (MainAgentPolicy::FollowSelected, "main", identity("Other", "Be verbose."), true),
+            (MainAgentPolicy::Independent, "main", identity("Other", "Be verbose."), true),
+        ];
+        for (policy, agent_id, next, allowed) in cases {
+            let result = check_main_agent_policy(agent_id, policy, &current, &next);
+            assert_eq!(result.is_ok(), allowed, "{:?} on {}", policy, agent_id);
+            if !allowed {
+                assert!(matches!(result, Err(AppError::MainAgentLocked)));
+            }
+        }
+    }
+
+    #[test]
+    fn unknown_agent_json_fields_survive_a_rewrite() {
+        let raw = serde_json::json!({
+            "name": "Helper",
+            "instructions": "Be brief.",
+            "thinkingLevel": "high",
+            "heartbeat": { "every": "30m" },
+        });
+        let mut config: AgentConfig = serde_json::from_value(raw.clone()).unwrap();
+        config.context_window = Some(8_000);
+        let written = serde_json::to_value(&config).unwrap();
+        assert_eq!(written["thinkingLevel"], raw["thinkingLevel"]);
+        assert_eq!(written["heartbeat"], raw["heartbeat"]);
+        assert_eq!(written["contextWindow"], 8_000);
+        // Known fields are not duplicated into `extra`
+        assert!(!config.extra.contains_key("name"));
+    }
+
+    #[test]
+    fn main_agent_policy_applies_to_both_write_paths() {
+        // The policy is passed in rather than saved to the shared config.json
+        let _main = MAIN_AGENT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        for policy in [MainAgentPolicy::Locked, MainAgentPolicy::FollowSelected, MainAgentPolicy::Independent] {
+            store_agent_config("main", &identity("Main", "Be brief."), &current_base(&agent_config_path("main"))).unwrap();
+            let locked = policy == MainAgentPolicy::Locked;
+
+            let renamed = write_agent_config_under(policy, "main", "Other", "Be brief.");
+            assert_eq!(renamed.is_err(), locked, "write_agent_config under {:?}", policy);
+            store_agent_config("main", &identity("Main", "Be brief."), &current_base(&agent_config_path("main"))).unwrap();
+
+            let saved = save_agent_config_under(policy, "main", &identity("Main", "Be verbose."), &current_base(&agent_config_path("main")));
+            assert_eq!(saved.is_err(), locked, "save_agent_config under {:?}", policy);
+            if locked {
+                let config = read_agent_config("main");
+                assert_eq!((config.name.as_str(), config.instructions.as_str()), ("Main", "Be brief."));
+            }
+
+            // Settings other than the identity still change on a locked main
+            let (config, base) = load_agent_config("main");
+            save_agent_config_under(policy, "main", &AgentConfig { context_window: Some(8_000), ..config }, &base).unwrap();
+            write_agent_config_under(policy, "policy-helper", "Helper", "Anything").unwrap();
+        }
+    }
+}
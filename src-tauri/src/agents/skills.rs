@@ -0,0 +1,277 @@
+//! Skills: folders of reusable instructions and tools in an agent's workspace.
+
+use crate::*;
+
+// ─── Skills ───────────────────────────────────────────────────────────────────
+
+/// Every skill folder holds one of these, with `name` and `description` in its front matter.
+pub(crate) const SKILL_FILE: &str = "SKILL.md";
+pub(crate) const SKILLS_WATCH_SECS: u64 = 10;
+/// Skill files larger than this are left out of agent exports.
+pub(crate) const SKILL_EXPORT_MAX_FILE_BYTES: u64 = 256 * 1024;
+
+#[derive(serde::Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SkillInfo {
+    /// Folder name
+    pub(crate) name: String,
+    pub(crate) description: Option<String>,
+    pub(crate) enabled: bool,
+    /// Why the folder isn't a usable skill; such folders are listed, not hidden
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) broken: Option<String>,
+}
+
+pub(crate) fn skills_dir(agent_id: &str) -> PathBuf {
+    agent_workspace(&read_agent_config(agent_id)).join("skills")
+}
+
+/// `name` and `description` from the `---` front matter of a SKILL.md.
+pub(crate) fn parse_skill_front_matter(content: &str) -> Result<(String, String), String> {
+    let rest = content.trim_start_matches('\u{feff}').strip_prefix("---")
+        .ok_or_else(|| format!("{} has no front matter", SKILL_FILE))?;
+    let end = rest.find("\n---").ok_or_else(|| format!("{} front matter is not closed", SKILL_FILE))?;
+    let field = |key: &str| {
+        rest[..end].lines()
+            .find_map(|l| l.trim().strip_prefix(key)?.trim_start().strip_prefix(':').map(str::trim))
+            .map(|v| v.trim_matches(|c| c == '"' || c == '\'').to_string())
+            .filter(|v| !v.is_empty())
+    };
+    let name = field("name").ok_or_else(|| format!("{} has no name", SKILL_FILE))?;
+    let description = field("description").ok_or_else(|| format!("{} has no description", SKILL_FILE))?;
+    Ok((name, description))
+}
+
+/// Skills switched off in openclaw.json (`skills.entries.<name>.enabled`).
+/// OpenClaw keys these by skill name, so a switch applies to every agent.
+pub(crate) fn disabled_skills() -> Vec<String> {
+    let Some(v) = fs::read_to_string(openclaw_config_path()).ok()
+        .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok()) else { return Vec::new() };
+    v["skills"]["entries"].as_object()
+        .map(|e| e.iter().filter(|(_, s)| s["enabled"] == false).map(|(k, _)| k.clone()).collect())
+        .unwrap_or_default()
+}
+
+pub(crate) fn read_skill(dir: &std::path::Path, disabled: &[String]) -> SkillInfo {
+    let name = dir.file_name().unwrap_or_default().to_string_lossy().into_owned();
+    let parsed = fs::read_to_string(dir.join(SKILL_FILE))
+        .map_err(|_| format!("{} is missing", SKILL_FILE))
+        .and_then(|c| parse_skill_front_matter(&c));
+    let (description, broken) = match parsed {
+        Ok((_, description)) => (Some(description), None),
+        Err(e) => (None, Some(e)),
+    };
+    SkillInfo { enabled: !disabled.contains(&name), name, description, broken }
+}
+
+pub(crate) fn list_skills(agent_id: &str) -> Vec<SkillInfo> {
+    let disabled = disabled_skills();
+    let Ok(entries) = fs::read_dir(skills_dir(agent_id)) else { return Vec::new() };
+    let mut skills: Vec<SkillInfo> = entries.flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .map(|p| read_skill(&p, &disabled))
+        .collect();
+    skills.sort_by(|a, b| a.name.cmp(&b.name));
+    skills
+}
+
+/// The folder that holds SKILL.md: `root` itself, or its only subfolder, the
+/// way most archives are packed.
+pub(crate) fn skill_root(root: &std::path::Path) -> Option<PathBuf> {
+    if root.join(SKILL_FILE).is_file() {
+        return Some(root.to_path_buf());
+    }
+    let dirs: Vec<PathBuf> = fs::read_dir(root).ok()?.flatten().map(|e| e.path()).filter(|p| p.is_dir()).collect();
+    match dirs.as_slice() {
+        [only] if only.join(SKILL_FILE).is_file() => Some(only.clone()),
+        _ => None,
+    }
+}
+
+/// Checks a skill folder and copies it in under the name from its SKILL.md.
+pub(crate) fn install_skill_dir(agent_id: &str, root: &std::path::Path) -> Result<SkillInfo, AppError> {
+    let src = skill_root(root).ok_or_else(|| AppError::UnsupportedFormat(format!("no {} in {}", SKILL_FILE, root.display())))?;
+    let (name, _) = parse_skill_front_matter(&fs::read_to_string(src.join(SKILL_FILE))?)
+        .map_err(AppError::UnsupportedFormat)?;
+    validate_agent_id(&name).map_err(|_| AppError::InvalidInput(format!(
+        "Skill name '{}' must be 1-64 characters of letters, digits, '-' or '_'", name
+    )))?;
+    let dest = skills_dir(agent_id).join(&name);
+    if dest.exists() {
+        return Err(AppError::AlreadyExists(format!("skill {}", name)));
+    }
+    copy_all(&src, &dest)?;
+    audit("skill_installed", serde_json::json!({ "agentId": agent_id, "skill": name }));
+    Ok(read_skill(&dest, &disabled_skills()))
+}
+
+/// Text files of a skill by relative path, for agent exports. Binary and large
+/// files are skipped and named in the second list.
+pub(crate) fn export_skill_files(dir: &std::path::Path) -> (serde_json::Map<String, serde_json::Value>, Vec<String>) {
+    fn walk(root: &std::path::Path, dir: &std::path::Path, files: &mut serde_json::Map<String, serde_json::Value>, skipped: &mut Vec<String>) {
+        let Ok(entries) = fs::read_dir(dir) else { return };
+        for path in entries.flatten().map(|e| e.path()) {
+            let rel = path.strip_prefix(root).unwrap_or(&path).to_string_lossy().replace('\\', "/");
+            // Links are not followed: they could point anywhere, or back up the tree
+            let Ok(meta) = fs::symlink_metadata(&path) else { continue };
+            if meta.file_type().is_symlink() {
+                skipped.push(rel);
+                continue;
+            }
+            if meta.is_dir() {
+                walk(root, &path, files, skipped);
+                continue;
+            }
+            let small = meta.len() <= SKILL_EXPORT_MAX_FILE_BYTES;
+            match fs::read_to_string(&path) {
+                Ok(text) if small => { files.insert(rel, text.into()); }
+                _ => skipped.push(rel),
+            }
+        }
+    }
+    let (mut files, mut skipped) = (serde_json::Map::new(), Vec::new());
+    walk(dir, dir, &mut files, &mut skipped);
+    (files, skipped)
+}
+
+/// `{ name: { files: { path: text }, skipped: [path] } }` for every skill of the agent.
+pub(crate) fn export_skills(agent_id: &str) -> serde_json::Value {
+    let Ok(entries) = fs::read_dir(skills_dir(agent_id)) else { return serde_json::json!({}) };
+    let mut out = serde_json::Map::new();
+    for dir in entries.flatten().map(|e| e.path()).filter(|p| fs::symlink_metadata(p).is_ok_and(|m| m.is_dir())) {
+        let (files, skipped) = export_skill_files(&dir);
+        let name = dir.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        out.insert(name, serde_json::json!({ "files": files, "skipped": skipped }));
+    }
+    out.into()
+}
+
+/// Writes exported skills the agent doesn't have yet. Paths that would leave the
+/// skill folder are dropped. Returns the skills written.
+pub(crate) fn import_skills(agent_id: &str, skills: &serde_json::Value) -> Vec<String> {
+    let Some(skills) = skills.as_object() else { return Vec::new() };
+    let mut written = Vec::new();
+    for (name, skill) in skills {
+        let dest = skills_dir(agent_id).join(name);
+        if validate_agent_id(name).is_err() || dest.exists() {
+            continue;
+        }
+        let files = skill["files"].as_object().cloned().unwrap_or_default();
+        for (rel, text) in files {
+            let safe = std::path::Path::new(&rel).components().all(|c| matches!(c, std::path::Component::Normal(_)));
+            let Some(text) = text.as_str().filter(|_| safe) else { continue };
+            let path = dest.join(&rel);
+            let ok = path.parent().map_or(Ok(()), fs::create_dir_all).and_then(|_| fs::write(&path, text));
+            if let Err(e) = ok {
+                eprintln!("[SKILLS ERR] {}/{}: {}", name, rel, e);
+            }
+        }
+        written.push(name.clone());
+    }
+    written
+}
+
+/// Emits `agent-skills-changed` when an agent's skills change outside the app.
+pub(crate) fn spawn_skills_watcher(app: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut seen: HashMap<String, Vec<SkillInfo>> = HashMap::new();
+        while !is_shutting_down(&app) {
+            let current = run_storage_io(&app, || {
+                get_all_agent_ids().into_iter().map(|id| { let s = list_skills(&id); (id, s) }).collect::<HashMap<_, _>>()
+            }).await.unwrap_or_default();
+            for (agent_id, skills) in &current {
+                if seen.get(agent_id).is_some_and(|old| old != skills) {
+                    app.emit("agent-skills-changed", serde_json::json!({ "agentId": agent_id, "skills": skills })).ok();
+                }
+            }
+            seen = current;
+            tokio::time::sleep(std::time::Duration::from_secs(SKILLS_WATCH_SECS)).await;
+        }
+    });
+}
+
+#[tauri::command]
+pub(crate) async fn list_agent_skills(app: tauri::AppHandle, agent_id: String) -> Result<Vec<SkillInfo>, AppError> {
+    validate_agent_id(&agent_id)?;
+    run_storage_io(&app, move || list_skills(&agent_id)).await
+}
+
+/// `source` is a skill folder or a .zip of one; zips are unpacked with the
+/// system `tar`, which reads zip on Windows 10 and later.
+#[tauri::command]
+pub(crate) async fn install_skill(app: tauri::AppHandle, agent_id: String, source: String) -> Result<SkillInfo, AppError> {
+    ensure_writable()?;
+    validate_agent_id(&agent_id)?;
+    let is_zip = source.to_lowercase().ends_with(".zip");
+    let source = if is_zip {
+        validate_user_path(&source, PathIntent::Import)?
+    } else {
+        check_path_syntax(&source)?;
+        let dir = fs::canonicalize(source.trim()).map_err(|_| path_rejected(&source, "does not exist"))?;
+        if !dir.is_dir() {
+            return Err(path_rejected(&source, "is neither a folder nor a .zip"));
+        }
+        strip_verbatim(dir)
+    };
+    if !is_zip {
+        return run_storage_operation(&app, "skill-install", move |_| install_skill_dir(&agent_id, &source)).await;
+    }
+
+    let unpack = clapp_dir().join("skill-unpack").join(format!("{:x}", now_ms()));
+    fs::create_dir_all(&unpack)?;
+    let out = app.shell().command("cmd")
+        .args(["/C", "tar", "-xf", &source.to_string_lossy(), "-C", &unpack.to_string_lossy()])
+        .output()
+        .await
+        .map_err(|e| AppError::Other(e.to_string()))?;
+    let result = if out.status.success() {
+        let dir = unpack.clone();
+        run_storage_operation(&app, "skill-install", move |_| install_skill_dir(&agent_id, &dir)).await
+    } else {
+        Err(AppError::UnsupportedFormat(format!("could not unpack: {}", String::from_utf8_lossy(&out.stderr).trim())))
+    };
+    fs::remove_dir_all(&unpack).ok();
+    result
+}
+
+#[tauri::command]
+pub(crate) async fn remove_skill(app: tauri::AppHandle, agent_id: String, name: String) -> Result<(), AppError> {
+    ensure_writable()?;
+    validate_agent_id(&agent_id)?;
+    validate_agent_id(&name).map_err(|_| AppError::InvalidInput(format!("Bad skill name '{}'", name)))?;
+    run_storage_io(&app, move || {
+        let dir = skills_dir(&agent_id).join(&name);
+        if !dir.is_dir() {
+            return Err(AppError::NotFound(format!("skill {}", name)));
+        }
+        fs::remove_dir_all(&dir)?;
+        audit("skill_removed", serde_json::json!({ "agentId": agent_id, "skill": name }));
+        Ok(())
+    }).await?
+}
+
+/// Writes `skills.entries.<name>.enabled` in openclaw.json, which applies to the
+/// skill of that name in every agent.
+#[tauri::command]
+pub(crate) async fn set_skill_enabled(app: tauri::AppHandle, agent_id: String, name: String, enabled: bool) -> Result<(), AppError> {
+    ensure_writable()?;
+    validate_agent_id(&agent_id)?;
+    validate_agent_id(&name).map_err(|_| AppError::InvalidInput(format!("Bad skill name '{}'", name)))?;
+    run_storage_io(&app, move || {
+        if !skills_dir(&agent_id).join(&name).is_dir() {
+            return Err(AppError::NotFound(format!("skill {}", name)));
+        }
+        let path = openclaw_config_path();
+        let (content, base) = read_guarded(&path)?;
+        let mut v: serde_json::Value = serde_json::from_str(&content)?;
+        if !v["skills"].is_object() {
+            v["skills"] = serde_json::json!({});
+        }
+        if !v["skills"]["entries"].is_object() {
+            v["skills"]["entries"] = serde_json::json!({});
+        }
+        v["skills"]["entries"][&name]["enabled"] = enabled.into();
+        write_guarded(&path, &base, &serde_json::to_string_pretty(&v)?)
+    }).await?
+}
@@ -0,0 +1,215 @@
+//! Tagged copies of agent.json and diffs between them.
+
+use crate::*;
+
+// ─── Agent snapshots ──────────────────────────────────────────────────────────
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AgentDiff {
+    pub(crate) name_changed: bool,
+    /// Unified diff of the instructions, empty when they match
+    pub(crate) prompt_diff: String,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SnapshotInfo {
+    pub(crate) tag: String,
+    pub(crate) created_at: u64,
+    pub(crate) size_bytes: u64,
+}
+
+pub(crate) fn agent_snapshot_path(agent_id: &str, tag: &str) -> PathBuf {
+    openclaw_agents_root().join(agent_id).join("snapshots").join(format!("{}.json", tag))
+}
+
+/// Tags become file names, so they follow the agent id rules.
+pub(crate) fn validate_snapshot_tag(tag: &str) -> Result<(), AppError> {
+    validate_agent_id(tag).map_err(|_| AppError::InvalidInput(format!(
+        "Snapshot tag '{}' must be 1-64 characters of letters, digits, '-' or '_'", tag
+    )))
+}
+
+pub(crate) fn read_agent_snapshot(agent_id: &str, tag: &str) -> Result<AgentConfig, AppError> {
+    let path = agent_snapshot_path(agent_id, tag);
+    let content = fs::read_to_string(&path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => AppError::NotFound(format!("snapshot {} of agent {}", tag, agent_id)),
+        _ => e.into(),
+    })?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+#[tauri::command]
+pub(crate) async fn save_agent_snapshot(app: tauri::AppHandle, agent_id: String, tag: String) -> Result<(), AppError> {
+    ensure_writable()?;
+    validate_agent_id(&agent_id)?;
+    validate_snapshot_tag(&tag)?;
+    run_storage_io(&app, move || write_agent_snapshot(&agent_id, &tag)).await?
+}
+
+pub(crate) fn write_agent_snapshot(agent_id: &str, tag: &str) -> Result<(), AppError> {
+    if !agent_exists(agent_id) {
+        return Err(AppError::NotFound(format!("agent {}", agent_id)));
+    }
+    let dest = agent_snapshot_path(agent_id, tag);
+    fs::create_dir_all(dest.parent().unwrap())?;
+    // Written rather than copied: a copy keeps the source's mtime on Windows,
+    // and purging goes by when the snapshot was taken
+    fs::write(dest, fs::read(agent_config_path(agent_id))?)?;
+    Ok(())
+}
+
+/// Tag the current agent.json is saved under before a restore replaces it.
+pub(crate) const PRE_RESTORE_TAG: &str = "pre-restore";
+
+/// Replaces agent.json with a snapshot, keeping the current one as `pre-restore`.
+/// The new file is renamed into place, so a failure leaves the old one intact.
+#[tauri::command]
+pub(crate) async fn restore_agent_from_snapshot(app: tauri::AppHandle, agent_id: String, tag: String) -> Result<(), AppError> {
+    ensure_writable()?;
+    validate_agent_id(&agent_id)?;
+    validate_snapshot_tag(&tag)?;
+    let policy = load_config().main_agent_policy;
+    run_storage_io(&app, move || restore_agent_snapshot(policy, &agent_id, &tag)).await?
+}
+
+/// Like any other write of agent.json, a restore can't change a locked main's identity.
+pub(crate) fn restore_agent_snapshot(policy: MainAgentPolicy, agent_id: &str, tag: &str) -> Result<(), AppError> {
+    // Parsed as a whole so a snapshot from an incompatible version is refused before anything changes
+    let snapshot = read_agent_snapshot(agent_id, tag)?;
+    let content = fs::read_to_string(agent_snapshot_path(agent_id, tag))?;
+    let path = agent_config_path(agent_id);
+    check_main_agent_policy(agent_id, policy, &read_agent_config(agent_id), &snapshot)?;
+    // Undoing a restore must not overwrite the copy it restores from
+    if tag != PRE_RESTORE_TAG {
+        write_agent_snapshot(agent_id, PRE_RESTORE_TAG)?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, &content)?;
+    fs::rename(&tmp, &path)?;
+    KNOWN_CONTENT.lock().unwrap().insert(path, content);
+    audit("agent_restored_from_snapshot", serde_json::json!({ "agentId": agent_id, "tag": tag }));
+    Ok(())
+}
+
+/// Newest first. An agent that never had a snapshot has none, not an error.
+#[tauri::command]
+pub(crate) async fn list_agent_snapshots(app: tauri::AppHandle, agent_id: String) -> Result<Vec<SnapshotInfo>, AppError> {
+    validate_agent_id(&agent_id)?;
+    run_storage_io(&app, move || -> Result<Vec<SnapshotInfo>, AppError> {
+        let dir = openclaw_agents_root().join(&agent_id).join("snapshots");
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut snapshots: Vec<SnapshotInfo> = entries.flatten()
+            .filter_map(|e| {
+                let tag = e.file_name().to_string_lossy().strip_suffix(".json")?.to_string();
+                let meta = e.metadata().ok().filter(|m| m.is_file())?;
+                // Not every filesystem records creation time
+                let created = meta.created().or_else(|_| meta.modified()).ok()?;
+                let created_at = created.duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+                Some(SnapshotInfo { tag, created_at, size_bytes: meta.len() })
+            })
+            .collect();
+        snapshots.sort_by_key(|s| std::cmp::Reverse(s.created_at));
+        Ok(snapshots)
+    }).await?
+}
+
+#[tauri::command]
+pub(crate) async fn compare_agent_versions(
+    app: tauri::AppHandle,
+    agent_id: String,
+    tag_a: String,
+    tag_b: String,
+) -> Result<AgentDiff, AppError> {
+    validate_agent_id(&agent_id)?;
+    validate_snapshot_tag(&tag_a)?;
+    validate_snapshot_tag(&tag_b)?;
+    run_storage_io(&app, move || -> Result<AgentDiff, AppError> {
+        let a = read_agent_snapshot(&agent_id, &tag_a)?;
+        let b = read_agent_snapshot(&agent_id, &tag_b)?;
+        let prompt_diff = if a.instructions == b.instructions {
+            String::new()
+        } else {
+            similar::TextDiff::from_lines(a.instructions.as_str(), b.instructions.as_str())
+                .unified_diff()
+                .header(&tag_a, &tag_b)
+                .to_string()
+        };
+        Ok(AgentDiff { name_changed: a.name != b.name, prompt_diff })
+    }).await?
+}
+
+/// Deletes snapshots taken more than `older_than_days` ago. Returns how many went.
+#[tauri::command]
+pub(crate) async fn purge_agent_snapshots(app: tauri::AppHandle, agent_id: String, older_than_days: u64) -> Result<u64, AppError> {
+    ensure_writable()?;
+    validate_agent_id(&agent_id)?;
+    run_storage_io(&app, move || purge_snapshots_older_than(&agent_id, older_than_days)).await?
+}
+
+pub(crate) fn purge_snapshots_older_than(agent_id: &str, older_than_days: u64) -> Result<u64, AppError> {
+    let dir = openclaw_agents_root().join(agent_id).join("snapshots");
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e.into()),
+    };
+    let max_age = std::time::Duration::from_secs(older_than_days.saturating_mul(86_400));
+    let mut purged = 0;
+    for entry in entries.flatten() {
+        let old = entry.metadata().ok()
+            .filter(|m| m.is_file())
+            .and_then(|m| m.modified().ok())
+            .and_then(|t| t.elapsed().ok())
+            .is_some_and(|age| age > max_age);
+        if old && fs::remove_file(entry.path()).is_ok() {
+            purged += 1;
+        }
+    }
+    if purged > 0 {
+        audit("agent_snapshots_purged", serde_json::json!({ "agentId": agent_id, "count": purged }));
+    }
+    Ok(purged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::tests::{identity, MAIN_AGENT_LOCK};
+
+    #[test]
+    fn restoring_a_snapshot_respects_a_locked_main() {
+        let _main = MAIN_AGENT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        store_agent_config("main", &identity("Renamed", "Be verbose."), &current_base(&agent_config_path("main"))).unwrap();
+        write_agent_snapshot("main", "policy-test").unwrap();
+        store_agent_config("main", &identity("Main", "Be brief."), &current_base(&agent_config_path("main"))).unwrap();
+
+        let err = restore_agent_snapshot(MainAgentPolicy::Locked, "main", "policy-test").unwrap_err();
+        assert!(matches!(err, AppError::MainAgentLocked), "{:?}", err);
+        assert_eq!(read_agent_config("main").name, "Main");
+
+        restore_agent_snapshot(MainAgentPolicy::Independent, "main", "policy-test").unwrap();
+        assert_eq!(read_agent_config("main").name, "Renamed");
+    }
+
+    #[test]
+    fn a_snapshot_is_dated_when_taken_not_when_the_agent_was_saved() {
+        let id = "snapshot-age";
+        store_agent_config(id, &identity("Aged", "Be brief."), &current_base(&agent_config_path(id))).unwrap();
+        let month_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(30 * 86_400);
+        let set_mtime = |path: PathBuf| fs::File::options().write(true).open(path).unwrap().set_modified(month_ago).unwrap();
+        set_mtime(agent_config_path(id));
+
+        write_agent_snapshot(id, "fresh").unwrap();
+        assert_eq!(purge_snapshots_older_than(id, 7).unwrap(), 0);
+
+        set_mtime(agent_snapshot_path(id, "fresh"));
+        assert_eq!(purge_snapshots_older_than(id, 7).unwrap(), 1);
+        assert!(!agent_snapshot_path(id, "fresh").exists());
+    }
+}
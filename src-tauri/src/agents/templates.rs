@@ -0,0 +1,86 @@
+//! Starter templates for new agents.
+
+use crate::*;
+
+// ─── Agent templates ──────────────────────────────────────────────────────────
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub(crate) struct AgentTemplate {
+    pub(crate) name_prefix: String,
+    pub(crate) system_prompt: String,
+    pub(crate) model: String,
+    pub(crate) temperature: f64,
+}
+
+pub(crate) fn templates_path() -> PathBuf {
+    clapp_dir().join("templates.json")
+}
+
+pub(crate) fn default_templates() -> std::collections::BTreeMap<String, AgentTemplate> {
+    let t = |name_prefix: &str, system_prompt: &str, temperature: f64| AgentTemplate {
+        name_prefix: name_prefix.into(),
+        system_prompt: system_prompt.into(),
+        model: "claude-sonnet-4-5".into(),
+        temperature,
+    };
+    [
+        ("code-assistant".to_string(), t(
+            "Code Assistant",
+            "You are a careful senior software engineer. Give precise, working code and explain trade-offs briefly.",
+            0.2,
+        )),
+        ("data-analyst".to_string(), t(
+            "Data Analyst",
+            "You are a data analyst. Ask about the shape of the data, state assumptions, and show your calculations.",
+            0.3,
+        )),
+        ("writer".to_string(), t(
+            "Writer",
+            "You are a concise editor. Improve clarity and tone without changing the author's meaning.",
+            0.7,
+        )),
+    ]
+    .into_iter()
+    .collect()
+}
+
+/// Reads templates.json, creating it with the built-in templates on first use.
+pub(crate) fn load_templates() -> Result<std::collections::BTreeMap<String, AgentTemplate>, AppError> {
+    let p = templates_path();
+    if !p.exists() {
+        let defaults = default_templates();
+        fs::write(&p, serde_json::to_string_pretty(&defaults)?)?;
+        return Ok(defaults);
+    }
+    serde_json::from_str(&fs::read_to_string(p)?)
+        .map_err(|e| AppError::InvalidInput(format!("templates.json is invalid: {}", e)))
+}
+
+#[tauri::command]
+pub(crate) fn list_agent_templates() -> Result<Vec<String>, AppError> {
+    Ok(load_templates()?.into_keys().collect())
+}
+
+#[tauri::command]
+pub(crate) async fn create_agent_from_template(
+    app: tauri::AppHandle,
+    template_name: String,
+    new_id: String,
+    api_key: String,
+) -> Result<(), AppError> {
+    ensure_writable()?;
+    let template = load_templates()?
+        .remove(&template_name)
+        .ok_or_else(|| AppError::NotFound(format!("template {}", template_name)))?;
+    let agent = NewAgent {
+        name: format!("{} {}", template.name_prefix, new_id),
+        id: new_id,
+        system_prompt: template.system_prompt,
+        api_key,
+        provider: default_provider(),
+        base_url: None,
+        model: Some(template.model),
+        temperature: Some(template.temperature),
+    };
+    create_agent(app, agent).await
+}
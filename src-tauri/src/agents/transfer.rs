@@ -0,0 +1,207 @@
+//! Export and import of agent bundles.
+
+use crate::*;
+
+// ─── Agent export ─────────────────────────────────────────────────────────────
+
+pub(crate) const AGENT_EXPORT_VERSION: u32 = 1;
+
+pub(crate) fn redact_secrets(v: &mut serde_json::Value) {
+    match v {
+        serde_json::Value::Object(m) => {
+            for (k, v) in m.iter_mut() {
+                let secret_value = v.as_str().is_some_and(contains_secret);
+                if (matches!(k.as_str(), "key" | "apiKey" | "token") && v.is_string()) || secret_value {
+                    *v = REDACTED.into();
+                } else {
+                    redact_secrets(v);
+                }
+            }
+        }
+        serde_json::Value::Array(a) => a.iter_mut().for_each(redact_secrets),
+        _ => {}
+    }
+}
+
+/// The agent's config plus its auth profile, with keys redacted.
+#[tauri::command]
+pub(crate) async fn export_agent_config(app: tauri::AppHandle, agent_id: String) -> Result<serde_json::Value, AppError> {
+    run_storage_io(&app, move || {
+        if !agent_exists(&agent_id) {
+            return Err(AppError::NotFound(format!("agent {}", agent_id)));
+        }
+        let mut auth = fs::read_to_string(agent_dir(&agent_id).join("auth-profiles.json"))
+            .ok()
+            .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok())
+            .unwrap_or(serde_json::Value::Null);
+        redact_secrets(&mut auth);
+        Ok(serde_json::json!({
+            "agent": read_agent_config(&agent_id),
+            "auth": auth,
+            "skills": agents::skills::export_skills(&agent_id),
+        }))
+    }).await?
+}
+
+#[tauri::command]
+pub(crate) async fn export_all_agents(app: tauri::AppHandle, dest_path: String) -> Result<usize, AppError> {
+    let dest = validate_user_path(&dest_path, PathIntent::Export)?;
+    let op = Operation::start(&app, "export", None);
+    let result = export_agents_to(&app, &op, dest).await;
+    op.finish(&result);
+    result
+}
+
+/// Written through a temp file, so a cancelled or failed export leaves no partial bundle.
+pub(crate) async fn export_agents_to(app: &tauri::AppHandle, op: &Operation, dest: PathBuf) -> Result<usize, AppError> {
+    let summaries = list_agents(app.clone()).await?;
+    op.set_total(summaries.len());
+    let mut agents = Vec::new();
+    for (i, summary) in summaries.into_iter().enumerate() {
+        op.check("no file was written")?;
+        op.progress("exporting", i, Some(&summary.id));
+        let config = export_agent_config(app.clone(), summary.id.clone()).await?;
+        agents.push(serde_json::json!({ "id": summary.id, "config": config }));
+    }
+    op.check("no file was written")?;
+    op.progress("writing", agents.len(), None);
+    let bundle = serde_json::json!({
+        "version": AGENT_EXPORT_VERSION,
+        "exported_at": now_ms(),
+        "agents": agents,
+    });
+    let content = serde_json::to_string_pretty(&bundle)?;
+    run_storage_io(app, move || -> Result<(), AppError> {
+        let tmp = PathBuf::from(format!("{}.tmp", dest.display()));
+        let written = fs::write(&tmp, &content).and_then(|_| fs::rename(&tmp, &dest));
+        if written.is_err() {
+            fs::remove_file(&tmp).ok();
+        }
+        Ok(written?)
+    }).await??;
+    Ok(agents.len())
+}
+
+#[derive(serde::Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ImportSummary {
+    pub(crate) created: Vec<String>,
+    pub(crate) skipped: Vec<String>,
+    pub(crate) failed: Vec<String>,
+    /// Stopped by `cancel_operation`; the lists say exactly what was done
+    pub(crate) cancelled: bool,
+}
+
+/// Rebuilds a `NewAgent` from one exported entry. Keys are never in the bundle.
+pub(crate) fn new_agent_from_export(id: &str, config: &AgentConfig, auth: &serde_json::Value, api_key: String) -> NewAgent {
+    let profile = auth["profiles"].as_object().and_then(|p| p.values().next());
+    let provider = profile.and_then(|p| p["provider"].as_str()).unwrap_or("anthropic");
+    let base_url = profile.and_then(|p| p["baseUrl"].as_str()).map(String::from);
+    // Ollama profiles are stored as OpenAI-compatible with a local /v1 URL and no real key
+    let is_ollama = api_key.is_empty() && base_url.as_deref().is_some_and(|u| u.contains(":11434"));
+    NewAgent {
+        id: id.to_string(),
+        name: config.name.clone(),
+        system_prompt: config.instructions.clone(),
+        api_key,
+        provider: if is_ollama { "ollama".into() } else { provider.to_string() },
+        base_url: if is_ollama { base_url.map(|u| u.trim_end_matches("/v1").to_string()) } else { base_url },
+        model: config.model.clone(),
+        temperature: config.temperature,
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn import_all_agents(
+    app: tauri::AppHandle,
+    src_path: String,
+    api_keys: HashMap<String, String>,
+    overwrite: bool,
+) -> Result<ImportSummary, AppError> {
+    ensure_writable()?;
+    let src = validate_user_path(&src_path, PathIntent::Import)?;
+    let raw = tauri::async_runtime::spawn_blocking(move || fs::read_to_string(src)).await
+        .map_err(|e| AppError::Other(e.to_string()))??;
+    let bundle: serde_json::Value = serde_json::from_str(&raw)?;
+    let version = bundle["version"].as_u64().unwrap_or(0);
+    if version != AGENT_EXPORT_VERSION as u64 {
+        return Err(AppError::UnsupportedFormat(format!("agent bundle version {}", version)));
+    }
+    let entries = bundle["agents"].as_array().cloned().unwrap_or_default();
+
+    let op = Operation::start(&app, "import", Some(entries.len()));
+    // Any early return still has to finish the operation
+    let result = async {
+        let mut summary = ImportSummary::default();
+        for (i, entry) in entries.into_iter().enumerate() {
+            if op.is_cancelled() {
+                summary.cancelled = true;
+                break;
+            }
+            let Some(id) = entry["id"].as_str().map(String::from) else { continue };
+            // Ids become paths under ~/.openclaw/agents, so a crafted bundle must not get past here
+            if let Err(e) = validate_agent_id(&id) {
+                eprintln!("[IMPORT ERR] {}", e);
+                summary.failed.push(id);
+                continue;
+            }
+            op.progress("importing", i, Some(&id));
+            let config: AgentConfig = match serde_json::from_value(entry["config"]["agent"].clone()) {
+                Ok(c) => c,
+                Err(e) => {
+                    eprintln!("[IMPORT ERR] {}: {}", id, e);
+                    summary.failed.push(id);
+                    continue;
+                }
+            };
+            let existing = id.clone();
+            let previous = run_storage_io(&app, move || {
+                agent_exists(&existing).then(|| read_agent_config(&existing))
+            }).await?;
+            if previous.is_some() {
+                if !overwrite {
+                    summary.skipped.push(id);
+                    continue;
+                }
+                let path = agent_config_path(&id);
+                run_storage_io(&app, move || fs::remove_file(path).ok()).await?;
+            }
+
+            let api_key = api_keys.get(&id).cloned().unwrap_or_default();
+            let agent = new_agent_from_export(&id, &config, &entry["config"]["auth"], api_key);
+            let result = match create_agent(app.clone(), agent).await {
+                // create_agent only writes the basics; restore the rest of the exported config
+                Ok(()) => {
+                    let (target, config) = (id.clone(), config.clone());
+                    run_storage_io(&app, move || {
+                        let (_, base) = load_agent_config(&target);
+                        save_agent_config(&target, &config, &base)
+                    }).await
+                        .and_then(|r| r.map_err(AppError::from))
+                }
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(()) => {
+                    let (target, skills) = (id.clone(), entry["config"]["skills"].clone());
+                    run_storage_job(&app, &op, move |_| Ok(agents::skills::import_skills(&target, &skills))).await.ok();
+                    summary.created.push(id);
+                }
+                Err(e) => {
+                    eprintln!("[IMPORT ERR] {}: {}", id, e);
+                    if let Some(previous) = previous {
+                        let target = id.clone();
+                        run_storage_io(&app, move || {
+                            let (_, base) = load_agent_config(&target);
+                            save_agent_config(&target, &previous, &base).ok()
+                        }).await.ok();
+                    }
+                    summary.failed.push(id);
+                }
+            }
+        }
+        Ok::<_, AppError>(summary)
+    }.await;
+    op.finish(&result);
+    result
+}
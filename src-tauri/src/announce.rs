@@ -0,0 +1,250 @@
+//! Spoken-style status announcements, batched streams for assistive tech, and the event catalog.
+
+use crate::*;
+
+// ─── Announcements ────────────────────────────────────────────────────────────
+
+#[derive(serde::Serialize, serde::Deserialize, Default, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum AnnouncementLevel {
+    #[default]
+    Verbose,
+    ErrorsOnly,
+}
+
+#[derive(serde::Serialize, Clone, Copy, PartialEq, PartialOrd)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum AnnouncementSeverity {
+    Info,
+    Warning,
+    Error,
+}
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Announcement {
+    /// Catalog key, e.g. "gateway.started"
+    pub(crate) key: String,
+    pub(crate) severity: AnnouncementSeverity,
+    pub(crate) text: String,
+    pub(crate) ts: u64,
+}
+
+/// Key → (language, text). `{name}` placeholders are filled from the announcement's
+/// arguments. Languages missing a text fall back to English.
+pub(crate) const MESSAGE_CATALOG: &[(&str, &[(&str, &str)])] = &[
+    ("gateway.started", &[("en", "Agent started"), ("ru", "Агент запущен")]),
+    ("gateway.stopped", &[("en", "Agent stopped"), ("ru", "Агент остановлен")]),
+    ("gateway.crashed", &[("en", "Agent stopped unexpectedly"), ("ru", "Агент неожиданно остановился")]),
+    ("gateway.unhealthy", &[("en", "Agent is not responding"), ("ru", "Агент не отвечает")]),
+    ("auth.expiring", &[("en", "Sign-in for {agent} expires soon"), ("ru", "Скоро истекает вход агента {agent}")]),
+    ("auth.expired", &[("en", "Sign-in for {agent} has expired"), ("ru", "Истёк вход агента {agent}")]),
+    ("call.started", &[("en", "Sending to {agent}"), ("ru", "Отправка агенту {agent}")]),
+    ("call.finished", &[("en", "Reply from {agent}"), ("ru", "Ответ от {agent}")]),
+    ("call.slow", &[("en", "Still waiting for a reply"), ("ru", "Ответ ещё не получен")]),
+    ("call.failed", &[("en", "Call to {agent} failed"), ("ru", "Ошибка вызова агента {agent}")]),
+];
+
+pub(crate) fn catalog_text(language: &str, key: &str) -> String {
+    let Some((_, texts)) = MESSAGE_CATALOG.iter().find(|(k, _)| *k == key) else {
+        return key.to_string();
+    };
+    texts.iter().find(|(l, _)| *l == language)
+        .or_else(|| texts.iter().find(|(l, _)| *l == "en"))
+        .map_or_else(|| key.to_string(), |(_, t)| t.to_string())
+}
+
+/// Emits `announcement` unless the user only wants errors announced.
+pub(crate) fn announce(app: &tauri::AppHandle, key: &str, severity: AnnouncementSeverity, args: &[(&str, &str)]) {
+    let config = load_config();
+    if config.announcements == AnnouncementLevel::ErrorsOnly && severity < AnnouncementSeverity::Error {
+        return;
+    }
+    let text = args.iter().fold(catalog_text(&config.ui_language, key), |text, (name, value)| {
+        text.replace(&format!("{{{}}}", name), value)
+    });
+    app.emit("announcement", Announcement { key: key.into(), severity, text, ts: now_ms() }).ok();
+}
+
+#[tauri::command]
+pub(crate) fn set_announcement_level(level: AnnouncementLevel) -> Result<(), AppError> {
+    ensure_writable()?;
+    let mut config = load_config();
+    config.announcements = level;
+    save_config(&config)?;
+    Ok(())
+}
+
+// ─── Reduced events ───────────────────────────────────────────────────────────
+
+/// How long stream events are collected before one `<event>-batch` is emitted.
+pub(crate) const EVENT_BATCH_MS: u64 = 1_000;
+
+/// For high-frequency streams. With reduced events on, payloads are collected
+/// and emitted as one `<event>-batch` array per `EVENT_BATCH_MS`.
+/// Every event is also kept for `resync` under `consumer`, what it is about,
+/// and held back while a reloaded frontend hasn't resynced.
+pub(crate) fn emit_stream<S: serde::Serialize>(app: &tauri::AppHandle, event: &'static str, consumer: &str, payload: &S) {
+    let Ok(value) = serde_json::to_value(payload) else { return };
+    if !app.state::<AppState>().stream_tails.record(event, consumer, &value) {
+        return;
+    }
+    if !load_config().reduced_events {
+        app.emit(event, value).ok();
+        return;
+    }
+    let first = {
+        let app_state = app.state::<AppState>();
+        let mut batches = app_state.event_batches.lock().unwrap();
+        let batch = batches.entry(event).or_default();
+        batch.push(value);
+        batch.len() == 1
+    };
+    // The first payload of a batch schedules its flush
+    if first {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(EVENT_BATCH_MS)).await;
+            let batch = app.state::<AppState>().event_batches.lock().unwrap().remove(event);
+            if let Some(batch) = batch {
+                app.emit(&format!("{}-batch", event), batch).ok();
+            }
+        });
+    }
+}
+
+/// Takes effect for the next event; a batch already collecting is still flushed.
+#[tauri::command]
+pub(crate) fn set_reduced_events(enabled: bool) -> Result<(), AppError> {
+    ensure_writable()?;
+    let mut config = load_config();
+    config.reduced_events = enabled;
+    save_config(&config)?;
+    Ok(())
+}
+
+// ─── Event catalog ────────────────────────────────────────────────────────────
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct EventInfo {
+    pub(crate) name: &'static str,
+    pub(crate) description: &'static str,
+    /// TypeScript notation
+    pub(crate) payload: &'static str,
+}
+
+const fn event(name: &'static str, description: &'static str, payload: &'static str) -> EventInfo {
+    EventInfo { name, description, payload }
+}
+
+/// Every event the backend emits. Keep in step with the `emit` calls.
+pub(crate) const EVENT_CATALOG: &[EventInfo] = &[
+    event("announcement", "Status change worded for a live region", "{ key: string, severity: \"info\" | \"warning\" | \"error\", text: string, ts: number }"),
+    event("gateway-starting", "A gateway launch has begun", "null"),
+    event("gateway-started", "The gateway is up and paired", "{ port: number, pid: number, token_present: boolean }"),
+    event("gateway-stopped", "The gateway was stopped", "string /* agent id */"),
+    event("gateway-restarting", "A restart has begun", "null"),
+    event("gateway-log", "A line of gateway output, unless emit_gateway_logs is off", "{ text: string, stderr: boolean }"),
+    event("gateway-log-batch", "gateway-log in reduced-events mode", "{ text: string, stderr: boolean }[]"),
+    event("gateway-warm", "The warm-up call finished", "{ ok: boolean, durationMs: number }"),
+    event("gateway-call-slow", "A call's output has been silent for a while", "{ idempotencyKey: string }"),
+    event("gateway-refused", "The model declined a request", "{ agentId: string, sessionKey: string, refusal: RefusalInfo }"),
+    event("channel-activity", "A channel message seen in gateway output", "ActivityEntry"),
+    event("channel-activity-batch", "channel-activity in reduced-events mode", "ActivityEntry[]"),
+    event("error-log-added", "A gateway, call or pairing error", "{ ts: number, seq: number, source: \"gateway\" | \"call\" | \"pair\", message: string }"),
+    event("agent-skills-changed", "An agent's skills folder changed outside the app", "{ agentId: string, skills: SkillInfo[] }"),
+    event("operation-progress", "A long operation advanced, or ended when status is not \"running\"", "OperationProgress"),
+    event("operation-progress-batch", "operation-progress in reduced-events mode", "OperationProgress[]"),
+    event("error-log-added-batch", "error-log-added in reduced-events mode", "ErrorLogEntry[]"),
+    event("call-expired", "A deferred call passed its deadline", "{ id: string, deadline: number }"),
+    event("deferred-call-dropped", "A deferred call was removed without running", "string /* call id */"),
+    event("deferred-call-requeued", "A deferred call was put back in the queue to run later", "string /* call id */"),
+    event("deferred-call-finished", "A deferred call ran", "{ id: string, ok: boolean, result: string }"),
+    event("workspace-changed", "A call changed files in the agent workspace", "{ sessionKey: string, diff: WorkspaceDiff }"),
+    event("history-pruned", "Old history records were removed", "number"),
+    event("trash-purged", "Expired trash entries were removed", "number"),
+    event("storage-health", "~/.openclaw became reachable or unreachable", "boolean"),
+    event("config-conflict", "A config file changed on disk under an edit", "ConfigConflict"),
+    event("config-file-changed", "A watched config file changed on disk", "string /* path */"),
+    event("crash-detected", "The previous run did not shut down cleanly", "CrashReport"),
+    event("safe-mode", "The app started in safe mode", "string /* reason */"),
+    event("power-suspend", "The machine is going to sleep", "null"),
+    event("power-resume", "The machine woke up", "null"),
+    event("clock-skew-detected", "The wall clock went backwards", "{ deltaMs: number, now: number }"),
+    event("shutdown-progress", "A quit phase started or ended", "{ phase: string, label: string, status: \"running\" | \"done\" | \"skipped\" }"),
+    event("data-format-mismatch", "~/.openclaw is in a shape this Clapp does not understand; writes to it are blocked", "DataFormat"),
+    event("openclaw-install-missing", "The selected OpenClaw installation is gone", "{ path: string }"),
+    event("openclaw-version-mismatch", "A plain npx call would run another OpenClaw version", "{ path: string, selectedVersion: string | null, npxVersion: string }"),
+    event("auth-expiring", "A subscription credential expires within the warning window", "{ agentId: string, expiresAt: number }"),
+    event("auth-expired", "A subscription credential has expired", "{ agentId: string, expiresAt: number }"),
+    event("auth-refresh-failed", "The auth refresh command failed or did not renew", "{ agentId: string, error: string }"),
+    event("shutdown-command-failed", "The configured shutdown command failed", "{ command: string, error: string }"),
+];
+
+#[tauri::command]
+pub(crate) fn get_event_catalog() -> Vec<EventInfo> {
+    EVENT_CATALOG.to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    /// Literal string arguments at `arg` (0-based) of every call to `function` under src.
+    fn literal_args(dir: &std::path::Path, function: &str, arg: usize, out: &mut HashSet<String>) {
+        for path in fs::read_dir(dir).unwrap().flatten().map(|e| e.path()) {
+            if path.is_dir() {
+                literal_args(&path, function, arg, out);
+                continue;
+            }
+            if path.extension().is_none_or(|e| e != "rs") {
+                continue;
+            }
+            let source = fs::read_to_string(&path).unwrap();
+            for (i, _) in source.match_indices(&format!("{}(", function)) {
+                let mut rest = &source[i + function.len() + 1..];
+                for _ in 0..arg {
+                    rest = rest.split_once(',').map_or("", |(_, r)| r);
+                }
+                // Names built at runtime (`format!`, variables) can't be checked here
+                let Some(literal) = rest.trim_start().strip_prefix('"') else { continue };
+                let name = literal.split('"').next().unwrap();
+                if !name.is_empty() && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '.') {
+                    out.insert(name.to_string());
+                }
+            }
+        }
+    }
+
+    fn src() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src")
+    }
+
+    #[test]
+    fn every_emitted_event_is_in_the_catalog() {
+        let catalog: HashSet<&str> = EVENT_CATALOG.iter().map(|e| e.name).collect();
+        let (mut emitted, mut streams) = (HashSet::new(), HashSet::new());
+        literal_args(&src(), ".emit", 0, &mut emitted);
+        literal_args(&src(), "emit_stream", 1, &mut streams);
+        assert!(emitted.contains("gateway-started") && streams.contains("gateway-log"), "the source scan found nothing");
+        // Streams go out as themselves, or batched in reduced-events mode
+        let mut missing: Vec<String> = emitted.iter().cloned()
+            .chain(streams.iter().flat_map(|s| [s.clone(), format!("{}-batch", s)]))
+            .filter(|name| !catalog.contains(name.as_str()))
+            .collect();
+        missing.sort();
+        assert!(missing.is_empty(), "emitted but not in EVENT_CATALOG: {:?}", missing);
+    }
+
+    #[test]
+    fn every_announcement_key_has_a_text() {
+        let mut keys = HashSet::new();
+        literal_args(&src(), "announce", 1, &mut keys);
+        assert!(keys.contains("gateway.unhealthy"));
+        let mut missing: Vec<&String> = keys.iter().filter(|k| !MESSAGE_CATALOG.iter().any(|(m, _)| m == k)).collect();
+        missing.sort();
+        assert!(missing.is_empty(), "announced but not in MESSAGE_CATALOG: {:?}", missing);
+    }
+}
@@ -0,0 +1,23 @@
+//! Append-only record of destructive actions.
+
+use crate::*;
+
+// ─── Audit log ────────────────────────────────────────────────────────────────
+
+pub(crate) fn audit_log_path() -> PathBuf {
+    clapp_dir().join("audit.log")
+}
+
+/// Appends one JSON line per destructive or restoring action.
+pub(crate) fn audit(action: &str, detail: serde_json::Value) {
+    use std::io::Write;
+    let line = serde_json::json!({ "ts": now_ms(), "seq": next_seq(), "action": action, "detail": detail });
+    let written = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(audit_log_path())
+        .and_then(|mut f| writeln!(f, "{}", line));
+    if let Err(e) = written {
+        eprintln!("[AUDIT ERR] {}", e);
+    }
+}
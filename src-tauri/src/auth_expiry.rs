@@ -0,0 +1,177 @@
+//! Expiring subscription credentials: warnings before expiry, optional refresh,
+//! and telling an expired credential apart from a wrong one.
+
+use crate::*;
+
+// ─── Credential expiry ────────────────────────────────────────────────────────
+
+/// Our clock may run ahead of the provider's; an expiry is only trusted past this margin.
+pub(crate) const AUTH_CLOCK_TOLERANCE_MS: u64 = 10 * 60 * 1000;
+pub(crate) const AUTH_CHECK_SECS: u64 = 15 * 60;
+
+/// Words providers and the gateway use when a call is rejected for its credentials.
+pub(crate) const AUTH_FAILURE_MARKERS: &[&str] = &[
+    "unauthorized", "authentication_error", "invalid x-api-key", "invalid api key",
+    "invalid_api_key", "token expired", "expired token", "oauth token has expired",
+];
+
+#[derive(serde::Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum AuthState {
+    /// No expiry is known, or it is comfortably far away
+    Ok,
+    AuthExpiring,
+    AuthExpired,
+}
+
+/// Expiry of the profile the agent last used successfully, in ms. Profiles that
+/// store seconds are converted.
+pub(crate) fn credential_expiry(agent_id: &str) -> Option<u64> {
+    let auth = load_auth_profiles(agent_id)?;
+    auth.last_good
+        .values()
+        .filter_map(|id| auth.profiles.get(id))
+        .filter_map(|p| p["expires"].as_u64().or(p["expiresAt"].as_u64()))
+        .map(|t| if t < 10_000_000_000 { t * 1000 } else { t })
+        .min()
+}
+
+pub(crate) fn auth_state_at(expires_at: Option<u64>, now: u64, warning_hours: u64) -> AuthState {
+    let Some(expires_at) = expires_at else { return AuthState::Ok };
+    let now = now.saturating_sub(AUTH_CLOCK_TOLERANCE_MS);
+    if now >= expires_at {
+        AuthState::AuthExpired
+    } else if now + warning_hours * 3_600_000 >= expires_at {
+        AuthState::AuthExpiring
+    } else {
+        AuthState::Ok
+    }
+}
+
+pub(crate) fn auth_state(agent_id: &str) -> AuthState {
+    auth_state_at(credential_expiry(agent_id), now_ms(), load_config().auth_expiry_warning_hours)
+}
+
+pub(crate) fn is_auth_failure(error: &str) -> bool {
+    let lower = error.to_lowercase();
+    AUTH_FAILURE_MARKERS.iter().any(|m| lower.contains(m))
+}
+
+/// Rewords a call error the provider raised over credentials: `AuthExpired`
+/// when the stored expiry has passed, `AuthInvalid` otherwise.
+pub(crate) async fn classify_auth_failure(app: &tauri::AppHandle, agent_id: &str, error: String) -> String {
+    if !is_auth_failure(&error) {
+        return error;
+    }
+    let id = agent_id.to_string();
+    let expired = run_storage_io(app, move || auth_state(&id) == AuthState::AuthExpired).await.unwrap_or(false)
+        || error.to_lowercase().contains("expired");
+    if expired {
+        AppError::AuthExpired(agent_id.to_string()).to_string()
+    } else {
+        AppError::AuthInvalid(error).to_string()
+    }
+}
+
+/// Stores the expiry on the agent's current profile, for after a token was set up
+/// outside the app. `None` removes it.
+pub(crate) fn write_profile_expiry(agent_id: &str, expires_at: Option<u64>) -> Result<(), AppError> {
+    let path = agent_dir(agent_id).join("auth-profiles.json");
+    let (content, base) = read_guarded(&path)?;
+    let mut v: serde_json::Value = serde_json::from_str(&content)?;
+    let ids: Vec<String> = v["lastGood"].as_object()
+        .map(|o| o.values().filter_map(|id| id.as_str().map(String::from)).collect())
+        .unwrap_or_default();
+    if ids.is_empty() {
+        return Err(AppError::NotFound(format!("auth profile of agent {}", agent_id)));
+    }
+    for id in ids {
+        let Some(profile) = v["profiles"].get_mut(&id).and_then(|p| p.as_object_mut()) else { continue };
+        match expires_at {
+            Some(t) => profile.insert("expires".into(), t.into()),
+            None => profile.remove("expires"),
+        };
+    }
+    write_guarded(&path, &base, &serde_json::to_string_pretty(&v)?)
+}
+
+/// Runs `auth_refresh_command` with `{agent}` replaced. Returns whether the
+/// credential is out of the warning window afterwards.
+pub(crate) async fn try_refresh_auth(app: &tauri::AppHandle, agent_id: &str) -> bool {
+    let Some(cmd) = load_config().auth_refresh_command.map(|c| c.trim().to_string()).filter(|c| !c.is_empty()) else {
+        return false;
+    };
+    let error = match shell_output(app, &cmd.replace("{agent}", agent_id)).await {
+        Ok(out) if out.status.success() => None,
+        Ok(out) => Some(String::from_utf8_lossy(&out.stderr).trim().to_string()),
+        Err(e) => Some(e),
+    };
+    let id = agent_id.to_string();
+    let refreshed = error.is_none() && run_storage_io(app, move || auth_state(&id) == AuthState::Ok).await.unwrap_or(false);
+    if refreshed {
+        audit("auth_refreshed", serde_json::json!({ "agentId": agent_id }));
+    } else {
+        let error = error.unwrap_or_else(|| "the credential still expires soon".into());
+        eprintln!("[AUTH ERR] refresh of {} failed: {}", agent_id, error);
+        app.emit("auth-refresh-failed", serde_json::json!({ "agentId": agent_id, "error": error })).ok();
+    }
+    refreshed
+}
+
+/// Checks every agent's credential expiry. Expiring and expired are each warned
+/// about once per expiry, after an automatic refresh was tried.
+pub(crate) fn spawn_auth_expiry_monitor(app: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        while !is_shutting_down(&app) {
+            let states = run_storage_io(&app, || {
+                get_all_agent_ids().into_iter()
+                    .filter_map(|id| {
+                        let expires_at = credential_expiry(&id)?;
+                        let state = auth_state_at(Some(expires_at), now_ms(), load_config().auth_expiry_warning_hours);
+                        (state != AuthState::Ok).then_some((id, expires_at, state))
+                    })
+                    .collect::<Vec<_>>()
+            }).await.unwrap_or_default();
+
+            for (agent_id, expires_at, state) in states {
+                let warned = app.state::<AppState>().auth_warned.lock().unwrap().get(&agent_id) == Some(&(expires_at, state));
+                if warned || try_refresh_auth(&app, &agent_id).await {
+                    continue;
+                }
+                app.state::<AppState>().auth_warned.lock().unwrap().insert(agent_id.clone(), (expires_at, state));
+                let event = if state == AuthState::AuthExpired { "auth-expired" } else { "auth-expiring" };
+                app.emit(event, serde_json::json!({ "agentId": agent_id, "expiresAt": expires_at })).ok();
+                let (key, severity) = match state {
+                    AuthState::AuthExpired => ("auth.expired", AnnouncementSeverity::Error),
+                    _ => ("auth.expiring", AnnouncementSeverity::Warning),
+                };
+                announce(&app, key, severity, &[("agent", &agent_id)]);
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(AUTH_CHECK_SECS)).await;
+        }
+    });
+}
+
+#[tauri::command]
+pub(crate) async fn set_auth_expiry(app: tauri::AppHandle, agent_id: String, expires_at: Option<u64>) -> Result<(), AppError> {
+    ensure_writable()?;
+    validate_agent_id(&agent_id)?;
+    let id = agent_id.clone();
+    run_storage_io(&app, move || write_profile_expiry(&id, expires_at)).await??;
+    app.state::<AppState>().auth_warned.lock().unwrap().remove(&agent_id);
+    Ok(())
+}
+
+/// `refresh_command` runs non-interactively before a warning; `{agent}` is the agent id.
+#[tauri::command]
+pub(crate) fn set_auth_expiry_options(warning_hours: u64, refresh_command: Option<String>) -> Result<(), AppError> {
+    ensure_writable()?;
+    if warning_hours == 0 || warning_hours > 24 * 30 {
+        return Err(AppError::InvalidInput("Warning time must be between 1 hour and 30 days".into()));
+    }
+    let mut config = load_config();
+    config.auth_expiry_warning_hours = warning_hours;
+    config.auth_refresh_command = refresh_command.map(|c| c.trim().to_string()).filter(|c| !c.is_empty());
+    save_config(&config)?;
+    Ok(())
+}
@@ -0,0 +1,333 @@
+//! Cancellation tokens for calls, terminal jobs and operations. Work checks its
+//! token at defined points and cleans up after itself; nothing is killed from outside.
+
+use crate::*;
+
+// ─── Cancellation ─────────────────────────────────────────────────────────────
+
+#[derive(Default)]
+pub(crate) struct TokenInner {
+    cancelled: std::sync::atomic::AtomicBool,
+    notify: tokio::sync::Notify,
+    children: Mutex<Vec<std::sync::Weak<TokenInner>>>,
+}
+
+/// A shared stop request. Clones see the same state; children are cancelled
+/// with their parent but not the other way round.
+#[derive(Clone, Default)]
+pub(crate) struct CancelToken(std::sync::Arc<TokenInner>);
+
+impl CancelToken {
+    pub(crate) fn child(&self) -> CancelToken {
+        let child = CancelToken::default();
+        let mut children = self.0.children.lock().unwrap();
+        children.retain(|c| c.strong_count() > 0);
+        children.push(std::sync::Arc::downgrade(&child.0));
+        drop(children);
+        if self.is_cancelled() {
+            child.cancel();
+        }
+        child
+    }
+
+    pub(crate) fn cancel(&self) {
+        if self.0.cancelled.swap(true, std::sync::atomic::Ordering::SeqCst) {
+            return;
+        }
+        self.0.notify.notify_waiters();
+        let children = std::mem::take(&mut *self.0.children.lock().unwrap());
+        for child in children.iter().filter_map(std::sync::Weak::upgrade) {
+            CancelToken(child).cancel();
+        }
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(std::sync::atomic::Ordering::SeqCst)
+    }
+
+    /// Resolves once the token is cancelled; immediately if it already is.
+    pub(crate) async fn cancelled(&self) {
+        // Created before the check, so a cancel in between still wakes it
+        let notified = self.0.notify.notified();
+        if self.is_cancelled() {
+            return;
+        }
+        notified.await;
+    }
+
+    /// For check points: fails with `Cancelled` once cancellation was asked for.
+    pub(crate) fn check(&self, what: &str) -> Result<(), AppError> {
+        if self.is_cancelled() {
+            return Err(AppError::Cancelled(what.into()));
+        }
+        Ok(())
+    }
+}
+
+/// Parent of every token; shutdown cancels it.
+pub(crate) static ROOT_CANCEL: std::sync::LazyLock<CancelToken> = std::sync::LazyLock::new(CancelToken::default);
+
+/// Tokens of running calls and jobs, by kind and id.
+pub(crate) static RUNNING: std::sync::LazyLock<Mutex<HashMap<(&'static str, String), CancelToken>>> =
+    std::sync::LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// A token findable by `cancel_registered` until dropped.
+pub(crate) struct RegisteredToken {
+    key: (&'static str, String),
+    pub(crate) token: CancelToken,
+}
+
+impl Drop for RegisteredToken {
+    fn drop(&mut self) {
+        RUNNING.lock().unwrap().remove(&self.key);
+    }
+}
+
+/// Fails with `AlreadyExists` while another call or job holds the same id, so
+/// a reused caller-supplied id can't take over the first one's token.
+pub(crate) fn register_cancel(kind: &'static str, id: &str) -> Result<RegisteredToken, AppError> {
+    let key = (kind, id.to_string());
+    let mut running = RUNNING.lock().unwrap();
+    if running.contains_key(&key) {
+        return Err(AppError::AlreadyExists(format!("running {} {}", kind, id)));
+    }
+    let token = ROOT_CANCEL.child();
+    running.insert(key.clone(), token.clone());
+    Ok(RegisteredToken { key, token })
+}
+
+pub(crate) fn cancel_registered(kind: &'static str, id: &str) -> Result<(), AppError> {
+    let token = RUNNING.lock().unwrap().get(&(kind, id.to_string())).cloned()
+        .ok_or_else(|| AppError::NotFound(format!("running {} {}", kind, id)))?;
+    token.cancel();
+    Ok(())
+}
+
+tokio::task_local! {
+    /// The token of the gateway call running in this task.
+    pub(crate) static CALL_CANCEL: CancelToken;
+}
+
+pub(crate) fn current_call_token() -> Option<CancelToken> {
+    CALL_CANCEL.try_with(CancelToken::clone).ok()
+}
+
+/// Runs `work` unless the token is cancelled first; `None` means it was.
+pub(crate) async fn until_cancelled<F: std::future::Future>(token: &CancelToken, work: F) -> Option<F::Output> {
+    let cancelled = token.cancelled();
+    futures::pin_mut!(work, cancelled);
+    match futures::future::select(work, cancelled).await {
+        futures::future::Either::Left((out, _)) => Some(out),
+        futures::future::Either::Right(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queued(id: &str) -> PendingCall {
+        PendingCall {
+            id: id.into(),
+            agent_id: "main".into(),
+            message: "hello".into(),
+            session_key: "s".into(),
+            priority: CallPriority::Background,
+            enqueued_at: 1,
+            pinned: false,
+            deadline: 0,
+        }
+    }
+
+    /// A call that never finishes on its own, like a gateway that is still thinking.
+    async fn stalled() {
+        futures::future::pending::<()>().await
+    }
+
+    #[test]
+    fn cancelled_before_start_fails_typed_in_preparation() {
+        let call = register_cancel("call", "stage-preparation").unwrap();
+        let timer = CallTimer::start("main", "s", None);
+        call.token.cancel();
+        let err = call.token.check("the call was stopped before it started").unwrap_err();
+        assert!(matches!(err, AppError::Cancelled(_)));
+        timer.cancelled();
+        let timing = timer.complete(false);
+        assert!(timing.cancelled && !timing.ok);
+        assert_eq!(timing.failed_in.as_deref(), Some("preparation"));
+    }
+
+    #[test]
+    fn cancelled_while_spawning_or_waiting_stops_the_wait() {
+        let call = register_cancel("call", "stage-gateway").unwrap();
+        let timer = CallTimer::start("main", "s", None);
+        timer.prepared();
+        timer.begin_attempt();
+        call.token.cancel();
+        assert!(futures::executor::block_on(until_cancelled(&call.token, stalled())).is_none());
+        timer.end_attempt(false);
+        timer.cancelled();
+        let timing = timer.complete(false);
+        assert_eq!(timing.attempts.len(), 1);
+        assert_eq!(timing.failed_in.as_deref(), Some("spawn"));
+
+        let timer = CallTimer::start("main", "s", None);
+        timer.begin_attempt();
+        timer.spawned();
+        timer.end_attempt(false);
+        assert_eq!(timer.complete(false).failed_in.as_deref(), Some("gateway"));
+    }
+
+    #[test]
+    fn cancelled_mid_reply_is_reported_as_streaming() {
+        let call = register_cancel("call", "stage-streaming").unwrap();
+        let timer = CallTimer::start("main", "s", None);
+        timer.begin_attempt();
+        timer.spawned();
+        timer.first_byte();
+        cancel_registered("call", "stage-streaming").unwrap();
+        assert!(futures::executor::block_on(until_cancelled(&call.token, stalled())).is_none());
+        timer.end_attempt(false);
+        timer.cancelled();
+        let timing = timer.complete(false);
+        assert!(timing.cancelled);
+        assert_eq!(timing.failed_in.as_deref(), Some("streaming"));
+    }
+
+    #[test]
+    fn cancelled_call_counts_once_and_leaves_nothing_in_flight() {
+        let calls = CallsInFlight::default();
+        let call = register_cancel("call", "stage-in-flight").unwrap();
+        let in_flight = calls.start("writer");
+        let other = calls.start("reviewer");
+        assert_eq!(calls.by_agent(), HashMap::from([("writer".to_string(), 1), ("reviewer".to_string(), 1)]));
+        assert_eq!(calls.total(), 2);
+        call.token.cancel();
+        let out = futures::executor::block_on(async move {
+            // Held for the whole call, the way `gateway_call` and the drain hold it
+            let _in_flight = in_flight;
+            until_cancelled(&call.token, stalled()).await
+        });
+        assert!(out.is_none());
+        assert!(!calls.contains("writer"));
+        drop(other);
+        assert!(calls.by_agent().is_empty());
+        assert_eq!(calls.total(), 0);
+    }
+
+    #[test]
+    fn finished_work_is_not_cut_short() {
+        let call = register_cancel("call", "stage-finished").unwrap();
+        let out = futures::executor::block_on(until_cancelled(&call.token, async { 7 }));
+        assert_eq!(out, Some(7));
+        drop(call);
+        // Nothing left to cancel once the call is over
+        assert!(matches!(cancel_registered("call", "stage-finished"), Err(AppError::NotFound(_))));
+    }
+
+    #[test]
+    fn duplicate_ids_are_rejected_until_the_first_call_ends() {
+        let first = register_cancel("call", "stage-duplicate").unwrap();
+        assert!(matches!(register_cancel("call", "stage-duplicate"), Err(AppError::AlreadyExists(_))));
+        // The same id for another kind is a different entry
+        let job = register_cancel("job", "stage-duplicate").unwrap();
+        cancel_registered("call", "stage-duplicate").unwrap();
+        assert!(first.token.is_cancelled());
+        assert!(!job.token.is_cancelled());
+        drop(first);
+        assert!(register_cancel("call", "stage-duplicate").is_ok());
+    }
+
+    #[test]
+    fn shutdown_reaches_every_registered_call() {
+        let parent = CancelToken::default();
+        let child = parent.child();
+        let grandchild = child.child();
+        grandchild.cancel();
+        assert!(!child.is_cancelled() && !parent.is_cancelled());
+        let other = parent.child();
+        parent.cancel();
+        assert!(child.is_cancelled() && other.is_cancelled());
+        // Created after the cancel: already stopped
+        assert!(parent.child().is_cancelled());
+    }
+
+    #[test]
+    fn deferred_call_cancelled_mid_drain_stays_queued() {
+        let calls = vec![queued("a"), queued("b")];
+        let mut running = std::collections::HashSet::new();
+        let first = next_deferred_call(&calls, &running).unwrap();
+        assert_eq!(first.id, "a");
+        running.insert(first.id.clone());
+        // A second drain skips the call the first one is running
+        assert_eq!(next_deferred_call(&calls, &running).unwrap().id, "b");
+        // Cancelled: the mark is dropped and nothing was removed
+        running.remove(&first.id);
+        assert_eq!(calls.len(), 2);
+        assert_eq!(next_deferred_call(&calls, &running).unwrap().id, "a");
+    }
+
+    #[test]
+    fn cancelled_call_leaves_the_question_and_a_marker_in_history() {
+        let [user, marker] = cancelled_records("s", "hello", 42);
+        assert_eq!((user.role.as_str(), user.text.as_str(), user.ts), ("user", "hello", 42));
+        assert_eq!(marker.role, "system");
+        assert_eq!(marker.session_key, "s");
+        // Both halves share one sequence number
+        assert_eq!(user.id.trim_end_matches("-u"), marker.id.trim_end_matches("-c"));
+        let [again, _] = cancelled_records("s", "hello", 42);
+        assert_ne!(user.id, again.id);
+    }
+
+    /// The deferred drain's path through a fixture gateway, cancelled mid-call the way
+    /// `cancel_call` does it: the call stays queued for the next drain, history gets
+    /// nothing, and the timings hold one cancelled call.
+    #[test]
+    fn a_deferred_call_cancelled_mid_reply_stays_queued_unrecorded_and_timed_once() {
+        let state = AppState::new(None);
+        let (id, agent) = ("e2e-cancel", "e2e-cancel-agent");
+        let now = now_ms();
+        state.pending_calls.lock().unwrap().push(PendingCall {
+            agent_id: agent.into(),
+            enqueued_at: now,
+            deadline: now + 60_000,
+            ..queued(id)
+        });
+        state.pending_calls.lock().unwrap().retain(|c| c.id == id);
+
+        let (call, expired) = claim_deferred_call(&state, now_ms()).unwrap();
+        assert!(!expired && call.id == id);
+        let registered = register_cancel("call", id).unwrap();
+        let timer = CallTimer::start(agent, &call.session_key, Some(0));
+        timer.prepared();
+        timer.begin_attempt();
+
+        let args = openclaw_args(&["gateway", "call", "agent", "--params", "{}"]);
+        let rt = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+        let result = rt.block_on(async {
+            let reply = CALL_TIMER.scope(timer.clone(), replay_call_fixture(&registered.token, &args, id));
+            let cancel = async {
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                // Running, so it can't be dropped from the queue; it is stopped instead
+                assert!(!drop_queued_call(&state, id));
+                cancel_registered("call", id).unwrap();
+            };
+            futures::join!(reply, cancel).0
+        });
+        assert!(matches!(result, Err(AppError::Cancelled(_))), "{:?}", result.map(|_| ()));
+
+        // What the drain does with a cancelled call
+        timer.end_attempt(false);
+        timer.cancelled();
+        timer.finish_in(&state, false);
+        state.deferred_running.lock().unwrap().remove(id);
+        drop(registered);
+
+        assert_eq!(claim_deferred_call(&state, now_ms()).map(|(c, _)| c.id).as_deref(), Some(id));
+        assert!(read_history(agent).is_empty());
+        let timings: Vec<CallTiming> = state.call_timings.lock().unwrap().iter().filter(|t| t.agent_id == agent).cloned().collect();
+        assert_eq!(timings.len(), 1);
+        assert!(timings[0].cancelled && !timings[0].ok);
+        assert_eq!(timings[0].failed_in.as_deref(), Some("gateway"));
+    }
+}
@@ -0,0 +1,453 @@
+//! Checkpoints of a session's workspace and history to return to after risky work.
+
+use crate::*;
+
+// ─── Checkpoints ──────────────────────────────────────────────────────────────
+
+/// A workspace bigger than this is refused rather than partially saved.
+pub(crate) const CHECKPOINT_MAX_BYTES: u64 = 256 * 1024 * 1024;
+/// Oldest checkpoints are pruned once all of them together pass this.
+pub(crate) const CHECKPOINTS_MAX_TOTAL_BYTES: u64 = 1024 * 1024 * 1024;
+/// Pre-checkpoint conversation sent when seeding the session after a restore.
+pub(crate) const CHECKPOINT_SEED_MAX_CHARS: usize = 20_000;
+/// OpenClaw's chat command for starting a new session under the same key.
+pub(crate) const CHECKPOINT_RESET_COMMAND: &str = "/new";
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CheckpointFile {
+    /// Relative to the workspace root, with `/` separators
+    pub(crate) path: String,
+    pub(crate) size: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) hash: Option<u64>,
+}
+
+/// Stored as checkpoints/{id}/manifest.json, with the workspace copy in files/
+/// and the gateway transcript, if found, in gateway-session.jsonl.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CheckpointManifest {
+    pub(crate) id: String,
+    pub(crate) label: String,
+    pub(crate) session_key: String,
+    pub(crate) agent_id: String,
+    pub(crate) workspace: String,
+    pub(crate) created_at: u64,
+    /// Last history record of the session when the checkpoint was taken
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) history_marker: Option<String>,
+    pub(crate) files: Vec<CheckpointFile>,
+    pub(crate) size_bytes: u64,
+    pub(crate) gateway_session: bool,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CheckpointSummary {
+    pub(crate) id: String,
+    pub(crate) label: String,
+    pub(crate) session_key: String,
+    pub(crate) agent_id: String,
+    pub(crate) created_at: u64,
+    pub(crate) file_count: usize,
+    pub(crate) size_bytes: u64,
+}
+
+#[derive(serde::Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum RestoreAction {
+    /// Changed or deleted since the checkpoint; written back
+    Restore,
+    /// Created since the checkpoint; removed
+    Delete,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct FileRestoreResult {
+    pub(crate) path: String,
+    pub(crate) action: RestoreAction,
+    /// Unified diff from the current file to the checkpoint's, for text files in a dry run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) diff: Option<String>,
+    pub(crate) ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) error: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RestoreReport {
+    pub(crate) checkpoint_id: String,
+    pub(crate) dry_run: bool,
+    pub(crate) files: Vec<FileRestoreResult>,
+    /// History records after the checkpoint, moved to the trash
+    pub(crate) history_removed: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) history_trash_id: Option<String>,
+}
+
+pub(crate) fn checkpoints_dir() -> PathBuf {
+    let p = clapp_dir().join("checkpoints");
+    fs::create_dir_all(&p).ok();
+    p
+}
+
+/// Joins a stored relative path onto the workspace root, refusing anything that
+/// could leave it: absolute paths, drive prefixes and `..`.
+pub(crate) fn workspace_file(root: &std::path::Path, rel: &str) -> Option<PathBuf> {
+    let rel = std::path::Path::new(rel);
+    rel.components()
+        .all(|c| matches!(c, std::path::Component::Normal(_)))
+        .then(|| root.join(rel))
+}
+
+/// `workspace_file`, also checked against the disk: the nearest existing part of
+/// the path must resolve inside the canonical root, and the file itself must not
+/// be a symlink, so a restore can't be redirected outside the workspace.
+pub(crate) fn contained_workspace_file(root: &std::path::Path, rel: &str) -> Result<PathBuf, String> {
+    let target = workspace_file(root, rel).ok_or("outside the workspace")?;
+    let canonical_root = root.canonicalize().map_err(|e| format!("workspace root: {}", e))?;
+    if fs::symlink_metadata(&target).is_ok_and(|m| m.file_type().is_symlink()) {
+        return Err("is a symlink".into());
+    }
+    let existing = target.ancestors()
+        .find(|p| fs::symlink_metadata(p).is_ok())
+        .ok_or("outside the workspace")?;
+    let resolved = existing.canonicalize().map_err(|e| e.to_string())?;
+    if !resolved.starts_with(&canonical_root) {
+        return Err("outside the workspace".into());
+    }
+    Ok(target)
+}
+
+pub(crate) fn relative_path(root: &std::path::Path, path: &std::path::Path) -> Option<String> {
+    let rel = path.strip_prefix(root).ok()?;
+    Some(rel.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/"))
+}
+
+pub(crate) fn read_checkpoint_manifest(id: &str) -> Result<CheckpointManifest, AppError> {
+    validate_agent_id(id).map_err(|_| AppError::InvalidInput(format!("Bad checkpoint id '{}'", id)))?;
+    let content = fs::read_to_string(checkpoints_dir().join(id).join("manifest.json"))
+        .map_err(|_| AppError::NotFound(format!("checkpoint {}", id)))?;
+    let manifest: CheckpointManifest = serde_json::from_str(&content)?;
+    if manifest.id != id {
+        return Err(AppError::InvalidInput(format!("Checkpoint {} has a mismatched manifest", id)));
+    }
+    Ok(manifest)
+}
+
+pub(crate) fn all_checkpoints() -> Vec<CheckpointManifest> {
+    let mut all: Vec<CheckpointManifest> = fs::read_dir(checkpoints_dir())
+        .map(|dirs| dirs.flatten().filter_map(|d| read_checkpoint_manifest(&d.file_name().to_string_lossy()).ok()).collect())
+        .unwrap_or_default();
+    all.sort_by_key(|m| std::cmp::Reverse(m.created_at));
+    all
+}
+
+/// Removes the oldest checkpoints until the rest fit in `CHECKPOINTS_MAX_TOTAL_BYTES`.
+pub(crate) fn prune_checkpoints_to_cap() -> usize {
+    let mut total = 0u64;
+    let mut pruned = 0;
+    for m in all_checkpoints() {
+        total += m.size_bytes;
+        if total > CHECKPOINTS_MAX_TOTAL_BYTES && fs::remove_dir_all(checkpoints_dir().join(&m.id)).is_ok() {
+            pruned += 1;
+        }
+    }
+    pruned
+}
+
+pub(crate) fn write_checkpoint(session_key: &str, label: &str) -> Result<CheckpointManifest, AppError> {
+    let agent_id = find_session_owner(session_key)
+        .ok_or_else(|| AppError::NotFound(format!("session {}", session_key)))?;
+    let config = read_agent_config(&agent_id);
+    let root = agent_workspace(&config);
+    let snapshot = scan_workspace(root.clone(), false);
+    if snapshot.truncated {
+        return Err(AppError::InvalidInput(format!(
+            "The workspace has more than {} files; a checkpoint would be incomplete", WORKSPACE_MAX_FILES
+        )));
+    }
+    let size_bytes: u64 = snapshot.files.values().map(|f| f.size).sum();
+    if size_bytes > CHECKPOINT_MAX_BYTES {
+        return Err(AppError::InvalidInput(format!(
+            "The workspace is {} MB, over the {} MB checkpoint limit", size_bytes >> 20, CHECKPOINT_MAX_BYTES >> 20
+        )));
+    }
+
+    let id = format!("cp-{:x}-{}", now_ms(), next_seq());
+    let dir = checkpoints_dir().join(&id);
+    let result = (|| -> Result<CheckpointManifest, AppError> {
+        let mut files = Vec::new();
+        for (path, state) in &snapshot.files {
+            let Some(rel) = relative_path(&root, path) else { continue };
+            let dest = dir.join("files").join(&rel);
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(path, &dest)?;
+            files.push(CheckpointFile { path: rel, size: state.size, hash: state.hash });
+        }
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+
+        // Kept for reference; a restore starts a fresh gateway session instead of replaying it
+        let transcript = gateway_session_id(&agent_id, session_key)
+            .and_then(|sid| find_gateway_transcript(&sid).ok())
+            .map(|(_, p)| p);
+        let gateway_session = match transcript {
+            Some(p) => fs::copy(p, dir.join("gateway-session.jsonl")).is_ok(),
+            None => false,
+        };
+
+        let history_marker = read_history(&agent_id).iter()
+            .rev()
+            .find(|r| r.session_key == session_key)
+            .map(|r| r.id.clone());
+        let manifest = CheckpointManifest {
+            id: id.clone(),
+            label: label.to_string(),
+            session_key: session_key.to_string(),
+            agent_id: agent_id.clone(),
+            workspace: root.to_string_lossy().into_owned(),
+            created_at: now_ms(),
+            history_marker,
+            files,
+            size_bytes,
+            gateway_session,
+        };
+        fs::write(dir.join("manifest.json"), serde_json::to_string_pretty(&manifest)?)?;
+        Ok(manifest)
+    })();
+    if result.is_err() {
+        fs::remove_dir_all(&dir).ok();
+    }
+    result
+}
+
+/// What a restore would do, with diffs for a dry run.
+pub(crate) fn plan_restore(manifest: &CheckpointManifest, with_diffs: bool) -> Result<Vec<FileRestoreResult>, AppError> {
+    let root = PathBuf::from(&manifest.workspace);
+    let saved = checkpoints_dir().join(&manifest.id).join("files");
+    let current = scan_workspace(root.clone(), false);
+    let mut plan = Vec::new();
+
+    for file in &manifest.files {
+        let Some(target) = workspace_file(&root, &file.path) else {
+            return Err(AppError::InvalidInput(format!("Checkpoint {} has a path outside the workspace: {}", manifest.id, file.path)));
+        };
+        let unchanged = current.files.get(&target)
+            .is_some_and(|now| now.size == file.size && file.hash.is_some() && now.hash == file.hash);
+        if unchanged {
+            continue;
+        }
+        // Only checked when the root exists; a missing workspace is recreated by the restore
+        let blocked = root.exists().then(|| contained_workspace_file(&root, &file.path).err()).flatten();
+        let diff = with_diffs.then(|| {
+            let old = fs::read_to_string(&target).unwrap_or_default();
+            let new = fs::read_to_string(saved.join(&file.path)).ok()?;
+            Some(similar::TextDiff::from_lines(old.as_str(), new.as_str())
+                .unified_diff()
+                .header(&format!("a/{}", file.path), &format!("b/{}", file.path))
+                .to_string())
+        }).flatten();
+        plan.push(FileRestoreResult {
+            path: file.path.clone(),
+            action: RestoreAction::Restore,
+            diff,
+            ok: blocked.is_none(),
+            error: blocked,
+        });
+    }
+
+    let kept: std::collections::HashSet<&str> = manifest.files.iter().map(|f| f.path.as_str()).collect();
+    for path in current.files.keys() {
+        let Some(rel) = relative_path(&root, path) else { continue };
+        if !kept.contains(rel.as_str()) {
+            plan.push(FileRestoreResult { path: rel, action: RestoreAction::Delete, diff: None, ok: true, error: None });
+        }
+    }
+    plan.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(plan)
+}
+
+pub(crate) fn apply_restore_plan(manifest: &CheckpointManifest, plan: &mut [FileRestoreResult]) {
+    let root = PathBuf::from(&manifest.workspace);
+    let saved = checkpoints_dir().join(&manifest.id).join("files");
+    if let Err(e) = fs::create_dir_all(&root) {
+        for item in plan.iter_mut() {
+            item.ok = false;
+            item.error = Some(format!("workspace root: {}", e));
+        }
+        return;
+    }
+    for item in plan.iter_mut() {
+        // Checked again right before each write, not just when planning
+        let target = match contained_workspace_file(&root, &item.path) {
+            Ok(target) => target,
+            Err(e) => {
+                item.ok = false;
+                item.error = Some(e);
+                continue;
+            }
+        };
+        let result = match item.action {
+            RestoreAction::Restore => target.parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| fs::copy(saved.join(&item.path), &target).map(|_| ())),
+            RestoreAction::Delete => fs::remove_file(&target),
+        };
+        item.ok = result.is_ok();
+        item.error = result.err().map(|e| e.to_string());
+    }
+}
+
+/// Moves the session's history after the marker to the trash. Returns how many
+/// records were removed and the trash entry holding them.
+pub(crate) fn roll_back_history(manifest: &CheckpointManifest) -> Result<(usize, Option<String>), AppError> {
+    let _lock = lock_history(&manifest.agent_id);
+    let records = read_history(&manifest.agent_id);
+    let cut = match &manifest.history_marker {
+        Some(marker) => records.iter().position(|r| &r.id == marker).map_or(records.len(), |i| i + 1),
+        None => 0,
+    };
+    let (mut kept, mut removed) = (records[..cut].to_vec(), Vec::new());
+    for r in records.into_iter().skip(cut) {
+        if r.session_key == manifest.session_key { removed.push(r) } else { kept.push(r) }
+    }
+    if removed.is_empty() {
+        return Ok((0, None));
+    }
+    let payload: String = removed.iter().map(|r| serde_json::to_string(r).unwrap() + "\n").collect();
+    let entry = move_to_trash(
+        TrashKind::History, &manifest.agent_id, Some(&manifest.session_key), &history_path(&manifest.agent_id), Some(&payload),
+    )?;
+    write_history(&manifest.agent_id, &kept)?;
+    Ok((removed.len(), Some(entry.id)))
+}
+
+/// Tells the agent, in the fresh gateway session, that everything after the
+/// checkpoint was undone, with the conversation up to it.
+pub(crate) fn checkpoint_seed_message(manifest: &CheckpointManifest, transcript: &str) -> String {
+    let skip = transcript.chars().count().saturating_sub(CHECKPOINT_SEED_MAX_CHARS);
+    let transcript: String = transcript.chars().skip(skip).collect();
+    format!(
+        "[Checkpoint restored: the workspace files and this conversation were rolled back to the checkpoint \"{}\". \
+Disregard everything after it, including any changes you made since. The conversation up to the checkpoint was:]\n\n{}\n\n\
+[Reply only with \"OK\".]",
+        manifest.label, transcript
+    )
+}
+
+#[tauri::command]
+pub(crate) async fn create_checkpoint(app: tauri::AppHandle, session_key: String, label: String) -> Result<CheckpointSummary, AppError> {
+    ensure_writable()?;
+    let label = label.trim().to_string();
+    // Copies up to CHECKPOINT_MAX_BYTES, so no storage deadline applies
+    let manifest = run_storage_operation(&app, "checkpoint", move |_| {
+        let manifest = write_checkpoint(&session_key, &label)?;
+        prune_checkpoints_to_cap();
+        Ok(manifest)
+    }).await?;
+    audit("checkpoint_created", serde_json::json!({ "id": manifest.id, "sessionKey": manifest.session_key }));
+    Ok(checkpoint_summary(&manifest))
+}
+
+pub(crate) fn checkpoint_summary(m: &CheckpointManifest) -> CheckpointSummary {
+    CheckpointSummary {
+        id: m.id.clone(),
+        label: m.label.clone(),
+        session_key: m.session_key.clone(),
+        agent_id: m.agent_id.clone(),
+        created_at: m.created_at,
+        file_count: m.files.len(),
+        size_bytes: m.size_bytes,
+    }
+}
+
+/// Newest first.
+#[tauri::command]
+pub(crate) async fn list_checkpoints(app: tauri::AppHandle, session_key: String) -> Result<Vec<CheckpointSummary>, AppError> {
+    run_storage_io(&app, move || {
+        all_checkpoints().iter().filter(|m| m.session_key == session_key).map(checkpoint_summary).collect()
+    }).await
+}
+
+/// Returns the workspace to the checkpoint and rolls the session's history back to it.
+/// With `dry_run` only the planned file changes are returned, with diffs.
+#[tauri::command]
+pub(crate) async fn restore_checkpoint(app: tauri::AppHandle, checkpoint_id: String, dry_run: bool) -> Result<RestoreReport, AppError> {
+    if !dry_run {
+        ensure_writable()?;
+    }
+    let calls = || app.state::<AppState>().calls_in_flight.total();
+    if !dry_run && calls() > 0 {
+        return Err(AppError::InvalidInput("Wait for the running call to finish before restoring".into()));
+    }
+    let id = checkpoint_id.clone();
+    let (manifest, mut plan) = run_storage_operation(&app, "checkpoint-plan", move |_| {
+        let manifest = read_checkpoint_manifest(&id)?;
+        let plan = plan_restore(&manifest, dry_run)?;
+        Ok((manifest, plan))
+    }).await?;
+    if dry_run {
+        return Ok(RestoreReport { checkpoint_id, dry_run, files: plan, history_removed: 0, history_trash_id: None });
+    }
+    // Checked again in case a call started while the plan was being made
+    if calls() > 0 {
+        return Err(AppError::InvalidInput("Wait for the running call to finish before restoring".into()));
+    }
+
+    let restoring = manifest.clone();
+    let (plan, history_removed, history_trash_id, transcript) = run_storage_operation(&app, "checkpoint-restore", move |_| {
+        apply_restore_plan(&restoring, &mut plan);
+        let (removed, trash_id) = roll_back_history(&restoring)?;
+        let transcript = agents::distill::session_transcript(&restoring.agent_id, &restoring.session_key);
+        Ok((plan, removed, trash_id, transcript))
+    }).await?;
+    audit("checkpoint_restored", serde_json::json!({
+        "id": manifest.id,
+        "failedFiles": plan.iter().filter(|f| !f.ok).count(),
+        "historyRemoved": history_removed,
+    }));
+
+    // `/new` has the gateway start a fresh session under the same key, so none of
+    // the undone work stays in its context; the seed then carries what came before
+    let seed = checkpoint_seed_message(&manifest, &transcript);
+    let in_flight = InFlightCall::start(&app, &manifest.agent_id);
+    for message in [CHECKPOINT_RESET_COMMAND, seed.as_str()] {
+        if let Err(e) = execute_gateway_call(&app, &in_flight, message, &manifest.session_key, false, None, None).await {
+            eprintln!("[CHECKPOINT ERR] seeding {} after restore: {}", manifest.session_key, e);
+            break;
+        }
+    }
+    Ok(RestoreReport { checkpoint_id, dry_run, files: plan, history_removed, history_trash_id })
+}
+
+#[tauri::command]
+pub(crate) fn delete_checkpoint(checkpoint_id: String) -> Result<(), AppError> {
+    ensure_writable()?;
+    read_checkpoint_manifest(&checkpoint_id)?;
+    fs::remove_dir_all(checkpoints_dir().join(&checkpoint_id))?;
+    Ok(())
+}
+
+/// Deletes checkpoints older than `older_than_days`, or all of them. Returns how many.
+#[tauri::command]
+pub(crate) async fn prune_checkpoints(app: tauri::AppHandle, older_than_days: Option<u64>) -> Result<usize, AppError> {
+    ensure_writable()?;
+    let cutoff = older_than_days.map(|d| now_ms().saturating_sub(d * MS_PER_DAY));
+    run_storage_operation(&app, "checkpoint-prune", move |op| {
+        let mut pruned = 0;
+        for m in all_checkpoints().iter().filter(|m| cutoff.is_none_or(|c| m.created_at <= c)) {
+            if op.is_cancelled() {
+                break;
+            }
+            if fs::remove_dir_all(checkpoints_dir().join(&m.id)).is_ok() {
+                pruned += 1;
+            }
+        }
+        Ok(pruned)
+    }).await
+}
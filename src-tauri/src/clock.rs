@@ -0,0 +1,146 @@
+//! Wall-clock readings checked for backward jumps, and a monotonic sequence for ordering.
+
+use crate::*;
+
+// ─── Clock ────────────────────────────────────────────────────────────────────
+
+/// A backward step larger than this is reported. NTP slews stay well under it.
+pub(crate) const CLOCK_SKEW_THRESHOLD_MS: u64 = 2_000;
+
+/// Set in setup so a jump seen anywhere can be announced.
+pub(crate) static CLOCK_EVENTS: std::sync::OnceLock<tauri::AppHandle> = std::sync::OnceLock::new();
+pub(crate) static CLOCK: std::sync::LazyLock<Clock> = std::sync::LazyLock::new(|| Clock::new(Box::new(SystemClock)));
+
+/// Where wall time comes from, so a drifting clock can be simulated.
+pub(crate) trait TimeSource: Send + Sync {
+    fn wall_ms(&self) -> u64;
+}
+
+pub(crate) struct SystemClock;
+
+impl TimeSource for SystemClock {
+    fn wall_ms(&self) -> u64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64
+    }
+}
+
+pub(crate) struct Clock {
+    pub(crate) source: Box<dyn TimeSource>,
+    /// Latest wall reading, to compare the next one against
+    pub(crate) last_wall: std::sync::atomic::AtomicU64,
+    pub(crate) seq: std::sync::atomic::AtomicU64,
+    pub(crate) started: std::time::Instant,
+}
+
+impl Clock {
+    pub(crate) fn new(source: Box<dyn TimeSource>) -> Self {
+        Self {
+            source,
+            last_wall: std::sync::atomic::AtomicU64::new(0),
+            seq: std::sync::atomic::AtomicU64::new(0),
+            started: std::time::Instant::now(),
+        }
+    }
+
+    /// Wall time in ms, and how far it went back since the previous reading
+    /// when that is more than `CLOCK_SKEW_THRESHOLD_MS`.
+    pub(crate) fn read(&self) -> (u64, Option<u64>) {
+        let now = self.source.wall_ms();
+        // The new reading becomes the baseline, so one jump is reported once
+        let previous = self.last_wall.swap(now, std::sync::atomic::Ordering::Relaxed);
+        let back = previous.saturating_sub(now);
+        (now, (back > CLOCK_SKEW_THRESHOLD_MS).then_some(back))
+    }
+
+    /// Strictly increasing for the life of the process, whatever the wall clock does.
+    pub(crate) fn next_seq(&self) -> u64 {
+        self.seq.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+    }
+
+    pub(crate) fn monotonic_ms(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+}
+
+/// Wall time for timestamps that are shown or persisted. Anything that needs
+/// ordering or uniqueness should add `next_seq()` rather than rely on this.
+pub(crate) fn now_ms() -> u64 {
+    let (now, back) = CLOCK.read();
+    if let Some(back) = back {
+        eprintln!("[CLOCK] wall clock went back {}ms", back);
+        if let Some(app) = CLOCK_EVENTS.get() {
+            app.emit("clock-skew-detected", serde_json::json!({ "deltaMs": -(back as i64), "now": now })).ok();
+        }
+    }
+    now
+}
+
+pub(crate) fn next_seq() -> u64 {
+    CLOCK.next_seq()
+}
+
+/// Milliseconds since the app started; for intervals that must not move with the wall clock.
+pub(crate) fn monotonic_ms() -> u64 {
+    CLOCK.monotonic_ms()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Wall time the test sets by hand.
+    struct ManualClock(std::sync::Arc<std::sync::atomic::AtomicU64>);
+
+    impl TimeSource for ManualClock {
+        fn wall_ms(&self) -> u64 {
+            self.0.load(std::sync::atomic::Ordering::Relaxed)
+        }
+    }
+
+    fn manual(start: u64) -> (Clock, std::sync::Arc<std::sync::atomic::AtomicU64>) {
+        let wall = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(start));
+        (Clock::new(Box::new(ManualClock(wall.clone()))), wall)
+    }
+
+    fn set(wall: &std::sync::atomic::AtomicU64, ms: u64) {
+        wall.store(ms, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    #[test]
+    fn only_steps_back_past_the_threshold_are_reported() {
+        let (clock, wall) = manual(100_000);
+        assert_eq!(clock.read(), (100_000, None));
+        // An NTP slew
+        set(&wall, 100_000 - 500);
+        assert_eq!(clock.read().1, None);
+        set(&wall, 99_500 - CLOCK_SKEW_THRESHOLD_MS);
+        assert_eq!(clock.read().1, None);
+        set(&wall, 50_000);
+        assert_eq!(clock.read(), (50_000, Some(97_500 - 50_000)));
+        // The jump is the new baseline, so it is reported once
+        assert_eq!(clock.read(), (50_000, None));
+        set(&wall, 60_000);
+        assert_eq!(clock.read().1, None);
+    }
+
+    #[test]
+    fn sequence_keeps_increasing_when_the_clock_goes_back() {
+        let (clock, wall) = manual(10_000_000);
+        let mut ids = std::collections::HashSet::new();
+        let mut last = None;
+        for step in 0..100u64 {
+            // Forward one tick, back an hour every tenth
+            let now = if step % 10 == 9 { 10_000_000 - 3_600_000 } else { 10_000_000 + step };
+            set(&wall, now);
+            let (wall_now, _) = clock.read();
+            let seq = clock.next_seq();
+            assert!(last.is_none_or(|l| seq > l));
+            last = Some(seq);
+            // The wall time repeats; the id doesn't
+            assert!(ids.insert(format!("{}-{}", wall_now, seq)));
+        }
+    }
+}
@@ -0,0 +1,281 @@
+//! The single list of commands.
+//!
+//! Each command is listed once, in the group that says whether it stays
+//! available in safe mode and whether it changes anything (and is therefore
+//! refused in observer mode). The invoke handlers and the observer-mode list
+//! are generated from these groups, so they can't drift apart.
+
+use crate::*;
+
+macro_rules! registry {
+    (
+        safe_read: [$($($safe_read:ident)::+),* $(,)?],
+        safe_write: [$($($safe_write:ident)::+),* $(,)?],
+        read: [$($($read:ident)::+),* $(,)?],
+        write: [$($($write:ident)::+),* $(,)?],
+    ) => {
+        /// Handler for a normal start: every command.
+        pub fn handler() -> impl Fn(tauri::ipc::Invoke) -> bool + Send + Sync + 'static {
+            tauri::generate_handler![
+                $($($safe_read)::+,)*
+                $($($safe_write)::+,)*
+                $($($read)::+,)*
+                $($($write)::+,)*
+            ]
+        }
+
+        /// Handler for safe mode: config and diagnostics only.
+        pub fn safe_mode_handler() -> impl Fn(tauri::ipc::Invoke) -> bool + Send + Sync + 'static {
+            tauri::generate_handler![
+                $($($safe_read)::+,)*
+                $($($safe_write)::+,)*
+            ]
+        }
+
+        /// Names of every registered command.
+        pub fn all() -> Vec<&'static str> {
+            vec![
+                $(command_name(stringify!($($safe_read)::+)),)*
+                $(command_name(stringify!($($safe_write)::+)),)*
+                $(command_name(stringify!($($read)::+)),)*
+                $(command_name(stringify!($($write)::+)),)*
+            ]
+        }
+
+        /// Names of the commands that change files, processes or settings.
+        pub fn mutating() -> Vec<&'static str> {
+            vec![
+                $(command_name(stringify!($($safe_write)::+)),)*
+                $(command_name(stringify!($($write)::+)),)*
+            ]
+        }
+    };
+}
+
+/// `gateway::call::gateway_call` → `gateway_call`
+fn command_name(path: &'static str) -> &'static str {
+    path.rsplit("::").next().unwrap_or(path).trim()
+}
+
+registry! {
+    safe_read: [
+        gateway::lifecycle::gateway_status,
+        credentials::load_api_key,
+        config::get_config,
+        environment::check_environment,
+        environment::get_environment_info,
+        environment::get_openclaw_help,
+        gateway::installs::diagnose_node_environment,
+        error_log::get_recent_errors,
+        gateway::output::get_last_gateway_error,
+        gateway::timings::get_recent_call_timings,
+        telemetry::preview_telemetry_payload,
+        shutdown::quit_app,
+        gateway::lifecycle::get_gateway_metrics,
+        gateway::health::get_gateway_process_memory_mb,
+        gateway::lifecycle::get_gateway_pid_file_path,
+        safe_mode::get_safe_mode,
+        crash::get_crash_report,
+        crash::dismiss_crash_report,
+        config::get_capabilities,
+        announce::get_event_catalog,
+        operations::list_operations,
+        config::set_observer_mode,
+        conflicts::get_config_conflicts,
+        safe_mode::leave_safe_mode,
+        // Controls over this window's own work; nothing observer mode protects
+        state::flush_state_now,
+        operations::cancel_operation,
+        gateway::call::cancel_call,
+        terminal::cancel_job,
+        resync::resync,
+        agents::distill::cancel_agent_from_session,
+    ],
+    safe_write: [
+        gateway::lifecycle::stop_agent,
+        gateway::lifecycle::stop_agent_graceful,
+        gateway::lifecycle::stop_all_agents,
+        credentials::save_api_key,
+        gateway::installs::select_openclaw_install,
+        config::set_ui_language,
+        announce::set_announcement_level,
+        announce::set_reduced_events,
+        crash::kill_orphan_gateway,
+        conflicts::resolve_config_conflict,
+        safe_mode::repair_config,
+        safe_mode::factory_reset,
+        telemetry::set_telemetry_consent,
+    ],
+    read: [
+        quick_actions::list_quick_actions,
+        gateway::launch::get_openclaw_default_port,
+        gateway::lint::lint_prompt,
+        agents::snapshots::compare_agent_versions,
+        agents::snapshots::list_agent_snapshots,
+        agents::skills::list_agent_skills,
+        agents::env::get_agent_env,
+        agents::list_agents,
+        credentials::get_agents_by_provider,
+        credentials::test_api_key,
+        agents::get_agent_config,
+        agents::get_default_agent_id,
+        agents::templates::list_agent_templates,
+        agents::transfer::export_agent_config,
+        agents::transfer::export_all_agents,
+        gateway::deferred::list_pending_calls,
+        gateway::cache::get_prompt_cache_stats,
+        gateway::cache::get_response_cache_stats,
+        gateway::refusal::get_refusal_config,
+        history::get_history,
+        workspace::get_last_workspace_diff,
+        workspace::get_file_diff,
+        history::export_history,
+        history::get_session_stats,
+        history::find_session_key_across_agents,
+        reply_assets::resolve_reply_asset,
+        history::get_token_usage_breakdown,
+        gateway::sessions::list_gateway_sessions,
+        gateway::activity::get_activity_feed,
+        gateway::sessions::read_gateway_session,
+        trash::list_trash,
+        checkpoints::list_checkpoints,
+    ],
+    write: [
+        agents::skills::install_skill,
+        agents::skills::remove_skill,
+        agents::skills::set_skill_enabled,
+        agents::env::set_agent_env,
+        agents::set_main_agent_identity,
+        agents::set_main_agent_policy,
+        config::set_session_key_prefix,
+        gateway::params::set_call_params_template,
+        auth_expiry::set_auth_expiry,
+        auth_expiry::set_auth_expiry_options,
+        gateway::fixtures::record_fixture,
+        gateway::fixtures::set_fixture_mode,
+        gateway::lifecycle::start_agent,
+        gateway::lifecycle::start_all_agents,
+        gateway::lifecycle::graceful_restart_gateway,
+        gateway::lifecycle::set_gateway_warm_up,
+        gateway::output::set_emit_gateway_logs,
+        gateway::launch::set_gateway_mode,
+        gateway::launch::set_gateway_binary_path,
+        gateway::launch::set_docker_image,
+        gateway::launch::set_gateway_cors,
+        gateway::launch::set_gateway_log_level,
+        gateway::launch::set_startup_command,
+        gateway::launch::set_shutdown_command,
+        gateway::health::stop_foreign_gateway,
+        gateway::call::gateway_call,
+        quick_actions::quick_action,
+        agents::sync_agent_auth,
+        agents::set_mirror_to_main,
+        agents::set_agent_context_window,
+        agents::snapshots::save_agent_snapshot,
+        agents::snapshots::purge_agent_snapshots,
+        agents::snapshots::restore_agent_from_snapshot,
+        agents::create_agent,
+        agents::set_default_agent_id,
+        agents::set_agent_session_mode,
+        agents::templates::create_agent_from_template,
+        agents::distill::preview_agent_from_session,
+        agents::distill::apply_agent_from_session,
+        agents::transfer::import_all_agents,
+        gateway::deferred::flush_deferred_calls,
+        gateway::deferred::set_deferral_window,
+        gateway::deferred::extend_call_deadline,
+        gateway::cache::clear_prompt_cache,
+        gateway::cache::clear_response_cache,
+        gateway::refusal::set_refusal_config,
+        gateway::call::archive_call_log,
+        gateway::call::set_call_log_format,
+        history::edit_and_resend,
+        history::set_data_retention_days,
+        gateway::sessions::import_gateway_session,
+        history_import::import_external_history,
+        history_import::continue_imported_session,
+        trash::delete_agent,
+        trash::cleanup_orphan_agents,
+        trash::delete_session,
+        trash::delete_history,
+        trash::restore_trash_entry,
+        trash::empty_trash,
+        checkpoints::create_checkpoint,
+        checkpoints::restore_checkpoint,
+        checkpoints::delete_checkpoint,
+        checkpoints::prune_checkpoints,
+        terminal::run_command,
+        self_test::run_self_test,
+        http_api::set_http_api_enabled,
+        http_api::rotate_http_api_token,
+    ],
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    /// Every `#[tauri::command]` function under src, with its body.
+    fn defined_commands(dir: &std::path::Path, out: &mut HashMap<String, String>) {
+        for path in fs::read_dir(dir).unwrap().flatten().map(|e| e.path()) {
+            if path.is_dir() {
+                defined_commands(&path, out);
+                continue;
+            }
+            if path.extension().is_none_or(|e| e != "rs") {
+                continue;
+            }
+            let source = fs::read_to_string(&path).unwrap();
+            let mut lines = source.lines();
+            while let Some(line) = lines.next() {
+                if line.trim() != "#[tauri::command]" {
+                    continue;
+                }
+                let signature = lines.by_ref().find(|l| l.contains("fn ")).unwrap();
+                let name = signature.split("fn ").nth(1).unwrap().split(['(', '<']).next().unwrap();
+                let body: Vec<&str> = lines.by_ref().take_while(|l| *l != "}").collect();
+                // Feature variants of one command (telemetry on and off) both have to qualify
+                let entry = out.entry(name.trim().to_string()).or_default();
+                entry.push_str(&body.join("\n"));
+            }
+        }
+    }
+
+    #[test]
+    fn every_command_is_registered_exactly_once() {
+        let all = all();
+        let mut seen = HashSet::new();
+        let duplicates: Vec<&str> = all.iter().copied().filter(|c| !seen.insert(*c)).collect();
+        assert!(duplicates.is_empty(), "listed more than once: {:?}", duplicates);
+        assert!(mutating().iter().all(|c| seen.contains(c)));
+    }
+
+    #[test]
+    fn registry_matches_the_command_handlers() {
+        let mut commands = HashMap::new();
+        defined_commands(&PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src"), &mut commands);
+        let defined: HashSet<String> = commands.into_keys().collect();
+        let registered: HashSet<String> = all().into_iter().map(String::from).collect();
+        let mut unregistered: Vec<&String> = defined.difference(&registered).collect();
+        let mut missing: Vec<&String> = registered.difference(&defined).collect();
+        unregistered.sort();
+        missing.sort();
+        assert!(unregistered.is_empty(), "commands not in the registry: {:?}", unregistered);
+        assert!(missing.is_empty(), "registered but not a command: {:?}", missing);
+    }
+
+    #[test]
+    fn every_command_observer_mode_lists_refuses_in_observer_mode() {
+        let mut commands = HashMap::new();
+        defined_commands(&PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src"), &mut commands);
+        // Refuses through `stop_agent`/`stop_agent_graceful` for each process it stops
+        let delegating = ["stop_all_agents"];
+        let mut unchecked: Vec<&str> = mutating().into_iter()
+            .filter(|c| !delegating.contains(c))
+            .filter(|c| commands.get(*c).is_none_or(|body| !body.contains("ensure_writable()")))
+            .collect();
+        unchecked.sort();
+        assert!(unchecked.is_empty(), "listed as disabled in observer mode but never call ensure_writable: {:?}", unchecked);
+    }
+}
@@ -0,0 +1,300 @@
+//! The app's own settings in config.json.
+
+use crate::*;
+
+// ─── App config (config.json) ─────────────────────────────────────────────────
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(default)]
+pub(crate) struct AppConfig {
+    pub(crate) api_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) deferral_window: Option<DeferralWindow>,
+    pub(crate) prompt_cache: PromptCacheConfig,
+    /// How long an ~/.openclaw access may block before the volume is treated as unreachable
+    pub(crate) storage_timeout_ms: u64,
+    pub(crate) pair_timeout_ms: u64,
+    pub(crate) call_log_format: CallLogFormat,
+    /// Agent used by commands that take an optional agent id
+    pub(crate) default_agent_id: String,
+    /// Stop the gateway before sleep instead of only marking it suspended
+    pub(crate) stop_gateway_on_suspend: bool,
+    pub(crate) refusal: RefusalConfig,
+    pub(crate) observer: ObserverConfig,
+    /// Opt-in anonymized usage events
+    pub(crate) telemetry: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) telemetry_endpoint: Option<String>,
+    /// Display language for the frontend, a two-letter BCP-47 code
+    pub(crate) ui_language: String,
+    /// Trash entries older than this are purged by daily maintenance; None keeps them
+    pub(crate) trash_retention_days: Option<u64>,
+    /// Local history sessions idle longer than this are pruned; None keeps everything
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) data_retention_days: Option<u64>,
+    /// When retention pruning last ran, in ms
+    pub(crate) last_auto_prune: u64,
+    /// Send a throwaway call after start so the first real one isn't slow
+    pub(crate) warm_up: bool,
+    /// Size of the in-memory cache of responses by idempotency key; 0 disables it
+    pub(crate) max_response_cache_entries: usize,
+    /// Silence on a call's output after which `gateway-call-slow` is emitted
+    pub(crate) call_timeout_warning_ms: u64,
+    /// Compatibility for gateways without per-call agent routing: mirror every
+    /// synced agent onto "main" and send all calls there
+    pub(crate) mirror_to_main: bool,
+    /// Gateway output past this rate is dropped and counted instead of buffered
+    pub(crate) gateway_output_max_lines_per_sec: u32,
+    /// Send gateway output lines to the frontend as `gateway-log` events
+    pub(crate) emit_gateway_logs: bool,
+    /// How the gateway process is launched
+    pub(crate) gateway_mode: GatewayMode,
+    /// OpenClaw executable used by the binary gateway mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) gateway_binary_path: Option<String>,
+    /// Image run by the docker gateway mode; it needs `openclaw` on its PATH
+    pub(crate) docker_image: String,
+    /// Start the gateway with `--enable-cors` so browser apps can call it
+    pub(crate) enable_cors: bool,
+    /// Passed to the gateway as `--log-level` unless it is the default "info"
+    pub(crate) gateway_log_level: String,
+    /// Shell command run before each gateway launch, e.g. to put npx on the PATH
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) startup_command: Option<String>,
+    /// Shell command run after the gateway is stopped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) shutdown_command: Option<String>,
+    /// Loopback HTTP API for external tools; off unless turned on
+    pub(crate) http_api: HttpApiConfig,
+    /// OpenClaw installation chosen by the user; None runs whatever `npx openclaw` finds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) openclaw_install: Option<String>,
+    /// How long before a subscription credential expires to warn about it
+    pub(crate) auth_expiry_warning_hours: u64,
+    /// Non-interactive command that renews a credential; `{agent}` is replaced by the agent id
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) auth_refresh_command: Option<String>,
+    /// Replay recorded OpenClaw fixtures instead of running the CLI; for UI development only
+    pub(crate) fixture_mode: bool,
+    /// Extra `gateway run` arguments, one per line; read at each gateway start
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) gateway_args_file: Option<String>,
+    /// What may change the "main" agent's name and instructions
+    pub(crate) main_agent_policy: MainAgentPolicy,
+    /// Prepended as "<prefix>:" to every session key sent to the gateway, so
+    /// installations sharing one gateway keep their sessions apart
+    pub(crate) session_key_prefix: String,
+    /// Fixed fields sent with every call, e.g. a tenant; the call's own fields go on top
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) call_params_template: Option<serde_json::Value>,
+    /// Fail calls whose output is not JSON instead of returning it as text
+    pub(crate) json_parse_strict: bool,
+    /// Refuse a message sent to the same session again within a minute
+    pub(crate) enable_request_deduplication: bool,
+    /// Check the key with the provider before `sync_agent_auth` writes it; needs network
+    pub(crate) api_key_check_on_sync: bool,
+    /// How often queued state files are written, see `write_behind`
+    pub(crate) state_flush_secs: u64,
+    /// Timeout for outgoing HTTP requests; read at startup
+    pub(crate) http_client_timeout_ms: u64,
+    /// Cap on the number of agents, for managed deployments; None is unlimited
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) max_agents: Option<usize>,
+    /// Which status changes are sent as `announcement` events
+    pub(crate) announcements: AnnouncementLevel,
+    /// Batch high-frequency event streams so screen readers keep up
+    pub(crate) reduced_events: bool,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            api_key: String::new(),
+            deferral_window: None,
+            prompt_cache: PromptCacheConfig::default(),
+            storage_timeout_ms: 5_000,
+            pair_timeout_ms: 10_000,
+            call_log_format: CallLogFormat::Jsonl,
+            default_agent_id: "main".into(),
+            stop_gateway_on_suspend: false,
+            refusal: RefusalConfig::default(),
+            observer: ObserverConfig::default(),
+            telemetry: false,
+            telemetry_endpoint: None,
+            ui_language: "en".into(),
+            trash_retention_days: Some(30),
+            data_retention_days: None,
+            last_auto_prune: 0,
+            warm_up: false,
+            max_response_cache_entries: 100,
+            call_timeout_warning_ms: 30_000,
+            mirror_to_main: false,
+            gateway_output_max_lines_per_sec: 2_000,
+            emit_gateway_logs: true,
+            gateway_mode: GatewayMode::Npx,
+            gateway_binary_path: None,
+            docker_image: String::new(),
+            enable_cors: false,
+            gateway_log_level: "info".into(),
+            startup_command: None,
+            shutdown_command: None,
+            http_api: HttpApiConfig::default(),
+            openclaw_install: None,
+            auth_expiry_warning_hours: 24,
+            auth_refresh_command: None,
+            fixture_mode: false,
+            gateway_args_file: None,
+            main_agent_policy: MainAgentPolicy::default(),
+            session_key_prefix: String::new(),
+            call_params_template: None,
+            json_parse_strict: false,
+            enable_request_deduplication: false,
+            api_key_check_on_sync: false,
+            state_flush_secs: 5,
+            http_client_timeout_ms: 10_000,
+            max_agents: None,
+            announcements: AnnouncementLevel::Verbose,
+            reduced_events: false,
+        }
+    }
+}
+
+/// The config on disk; defaults when there is none yet.
+pub(crate) fn read_config() -> Result<AppConfig, String> {
+    match fs::read_to_string(config_path()) {
+        Ok(c) => serde_json::from_str(&c).map_err(|e| format!("config.json is corrupted: {}", e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(AppConfig::default()),
+        Err(e) => Err(format!("config.json could not be read: {}", e)),
+    }
+}
+
+/// Falls back to the defaults when the file can't be read; `save_config` then
+/// refuses to write, so the defaults never replace it.
+pub(crate) fn load_config() -> AppConfig {
+    read_config().unwrap_or_else(|e| {
+        eprintln!("[CONFIG ERR] {}", e);
+        AppConfig::default()
+    })
+}
+
+/// Fails while the file on disk can't be read: the config being saved was then
+/// built from the defaults and would wipe the settings and API key in it.
+/// `repair_config` clears a corrupted file.
+pub(crate) fn save_config(config: &AppConfig) -> Result<(), String> {
+    read_config()?;
+    fs::write(config_path(), serde_json::to_string_pretty(config).unwrap())
+        .map_err(|e| e.to_string())
+}
+
+/// The whole config for the frontend; the API key stays behind `load_api_key`.
+#[tauri::command]
+pub(crate) fn get_config() -> AppConfig {
+    AppConfig { api_key: String::new(), ..load_config() }
+}
+
+#[tauri::command]
+pub(crate) fn set_ui_language(language: String) -> Result<(), AppError> {
+    ensure_writable()?;
+    let code = language.trim().to_ascii_lowercase();
+    if code.len() != 2 || !code.bytes().all(|b| b.is_ascii_lowercase()) {
+        return Err(AppError::InvalidInput(format!("'{}' is not a two-letter language code", language)));
+    }
+    let mut config = load_config();
+    config.ui_language = code;
+    save_config(&config)?;
+    Ok(())
+}
+
+pub(crate) fn validate_session_key_prefix(prefix: &str) -> Result<(), AppError> {
+    let ok = prefix.len() <= 32 && prefix.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !ok {
+        return Err(AppError::InvalidInput(format!(
+            "Session key prefix '{}' must be at most 32 letters, digits, '-' or '_' (no ':')", prefix
+        )));
+    }
+    Ok(())
+}
+
+/// The session key as the gateway sees it.
+pub(crate) fn prefixed_session_key(config: &AppConfig, key: &str) -> Result<String, AppError> {
+    if config.session_key_prefix.is_empty() {
+        return Ok(key.to_string());
+    }
+    validate_session_key_prefix(&config.session_key_prefix)?;
+    Ok(format!("{}:{}", config.session_key_prefix, key))
+}
+
+/// Sessions started under another prefix stay in the gateway but are no longer used.
+#[tauri::command]
+pub(crate) fn set_session_key_prefix(prefix: String) -> Result<(), AppError> {
+    ensure_writable()?;
+    let prefix = prefix.trim().to_string();
+    validate_session_key_prefix(&prefix)?;
+    let mut config = load_config();
+    config.session_key_prefix = prefix;
+    save_config(&config)?;
+    Ok(())
+}
+
+// ─── Observer mode ────────────────────────────────────────────────────────────
+
+/// Read-only connection: watch another machine's gateway without changing anything.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub(crate) struct ObserverConfig {
+    pub(crate) enabled: bool,
+    /// Synced history directory shared with the observed machine
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) history_dir: Option<String>,
+}
+
+pub(crate) fn ensure_writable() -> Result<(), AppError> {
+    if load_config().observer.enabled {
+        return Err(AppError::ReadOnlyMode);
+    }
+    Ok(())
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Capabilities {
+    pub(crate) read_only: bool,
+    pub(crate) safe_mode: bool,
+    pub(crate) disabled_commands: Vec<&'static str>,
+    /// Whether the gateway accepted per-call agent routing; None until a routed call was made
+    pub(crate) agent_routing: Option<bool>,
+}
+
+#[tauri::command]
+pub(crate) fn get_capabilities(state: tauri::State<AppState>) -> Capabilities {
+    capabilities(&state, load_config().observer.enabled)
+}
+
+pub(crate) fn capabilities(state: &AppState, read_only: bool) -> Capabilities {
+    Capabilities {
+        read_only,
+        safe_mode: state.safe_mode.is_some(),
+        disabled_commands: if read_only { commands::mutating() } else { Vec::new() },
+        agent_routing: *state.agent_routing.lock().unwrap(),
+    }
+}
+
+/// Entering observer mode is always allowed; leaving it needs the gateway token.
+#[tauri::command]
+pub(crate) fn set_observer_mode(state: tauri::State<AppState>, enabled: bool, history_dir: Option<String>, token: Option<String>) -> Result<Capabilities, AppError> {
+    let mut config = load_config();
+    if config.observer.enabled && !enabled {
+        let expected = read_gateway_token().map_err(AppError::Other)?;
+        if expected.is_empty() || token.as_deref().map(str::trim) != Some(expected.as_str()) {
+            return Err(AppError::InvalidInput("Enter the gateway token to leave observer mode".into()));
+        }
+    }
+    if let Some(dir) = &history_dir {
+        if !PathBuf::from(dir).is_dir() {
+            return Err(AppError::NotFound(dir.clone()));
+        }
+    }
+    config.observer = ObserverConfig { enabled, history_dir };
+    save_config(&config)?;
+    Ok(capabilities(&state, enabled))
+}
@@ -0,0 +1,302 @@
+//! Write guards for config files that are also edited by hand.
+
+use crate::*;
+
+// ─── Config conflicts ─────────────────────────────────────────────────────────
+
+/// Content of each guarded file (openclaw.json, agent.json, auth-profiles.json) as we last
+/// read or wrote it, so the watcher can tell our writes from outside edits. Writes are
+/// checked against the `FileBase` of their own read instead: another reader of the same
+/// file must not make a stale read-modify-write look fresh.
+pub(crate) static KNOWN_CONTENT: std::sync::LazyLock<Mutex<HashMap<PathBuf, String>>> =
+    std::sync::LazyLock::new(|| Mutex::new(HashMap::new()));
+pub(crate) static PENDING_CONFLICTS: std::sync::LazyLock<Mutex<HashMap<PathBuf, ConfigConflict>>> =
+    std::sync::LazyLock::new(|| Mutex::new(HashMap::new()));
+/// Set in setup so conflicts found inside storage jobs can still be announced.
+pub(crate) static CONFLICT_EVENTS: std::sync::OnceLock<tauri::AppHandle> = std::sync::OnceLock::new();
+
+pub(crate) const CONFIG_WATCH_SECS: u64 = 5;
+
+#[derive(serde::Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ConfigConflict {
+    pub(crate) path: String,
+    pub(crate) ours: serde_json::Value,
+    pub(crate) theirs: serde_json::Value,
+    /// Dotted paths where the two versions differ
+    pub(crate) changed_fields: Vec<String>,
+    #[serde(skip)]
+    pub(crate) base: serde_json::Value,
+}
+
+#[derive(serde::Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ConflictChoice {
+    Theirs,
+    Ours,
+    /// Re-apply our changes on top of the fresh file
+    Merge,
+}
+
+pub(crate) fn parse_json_or_null(s: &str) -> serde_json::Value {
+    serde_json::from_str(s).unwrap_or(serde_json::Value::Null)
+}
+
+pub(crate) fn changed_fields(a: &serde_json::Value, b: &serde_json::Value, prefix: &str, out: &mut Vec<String>) {
+    match (a, b) {
+        (serde_json::Value::Object(x), serde_json::Value::Object(y)) => {
+            let mut keys: Vec<&String> = x.keys().chain(y.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for k in keys {
+                let path = if prefix.is_empty() { k.clone() } else { format!("{}.{}", prefix, k) };
+                let null = serde_json::Value::Null;
+                changed_fields(x.get(k).unwrap_or(&null), y.get(k).unwrap_or(&null), &path, out);
+            }
+        }
+        _ if a != b => out.push(if prefix.is_empty() { "(root)".into() } else { prefix.to_string() }),
+        _ => {}
+    }
+}
+
+/// Three-way merge: fields we changed since `base` win, everything else comes from `theirs`.
+pub(crate) fn merge_json(base: &serde_json::Value, ours: &serde_json::Value, theirs: &serde_json::Value) -> serde_json::Value {
+    if ours == base {
+        return theirs.clone();
+    }
+    if theirs == base {
+        return ours.clone();
+    }
+    match (base, ours, theirs) {
+        (serde_json::Value::Object(b), serde_json::Value::Object(o), serde_json::Value::Object(t)) => {
+            let null = serde_json::Value::Null;
+            let mut merged = serde_json::Map::new();
+            for k in b.keys().chain(o.keys()).chain(t.keys()) {
+                if merged.contains_key(k) {
+                    continue;
+                }
+                let (bv, ov, tv) = (b.get(k), o.get(k), t.get(k));
+                // A key one side removed and the other left alone stays removed
+                let v = match (bv, ov, tv) {
+                    (Some(_), None, Some(t)) if Some(t) == bv => continue,
+                    (Some(_), Some(o), None) if Some(o) == bv => continue,
+                    _ => merge_json(bv.unwrap_or(&null), ov.unwrap_or(&null), tv.unwrap_or(&null)),
+                };
+                if !(v.is_null() && ov.is_none() && tv.is_none()) {
+                    merged.insert(k.clone(), v);
+                }
+            }
+            serde_json::Value::Object(merged)
+        }
+        // Both changed a leaf: our edit is the one the user just made in Clapp
+        _ => ours.clone(),
+    }
+}
+
+/// A guarded file as one read saw it; `None` when it did not exist. Handed back
+/// to `write_guarded` by the same read-modify-write.
+#[derive(Clone, Default, Debug)]
+pub(crate) struct FileBase(Option<String>);
+
+/// Reads a guarded file along with the base its write has to pass.
+pub(crate) fn read_guarded(path: &std::path::Path) -> std::io::Result<(String, FileBase)> {
+    let _watch = SyncIoWatch::start(path.display());
+    let content = fs::read_to_string(path)?;
+    KNOWN_CONTENT.lock().unwrap().insert(path.to_path_buf(), content.clone());
+    Ok((content.clone(), FileBase(Some(content))))
+}
+
+/// The base for a write that replaces the whole file: what is there now, or
+/// nothing if it does not exist yet.
+pub(crate) fn current_base(path: &std::path::Path) -> FileBase {
+    read_guarded(path).map(|(_, base)| base).unwrap_or_default()
+}
+
+/// Writes a guarded file unless it changed on disk since `base` was read.
+pub(crate) fn write_guarded(path: &std::path::Path, base: &FileBase, content: &str) -> Result<(), AppError> {
+    ensure_data_format_writable(path)?;
+    let _watch = SyncIoWatch::start(path.display());
+    // Held across check and write, so two of our own writers can't interleave
+    let mut known = KNOWN_CONTENT.lock().unwrap();
+    if let Ok(disk) = fs::read_to_string(path) {
+        if base.0.as_ref() != Some(&disk) && disk != content {
+            let (ours, theirs) = (parse_json_or_null(content), parse_json_or_null(&disk));
+            let mut fields = Vec::new();
+            changed_fields(&ours, &theirs, "", &mut fields);
+            let conflict = ConfigConflict {
+                path: path.display().to_string(),
+                ours,
+                theirs,
+                changed_fields: fields,
+                base: base.0.as_deref().map_or(serde_json::Value::Null, parse_json_or_null),
+            };
+            if let Some(app) = CONFLICT_EVENTS.get() {
+                app.emit("config-conflict", &conflict).ok();
+            }
+            PENDING_CONFLICTS.lock().unwrap().insert(path.to_path_buf(), conflict.clone());
+            return Err(AppError::ConflictDetected(Box::new(conflict)));
+        }
+    }
+    fs::write(path, content)?;
+    known.insert(path.to_path_buf(), content.to_string());
+    Ok(())
+}
+
+/// Tells the UI when a guarded file changes on disk so it can reload its copy before
+/// the next save, which is what keeps conflicts rare.
+pub(crate) fn spawn_config_watcher(app: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut seen: HashMap<PathBuf, String> = HashMap::new();
+        while !is_shutting_down(&app) {
+            tokio::time::sleep(std::time::Duration::from_secs(CONFIG_WATCH_SECS)).await;
+            let paths: Vec<PathBuf> = KNOWN_CONTENT.lock().unwrap().keys().cloned().collect();
+            for path in paths {
+                let Ok(disk) = fs::read_to_string(&path) else { continue };
+                let ours = KNOWN_CONTENT.lock().unwrap().get(&path).cloned();
+                // Our own writes update the base, so only foreign edits differ from both
+                let known = seen.get(&path).or(ours.as_ref());
+                if known.is_some_and(|k| *k != disk) && ours.as_ref() != Some(&disk) {
+                    app.emit("config-file-changed", path.display().to_string()).ok();
+                }
+                seen.insert(path, disk);
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub(crate) fn get_config_conflicts() -> Vec<ConfigConflict> {
+    PENDING_CONFLICTS.lock().unwrap().values().cloned().collect()
+}
+
+#[tauri::command]
+pub(crate) fn resolve_config_conflict(path: String, choice: ConflictChoice) -> Result<(), AppError> {
+    ensure_writable()?;
+    let path = PathBuf::from(path);
+    let conflict = PENDING_CONFLICTS.lock().unwrap().remove(&path)
+        .ok_or_else(|| AppError::NotFound(format!("conflict for {}", path.display())))?;
+    let (fresh, base) = read_guarded(&path).unwrap_or_default();
+    let resolved = match choice {
+        ConflictChoice::Theirs => return Ok(()),
+        ConflictChoice::Ours => conflict.ours,
+        ConflictChoice::Merge => merge_json(&conflict.base, &conflict.ours, &parse_json_or_null(&fresh)),
+    };
+    write_guarded(&path, &base, &serde_json::to_string_pretty(&resolved)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guarded_file(name: &str, content: &serde_json::Value) -> PathBuf {
+        let dir = TEST_ROOT.join("conflicts");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, content.to_string()).unwrap();
+        path
+    }
+
+    fn on_disk(path: &std::path::Path) -> serde_json::Value {
+        parse_json_or_null(&fs::read_to_string(path).unwrap())
+    }
+
+    /// Reads the file, has another program edit it, then tries to write ours.
+    fn conflicting_write(name: &str, base: &serde_json::Value, theirs: &serde_json::Value, ours: &serde_json::Value) -> PathBuf {
+        let path = guarded_file(name, base);
+        let (_, read) = read_guarded(&path).unwrap();
+        fs::write(&path, theirs.to_string()).unwrap();
+        let err = write_guarded(&path, &read, &ours.to_string()).unwrap_err();
+        let AppError::ConflictDetected(conflict) = err else { panic!("expected a conflict, got {:?}", err) };
+        assert_eq!(&conflict.ours, ours);
+        assert_eq!(&conflict.theirs, theirs);
+        // Their edit is still on disk
+        assert_eq!(&on_disk(&path), theirs);
+        path
+    }
+
+    fn resolve(path: &std::path::Path, choice: ConflictChoice) {
+        resolve_config_conflict(path.display().to_string(), choice).unwrap();
+        assert!(!PENDING_CONFLICTS.lock().unwrap().contains_key(path));
+    }
+
+    #[test]
+    fn write_after_an_outside_edit_is_a_conflict() {
+        let base = serde_json::json!({ "model": "a" });
+        let path = conflicting_write("detect.json", &base, &serde_json::json!({ "model": "b" }), &serde_json::json!({ "model": "c" }));
+        let pending = PENDING_CONFLICTS.lock().unwrap();
+        assert_eq!(pending[&path].changed_fields, vec!["model".to_string()]);
+        assert_eq!(pending[&path].base, base);
+    }
+
+    #[test]
+    fn writes_without_an_outside_edit_go_through() {
+        let path = guarded_file("quiet.json", &serde_json::json!({ "model": "a" }));
+        let (_, base) = read_guarded(&path).unwrap();
+        write_guarded(&path, &base, &serde_json::json!({ "model": "b" }).to_string()).unwrap();
+        let (_, base) = read_guarded(&path).unwrap();
+        write_guarded(&path, &base, &serde_json::json!({ "model": "c" }).to_string()).unwrap();
+        assert_eq!(on_disk(&path), serde_json::json!({ "model": "c" }));
+        // A file that did not exist when read can be created
+        let created = TEST_ROOT.join("conflicts").join("created.json");
+        fs::remove_file(&created).ok();
+        write_guarded(&created, &current_base(&created), "{}").unwrap();
+    }
+
+    #[test]
+    fn a_later_reader_does_not_refresh_an_earlier_readers_base() {
+        let path = guarded_file("interleaved.json", &serde_json::json!({ "model": "a" }));
+        // A save starts from the file as it is now
+        let (_, stale) = read_guarded(&path).unwrap();
+        fs::write(&path, serde_json::json!({ "model": "edited by hand" }).to_string()).unwrap();
+        // Meanwhile the UI polls the file and sees the outside edit
+        let (polled, fresh) = read_guarded(&path).unwrap();
+        assert_eq!(parse_json_or_null(&polled), serde_json::json!({ "model": "edited by hand" }));
+        // The save built on the old content is still refused
+        let err = write_guarded(&path, &stale, &serde_json::json!({ "model": "b" }).to_string()).unwrap_err();
+        assert!(matches!(err, AppError::ConflictDetected(_)), "{:?}", err);
+        assert_eq!(on_disk(&path), serde_json::json!({ "model": "edited by hand" }));
+        PENDING_CONFLICTS.lock().unwrap().remove(&path);
+        // While the poller's own write goes through
+        write_guarded(&path, &fresh, &serde_json::json!({ "model": "c" }).to_string()).unwrap();
+    }
+
+    #[test]
+    fn keeping_theirs_leaves_their_edit_and_unblocks_writes() {
+        let theirs = serde_json::json!({ "model": "b", "added": true });
+        let path = conflicting_write("theirs.json", &serde_json::json!({ "model": "a" }), &theirs, &serde_json::json!({ "model": "c" }));
+        resolve(&path, ConflictChoice::Theirs);
+        assert_eq!(on_disk(&path), theirs);
+        let (_, base) = read_guarded(&path).unwrap();
+        write_guarded(&path, &base, &serde_json::json!({ "model": "d", "added": true }).to_string()).unwrap();
+    }
+
+    #[test]
+    fn keeping_ours_writes_ours() {
+        let ours = serde_json::json!({ "model": "c", "tools": ["read"] });
+        let path = conflicting_write("ours.json", &serde_json::json!({ "model": "a" }), &serde_json::json!({ "model": "b" }), &ours);
+        resolve(&path, ConflictChoice::Ours);
+        assert_eq!(on_disk(&path), ours);
+    }
+
+    #[test]
+    fn merging_keeps_both_sides_edits() {
+        let base = serde_json::json!({ "model": "a", "temperature": 1, "gone": 1, "nested": { "x": 1, "y": 1 } });
+        let theirs = serde_json::json!({ "model": "a", "temperature": 0.2, "extra": "theirs", "nested": { "x": 1, "y": 2 } });
+        let ours = serde_json::json!({ "model": "b", "temperature": 1, "gone": 1, "nested": { "x": 3, "y": 1 } });
+        let path = conflicting_write("merge.json", &base, &theirs, &ours);
+        resolve(&path, ConflictChoice::Merge);
+        assert_eq!(on_disk(&path), serde_json::json!({
+            "model": "b",
+            "temperature": 0.2,
+            "extra": "theirs",
+            "nested": { "x": 3, "y": 2 },
+        }));
+    }
+
+    #[test]
+    fn both_changing_a_field_keeps_ours() {
+        let base = serde_json::json!({ "model": "a" });
+        let merged = merge_json(&base, &serde_json::json!({ "model": "ours" }), &serde_json::json!({ "model": "theirs" }));
+        assert_eq!(merged, serde_json::json!({ "model": "ours" }));
+    }
+}
@@ -0,0 +1,188 @@
+//! Detecting and reporting an unclean previous shutdown.
+
+use crate::*;
+
+// ─── Crash recovery ───────────────────────────────────────────────────────────
+
+/// Rewritten while the app runs; `clean_shutdown` is only set on a normal exit.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub(crate) struct Heartbeat {
+    pub(crate) started_at: u64,
+    pub(crate) last_beat: u64,
+    pub(crate) gateway_pid: Option<u32>,
+    /// "running" or "stopped", as last seen by this app
+    pub(crate) last_status: String,
+    /// Set when the gateway exited without us stopping it
+    pub(crate) gateway_crashed_at: Option<u64>,
+    pub(crate) clean_shutdown: bool,
+}
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CrashReport {
+    /// The app itself went away without a clean shutdown
+    pub(crate) app_crashed: bool,
+    pub(crate) started_at: u64,
+    pub(crate) last_beat: u64,
+    pub(crate) last_status: String,
+    pub(crate) gateway_pid: Option<u32>,
+    pub(crate) gateway_crashed_at: Option<u64>,
+    /// The old gateway is still running, orphaned from any app window
+    pub(crate) gateway_alive: bool,
+    pub(crate) pending_calls: Vec<PendingCall>,
+    pub(crate) gateway_log_tail: Vec<String>,
+    pub(crate) call_log_tail: Vec<String>,
+}
+
+pub(crate) const HEARTBEAT_SECS: u64 = 30;
+pub(crate) const CRASH_LOG_LINES: usize = 50;
+pub(crate) const GATEWAY_LOG_MAX_BYTES: u64 = 1024 * 1024;
+
+pub(crate) fn heartbeat_path() -> PathBuf {
+    clapp_dir().join("heartbeat.json")
+}
+
+pub(crate) fn gateway_log_path() -> PathBuf {
+    clapp_dir().join("gateway.log")
+}
+
+pub(crate) fn read_heartbeat() -> Option<Heartbeat> {
+    serde_json::from_str(&fs::read_to_string(heartbeat_path()).ok()?).ok()
+}
+
+pub(crate) fn update_heartbeat(f: impl FnOnce(&mut Heartbeat)) {
+    let mut beat = read_heartbeat().unwrap_or_default();
+    f(&mut beat);
+    fs::write(heartbeat_path(), serde_json::to_string(&beat).unwrap()).ok();
+}
+
+/// Starts a fresh heartbeat and returns the previous one.
+pub(crate) fn begin_heartbeat() -> Option<Heartbeat> {
+    let previous = read_heartbeat();
+    let now = now_ms();
+    let beat = Heartbeat { started_at: now, last_beat: now, last_status: "stopped".into(), ..Default::default() };
+    fs::write(heartbeat_path(), serde_json::to_string(&beat).unwrap()).ok();
+    previous
+}
+
+pub(crate) fn mark_clean_shutdown() {
+    update_heartbeat(|h| h.clean_shutdown = true);
+}
+
+pub(crate) fn append_gateway_log(line: &str) {
+    use std::io::Write;
+    let path = gateway_log_path();
+    if fs::metadata(&path).is_ok_and(|m| m.len() > GATEWAY_LOG_MAX_BYTES) {
+        fs::rename(&path, path.with_extension("log.1")).ok();
+    }
+    if let Ok(mut f) = fs::OpenOptions::new().create(true).append(true).open(&path) {
+        write!(f, "{}", line).ok();
+    }
+}
+
+pub(crate) fn tail_lines(path: &std::path::Path, n: usize) -> Vec<String> {
+    let content = fs::read_to_string(path).unwrap_or_default();
+    let lines: Vec<&str> = content.lines().collect();
+    lines[lines.len().saturating_sub(n)..].iter().map(|l| l.to_string()).collect()
+}
+
+pub(crate) async fn pid_alive(app: &tauri::AppHandle, pid: u32) -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        let _ = app;
+        PathBuf::from(format!("/proc/{}", pid)).exists()
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let filter = format!("PID eq {}", pid);
+        app.shell()
+            .command("cmd")
+            .args(["/C", "tasklist", "/FI", &filter, "/FO", "CSV", "/NH"])
+            .output()
+            .await
+            .map(|out| String::from_utf8_lossy(&out.stdout).contains(&format!("\"{}\"", pid)))
+            .unwrap_or(false)
+    }
+}
+
+pub(crate) fn spawn_heartbeat(app: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        while !is_shutting_down(&app) {
+            let pid = app.state::<AppState>().process.lock().unwrap().as_ref().map(|c| c.pid());
+            run_storage_io(&app, move || update_heartbeat(|h| {
+                h.last_beat = now_ms();
+                h.gateway_pid = pid;
+                h.last_status = if pid.is_some() { "running" } else { "stopped" }.into();
+            })).await.ok();
+            tokio::time::sleep(std::time::Duration::from_secs(HEARTBEAT_SECS)).await;
+        }
+    });
+}
+
+/// Builds a report if the previous run died or lost its gateway, then announces it.
+pub(crate) async fn report_previous_crash(app: tauri::AppHandle, previous: Heartbeat) {
+    let app_crashed = !previous.clean_shutdown;
+    if !app_crashed && previous.gateway_crashed_at.is_none() {
+        return;
+    }
+    let gateway_alive = match previous.gateway_pid {
+        Some(pid) if app_crashed => pid_alive(&app, pid).await,
+        _ => false,
+    };
+    let report = CrashReport {
+        app_crashed,
+        started_at: previous.started_at,
+        last_beat: previous.last_beat,
+        last_status: previous.last_status,
+        gateway_pid: previous.gateway_pid,
+        gateway_crashed_at: previous.gateway_crashed_at,
+        gateway_alive,
+        pending_calls: load_pending_calls(),
+        gateway_log_tail: tail_lines(&gateway_log_path(), CRASH_LOG_LINES),
+        call_log_tail: tail_lines(&call_log_path(), CRASH_LOG_LINES),
+    };
+    if let Ok(json) = serde_json::to_string_pretty(&report) {
+        fs::write(diagnostics_dir().join("crash-report.json"), json).ok();
+    }
+    *app.state::<AppState>().last_crash.lock().unwrap() = Some(report.clone());
+    app.emit("crash-detected", report).ok();
+}
+
+#[tauri::command]
+pub(crate) fn get_crash_report(state: tauri::State<AppState>) -> Option<CrashReport> {
+    state.last_crash.lock().unwrap().clone()
+}
+
+#[tauri::command]
+pub(crate) fn dismiss_crash_report(state: tauri::State<AppState>) {
+    state.last_crash.lock().unwrap().take();
+}
+
+/// Kills the gateway left running by a crashed session.
+#[tauri::command]
+pub(crate) async fn kill_orphan_gateway(app: tauri::AppHandle) -> Result<(), AppError> {
+    ensure_writable()?;
+    let pid = app.state::<AppState>().last_crash.lock().unwrap().as_ref()
+        .filter(|r| r.gateway_alive)
+        .and_then(|r| r.gateway_pid)
+        .ok_or_else(|| AppError::NotFound("orphaned gateway".into()))?;
+    // Never kill the gateway this session manages
+    let managed = app.state::<AppState>().process.lock().unwrap().as_ref().map(|c| c.pid());
+    if managed == Some(pid) {
+        return Err(AppError::InvalidInput("That gateway belongs to this session".into()));
+    }
+    let out = app.shell()
+        .command("cmd")
+        .args(["/C", "taskkill", "/PID", &pid.to_string(), "/T", "/F"])
+        .output()
+        .await
+        .map_err(|e| AppError::Other(e.to_string()))?;
+    if !out.status.success() {
+        return Err(AppError::Other(String::from_utf8_lossy(&out.stderr).trim().to_string()));
+    }
+    if let Some(r) = app.state::<AppState>().last_crash.lock().unwrap().as_mut() {
+        r.gateway_alive = false;
+    }
+    Ok(())
+}
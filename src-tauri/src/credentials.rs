@@ -0,0 +1,236 @@
+//! API keys, auth profiles, openclaw.json and the gateway token.
+
+use crate::*;
+
+// ─── API key ──────────────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub(crate) fn save_api_key(key: String) -> Result<(), String> {
+    ensure_writable().map_err(|e| e.to_string())?;
+    let mut config = load_config();
+    config.api_key = key;
+    save_config(&config)
+}
+
+#[tauri::command]
+pub(crate) fn load_api_key() -> Result<String, String> {
+    Ok(load_config().api_key)
+}
+
+// ─── API key check ────────────────────────────────────────────────────────────
+
+pub(crate) const ANTHROPIC_API_VERSION: &str = "2023-06-01";
+
+/// One cheap authenticated request (listing models) to see whether the provider
+/// takes the key. Ollama has no keys and always passes.
+#[tauri::command]
+pub(crate) async fn test_api_key(
+    app: tauri::AppHandle,
+    api_key: String,
+    provider: Option<String>,
+    base_url: Option<String>,
+) -> Result<(), AppError> {
+    let provider = provider.unwrap_or_else(default_provider);
+    let client = app.state::<AppState>().http_client.clone();
+    let request = match provider.as_str() {
+        "ollama" => return Ok(()),
+        "anthropic" => client.0.get("https://api.anthropic.com/v1/models")
+            .header("x-api-key", &api_key)
+            .header("anthropic-version", ANTHROPIC_API_VERSION),
+        other => {
+            let base = match other {
+                "groq" => "https://api.groq.com/openai/v1".to_string(),
+                "together" => "https://api.together.xyz/v1".to_string(),
+                "openai" => base_url.filter(|u| !u.trim().is_empty()).unwrap_or_else(|| "https://api.openai.com/v1".into()),
+                _ => base_url.filter(|u| !u.trim().is_empty())
+                    .ok_or_else(|| AppError::InvalidInput(format!("{} needs a base URL to check the key", other)))?,
+            };
+            client.0.get(format!("{}/models", base.trim_end_matches('/'))).bearer_auth(&api_key)
+        }
+    };
+    let (status, _) = client.send(request).await
+        .map_err(|e| AppError::Other(format!("Could not reach {} to check the key: {}", provider, e)))?;
+    match status {
+        200..=299 => Ok(()),
+        401 | 403 => Err(AppError::InvalidApiKey(provider)),
+        status => Err(AppError::Other(format!("{} answered {} when checking the key", provider, status))),
+    }
+}
+
+// ─── Auth profile ─────────────────────────────────────────────────────────────
+
+/// Auth profile schema version written for the OpenClaw release we target.
+pub(crate) const OPENCLAW_AUTH_VERSION: u32 = 1;
+
+pub(crate) fn write_auth_profile(
+    agent_id: &str,
+    api_key: &str,
+    provider: &str,
+    base_url: Option<&str>,
+    profile_version: u32,
+) -> Result<(), String> {
+    let _watch = SyncIoWatch::start(format_args!("auth profile of {}", agent_id));
+    let mut dir = openclaw_agents_root();
+    dir.push(agent_id);
+    dir.push("agent");
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    dir.push("auth-profiles.json");
+    // The whole profile is replaced, so the base is just whatever is there now
+    let base = current_base(&dir);
+
+    // Normalize provider name for openclaw
+    let provider_id = match provider {
+        "openai" | "groq" | "together" | "custom" => "openai", // OpenAI-compatible
+        _ => "anthropic",
+    };
+
+    let profile_key = format!("{}:default", provider_id);
+
+    let mut profile_obj = serde_json::json!({
+        "type": "api_key",
+        "provider": provider_id,
+        "key": api_key
+    });
+
+    // Add baseUrl for OpenAI-compatible providers
+    if let Some(url) = base_url.filter(|u| !u.trim().is_empty()) {
+        profile_obj["baseUrl"] = serde_json::Value::String(url.to_string());
+    }
+    // Groq
+    if provider == "groq" {
+        profile_obj["baseUrl"] = serde_json::Value::String("https://api.groq.com/openai/v1".into());
+    }
+    // Together
+    if provider == "together" {
+        profile_obj["baseUrl"] = serde_json::Value::String("https://api.together.xyz/v1".into());
+    }
+    // Ollama — no key, only URL
+    if provider == "ollama" {
+        let url = base_url.unwrap_or("http://localhost:11434");
+        let profile = serde_json::json!({
+            "version": profile_version,
+            "profiles": {
+                "openai:default": {
+                    "type": "api_key",
+                    "provider": "openai",
+                    "key": "ollama",
+                    "baseUrl": format!("{}/v1", url.trim_end_matches("/"))
+                }
+            },
+            "lastGood": { "openai": "openai:default" },
+            "usageStats": {}
+        });
+        return write_guarded(&dir, &base, &serde_json::to_string_pretty(&profile).unwrap())
+            .map_err(|e| e.to_string());
+    }
+
+    let profile = serde_json::json!({
+        "version": profile_version,
+        "profiles": {
+            (profile_key.clone()): profile_obj
+        },
+        "lastGood": { (provider_id): (profile_key) },
+        "usageStats": {}
+    });
+    write_guarded(&dir, &base, &serde_json::to_string_pretty(&profile).unwrap())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub(crate) async fn get_agents_by_provider(app: tauri::AppHandle) -> Result<HashMap<String, Vec<String>>, AppError> {
+    run_storage_io(&app, || {
+        let mut by_provider: HashMap<String, Vec<String>> = HashMap::new();
+        let Ok(entries) = fs::read_dir(openclaw_agents_root()) else { return by_provider };
+        for entry in entries.flatten() {
+            let agent_id = entry.file_name().to_string_lossy().into_owned();
+            let Some(profiles) = load_auth_profiles(&agent_id) else { continue };
+            for provider in profiles.last_good.keys() {
+                by_provider.entry(provider.clone()).or_default().push(agent_id.clone());
+            }
+        }
+        for ids in by_provider.values_mut() {
+            ids.sort();
+        }
+        by_provider
+    }).await
+}
+
+// ─── openclaw.json ────────────────────────────────────────────────────────────
+
+/// Random, so a clock that was set back can't reproduce an earlier token.
+pub(crate) fn generate_token() -> String {
+    let mut bytes = [0u8; 16];
+    if getrandom::getrandom(&mut bytes).is_err() {
+        return format!("local-{:x}-{:x}-{:x}", now_ms(), std::process::id(), next_seq());
+    }
+    format!("local-{}", bytes.iter().map(|b| format!("{:02x}", b)).collect::<String>())
+}
+
+pub(crate) fn ensure_openclaw_config() -> Result<String, String> {
+    let dir = openclaw_dir();
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+    let config_file = openclaw_config_path();
+
+    let mut base = FileBase::default();
+    if config_file.exists() {
+        if let Ok((content, read)) = read_guarded(&config_file) {
+            base = read;
+            if let Ok(mut v) = serde_json::from_str::<serde_json::Value>(&content) {
+                // Remove keys that openclaw does not accept
+                let removed = v.as_object_mut().is_some_and(|obj| {
+                    obj.remove("providers").is_some() | obj.remove("version").is_some()
+                });
+                let token = v["gateway"]["auth"]["token"].as_str().unwrap_or("").to_string();
+                if !token.is_empty() {
+                    if removed {
+                        // Rewrite without garbage
+                        write_guarded(&config_file, &base, &serde_json::to_string_pretty(&v).unwrap())
+                            .map_err(|e| e.to_string())?;
+                    }
+                    return Ok(token);
+                }
+            }
+        }
+    }
+
+    // Create a minimal valid config
+    let token = generate_token();
+    let config = serde_json::json!({
+        "gateway": {
+            "mode": "local",
+            "port": 18789,
+            "bind": "loopback",
+            "auth": {
+                "token": token
+            }
+        }
+    });
+
+    write_guarded(&config_file, &base, &serde_json::to_string_pretty(&config).unwrap())
+        .map_err(|e| e.to_string())?;
+
+    Ok(token)
+}
+
+// ─── Gateway token ────────────────────────────────────────────────────────────
+
+pub(crate) fn read_gateway_token() -> Result<String, String> {
+    let _watch = SyncIoWatch::start("openclaw.json");
+    let p = openclaw_config_path();
+    if !p.exists() { return Err("openclaw.json not found".into()); }
+    let v: serde_json::Value = serde_json::from_str(&fs::read_to_string(p).unwrap_or_default())
+        .map_err(|_| "openclaw.json is corrupted".to_string())?;
+    let token = v["gateway"]["auth"]["token"].as_str().unwrap_or("").to_string();
+    if token.is_empty() { return Err("Token is empty".into()); }
+    Ok(token)
+}
+
+pub(crate) fn read_gateway_port() -> u16 {
+    fs::read_to_string(openclaw_config_path())
+        .ok()
+        .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok())
+        .and_then(|v| v["gateway"]["port"].as_u64())
+        .and_then(|p| u16::try_from(p).ok())
+        .unwrap_or(18789)
+}
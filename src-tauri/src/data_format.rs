@@ -0,0 +1,234 @@
+//! Which shape OpenClaw's files in ~/.openclaw are in, and reading them in any
+//! shape we know. A shape newer than this build understands blocks writes to them.
+
+use crate::*;
+
+// ─── Format generation ────────────────────────────────────────────────────────
+
+/// Newest generation this build reads and writes. Generation 0 is auth profiles
+/// without a `version`; from 1 on it is their `version` field.
+pub(crate) const DATA_FORMAT_SUPPORTED: u32 = OPENCLAW_AUTH_VERSION;
+
+#[derive(serde::Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DataFormat {
+    /// None when nothing on disk tells, e.g. before the first launch
+    pub(crate) generation: Option<u32>,
+    pub(crate) supported: u32,
+    /// Files we found but could not read in any known shape
+    pub(crate) unrecognized: Vec<String>,
+    /// Agents owning those files; writes to their folders are refused
+    pub(crate) unrecognized_agents: Vec<String>,
+}
+
+impl DataFormat {
+    /// Only a generation newer than ours blocks every write. A file we can't
+    /// read blocks writes to its own agent only.
+    pub(crate) fn mismatch(&self) -> bool {
+        self.generation.is_some_and(|g| g > self.supported)
+    }
+}
+
+/// Result of the last probe; None until the first one ran. Probed at startup,
+/// after the OpenClaw installation changes and before each launch, never per status poll.
+pub(crate) static DATA_FORMAT: std::sync::LazyLock<Mutex<Option<DataFormat>>> =
+    std::sync::LazyLock::new(|| Mutex::new(None));
+
+pub(crate) fn probe_data_format() -> DataFormat {
+    probe_data_format_in(&openclaw_dir())
+}
+
+/// Reads the markers under an OpenClaw data dir: the `version` of every auth
+/// profile. The newest generation found wins. openclaw.json has no marker; one
+/// without a token is half-written, which `ensure_openclaw_config` repairs.
+pub(crate) fn probe_data_format_in(root: &std::path::Path) -> DataFormat {
+    let mut format = DataFormat {
+        generation: None,
+        supported: DATA_FORMAT_SUPPORTED,
+        unrecognized: Vec::new(),
+        unrecognized_agents: Vec::new(),
+    };
+    let Ok(entries) = fs::read_dir(root.join("agents")) else { return format };
+    let mut entries: Vec<_> = entries.flatten().collect();
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+        let agent_id = entry.file_name().to_string_lossy().into_owned();
+        let Ok(content) = fs::read_to_string(entry.path().join("agent").join("auth-profiles.json")) else { continue };
+        match serde_json::from_str::<serde_json::Value>(&content).ok().as_ref().and_then(read_auth_profiles) {
+            Some(profiles) => {
+                let generation = profiles.version.unwrap_or(0);
+                format.generation = Some(format.generation.map_or(generation, |g| g.max(generation)));
+            }
+            None => {
+                format.unrecognized.push(format!("{}/auth-profiles.json", agent_id));
+                format.unrecognized_agents.push(agent_id);
+            }
+        }
+    }
+    format
+}
+
+/// Probes again and remembers the result. A change lands in the diagnostics
+/// folder; a new mismatch is also emitted.
+pub(crate) fn refresh_data_format(app: &tauri::AppHandle) -> DataFormat {
+    let format = probe_data_format();
+    let previous = DATA_FORMAT.lock().unwrap().replace(format.clone());
+    if previous.as_ref() != Some(&format) {
+        fs::write(diagnostics_dir().join("data-format.json"), serde_json::to_string_pretty(&format).unwrap()).ok();
+        if format.mismatch() {
+            eprintln!(
+                "[DATA FORMAT] ~/.openclaw is generation {:?}, this Clapp understands up to {}. Writes are blocked.",
+                format.generation, format.supported
+            );
+            app.emit("data-format-mismatch", &format).ok();
+        }
+        if !format.unrecognized.is_empty() {
+            eprintln!("[DATA FORMAT] unrecognized, their agents are read-only: {:?}", format.unrecognized);
+        }
+    }
+    format
+}
+
+/// The last probe's result, without touching the disk.
+pub(crate) fn cached_data_format() -> Option<DataFormat> {
+    DATA_FORMAT.lock().unwrap().clone()
+}
+
+/// For writes to OpenClaw's files; reads go on best-effort.
+pub(crate) fn ensure_data_format_writable(path: &std::path::Path) -> Result<(), AppError> {
+    let format = DATA_FORMAT.lock().unwrap();
+    let Some(f) = format.as_ref() else { return Ok(()) };
+    if f.mismatch() {
+        return Err(AppError::DataFormatMismatch(format!(
+            "~/.openclaw is generation {}, this Clapp writes up to {}; update Clapp before changing agents",
+            f.generation.unwrap_or_default(), f.supported
+        )));
+    }
+    let root = openclaw_agents_root();
+    if let Some(agent) = f.unrecognized_agents.iter().find(|a| path.starts_with(root.join(a))) {
+        return Err(AppError::DataFormatMismatch(format!(
+            "{}/auth-profiles.json is not in a shape this Clapp knows; update Clapp before changing agent {}", agent, agent
+        )));
+    }
+    Ok(())
+}
+
+// ─── Adapters ─────────────────────────────────────────────────────────────────
+
+/// auth-profiles.json in any generation we know.
+pub(crate) struct AuthProfiles {
+    pub(crate) version: Option<u32>,
+    pub(crate) profiles: serde_json::Map<String, serde_json::Value>,
+    /// Provider → profile id it last used successfully
+    pub(crate) last_good: std::collections::BTreeMap<String, String>,
+}
+
+/// None when there is no `profiles` object. Generation 0 has no `lastGood`, so
+/// each provider's first profile stands in; newer generations are read as the
+/// newest one we know.
+pub(crate) fn read_auth_profiles(v: &serde_json::Value) -> Option<AuthProfiles> {
+    let profiles = v["profiles"].as_object()?.clone();
+    let version = v["version"].as_u64().map(|n| n as u32);
+    let last_good = match v["lastGood"].as_object() {
+        Some(last_good) => last_good.iter()
+            .filter_map(|(provider, id)| Some((provider.clone(), id.as_str()?.to_string())))
+            .collect(),
+        None => {
+            let mut derived = std::collections::BTreeMap::new();
+            for id in profiles.keys() {
+                let provider = id.split(':').next().unwrap_or(id);
+                derived.entry(provider.to_string()).or_insert_with(|| id.clone());
+            }
+            derived
+        }
+    };
+    Some(AuthProfiles { version, profiles, last_good })
+}
+
+pub(crate) fn load_auth_profiles(agent_id: &str) -> Option<AuthProfiles> {
+    let v: serde_json::Value = serde_json::from_str(&fs::read_to_string(agent_dir(agent_id).join("auth-profiles.json")).ok()?).ok()?;
+    read_auth_profiles(&v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One ~/.openclaw per OpenClaw data-format generation, under tests/fixtures/openclaw.
+    fn fixture(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/openclaw").join(name)
+    }
+
+    fn fixture_profiles(name: &str, agent_id: &str) -> AuthProfiles {
+        let path = fixture(name).join("agents").join(agent_id).join("agent/auth-profiles.json");
+        let v: serde_json::Value = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        read_auth_profiles(&v).expect("fixture should be readable")
+    }
+
+    #[test]
+    fn reads_unversioned_auth_profiles() {
+        let profiles = fixture_profiles("gen0", "main");
+        assert_eq!(profiles.version, None);
+        assert!(profiles.profiles.contains_key("anthropic:default"));
+        // No lastGood in generation 0: each provider's first profile stands in
+        assert_eq!(profiles.last_good.get("anthropic").map(String::as_str), Some("anthropic:default"));
+        assert_eq!(profiles.last_good.get("openai").map(String::as_str), Some("openai:default"));
+    }
+
+    #[test]
+    fn reads_versioned_auth_profiles() {
+        let profiles = fixture_profiles("gen1", "main");
+        assert_eq!(profiles.version, Some(1));
+        assert_eq!(profiles.last_good.get("anthropic").map(String::as_str), Some("anthropic:work"));
+        let research = fixture_profiles("gen1", "research");
+        assert_eq!(research.last_good.get("openai").map(String::as_str), Some("openai:default"));
+    }
+
+    #[test]
+    fn rejects_auth_profiles_without_profiles() {
+        assert!(read_auth_profiles(&serde_json::json!({ "version": 1 })).is_none());
+        assert!(read_auth_profiles(&serde_json::json!({ "profiles": [] })).is_none());
+    }
+
+    #[test]
+    fn probes_each_generation() {
+        let gen0 = probe_data_format_in(&fixture("gen0"));
+        assert_eq!(gen0.generation, Some(0));
+        assert!(!gen0.mismatch());
+        assert!(gen0.unrecognized.is_empty());
+
+        let gen1 = probe_data_format_in(&fixture("gen1"));
+        assert_eq!(gen1.generation, Some(1));
+        assert!(!gen1.mismatch());
+    }
+
+    #[test]
+    fn newer_generation_is_a_mismatch() {
+        let newer = probe_data_format_in(&fixture("gen2"));
+        assert_eq!(newer.generation, Some(2));
+        assert!(newer.mismatch());
+    }
+
+    #[test]
+    fn unreadable_profiles_are_scoped_to_their_agent() {
+        let format = probe_data_format_in(&fixture("gen1-broken-agent"));
+        assert_eq!(format.generation, Some(1));
+        assert!(!format.mismatch());
+        assert_eq!(format.unrecognized, vec!["broken/auth-profiles.json".to_string()]);
+        assert_eq!(format.unrecognized_agents, vec!["broken".to_string()]);
+    }
+
+    #[test]
+    fn config_without_token_is_not_a_mismatch() {
+        // gen1-broken-agent's openclaw.json uses password auth and has no token
+        let format = probe_data_format_in(&fixture("gen1-broken-agent"));
+        assert!(format.unrecognized.iter().all(|f| !f.starts_with("openclaw.json")));
+    }
+
+    #[test]
+    fn missing_dir_has_no_generation() {
+        let format = probe_data_format_in(&fixture("does-not-exist"));
+        assert_eq!(format.generation, None);
+        assert!(!format.mismatch());
+    }
+}
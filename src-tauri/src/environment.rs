@@ -0,0 +1,192 @@
+//! Tool and platform versions for setup checks and support.
+
+use crate::*;
+
+// ─── Environment check ───────────────────────────────────────────────────────
+
+#[derive(serde::Serialize)]
+pub(crate) struct EnvCheck {
+    pub(crate) node: bool,
+    pub(crate) node_version: String,
+    pub(crate) openclaw: bool,
+    pub(crate) openclaw_version: String,
+}
+
+#[tauri::command]
+pub(crate) async fn check_environment(app: tauri::AppHandle) -> Result<EnvCheck, String> {
+    let shell = app.shell();
+
+    // Check Node.js
+    let node_out = shell
+        .command("cmd")
+        .args(["/C", "node", "--version"])
+        .output()
+        .await;
+
+    let (node, node_version) = match node_out {
+        Ok(out) if out.status.success() => {
+            let v = String::from_utf8_lossy(&out.stdout).trim().to_string();
+            (true, v)
+        }
+        _ => (false, String::new()),
+    };
+
+    // Check openclaw
+    let openclaw_out = shell
+        .command("cmd")
+        .args(openclaw_args(&["--version"]))
+        .output()
+        .await;
+
+    let (openclaw, openclaw_version) = match openclaw_out {
+        Ok(out) => {
+            let stdout = String::from_utf8_lossy(&out.stdout).trim().to_string();
+            let stderr = String::from_utf8_lossy(&out.stderr).trim().to_string();
+            let v = if !stdout.is_empty() { stdout } else { stderr };
+            let ok = out.status.success() || v.contains(".");
+            (ok, if ok { v } else { String::new() })
+        }
+        _ => (false, String::new()),
+    };
+
+    Ok(EnvCheck { node, node_version, openclaw, openclaw_version })
+}
+
+// ─── OpenClaw help ────────────────────────────────────────────────────────────
+
+/// `openclaw [subcommand] --help`, for help shown next to the setting it explains.
+/// `subcommand` may be several words ("gateway health"); each must be a plain name.
+#[tauri::command]
+pub(crate) async fn get_openclaw_help(app: tauri::AppHandle, subcommand: Option<String>) -> Result<String, AppError> {
+    let words: Vec<&str> = subcommand.as_deref().unwrap_or("").split_whitespace().collect();
+    if let Some(bad) = words.iter().find(|w| w.starts_with('-') || !w.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')) {
+        return Err(AppError::InvalidInput(format!("'{}' is not an OpenClaw subcommand", bad)));
+    }
+    let mut rest = words;
+    rest.push("--help");
+    let out = app.shell().command("cmd").args(openclaw_args(&rest)).output().await
+        .map_err(|e| AppError::Other(format!("Could not run openclaw: {}", e)))?;
+    let stdout = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    if stdout.is_empty() {
+        let stderr = String::from_utf8_lossy(&out.stderr).trim().to_string();
+        return Err(AppError::Other(if stderr.is_empty() { "openclaw printed no help".into() } else { stderr }));
+    }
+    Ok(stdout)
+}
+
+// ─── Environment info ─────────────────────────────────────────────────────────
+
+/// What support asks for first. Cheap fields are filled at startup, tool versions as
+/// they resolve. Never holds secrets; paths are shown relative to the home directory.
+#[derive(serde::Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct EnvironmentInfo {
+    pub(crate) app_version: String,
+    pub(crate) build_hash: String,
+    pub(crate) os: String,
+    pub(crate) os_version: Option<String>,
+    pub(crate) arch: String,
+    pub(crate) node_version: Option<String>,
+    pub(crate) npm_version: Option<String>,
+    pub(crate) openclaw_version: Option<String>,
+    /// "selected" when the user chose an installation, "global" when openclaw is on PATH, otherwise "npx"
+    pub(crate) install_method: Option<String>,
+    pub(crate) locale: Option<String>,
+    /// A `portable` file next to the executable
+    pub(crate) portable: bool,
+    pub(crate) safe_mode: Option<String>,
+    pub(crate) config_dir: String,
+    pub(crate) complete: bool,
+}
+
+pub(crate) fn abbreviate_home(p: &std::path::Path) -> String {
+    match dirs::home_dir().and_then(|h| p.strip_prefix(h).ok().map(|r| r.to_path_buf())) {
+        Some(rest) => format!("~{}{}", std::path::MAIN_SEPARATOR, rest.display()),
+        None => p.display().to_string(),
+    }
+}
+
+pub(crate) fn initial_environment_info(safe_mode: Option<String>) -> EnvironmentInfo {
+    let locale = ["LC_ALL", "LC_MESSAGES", "LANG"].iter()
+        .find_map(|v| std::env::var(v).ok().filter(|l| !l.is_empty()));
+    let portable = std::env::current_exe().ok()
+        .and_then(|e| e.parent().map(|d| d.join("portable").exists()))
+        .unwrap_or(false);
+    EnvironmentInfo {
+        app_version: env!("CARGO_PKG_VERSION").into(),
+        build_hash: env!("CLAPP_BUILD_HASH").into(),
+        os: std::env::consts::OS.into(),
+        arch: std::env::consts::ARCH.into(),
+        locale,
+        portable,
+        safe_mode,
+        config_dir: abbreviate_home(&clapp_dir()),
+        ..Default::default()
+    }
+}
+
+/// One line for logs and `gateway_status`.
+pub(crate) fn compact_environment(info: &EnvironmentInfo) -> String {
+    let unknown = || "?".to_string();
+    format!(
+        "clapp {} ({}) {} {} {} node {} openclaw {} via {}",
+        info.app_version,
+        info.build_hash,
+        info.os,
+        info.os_version.clone().unwrap_or_else(unknown),
+        info.arch,
+        info.node_version.clone().unwrap_or_else(unknown),
+        info.openclaw_version.clone().unwrap_or_else(unknown),
+        info.install_method.clone().unwrap_or_else(unknown),
+    )
+}
+
+pub(crate) async fn command_version(app: &tauri::AppHandle, args: &[&str]) -> Option<String> {
+    let out = app.shell().command("cmd").args(args).output().await.ok()?;
+    let text = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    (out.status.success() && !text.is_empty()).then(|| text.lines().next().unwrap_or("").to_string())
+}
+
+/// Runs in the background after the window is up; slow tools just fill in later.
+pub(crate) async fn collect_environment_info(app: tauri::AppHandle) {
+    let set = |f: &dyn Fn(&mut EnvironmentInfo)| f(&mut app.state::<AppState>().env_info.lock().unwrap());
+
+    #[cfg(target_os = "linux")]
+    let os_version = fs::read_to_string("/etc/os-release").ok().and_then(|s| {
+        s.lines().find_map(|l| l.strip_prefix("PRETTY_NAME=").map(|v| v.trim_matches('"').to_string()))
+    });
+    #[cfg(not(target_os = "linux"))]
+    let os_version = command_version(&app, &["/C", "ver"]).await;
+    set(&|i| i.os_version = os_version.clone());
+
+    let node = command_version(&app, &["/C", "node", "--version"]).await;
+    set(&|i| i.node_version = node.clone());
+    let npm = command_version(&app, &["/C", "npm", "--version"]).await;
+    set(&|i| i.npm_version = npm.clone());
+    let method = if load_config().openclaw_install.is_some() {
+        "selected"
+    } else if command_version(&app, &["/C", "where", "openclaw"]).await.is_some() {
+        "global"
+    } else {
+        "npx"
+    };
+    set(&|i| i.install_method = Some(method.into()));
+    let selected = openclaw_args(&["--version"]);
+    let openclaw = command_version(&app, &selected.iter().map(String::as_str).collect::<Vec<_>>()).await;
+    set(&|i| {
+        i.openclaw_version = openclaw.clone();
+        i.complete = true;
+    });
+
+    let info = app.state::<AppState>().env_info.lock().unwrap().clone();
+    println!("[ENV] {}", compact_environment(&info));
+    if let Ok(json) = serde_json::to_string_pretty(&info) {
+        fs::write(diagnostics_dir().join("environment.json"), json).ok();
+    }
+    check_selected_install(&app).await;
+}
+
+#[tauri::command]
+pub(crate) fn get_environment_info(state: tauri::State<AppState>) -> EnvironmentInfo {
+    state.env_info.lock().unwrap().clone()
+}
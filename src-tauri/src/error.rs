@@ -0,0 +1,117 @@
+//! The error type commands return.
+
+use crate::*;
+
+// ─── Errors ───────────────────────────────────────────────────────────────────
+
+#[derive(Debug)]
+pub(crate) enum AppError {
+    Io(String),
+    InvalidInput(String),
+    /// Gateway call params failed validation; names the field
+    InvalidParams(String),
+    NotFound(String),
+    AlreadyExists(String),
+    StorageUnavailable(String),
+    UnsupportedFormat(String),
+    /// The running OpenClaw gateway lacks a feature the call needs
+    UnsupportedByGateway(String),
+    Timeout(String),
+    /// A queued call passed its deadline before it ran
+    Expired(String),
+    /// Observer mode refuses anything that changes state
+    ReadOnlyMode,
+    /// A guarded config file was edited outside the app since we read it
+    ConflictDetected(Box<ConfigConflict>),
+    /// The reply was cut off; carries the text that could be recovered
+    PartialResponse(String),
+    /// `startup_command` exited with an error; carries its stderr
+    StartupCommandFailed(String),
+    /// The provider rejected the credentials and their stored expiry has passed; carries the agent id
+    AuthExpired(String),
+    /// The provider rejected the credentials for another reason; carries its message
+    AuthInvalid(String),
+    /// `max_agents` agents already exist
+    AgentLimitReached { limit: usize },
+    /// `json_parse_strict` is on and the gateway printed something other than JSON; carries its stdout
+    ResponseNotJson(String),
+    /// The same message went to the same session moments ago; carries the session key
+    DuplicateRequest(String),
+    /// A path from the webview broke one of the `validate_user_path` rules
+    PathRejected { path: String, rule: String },
+    /// `main_agent_policy` is locked; only `set_main_agent_identity` changes main's identity
+    MainAgentLocked,
+    /// A long operation was cancelled; says what was left behind
+    Cancelled(String),
+    /// The provider refused the key in `test_api_key`; carries the provider
+    InvalidApiKey(String),
+    /// ~/.openclaw is in a shape newer than this build understands; writes to it are refused
+    DataFormatMismatch(String),
+    /// A gateway Clapp didn't start is running, so Clapp can't restart it; carries its port
+    GatewayNotManaged(u16),
+    Other(String),
+}
+
+impl std::fmt::Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AppError::Io(e) => write!(f, "I/O error: {}", e),
+            AppError::InvalidInput(e) => write!(f, "Invalid input: {}", e),
+            AppError::InvalidParams(e) => write!(f, "Invalid gateway params: {}", e),
+            AppError::NotFound(e) => write!(f, "Not found: {}", e),
+            AppError::AlreadyExists(e) => write!(f, "Already exists: {}", e),
+            AppError::Timeout(what) => write!(f, "Timed out: {}", what),
+            AppError::Expired(id) => write!(f, "Expired: {} passed its deadline before it ran", id),
+            AppError::UnsupportedFormat(v) => write!(f, "Unsupported format: {}", v),
+            AppError::UnsupportedByGateway(what) => write!(f, "Not supported by this OpenClaw gateway: {}", what),
+            AppError::StorageUnavailable(p) => write!(f, "Storage unavailable: {} is not reachable", p),
+            AppError::ConflictDetected(c) => write!(f, "Conflict: {} was changed outside Clapp", c.path),
+            AppError::PartialResponse(text) => write!(f, "Partial response: the reply was cut off. Recovered text:\n{}", text),
+            AppError::StartupCommandFailed(e) => write!(f, "Startup command failed: {}", e),
+            AppError::AuthExpired(agent) => write!(f, "Auth expired: sign in again for agent {} (setup-token)", agent),
+            AppError::AuthInvalid(e) => write!(f, "Auth invalid: {}", e),
+            AppError::AgentLimitReached { limit } => write!(f, "Agent limit reached: at most {} agents can be created", limit),
+            AppError::ResponseNotJson(out) => write!(f, "Response is not JSON: {}", out),
+            AppError::DuplicateRequest(s) => write!(f, "Duplicate request: the same message was just sent to session {}", s),
+            AppError::PathRejected { path, rule } => write!(f, "Path rejected: {} ({})", path, rule),
+            AppError::MainAgentLocked => write!(f, "Main agent is locked: change its name and instructions from the main agent settings"),
+            AppError::Cancelled(e) => write!(f, "Cancelled: {}", e),
+            AppError::DataFormatMismatch(e) => write!(f, "Data format mismatch: {}", e),
+            AppError::InvalidApiKey(provider) => write!(f, "Invalid API key: {} did not accept it", provider),
+            AppError::GatewayNotManaged(port) => write!(
+                f,
+                "Gateway not managed by Clapp: the gateway on port {} was started elsewhere. Stop it there, or with \"Stop foreign gateway\", then start it from Clapp",
+                port
+            ),
+            AppError::ReadOnlyMode => write!(f, "Read-only: this window is in observer mode"),
+            AppError::Other(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+// The frontend shows errors as plain strings, so serialize through Display
+impl serde::Serialize for AppError {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&self.to_string())
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        AppError::Io(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(e: serde_json::Error) -> Self {
+        AppError::Other(e.to_string())
+    }
+}
+
+impl From<String> for AppError {
+    fn from(e: String) -> Self {
+        AppError::Other(e)
+    }
+}
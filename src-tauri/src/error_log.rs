@@ -0,0 +1,48 @@
+//! Recent gateway, call and pairing errors for the diagnostics panel.
+
+use crate::*;
+
+// ─── Error log ────────────────────────────────────────────────────────────────
+
+pub(crate) const ERROR_LOG_CAPACITY: usize = 200;
+
+#[derive(serde::Serialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ErrorSource {
+    Gateway,
+    Call,
+    Pair,
+}
+
+#[derive(serde::Serialize, Clone)]
+pub(crate) struct ErrorLogEntry {
+    pub(crate) ts: u64,
+    /// Orders entries even if the wall clock stepped back between them
+    pub(crate) seq: u64,
+    pub(crate) source: ErrorSource,
+    pub(crate) message: String,
+}
+
+/// Keeps the newest `ERROR_LOG_CAPACITY` entries in memory and emits `error-log-added`.
+pub(crate) fn log_error(app: &tauri::AppHandle, source: ErrorSource, message: &str) {
+    let message = message.trim();
+    if message.is_empty() {
+        return;
+    }
+    let entry = ErrorLogEntry { ts: now_ms(), seq: next_seq(), source, message: message.to_string() };
+    {
+        let app_state = app.state::<AppState>();
+        let mut log = app_state.error_log.lock().unwrap();
+        if log.len() == ERROR_LOG_CAPACITY {
+            log.pop_front();
+        }
+        log.push_back(entry.clone());
+    }
+    emit_stream(app, "error-log-added", "errors", &entry);
+}
+
+/// Newest first.
+#[tauri::command]
+pub(crate) fn get_recent_errors(state: tauri::State<AppState>, n: usize) -> Result<Vec<ErrorLogEntry>, AppError> {
+    Ok(state.error_log.lock().unwrap().iter().rev().take(n).cloned().collect())
+}
@@ -0,0 +1,188 @@
+//! Channel activity reconstructed from gateway output.
+
+use crate::*;
+
+// ─── Channel activity ─────────────────────────────────────────────────────────
+
+/// Bump when the phrasing rules below change, so stored entries say which rules produced them.
+pub(crate) const ACTIVITY_RULES_VERSION: u32 = 1;
+pub(crate) const ACTIVITY_TEXT_MAX_CHARS: usize = 200;
+pub(crate) const ACTIVITY_LOG_MAX_BYTES: u64 = 1024 * 1024;
+pub(crate) const KNOWN_CHANNELS: &[&str] = &[
+    "telegram", "whatsapp", "discord", "slack", "signal", "imessage", "matrix", "msteams", "googlechat",
+];
+/// Lowercased phrases OpenClaw has used for inbound messages and replies.
+pub(crate) const INBOUND_PHRASES: &[&str] = &["inbound message", "received message", "message received", "incoming message"];
+pub(crate) const REPLY_PHRASES: &[&str] = &["sending reply", "reply sent", "sent reply", "outbound message", "delivered reply"];
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ActivityEntry {
+    pub(crate) ts: u64,
+    /// "inbound", "reply", or "activity" when no rule matched the phrasing
+    pub(crate) kind: String,
+    pub(crate) channel: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) sender: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) text: Option<String>,
+    /// Gateway session the message belongs to, when the log names one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) session_id: Option<String>,
+    pub(crate) rules_version: u32,
+}
+
+pub(crate) fn activity_log_path() -> PathBuf {
+    clapp_dir().join("activity.jsonl")
+}
+
+/// Byte offset of `needle` (ASCII) in `haystack`, ignoring ASCII case. Offsets are into
+/// `haystack` itself; a lowercased copy can differ in length.
+pub(crate) fn find_ignore_ascii_case(haystack: &str, needle: &str) -> Option<usize> {
+    let bytes = haystack.as_bytes();
+    haystack.char_indices().map(|(i, _)| i).find(|&i| {
+        bytes[i..].get(..needle.len()).is_some_and(|s| s.eq_ignore_ascii_case(needle.as_bytes()))
+            // A key, not the end of a longer one ("context=" is not "text=")
+            && !haystack[..i].chars().next_back().is_some_and(|c| c.is_alphanumeric() || c == '_')
+    })
+}
+
+/// Value after `key=` or `key:` in a log line, unquoted.
+pub(crate) fn log_field(line: &str, keys: &[&str]) -> Option<String> {
+    keys.iter().find_map(|k| {
+        let start = [format!("{}=", k), format!("{}:", k), format!("\"{}\":", k)]
+            .iter()
+            .find_map(|p| find_ignore_ascii_case(line, p).map(|i| i + p.len()))?;
+        let rest = line.get(start..)?.trim_start();
+        let value = if let Some(q) = rest.strip_prefix('"') {
+            q.split('"').next()?
+        } else {
+            rest.split(|c: char| c.is_whitespace() || c == ',' || c == '}').next()?
+        };
+        (!value.is_empty()).then(|| value.to_string())
+    })
+}
+
+/// The line's `channel` field, or a `[telegram]`-style tag naming a known channel.
+/// A channel name merely appearing in the text ("received signal") doesn't count.
+pub(crate) fn line_channel(line: &str, json: Option<&serde_json::Value>) -> Option<String> {
+    let field = match json {
+        Some(v) => v["channel"].as_str().map(String::from),
+        None => log_field(line, &["channel"]),
+    };
+    field.map(|c| c.to_lowercase()).or_else(|| {
+        KNOWN_CHANNELS.iter()
+            .find(|c| find_ignore_ascii_case(line, &format!("[{}]", c)).is_some())
+            .map(|c| c.to_string())
+    })
+}
+
+pub(crate) fn truncate_chars(s: &str, max: usize) -> String {
+    match s.char_indices().nth(max) {
+        Some((i, _)) => format!("{}…", &s[..i]),
+        None => s.to_string(),
+    }
+}
+
+/// Structured (JSON) lines are read by field; plain lines by phrase. Lines that name a
+/// channel but match no phrase still produce a generic entry, so a reworded log isn't lost.
+pub(crate) fn parse_activity_line(line: &str) -> Option<ActivityEntry> {
+    let lower = line.to_lowercase();
+    let json = serde_json::from_str::<serde_json::Value>(line.trim()).ok().filter(|v| v.is_object());
+    let channel = line_channel(line, json.as_ref())?;
+
+    let kind = if INBOUND_PHRASES.iter().any(|p| lower.contains(p)) {
+        "inbound"
+    } else if REPLY_PHRASES.iter().any(|p| lower.contains(p)) {
+        "reply"
+    } else {
+        "activity"
+    };
+    let field = |keys: &[&str]| match &json {
+        Some(v) => keys.iter().find_map(|k| v[*k].as_str().map(String::from)),
+        None => log_field(line, keys),
+    };
+    Some(ActivityEntry {
+        ts: now_ms(),
+        kind: kind.into(),
+        channel,
+        sender: field(&["from", "sender", "username", "handle"]),
+        text: field(&["text", "body", "message"]).map(|t| truncate_chars(&t, ACTIVITY_TEXT_MAX_CHARS)),
+        session_id: field(&["sessionKey", "sessionId", "session"]),
+        rules_version: ACTIVITY_RULES_VERSION,
+    })
+}
+
+pub(crate) fn append_activity(entry: &ActivityEntry) {
+    use std::io::Write;
+    let path = activity_log_path();
+    if fs::metadata(&path).is_ok_and(|m| m.len() > ACTIVITY_LOG_MAX_BYTES) {
+        fs::rename(&path, path.with_extension("jsonl.1")).ok();
+    }
+    let written = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut f| writeln!(f, "{}", serde_json::to_string(entry).unwrap()));
+    if let Err(e) = written {
+        eprintln!("[ACTIVITY ERR] {}", e);
+    }
+}
+
+/// Called by the gateway forwarding task for every chunk of output.
+pub(crate) fn record_channel_activity(app: &tauri::AppHandle, chunk: &str) {
+    for entry in chunk.lines().filter_map(parse_activity_line) {
+        append_activity(&entry);
+        emit_stream(app, "channel-activity", &entry.channel, &entry);
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn get_activity_feed(
+    app: tauri::AppHandle,
+    since: Option<u64>,
+    channel_filter: Option<String>,
+) -> Result<Vec<ActivityEntry>, AppError> {
+    run_storage_io(&app, move || {
+        let path = activity_log_path();
+        // Rotated file first so entries stay in order
+        [path.with_extension("jsonl.1"), path]
+            .iter()
+            .filter_map(|p| fs::read_to_string(p).ok())
+            .flat_map(|c| c.lines().filter_map(|l| serde_json::from_str::<ActivityEntry>(l).ok()).collect::<Vec<_>>())
+            .filter(|e| since.is_none_or(|s| e.ts > s))
+            .filter(|e| channel_filter.as_ref().is_none_or(|c| e.channel.eq_ignore_ascii_case(c)))
+            .collect()
+    }).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_channel_comes_from_its_field_not_from_any_mention() {
+        let json = r#"{"channel":"Telegram","msg":"inbound message","from":"alice","text":"hi"}"#;
+        let entry = parse_activity_line(json).unwrap();
+        assert_eq!((entry.channel.as_str(), entry.kind.as_str()), ("telegram", "inbound"));
+        assert_eq!(entry.sender.as_deref(), Some("alice"));
+
+        let plain = parse_activity_line(r#"[gateway] sending reply channel=slack to=#ops text="done""#).unwrap();
+        assert_eq!((plain.channel.as_str(), plain.kind.as_str()), ("slack", "reply"));
+        assert_eq!(plain.text.as_deref(), Some("done"));
+
+        assert_eq!(parse_activity_line("[discord] message received").unwrap().channel, "discord");
+        assert!(parse_activity_line("received signal SIGTERM, shutting down").is_none());
+        assert!(parse_activity_line(r#"{"level":"info","msg":"slack webhook retry"}"#).is_none());
+    }
+
+    #[test]
+    fn fields_are_sliced_from_the_original_line_whatever_its_case() {
+        // 'İ' lowercases to three bytes, so offsets from a lowercased copy would be off
+        let line = "İİİ Channel=WhatsApp From=Bob Context=x Text=\"Größe ok\"";
+        assert_eq!(log_field(line, &["from"]).as_deref(), Some("Bob"));
+        assert_eq!(log_field(line, &["text"]).as_deref(), Some("Größe ok"));
+        assert_eq!(line_channel(line, None).as_deref(), Some("whatsapp"));
+        assert_eq!(log_field("context=only", &["text"]), None);
+    }
+}
@@ -0,0 +1,296 @@
+//! Response caches: the persistent prompt cache for background calls and the
+//! in-memory cache of responses by idempotency key.
+
+use crate::*;
+
+// ─── Prompt cache ─────────────────────────────────────────────────────────────
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(default)]
+pub(crate) struct PromptCacheConfig {
+    pub(crate) enabled: bool,
+    pub(crate) ttl_secs: u64,
+    /// Oldest entries are evicted once keys and responses together pass this
+    pub(crate) max_bytes: u64,
+}
+
+impl Default for PromptCacheConfig {
+    fn default() -> Self {
+        Self { enabled: false, ttl_secs: 3600, max_bytes: 10 * 1024 * 1024 }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PromptCacheEntry {
+    pub(crate) agent_id: String,
+    pub(crate) response: String,
+    pub(crate) created_at: u64,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PromptCacheStats {
+    pub(crate) entries: usize,
+    pub(crate) hits: u64,
+    pub(crate) misses: u64,
+    pub(crate) size_bytes: u64,
+}
+
+#[derive(Default)]
+pub(crate) struct PromptCache {
+    pub(crate) hits: std::sync::atomic::AtomicU64,
+    pub(crate) misses: std::sync::atomic::AtomicU64,
+    // Serializes read-modify-write of the cache file
+    pub(crate) lock: Mutex<()>,
+}
+
+pub(crate) fn prompt_cache_path() -> PathBuf {
+    clapp_dir().join("prompt_cache.json")
+}
+
+pub(crate) fn load_prompt_cache() -> HashMap<String, PromptCacheEntry> {
+    read_behind(&prompt_cache_path())
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+pub(crate) fn persist_prompt_cache(entries: &HashMap<String, PromptCacheEntry>) {
+    write_behind(prompt_cache_path(), serde_json::to_string(entries).unwrap());
+}
+
+/// Everything that shapes the answer is part of the key: the agent's model,
+/// instructions and context window, the message, and the workspace files
+/// OpenClaw injects into every prompt. Editing any of them never serves answers
+/// produced under the old ones. SHA-256, so keys on disk stay valid across
+/// builds. Reads files; call it from a storage job.
+pub(crate) fn prompt_cache_key(agent_id: &str, message: &str) -> String {
+    use sha2::Digest;
+    let config = read_agent_config(agent_id);
+    let workspace = agent_workspace(&config);
+    let mut h = sha2::Sha256::new();
+    // Length-prefixed, so neighbouring fields can't run together into the same bytes
+    let mut field = |bytes: &[u8]| {
+        h.update((bytes.len() as u64).to_le_bytes());
+        h.update(bytes);
+    };
+    field(agent_id.as_bytes());
+    field(config.model.as_deref().unwrap_or_default().as_bytes());
+    field(config.instructions.as_bytes());
+    field(&config.context_window.unwrap_or_default().to_le_bytes());
+    field(message.as_bytes());
+    for name in INJECTED_CONTEXT_FILES {
+        // A missing file hashes differently from an empty one
+        let digest = fs::read(workspace.join(name)).map(|b| sha2::Sha256::digest(b).to_vec()).unwrap_or_default();
+        field(name.as_bytes());
+        field(&digest);
+    }
+    format!("{:x}", h.finalize())
+}
+
+pub(crate) fn entry_bytes(key: &str, entry: &PromptCacheEntry) -> u64 {
+    (key.len() + entry.agent_id.len() + entry.response.len()) as u64
+}
+
+/// Drops the oldest entries until the rest fit in `max_bytes`.
+pub(crate) fn evict_to_budget(entries: &mut HashMap<String, PromptCacheEntry>, max_bytes: u64) {
+    let mut total: u64 = entries.iter().map(|(k, e)| entry_bytes(k, e)).sum();
+    let mut by_age: Vec<(u64, String)> = entries.iter().map(|(k, e)| (e.created_at, k.clone())).collect();
+    by_age.sort();
+    for (_, key) in by_age {
+        if total <= max_bytes {
+            break;
+        }
+        if let Some(e) = entries.remove(&key) {
+            total -= entry_bytes(&key, &e);
+        }
+    }
+}
+
+/// Errors and anything that went through a tool are never cached.
+pub(crate) fn is_cacheable_response(raw: &str) -> bool {
+    fn mentions_tools(v: &serde_json::Value) -> bool {
+        match v {
+            serde_json::Value::Object(m) => m.iter().any(|(k, v)| k.starts_with("tool") || mentions_tools(v)),
+            serde_json::Value::Array(a) => a.iter().any(mentions_tools),
+            _ => false,
+        }
+    }
+    match serde_json::from_str::<serde_json::Value>(raw) {
+        Ok(v) => v.get("error").is_none_or(|e| e.is_null()) && !mentions_tools(&v),
+        Err(_) => false,
+    }
+}
+
+impl PromptCache {
+    pub(crate) fn get(&self, key: &str, config: &PromptCacheConfig) -> Option<String> {
+        use std::sync::atomic::Ordering;
+        let _guard = self.lock.lock().unwrap();
+        let entry = load_prompt_cache().remove(key)
+            .filter(|e| now_ms().saturating_sub(e.created_at) < config.ttl_secs * 1000);
+        let Some(entry) = entry else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        };
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        let mut v: serde_json::Value = serde_json::from_str(&entry.response).ok()?;
+        v["cached"] = true.into();
+        v["cachedAt"] = entry.created_at.into();
+        Some(v.to_string())
+    }
+
+    pub(crate) fn put(&self, key: String, agent_id: &str, response: &str, config: &PromptCacheConfig) {
+        let _guard = self.lock.lock().unwrap();
+        let mut entries = load_prompt_cache();
+        let now = now_ms();
+        entries.retain(|_, e| now.saturating_sub(e.created_at) < config.ttl_secs * 1000);
+        entries.insert(key, PromptCacheEntry {
+            agent_id: agent_id.to_string(),
+            response: response.to_string(),
+            created_at: now,
+        });
+        evict_to_budget(&mut entries, config.max_bytes);
+        persist_prompt_cache(&entries);
+    }
+
+    pub(crate) fn invalidate_agent(&self, agent_id: &str) {
+        let _guard = self.lock.lock().unwrap();
+        let mut entries = load_prompt_cache();
+        let before = entries.len();
+        entries.retain(|_, e| e.agent_id != agent_id);
+        if entries.len() != before {
+            persist_prompt_cache(&entries);
+        }
+    }
+}
+
+#[tauri::command]
+pub(crate) fn get_prompt_cache_stats(state: tauri::State<AppState>) -> PromptCacheStats {
+    use std::sync::atomic::Ordering;
+    let cache = &state.prompt_cache;
+    let _guard = cache.lock.lock().unwrap();
+    PromptCacheStats {
+        entries: load_prompt_cache().len(),
+        hits: cache.hits.load(Ordering::Relaxed),
+        misses: cache.misses.load(Ordering::Relaxed),
+        size_bytes: read_behind(&prompt_cache_path()).map_or(0, |c| c.len() as u64),
+    }
+}
+
+#[tauri::command]
+pub(crate) fn clear_prompt_cache(state: tauri::State<AppState>) -> Result<(), AppError> {
+    ensure_writable()?;
+    let _guard = state.prompt_cache.lock.lock().unwrap();
+    discard_behind(&prompt_cache_path())?;
+    Ok(())
+}
+
+// ─── Response cache ───────────────────────────────────────────────────────────
+
+/// Responses of this app session by idempotency key, so a retried request is
+/// answered without calling the gateway again. Never persisted.
+#[derive(Default)]
+pub(crate) struct ResponseCache {
+    pub(crate) entries: Mutex<Option<lru::LruCache<String, String>>>,
+    pub(crate) hits: std::sync::atomic::AtomicU64,
+    pub(crate) misses: std::sync::atomic::AtomicU64,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CacheStats {
+    pub(crate) entries: usize,
+    pub(crate) capacity: usize,
+    pub(crate) hits: u64,
+    pub(crate) misses: u64,
+}
+
+impl ResponseCache {
+    pub(crate) fn get(&self, key: &str) -> Option<String> {
+        use std::sync::atomic::Ordering;
+        let hit = self.entries.lock().unwrap().as_mut().and_then(|c| c.get(key).cloned());
+        let counter = if hit.is_some() { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        hit
+    }
+
+    /// `capacity` comes from the current config, so a changed limit applies on the next call.
+    pub(crate) fn put(&self, key: String, response: &str, capacity: usize) {
+        let mut entries = self.entries.lock().unwrap();
+        let Some(capacity) = std::num::NonZeroUsize::new(capacity) else {
+            *entries = None;
+            return;
+        };
+        let cache = entries.get_or_insert_with(|| lru::LruCache::new(capacity));
+        if cache.cap() != capacity {
+            cache.resize(capacity);
+        }
+        cache.put(key, response.to_string());
+    }
+}
+
+#[tauri::command]
+pub(crate) fn get_response_cache_stats(state: tauri::State<AppState>) -> CacheStats {
+    use std::sync::atomic::Ordering;
+    let cache = &state.response_cache;
+    CacheStats {
+        entries: cache.entries.lock().unwrap().as_ref().map_or(0, |c| c.len()),
+        capacity: load_config().max_response_cache_entries,
+        hits: cache.hits.load(Ordering::Relaxed),
+        misses: cache.misses.load(Ordering::Relaxed),
+    }
+}
+
+#[tauri::command]
+pub(crate) fn clear_response_cache(state: tauri::State<AppState>) -> Result<(), AppError> {
+    ensure_writable()?;
+    *state.response_cache.entries.lock().unwrap() = None;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(created_at: u64, response: &str) -> PromptCacheEntry {
+        PromptCacheEntry { agent_id: "main".into(), response: response.into(), created_at }
+    }
+
+    #[test]
+    fn eviction_keeps_the_newest_entries_within_the_byte_budget() {
+        let mut entries: HashMap<String, PromptCacheEntry> = (0..10)
+            .map(|i| (format!("k{}", i), entry(i, &"x".repeat(100))))
+            .collect();
+        let one = entry_bytes("k0", &entries["k0"]);
+        evict_to_budget(&mut entries, one * 3 + 1);
+        let mut kept: Vec<&String> = entries.keys().collect();
+        kept.sort();
+        assert_eq!(kept, ["k7", "k8", "k9"]);
+        // An entry bigger than the whole budget doesn't stay either
+        entries.insert("big".into(), entry(11, &"x".repeat(10_000)));
+        evict_to_budget(&mut entries, 1_000);
+        assert!(entries.values().map(|e| e.response.len()).sum::<usize>() <= 1_000);
+    }
+
+    #[test]
+    fn key_changes_with_what_the_agent_sees() {
+        let agent = "cache-key-agent";
+        let workspace = TEST_ROOT.join("cache-key-workspace");
+        fs::create_dir_all(agent_dir(agent)).unwrap();
+        fs::create_dir_all(&workspace).unwrap();
+        let config = AgentConfig { workspace: Some(workspace.display().to_string()), ..Default::default() };
+        store_agent_config(agent, &config, &current_base(&agent_config_path(agent))).unwrap();
+        let first = prompt_cache_key(agent, "summarize config X");
+        assert_eq!(first.len(), 64);
+        assert_eq!(prompt_cache_key(agent, "summarize config X"), first);
+        assert_ne!(prompt_cache_key(agent, "summarize config Y"), first);
+
+        fs::write(workspace.join("AGENTS.md"), "Answer in French.").unwrap();
+        let with_context = prompt_cache_key(agent, "summarize config X");
+        assert_ne!(with_context, first);
+
+        let (_, base) = load_agent_config(agent);
+        store_agent_config(agent, &AgentConfig { model: Some("claude-haiku".into()), ..config }, &base).unwrap();
+        assert_ne!(prompt_cache_key(agent, "summarize config X"), with_context);
+    }
+}
@@ -0,0 +1,718 @@
+//! Sending messages to agents through the gateway.
+
+use crate::*;
+
+// ─── Gateway call ─────────────────────────────────────────────────────────────
+
+/// Gateway calls currently running, by agent id.
+#[derive(Default)]
+pub(crate) struct CallsInFlight(std::sync::Arc<Mutex<HashMap<String, usize>>>);
+
+impl CallsInFlight {
+    pub(crate) fn start(&self, agent_id: &str) -> InFlightCall {
+        *self.0.lock().unwrap().entry(agent_id.to_string()).or_default() += 1;
+        InFlightCall(self.0.clone(), agent_id.to_string())
+    }
+
+    pub(crate) fn total(&self) -> usize {
+        self.0.lock().unwrap().values().sum()
+    }
+
+    pub(crate) fn by_agent(&self) -> HashMap<String, usize> {
+        self.0.lock().unwrap().clone()
+    }
+
+    pub(crate) fn contains(&self, agent_id: &str) -> bool {
+        self.0.lock().unwrap().contains_key(agent_id)
+    }
+}
+
+/// Counts a call as in flight until dropped, so checkpoint restores and agent
+/// cleanup can refuse to run alongside one. Taken once per call, by whoever
+/// also writes its outcome to history.
+pub(crate) struct InFlightCall(std::sync::Arc<Mutex<HashMap<String, usize>>>, String);
+
+impl InFlightCall {
+    pub(crate) fn start(app: &tauri::AppHandle, agent_id: &str) -> Self {
+        app.state::<AppState>().calls_in_flight.start(agent_id)
+    }
+
+    pub(crate) fn agent_id(&self) -> &str {
+        &self.1
+    }
+}
+
+impl Drop for InFlightCall {
+    fn drop(&mut self) {
+        let mut by_agent = self.0.lock().unwrap();
+        if let Some(count) = by_agent.get_mut(&self.1) {
+            *count -= 1;
+            if *count == 0 {
+                by_agent.remove(&self.1);
+            }
+        }
+    }
+}
+
+/// What `gateway call` is given as its own timeout, and the default deadline of an interactive call.
+pub(crate) const GATEWAY_CALL_TIMEOUT_MS: u64 = 130_000;
+
+tokio::task_local! {
+    /// Absolute time (ms) after which the call in this task is dropped instead of started.
+    pub(crate) static CALL_DEADLINE: u64;
+}
+
+/// Fails with `Expired` once the deadline of the call in this task has passed.
+pub(crate) fn check_call_deadline(id: &str) -> Result<(), AppError> {
+    match CALL_DEADLINE.try_with(|d| *d) {
+        Ok(deadline) if now_ms() > deadline => Err(AppError::Expired(id.to_string())),
+        _ => Ok(()),
+    }
+}
+
+/// "main" backs the gateway, so it counts as running whenever the gateway might be.
+pub(crate) fn agent_is_running(app: &tauri::AppHandle, agent_id: &str) -> bool {
+    agent_id == "main" || app.state::<AppState>().calls_in_flight.contains(agent_id)
+}
+
+/// Runs one call for the agent of `in_flight`, the guard the caller holds
+/// until the outcome is in history.
+pub(crate) async fn execute_gateway_call(
+    app: &tauri::AppHandle,
+    in_flight: &InFlightCall,
+    message: &str,
+    session_key: &str,
+    pinned: bool,
+    idempotency_key: Option<&str>,
+    extra_params: Option<&serde_json::Value>,
+) -> Result<String, AppError> {
+    let agent_id = in_flight.agent_id();
+    let started = std::time::Instant::now();
+    with_call_timer(|t| t.begin_attempt());
+    let result = call_gateway_agent(
+        app, agent_id, message, session_key, pinned.then_some(session_key), idempotency_key, extra_params,
+    ).await;
+    with_call_timer(|t| t.end_attempt(result.is_ok()));
+    let entry = CallLogEntry {
+        ts: now_ms(),
+        agent_id: agent_id.to_string(),
+        session_key: session_key.to_string(),
+        duration_ms: started.elapsed().as_millis() as u64,
+        ok: result.is_ok(),
+        response_bytes: result.as_ref().map(|r| r.len()).unwrap_or(0),
+        error: result.as_ref().err().map(|e| e.to_string()),
+    };
+    if let Err(e) = run_storage_io(app, move || log_call(&entry)).await {
+        eprintln!("[CALL LOG ERR] {}", e);
+    }
+    result
+}
+
+/// `gateway_session` is the session the gateway appends to; `session_key` only scopes the
+/// generated idempotency key, which a caller-supplied `idempotency_key` replaces.
+/// Without an explicit session, ephemeral agents get a fresh one per message and others use "main".
+/// `extra_params` are merged into the params unvalidated.
+pub(crate) async fn call_gateway_agent(
+    app: &tauri::AppHandle,
+    agent_id: &str,
+    message: &str,
+    session_key: &str,
+    gateway_session: Option<&str>,
+    idempotency_key: Option<&str>,
+    extra_params: Option<&serde_json::Value>,
+) -> Result<String, AppError> {
+    // With mirroring on, "main" carries whatever was synced last, so calls go there unrouted
+    let routed = agent_id != "main" && !load_config().mirror_to_main;
+    let schema = agent_params_schema(app);
+    if routed && !schema.accepts("agentId") {
+        return Err(routing_unsupported());
+    }
+
+    let id = agent_id.to_string();
+    let (token, agent_config, agent_env) = run_storage_io(app, move || {
+        (read_gateway_token().unwrap_or_default(), read_agent_config(&id), agents::env::read_agent_env(&id))
+    }).await?;
+
+    let gateway_session = match gateway_session {
+        Some(s) => s.to_string(),
+        None if agent_config.session_mode == SessionMode::Ephemeral => {
+            format!("{}-ephemeral-{}-{}", agent_id, now_ms(), next_seq())
+        }
+        None => "main".to_string(),
+    };
+    let gateway_session = prefixed_session_key(&load_config(), &gateway_session)?;
+
+    let ikey = idempotency_key
+        .map(String::from)
+        // The sequence keeps keys unique when the wall clock steps back
+        .unwrap_or_else(|| format!("{}-{}-{}", session_key, now_ms(), next_seq()));
+
+    let params = AgentParams {
+        message: message.to_string(),
+        session_key: gateway_session,
+        idempotency_key: ikey.clone(),
+        deliver: false,
+        context_window: agent_config.context_window,
+        agent_id: routed.then(|| agent_id.to_string()),
+    };
+    let template = load_config().call_params_template;
+    let extra = agents::env::with_agent_env(&agent_env, extra_params);
+    let params_str = params.to_wire(schema, template.as_ref(), extra.as_ref())?;
+
+    // Agent variables are secrets: a command line is readable by other programs
+    // and `cmd` would expand `%VAR%` in them, so they go through a private file
+    let params_file = if agent_env.is_empty() {
+        None
+    } else if agents::env::params_file_supported(app).await {
+        Some(agents::env::ParamsFile::write(&params_str)?)
+    } else {
+        return Err(AppError::UnsupportedByGateway(format!(
+            "{}. {}", agents::env::PARAMS_FILE_FLAG, agents::env::AGENT_ENV_UNAVAILABLE_DETAIL
+        )));
+    };
+    let params_file_path = params_file.as_ref().map(|f| f.path());
+    let params_arg = match &params_file_path {
+        Some(path) => [agents::env::PARAMS_FILE_FLAG, path.as_str()],
+        None => ["--params", params_str.as_str()],
+    };
+    let timeout = GATEWAY_CALL_TIMEOUT_MS.to_string();
+    let mut args = openclaw_args(&[
+        "gateway", "call",
+        "agent",
+        "--json",
+        "--expect-final",
+        "--timeout", &timeout,
+        params_arg[0], params_arg[1],
+    ]);
+
+    if !token.is_empty() {
+        args.push("--token".into());
+        args.push(token.clone());
+    }
+
+    let (stdout, stderr) = collect_call_output(app, &args, &ikey).await?;
+    // Tool output may echo an agent's environment values
+    let stdout = redact_known_secrets(String::from_utf8_lossy(&stdout).trim());
+    let stderr = redact_known_secrets(String::from_utf8_lossy(&stderr).trim());
+
+    if routed {
+        // Never the reply itself: a model may well talk about an unknown agentId
+        let rejected = rejects_agent_param(&stderr)
+            || structured_error(&stdout).is_some_and(|e| rejects_agent_param(&e));
+        *app.state::<AppState>().agent_routing.lock().unwrap() = Some(!rejected);
+        if rejected {
+            return Err(routing_unsupported());
+        }
+    }
+
+    if stdout.is_empty() {
+        Err(AppError::Other(if stderr.is_empty() { "Empty response from gateway".into() } else { stderr }))
+    } else {
+        let reply = assemble_reply(&stdout)?;
+        // Plain text usually means the CLI printed help or a banner instead of calling
+        if load_config().json_parse_strict && serde_json::from_str::<serde_json::Value>(&reply).is_err() {
+            return Err(AppError::ResponseNotJson(stdout));
+        }
+        Ok(reply)
+    }
+}
+
+pub(crate) fn routing_unsupported() -> AppError {
+    AppError::UnsupportedByGateway(
+        "calls to a specific agent. Update OpenClaw, or turn on mirroring to main to send every call to \"main\"".into(),
+    )
+}
+
+/// The message of an error object the gateway printed instead of a result.
+pub(crate) fn structured_error(stdout: &str) -> Option<String> {
+    let v: serde_json::Value = serde_json::from_str(stdout).ok()?;
+    if !v["result"].is_null() {
+        return None;
+    }
+    match &v["error"] {
+        serde_json::Value::String(e) => Some(e.clone()),
+        serde_json::Value::Object(e) => Some(serde_json::Value::Object(e.clone()).to_string()),
+        _ => None,
+    }
+}
+
+/// Older gateways validate `agent` params strictly and name the field they don't know.
+pub(crate) fn rejects_agent_param(output: &str) -> bool {
+    let lower = output.to_lowercase();
+    lower.contains("agentid")
+        && ["unexpected", "unknown", "additional propert", "not allowed"].iter().any(|w| lower.contains(w))
+}
+
+pub(crate) fn call_stopped(idempotency_key: &str) -> AppError {
+    AppError::Cancelled(format!("call {} was stopped before the reply finished", idempotency_key))
+}
+
+/// Fixture mode's stand-in for the CLI, stopped by the call's token like the real one.
+pub(crate) async fn replay_call_fixture(token: &CancelToken, args: &[String], idempotency_key: &str) -> Result<(Vec<u8>, Vec<u8>), AppError> {
+    with_call_timer(|t| t.spawned());
+    match until_cancelled(token, play_fixture(args)).await {
+        Some(output) => Ok(output?),
+        None => Err(call_stopped(idempotency_key)),
+    }
+}
+
+/// Same as `Command::output`, but emits `gateway-call-slow` whenever stdout stays
+/// silent for `call_timeout_warning_ms`. Slowness never cuts the call short; only
+/// its cancel token does, which kills the CLI and returns `Cancelled`.
+/// Replays a fixture in fixture mode, and records one when `record_fixture` asked for it.
+pub(crate) async fn collect_call_output(
+    app: &tauri::AppHandle,
+    args: &[String],
+    idempotency_key: &str,
+) -> Result<(Vec<u8>, Vec<u8>), AppError> {
+    use tauri_plugin_shell::process::CommandEvent;
+    // Calls outside `gateway_call` can still be stopped by shutdown
+    let token = current_call_token().unwrap_or_else(|| ROOT_CANCEL.child());
+    let stopped = || call_stopped(idempotency_key);
+    token.check("the call was stopped before it started")?;
+    // Last point before anything runs; a call that waited too long is dropped here
+    check_call_deadline(idempotency_key)?;
+    if fixture_mode() {
+        return replay_call_fixture(&token, args, idempotency_key).await;
+    }
+    let recording = app.state::<AppState>().fixture_recording.lock().unwrap().take();
+    let mut chunks: Vec<FixtureChunk> = Vec::new();
+    let mut last_chunk = std::time::Instant::now();
+    let mut record = |stderr: bool, bytes: &[u8]| {
+        if recording.is_some() {
+            let delay_ms = last_chunk.elapsed().as_millis() as u64;
+            last_chunk = std::time::Instant::now();
+            // Tool output may echo an agent's environment values
+            chunks.push(FixtureChunk { delay_ms, stderr, text: redact_known_secrets(&String::from_utf8_lossy(bytes)) });
+        }
+    };
+    let mut exit_code = 0;
+    let (mut rx, child) = app.shell()
+        .command("cmd")
+        .args(args)
+        .spawn()
+        .map_err(|e| AppError::Other(e.to_string()))?;
+    with_call_timer(|t| t.spawned());
+
+    let warn_after = std::time::Duration::from_millis(load_config().call_timeout_warning_ms.max(1));
+    let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+    let mut warned = false;
+    loop {
+        let Some(next) = until_cancelled(&token, tokio::time::timeout(warn_after, rx.recv())).await else {
+            // The gateway may still finish the run; nothing of it is recorded here
+            child.kill().ok();
+            return Err(stopped());
+        };
+        match next {
+            Ok(Some(CommandEvent::Stdout(line))) => {
+                with_call_timer(|t| t.first_byte());
+                record(false, &line);
+                stdout.extend(line);
+                stdout.push(b'\n');
+                warned = false;
+            }
+            Ok(Some(CommandEvent::Stderr(line))) => {
+                record(true, &line);
+                stderr.extend(line);
+                stderr.push(b'\n');
+            }
+            Ok(Some(CommandEvent::Terminated(status))) => exit_code = status.code.unwrap_or(0),
+            Ok(Some(_)) => {}
+            Ok(None) => break,
+            // One event per silent stretch; the next chunk re-arms it
+            Err(_) if !warned => {
+                warned = true;
+                app.emit("gateway-call-slow", serde_json::json!({ "idempotencyKey": idempotency_key })).ok();
+                announce(app, "call.slow", AnnouncementSeverity::Warning, &[]);
+            }
+            Err(_) => {}
+        }
+    }
+    if let Some(name) = recording {
+        save_recording(&name, chunks, exit_code);
+    }
+    Ok((stdout, stderr))
+}
+
+/// Optional per-call behaviour for `gateway_call`.
+#[derive(serde::Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub(crate) struct CallOptions {
+    /// Defaults to interactive
+    pub(crate) priority: Option<CallPriority>,
+    /// Let interactive calls use the prompt cache
+    pub(crate) allow_cached: bool,
+    /// Use the caller's session even for ephemeral agents
+    pub(crate) pinned_session: bool,
+    /// Run `lint_prompt` first; hard failures refuse the call
+    pub(crate) lint: bool,
+    /// Identifies a retry of the same request; repeats are answered from the response cache
+    pub(crate) idempotency_key: Option<String>,
+    /// Calls not started within this many seconds are dropped as expired. Defaults to
+    /// the gateway's own timeout, or a day for deferred calls
+    pub(crate) timeout_secs: Option<u64>,
+    /// Merged into the gateway params last, without validation. For experiments only.
+    pub(crate) extra_params: Option<serde_json::Value>,
+    /// Lets `cancel_call` stop the call; defaults to the idempotency key
+    pub(crate) call_id: Option<String>,
+}
+
+#[tauri::command]
+pub(crate) async fn gateway_call(
+    app: tauri::AppHandle,
+    agent_id: String,
+    message: String,
+    session_key: String,
+    _system_prompt: Option<String>,
+    options: Option<CallOptions>,
+) -> Result<String, String> {
+    // The deadline counts from here, so linting and the snapshot eat into it
+    let received_at = now_ms();
+    let timer = CallTimer::start(&agent_id, &session_key, None);
+    // Every return before the call runs is a failure in "preparation"
+    let refuse = |e: String| {
+        timer.finish(&app, false);
+        e
+    };
+    ensure_writable().map_err(|e| refuse(e.to_string()))?;
+    ensure_live_session(&session_key).map_err(|e| refuse(e.to_string()))?;
+    if is_shutting_down(&app) {
+        return Err(refuse("Clapp is shutting down".into()));
+    }
+    let options = options.unwrap_or_default();
+    let config = load_config();
+    let background = options.priority == Some(CallPriority::Background);
+    let pinned = options.pinned_session;
+    let idempotency_key = options.idempotency_key.filter(|k| !k.trim().is_empty());
+
+    if let Some(hit) = idempotency_key.as_deref().and_then(|k| app.state::<AppState>().response_cache.get(k)) {
+        timer.cached();
+        timer.finish(&app, true);
+        return Ok(hit);
+    }
+    // Forgotten again on every path that doesn't end in a sent call
+    let sent = if config.enable_request_deduplication {
+        Some(check_duplicate_call(&app, &session_key, &message).map_err(|e| refuse(e.to_string()))?)
+    } else {
+        None
+    };
+
+    let lint_warnings = if options.lint {
+        let (id, key, msg) = (agent_id.clone(), session_key.clone(), message.clone());
+        let warnings = run_storage_io(&app, move || lint_message(&id, &key, &msg)).await
+            .map_err(|e| refuse(e.to_string()))?;
+        if let Some(hard) = warnings.iter().find(|w| w.severity == LintSeverity::Error) {
+            return Err(refuse(hard.message.clone()));
+        }
+        warnings
+    } else {
+        Vec::new()
+    };
+
+    // Interactive calls are never deferred. The drain times the call when it runs.
+    if background && in_deferral_window(&config) {
+        let id = enqueue_deferred_call(&app, agent_id, message, session_key, pinned, options.timeout_secs)
+            .map_err(refuse)?;
+        if let Some(sent) = sent {
+            sent.keep();
+        }
+        return Ok(serde_json::json!({ "status": "deferred", "id": id }).to_string());
+    }
+
+    let call_id = options.call_id.clone().filter(|id| !id.trim().is_empty())
+        .or_else(|| idempotency_key.clone())
+        .unwrap_or_else(|| format!("call-{}-{}", now_ms(), next_seq()));
+    let deadline = deadline_after(received_at, options.timeout_secs, GATEWAY_CALL_TIMEOUT_MS);
+    let registered = register_cancel("call", &call_id).map_err(|e| refuse(e.to_string()))?;
+    // Held to the end, so shutdown waits for the history write of a cancelled call too
+    let in_flight = InFlightCall::start(&app, &agent_id);
+    let sent_at = now_ms();
+    announce(&app, "call.started", AnnouncementSeverity::Info, &[("agent", &agent_id)]);
+    let workspace_before = snapshot_agent_workspace(&app, &agent_id).await;
+    let use_cache = config.prompt_cache.enabled && (background || options.allow_cached);
+    timer.prepared();
+    let call = CALL_CANCEL.scope(registered.token.clone(), CALL_TIMER.scope(timer.clone(), CALL_DEADLINE.scope(deadline, async {
+        let result = execute_gateway_call(
+            &app, &in_flight, &message, &session_key, pinned, idempotency_key.as_deref(), options.extra_params.as_ref(),
+        ).await;
+        let result = match result {
+            Ok(response) => Ok(response),
+            // Not a failure: the marker keeps history honest and the slot is already free
+            Err(e @ AppError::Cancelled(_)) => {
+                timer.cancelled();
+                timer.finish(&app, false);
+                report_usage_event("gateway_call", HashMap::from([
+                    ("ok".to_string(), "false".to_string()),
+                    ("error".to_string(), e.to_string()),
+                ]));
+                record_cancelled(&app, &agent_id, &session_key, &message, sent_at).await;
+                return Err(e.to_string());
+            }
+            Err(e @ AppError::Expired(_)) => {
+                app.emit("call-expired", serde_json::json!({ "id": call_id, "deadline": deadline })).ok();
+                Err(e.to_string())
+            }
+            Err(e) => Err(classify_auth_failure(&app, &agent_id, e.to_string()).await),
+        };
+        result.inspect_err(|e| {
+            timer.finish(&app, false);
+            report_usage_event("gateway_call", HashMap::from([
+                ("ok".to_string(), "false".to_string()),
+                ("error".to_string(), e.to_string()),
+            ]));
+            log_error(&app, ErrorSource::Call, e);
+            announce(&app, "call.failed", AnnouncementSeverity::Error, &[("agent", &agent_id)]);
+            if let Some(r) = config.refusal.enabled.then(|| content_policy_error(e)).flatten() {
+                emit_refusal(&app, &agent_id, &session_key, &r);
+            }
+        })
+    })));
+    // Cache hits were never refusals, so only fresh responses are classified
+    let (mut response, refusal) = if use_cache {
+        // The key hashes the agent's context files, so it is worked out off the runtime;
+        // a lookup that can't run is a miss that isn't stored either
+        let (id, msg, cache_config, handle) = (agent_id.clone(), message.clone(), config.prompt_cache.clone(), app.clone());
+        let lookup = run_storage_io(&app, move || {
+            let key = prompt_cache_key(&id, &msg);
+            let hit = handle.state::<AppState>().prompt_cache.get(&key, &cache_config);
+            (key, hit)
+        }).await;
+        match lookup {
+            Ok((_, Some(hit))) => {
+                timer.cached();
+                (hit, None)
+            }
+            lookup => {
+                let response = call.await?;
+                let refusal = classify_refusal(&response, &config.refusal);
+                if let (Ok((key, _)), None) = (lookup, &refusal) {
+                    if is_cacheable_response(&response) {
+                        let (id, stored, cache_config, handle) =
+                            (agent_id.clone(), response.clone(), config.prompt_cache.clone(), app.clone());
+                        run_storage_io(&app, move || {
+                            handle.state::<AppState>().prompt_cache.put(key, &id, &stored, &cache_config);
+                        }).await.ok();
+                    }
+                }
+                (response, refusal)
+            }
+        }
+    } else {
+        let response = call.await?;
+        let refusal = classify_refusal(&response, &config.refusal);
+        (response, refusal)
+    };
+
+    if let Some(r) = &refusal {
+        emit_refusal(&app, &agent_id, &session_key, r);
+        if let Ok(mut v) = serde_json::from_str::<serde_json::Value>(&response) {
+            v["refusal"] = serde_json::to_value(r).unwrap();
+            response = v.to_string();
+        }
+    }
+
+    if !lint_warnings.is_empty() {
+        if let Ok(mut v) = serde_json::from_str::<serde_json::Value>(&response) {
+            v["lintWarnings"] = serde_json::to_value(&lint_warnings).unwrap();
+            response = v.to_string();
+        }
+    }
+
+    response = attach_reply_assets(&app, &agent_id, response).await;
+
+    if let Some(before) = workspace_before {
+        response = attach_workspace_diff(&app, &session_key, before, response).await;
+    }
+
+    record_exchange(&app, &agent_id, &session_key, &message, sent_at, &response).await;
+    report_usage_event("gateway_call", HashMap::from([
+        ("ok".to_string(), "true".to_string()),
+        ("durationMs".to_string(), now_ms().saturating_sub(sent_at).to_string()),
+        ("refused".to_string(), refusal.is_some().to_string()),
+    ]));
+    let timing = timer.finish(&app, true);
+    if let Ok(mut v) = serde_json::from_str::<serde_json::Value>(&response) {
+        v["timings"] = serde_json::to_value(&timing).unwrap();
+        response = v.to_string();
+    }
+    if let Some(key) = &idempotency_key {
+        app.state::<AppState>().response_cache.put(key.clone(), &response, config.max_response_cache_entries);
+    }
+    if let Some(sent) = sent {
+        sent.keep();
+    }
+    announce(&app, "call.finished", AnnouncementSeverity::Info, &[("agent", &agent_id)]);
+    Ok(response)
+}
+
+// ─── Duplicate requests ───────────────────────────────────────────────────────
+
+pub(crate) const DUPLICATE_WINDOW_MS: u64 = 60_000;
+
+pub(crate) fn call_hash(session_key: &str, message: &str) -> String {
+    use sha2::Digest;
+    let mut h = sha2::Sha256::new();
+    h.update(session_key.as_bytes());
+    h.update(message.as_bytes());
+    format!("{:x}", h.finalize())
+}
+
+/// A remembered call hash. Dropping it forgets the hash again, so a call that
+/// failed or was cancelled can be retried right away; `keep` once it went out.
+pub(crate) struct RecentCall {
+    app: tauri::AppHandle,
+    hash: Option<String>,
+}
+
+impl RecentCall {
+    pub(crate) fn keep(mut self) {
+        self.hash = None;
+    }
+}
+
+impl Drop for RecentCall {
+    fn drop(&mut self) {
+        if let Some(hash) = self.hash.take() {
+            self.app.state::<AppState>().recent_call_hashes.lock().unwrap().remove(&hash);
+        }
+    }
+}
+
+/// Fails with `DuplicateRequest` if the same message went to the same session
+/// within the last minute; otherwise remembers it.
+pub(crate) fn check_duplicate_call(app: &tauri::AppHandle, session_key: &str, message: &str) -> Result<RecentCall, AppError> {
+    let hash = call_hash(session_key, message);
+    let now = now_ms();
+    let state = app.state::<AppState>();
+    let mut seen = state.recent_call_hashes.lock().unwrap();
+    seen.retain(|_, at| now.saturating_sub(*at) < DUPLICATE_WINDOW_MS);
+    if seen.contains_key(&hash) {
+        return Err(AppError::DuplicateRequest(session_key.to_string()));
+    }
+    seen.insert(hash.clone(), now);
+    Ok(RecentCall { app: app.clone(), hash: Some(hash) })
+}
+
+// ─── Call log ─────────────────────────────────────────────────────────────────
+
+#[derive(serde::Serialize, serde::Deserialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum CallLogFormat {
+    #[default]
+    Jsonl,
+    Csv,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CallLogEntry {
+    pub(crate) ts: u64,
+    pub(crate) agent_id: String,
+    pub(crate) session_key: String,
+    pub(crate) duration_ms: u64,
+    pub(crate) ok: bool,
+    pub(crate) response_bytes: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) error: Option<String>,
+}
+
+pub(crate) const CALL_LOG_CSV_HEADER: &str = "ts,agent_id,session_key,duration_ms,ok,response_bytes,error";
+
+pub(crate) fn call_log_path() -> PathBuf {
+    clapp_dir().join("calls.log")
+}
+
+pub(crate) fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// Appends one entry in the configured format. Blocking; calls on the runtime go through `run_storage_io`.
+pub(crate) fn log_call(entry: &CallLogEntry) {
+    use std::io::Write;
+    let path = call_log_path();
+    let fresh = !path.exists();
+    let line = match load_config().call_log_format {
+        CallLogFormat::Jsonl => serde_json::to_string(entry).unwrap(),
+        CallLogFormat::Csv => {
+            let row = [
+                entry.ts.to_string(),
+                csv_field(&entry.agent_id),
+                csv_field(&entry.session_key),
+                entry.duration_ms.to_string(),
+                entry.ok.to_string(),
+                entry.response_bytes.to_string(),
+                csv_field(entry.error.as_deref().unwrap_or("")),
+            ]
+            .join(",");
+            if fresh { format!("{}\n{}", CALL_LOG_CSV_HEADER, row) } else { row }
+        }
+    };
+    let written = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut f| writeln!(f, "{}", line));
+    if let Err(e) = written {
+        eprintln!("[CALL LOG ERR] {}", e);
+    }
+}
+
+/// Moves the current call log aside so the next call starts a fresh file. Returns the archive path.
+#[tauri::command]
+pub(crate) fn archive_call_log() -> Result<Option<String>, AppError> {
+    ensure_writable()?;
+    let path = call_log_path();
+    if !path.exists() {
+        return Ok(None);
+    }
+    // The archive keeps the format the file was written in
+    let mut first = String::new();
+    std::io::BufRead::read_line(&mut std::io::BufReader::new(fs::File::open(&path)?), &mut first)?;
+    let ext = if first.starts_with(CALL_LOG_CSV_HEADER) { "csv" } else { "jsonl" };
+    let dir = clapp_dir().join("call_logs");
+    fs::create_dir_all(&dir)?;
+    let dest = dir.join(format!("calls-{}.{}", now_ms(), ext));
+    fs::rename(&path, &dest)?;
+    Ok(Some(dest.to_string_lossy().into_owned()))
+}
+
+/// Drops a call from the deferred queue, or stops a running one at its next
+/// check point. A deferred call that is already running is stopped and stays queued.
+#[tauri::command]
+pub(crate) fn cancel_call(app: tauri::AppHandle, call_id: String) -> Result<(), AppError> {
+    if drop_queued_call(&app.state::<AppState>(), &call_id) {
+        app.emit("deferred-call-dropped", &call_id).ok();
+        return Ok(());
+    }
+    cancel_registered("call", &call_id)
+}
+
+/// Removes a queued call no drain is running. False when there was none to remove.
+pub(crate) fn drop_queued_call(state: &AppState, call_id: &str) -> bool {
+    let mut pending = state.pending_calls.lock().unwrap();
+    let before = pending.len();
+    let running = state.deferred_running.lock().unwrap();
+    pending.retain(|c| c.id != call_id || running.contains(&c.id));
+    drop(running);
+    let removed = pending.len() < before;
+    if removed {
+        persist_pending_calls(&pending).ok();
+    }
+    removed
+}
+
+#[tauri::command]
+pub(crate) fn set_call_log_format(format: CallLogFormat) -> Result<(), AppError> {
+    ensure_writable()?;
+    let mut config = load_config();
+    if config.call_log_format != format {
+        archive_call_log()?;
+        config.call_log_format = format;
+        save_config(&config)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,387 @@
+//! Background calls held until outside the deferral window.
+
+use crate::*;
+
+// ─── Deferred calls ───────────────────────────────────────────────────────────
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum CallPriority {
+    Interactive,
+    Background,
+}
+
+/// Local time range ("HH:MM") during which background calls are queued.
+/// `start > end` means the window spans midnight.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub(crate) struct DeferralWindow {
+    pub(crate) start: String,
+    pub(crate) end: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PendingCall {
+    pub(crate) id: String,
+    pub(crate) agent_id: String,
+    pub(crate) message: String,
+    pub(crate) session_key: String,
+    pub(crate) priority: CallPriority,
+    pub(crate) enqueued_at: u64,
+    #[serde(default)]
+    pub(crate) pinned: bool,
+    /// Absolute time (ms) after which the call is dropped instead of run; 0 for
+    /// calls queued before deadlines existed, which get the default
+    #[serde(default)]
+    pub(crate) deadline: u64,
+}
+
+impl PendingCall {
+    pub(crate) fn effective_deadline(&self) -> u64 {
+        if self.deadline == 0 { self.enqueued_at.saturating_add(DEFERRED_STALE_MS) } else { self.deadline }
+    }
+
+    pub(crate) fn is_expired(&self, now: u64) -> bool {
+        now > self.effective_deadline()
+    }
+}
+
+/// `timeout_secs` after `from`, or `default_ms` without one. Saturates, so a
+/// timeout too large to add up means no deadline rather than a wrapped one.
+pub(crate) fn deadline_after(from: u64, timeout_secs: Option<u64>, default_ms: u64) -> u64 {
+    from.saturating_add(timeout_secs.map_or(default_ms, |s| s.saturating_mul(1000)))
+}
+
+// Default lifetime of a deferred call, so one queued before the app was closed for days doesn't run
+pub(crate) const DEFERRED_STALE_MS: u64 = 24 * 60 * 60 * 1000;
+pub(crate) const DEFERRED_CHECK_SECS: u64 = 60;
+
+pub(crate) fn pending_calls_path() -> PathBuf {
+    clapp_dir().join("pending_calls.json")
+}
+
+pub(crate) fn load_pending_calls() -> Vec<PendingCall> {
+    fs::read_to_string(pending_calls_path())
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+/// Written to a temp file and renamed over the old one, so an exit mid-write never leaves it truncated.
+pub(crate) fn persist_pending_calls(calls: &[PendingCall]) -> Result<(), String> {
+    let path = pending_calls_path();
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, serde_json::to_string_pretty(calls).unwrap()).map_err(|e| e.to_string())?;
+    fs::rename(&tmp, &path).map_err(|e| e.to_string())
+}
+
+pub(crate) fn parse_hhmm(s: &str) -> Option<u32> {
+    let (h, m) = s.trim().split_once(':')?;
+    let (h, m): (u32, u32) = (h.parse().ok()?, m.parse().ok()?);
+    (h < 24 && m < 60).then_some(h * 60 + m)
+}
+
+pub(crate) fn in_deferral_window(config: &AppConfig) -> bool {
+    use chrono::Timelike;
+    let Some(w) = &config.deferral_window else { return false };
+    let (Some(start), Some(end)) = (parse_hhmm(&w.start), parse_hhmm(&w.end)) else { return false };
+    let now = chrono::Local::now();
+    let m = now.hour() * 60 + now.minute();
+    if start <= end {
+        m >= start && m < end
+    } else {
+        m >= start || m < end
+    }
+}
+
+pub(crate) fn enqueue_deferred_call(
+    app: &tauri::AppHandle,
+    agent_id: String,
+    message: String,
+    session_key: String,
+    pinned: bool,
+    timeout_secs: Option<u64>,
+) -> Result<String, String> {
+    let state = app.state::<AppState>();
+    let mut calls = state.pending_calls.lock().unwrap();
+    let id = format!("deferred-{:x}-{}", now_ms(), next_seq());
+    let enqueued_at = now_ms();
+    calls.push(PendingCall {
+        id: id.clone(),
+        agent_id,
+        message,
+        session_key,
+        priority: CallPriority::Background,
+        enqueued_at,
+        pinned,
+        deadline: deadline_after(enqueued_at, timeout_secs, DEFERRED_STALE_MS),
+    });
+    persist_pending_calls(&calls)?;
+    Ok(id)
+}
+
+/// The first queued call no drain is running yet.
+pub(crate) fn next_deferred_call(calls: &[PendingCall], running: &std::collections::HashSet<String>) -> Option<PendingCall> {
+    calls.iter().find(|c| !running.contains(&c.id)).cloned()
+}
+
+/// The next queued call, marked as running unless it has expired; the flag says whether it has.
+pub(crate) fn claim_deferred_call(state: &AppState, now: u64) -> Option<(PendingCall, bool)> {
+    let guard = state.pending_calls.lock().unwrap();
+    let mut running = state.deferred_running.lock().unwrap();
+    let call = next_deferred_call(&guard, &running)?;
+    let expired = call.is_expired(now);
+    if !expired {
+        running.insert(call.id.clone());
+    }
+    Some((call, expired))
+}
+
+/// The catch-up pass: calls whose deadline passed while the app was closed or
+/// asleep come off the queue at once. Running calls are left to finish.
+pub(crate) fn drop_expired_calls(state: &AppState, now: u64) -> Vec<PendingCall> {
+    let mut calls = state.pending_calls.lock().unwrap();
+    let running = state.deferred_running.lock().unwrap();
+    let (expired, kept): (Vec<_>, Vec<_>) = calls.drain(..).partition(|c| !running.contains(&c.id) && c.is_expired(now));
+    *calls = kept;
+    if !expired.is_empty() {
+        persist_pending_calls(&calls).ok();
+    }
+    expired
+}
+
+fn emit_expired(app: &tauri::AppHandle, call: &PendingCall) {
+    app.emit("call-expired", serde_json::json!({
+        "id": call.id,
+        "deadline": call.effective_deadline(),
+    })).ok();
+    app.emit("deferred-call-dropped", &call.id).ok();
+}
+
+/// Marks a queued call as running until dropped.
+pub(crate) struct RunningDeferredCall(tauri::AppHandle, String);
+
+impl Drop for RunningDeferredCall {
+    fn drop(&mut self) {
+        self.0.state::<AppState>().deferred_running.lock().unwrap().remove(&self.1);
+    }
+}
+
+pub(crate) fn remove_pending_call(app: &tauri::AppHandle, id: &str) {
+    let state = app.state::<AppState>();
+    let mut calls = state.pending_calls.lock().unwrap();
+    calls.retain(|c| c.id != id);
+    persist_pending_calls(&calls).ok();
+}
+
+/// Runs queued calls one at a time. A call stays in the pending list until it
+/// has completed, so one that was running when the app quit or crashed runs
+/// again on the next start. A call cancelled while it runs stays queued and
+/// the drain stops.
+pub(crate) async fn drain_deferred_calls(app: &tauri::AppHandle) -> usize {
+    let mut ran = 0;
+    for call in drop_expired_calls(&app.state::<AppState>(), now_ms()) {
+        emit_expired(app, &call);
+    }
+    loop {
+        // Whatever is left stays queued for the next start
+        if is_shutting_down(app) {
+            break;
+        }
+        let Some((call, expired)) = claim_deferred_call(&app.state::<AppState>(), now_ms()) else { break };
+        // Ran out of time while the calls before it ran
+        if expired {
+            remove_pending_call(app, &call.id);
+            emit_expired(app, &call);
+            continue;
+        }
+        let _running = RunningDeferredCall(app.clone(), call.id.clone());
+        let registered = match register_cancel("call", &call.id) {
+            Ok(registered) => registered,
+            Err(e) => {
+                eprintln!("[DEFERRED ERR] {}", e);
+                break;
+            }
+        };
+        // Held until the outcome is recorded, so shutdown waits for it
+        let in_flight = InFlightCall::start(app, &call.agent_id);
+        let sent_at = now_ms();
+        let timer = CallTimer::start(&call.agent_id, &call.session_key, Some(sent_at.saturating_sub(call.enqueued_at)));
+        timer.prepared();
+        let deadline = call.effective_deadline();
+        let result = CALL_CANCEL.scope(registered.token.clone(), CALL_TIMER.scope(timer.clone(), CALL_DEADLINE.scope(deadline, execute_gateway_call(
+            app, &in_flight, &call.message, &call.session_key, call.pinned, None, None,
+        )))).await;
+        match &result {
+            Ok(response) => {
+                timer.finish(app, true);
+                record_exchange(app, &call.agent_id, &call.session_key, &call.message, sent_at, response).await;
+            }
+            Err(AppError::Cancelled(_)) => {
+                timer.cancelled();
+                timer.finish(app, false);
+                app.emit("deferred-call-requeued", &call.id).ok();
+                break;
+            }
+            Err(e) => {
+                if matches!(e, AppError::Expired(_)) {
+                    app.emit("call-expired", serde_json::json!({ "id": call.id, "deadline": deadline })).ok();
+                }
+                timer.finish(app, false);
+            }
+        }
+        remove_pending_call(app, &call.id);
+        app.emit("deferred-call-finished", serde_json::json!({
+            "id": call.id,
+            "ok": result.is_ok(),
+            "result": result.unwrap_or_else(|e| e.to_string()),
+        })).ok();
+        ran += 1;
+    }
+    ran
+}
+
+pub(crate) fn spawn_deferred_drain_loop(app: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        while !is_shutting_down(&app) {
+            let has_pending = !app.state::<AppState>().pending_calls.lock().unwrap().is_empty();
+            // Paused across sleep; the resume handler lets it catch up on the next tick
+            let suspended = app.state::<AppState>().power.suspended.load(std::sync::atomic::Ordering::Relaxed);
+            if has_pending && !suspended && !in_deferral_window(&load_config()) {
+                drain_deferred_calls(&app).await;
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(DEFERRED_CHECK_SECS)).await;
+        }
+    });
+}
+
+#[tauri::command]
+pub(crate) fn list_pending_calls(state: tauri::State<AppState>) -> Vec<PendingCall> {
+    state.pending_calls.lock().unwrap().clone()
+}
+
+#[tauri::command]
+pub(crate) async fn flush_deferred_calls(app: tauri::AppHandle) -> Result<usize, AppError> {
+    ensure_writable()?;
+    Ok(drain_deferred_calls(&app).await)
+}
+
+/// Pushes back the deadline of a call that is still queued. Returns the new deadline.
+/// A call that is already running keeps running.
+#[tauri::command]
+pub(crate) fn extend_call_deadline(state: tauri::State<AppState>, call_id: String, extra_secs: u64) -> Result<u64, AppError> {
+    ensure_writable()?;
+    let mut calls = state.pending_calls.lock().unwrap();
+    let call = calls.iter_mut()
+        .find(|c| c.id == call_id)
+        .ok_or_else(|| AppError::NotFound(format!("queued call {}", call_id)))?;
+    if call.is_expired(now_ms()) {
+        return Err(AppError::Expired(call_id));
+    }
+    call.deadline = deadline_after(call.effective_deadline(), Some(extra_secs), 0);
+    let deadline = call.deadline;
+    persist_pending_calls(&calls)?;
+    Ok(deadline)
+}
+
+#[tauri::command]
+pub(crate) fn set_deferral_window(window: Option<DeferralWindow>) -> Result<(), AppError> {
+    ensure_writable()?;
+    if let Some(w) = &window {
+        if parse_hhmm(&w.start).is_none() || parse_hhmm(&w.end).is_none() {
+            return Err(AppError::InvalidInput("Deferral window times must be HH:MM".into()));
+        }
+    }
+    let mut config = load_config();
+    config.deferral_window = window;
+    save_config(&config)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queued(id: &str, enqueued_at: u64, timeout_secs: Option<u64>) -> PendingCall {
+        PendingCall {
+            id: id.into(),
+            agent_id: "main".into(),
+            message: "hello".into(),
+            session_key: "s".into(),
+            priority: CallPriority::Background,
+            enqueued_at,
+            pinned: false,
+            deadline: deadline_after(enqueued_at, timeout_secs, DEFERRED_STALE_MS),
+        }
+    }
+
+    #[test]
+    fn call_behind_a_long_one_expires_while_it_waits() {
+        let calls = vec![queued("long", 0, None), queued("short", 1_000, Some(60))];
+        let mut running = std::collections::HashSet::new();
+        let first = next_deferred_call(&calls, &running).unwrap();
+        running.insert(first.id.clone());
+        // The first call runs for two minutes; the one behind it only had one
+        let now = 120_000;
+        let next = next_deferred_call(&calls, &running).unwrap();
+        assert_eq!(next.id, "short");
+        assert!(next.is_expired(now));
+        assert!(!first.is_expired(now));
+    }
+
+    #[test]
+    fn deadlines_saturate_instead_of_wrapping() {
+        assert_eq!(deadline_after(5, Some(u64::MAX), 0), u64::MAX);
+        assert_eq!(deadline_after(u64::MAX - 1, None, DEFERRED_STALE_MS), u64::MAX);
+        let mut call = queued("a", 0, Some(u64::MAX / 1000 + 1));
+        assert!(!call.is_expired(u64::MAX - 1));
+        call.deadline = deadline_after(call.effective_deadline(), Some(u64::MAX), 0);
+        assert_eq!(call.deadline, u64::MAX);
+    }
+
+    #[test]
+    fn extending_moves_the_deadline_from_the_current_one() {
+        let mut call = queued("a", 1_000, Some(10));
+        assert_eq!(call.effective_deadline(), 11_000);
+        assert!(call.is_expired(11_001));
+        call.deadline = deadline_after(call.effective_deadline(), Some(30), 0);
+        assert_eq!(call.deadline, 41_000);
+        assert!(!call.is_expired(11_001));
+    }
+
+    #[test]
+    fn calls_queued_before_deadlines_existed_get_the_default() {
+        let mut call = queued("a", 1_000, None);
+        call.deadline = 0;
+        assert_eq!(call.effective_deadline(), 1_000 + DEFERRED_STALE_MS);
+    }
+
+    /// Calls queued before the app was closed: on the next start the ones past their
+    /// deadline go in one pass, and the rest stay queued in order to run.
+    #[test]
+    fn catching_up_after_a_restart_drops_past_due_calls_and_keeps_the_rest() {
+        let now = now_ms();
+        persist_pending_calls(&[
+            queued("catchup-stale", now - 2 * DEFERRED_STALE_MS, None),
+            queued("catchup-due", now - DEFERRED_STALE_MS / 2, None),
+            queued("catchup-running", now - 120_000, Some(60)),
+            queued("catchup-short", now - 120_000, Some(60)),
+            queued("catchup-fresh", now - 1_000, Some(60)),
+        ]).unwrap();
+        let state = AppState::new(None);
+        state.pending_calls.lock().unwrap().retain(|c| c.id.starts_with("catchup-"));
+        state.deferred_running.lock().unwrap().insert("catchup-running".into());
+
+        let dropped: Vec<String> = drop_expired_calls(&state, now).into_iter().map(|c| c.id).collect();
+        assert_eq!(dropped, ["catchup-stale", "catchup-short"]);
+        let ids = |calls: &[PendingCall]| calls.iter().map(|c| c.id.clone()).filter(|id| id.starts_with("catchup-")).collect::<Vec<_>>();
+        let kept = ["catchup-due", "catchup-running", "catchup-fresh"];
+        assert_eq!(ids(&state.pending_calls.lock().unwrap()), kept);
+        assert_eq!(ids(&load_pending_calls()), kept);
+        let (next, expired) = claim_deferred_call(&state, now).unwrap();
+        assert_eq!(next.id, "catchup-due");
+        assert!(!expired);
+        // Nothing left to catch up on
+        assert!(drop_expired_calls(&state, now).is_empty());
+    }
+}
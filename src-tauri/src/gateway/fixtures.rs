@@ -0,0 +1,264 @@
+//! Fixture mode: recorded OpenClaw interactions replayed instead of running the CLI,
+//! so the app works offline without node, OpenClaw or an API key.
+
+use crate::*;
+
+// ─── Fixtures ─────────────────────────────────────────────────────────────────
+
+/// Set to a fixtures directory, or to "1" for the default one, to start in fixture mode.
+pub(crate) const FIXTURES_ENV: &str = "CLAPP_FIXTURES";
+
+/// One piece of output, `delay_ms` after the previous one.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct FixtureChunk {
+    pub(crate) delay_ms: u64,
+    #[serde(default)]
+    pub(crate) stderr: bool,
+    pub(crate) text: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Fixture {
+    pub(crate) name: String,
+    /// OpenClaw subcommand it answers, e.g. "gateway health"
+    pub(crate) command: String,
+    /// Only played when the command line contains this, e.g. a word of the message.
+    /// Fixtures without it are the fallback for their command.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) when_args_contain: Option<String>,
+    pub(crate) chunks: Vec<FixtureChunk>,
+    #[serde(default)]
+    pub(crate) exit_code: i32,
+    /// HTTP status of an `http <METHOD>` fixture; 200 when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) status: Option<u16>,
+}
+
+pub(crate) fn fixture_mode() -> bool {
+    std::env::var_os(FIXTURES_ENV).is_some() || load_config().fixture_mode
+}
+
+pub(crate) fn fixtures_dir() -> PathBuf {
+    match std::env::var(FIXTURES_ENV) {
+        Ok(dir) if !dir.trim().is_empty() && dir != "1" => PathBuf::from(dir),
+        _ => clapp_dir().join("fixtures"),
+    }
+}
+
+pub(crate) fn chunk(delay_ms: u64, text: &str) -> FixtureChunk {
+    FixtureChunk { delay_ms, stderr: false, text: text.into() }
+}
+
+/// A reply document split over several lines, the way a long reply streams in.
+pub(crate) fn reply_chunks(text: &str, latency_ms: u64) -> Vec<FixtureChunk> {
+    let doc = serde_json::json!({
+        "status": "ok",
+        "result": { "payloads": [{ "text": text }], "usage": { "input": 412, "output": text.len() / 4 } },
+    });
+    let pretty = serde_json::to_string_pretty(&doc).unwrap();
+    pretty.lines()
+        .enumerate()
+        .map(|(i, line)| chunk(if i == 0 { latency_ms } else { 40 }, line))
+        .collect()
+}
+
+/// Written to an empty fixtures directory so fixture mode works out of the box.
+pub(crate) fn builtin_fixtures() -> Vec<Fixture> {
+    let fixture = |name: &str, command: &str, when: Option<&str>, chunks: Vec<FixtureChunk>, exit_code: i32| Fixture {
+        name: name.into(),
+        command: command.into(),
+        when_args_contain: when.map(String::from),
+        chunks,
+        exit_code,
+        status: None,
+    };
+    vec![
+        fixture("health", "gateway health", None, vec![chunk(120, "Gateway: ok (port 18789)")], 0),
+        fixture("pair", "gateway pair", None, vec![chunk(200, "Paired: ok")], 0),
+        fixture("config", "gateway config", None, vec![chunk(150, r#"{"gateway": {"port": 18789, "bind": "loopback"}}"#)], 0),
+        fixture("metrics", "gateway metrics", None, vec![chunk(150, r#"{"uptimeSeconds": 3600, "requests": 42, "activeSessions": 2}"#)], 0),
+        fixture("reply", "gateway call", None, reply_chunks(
+            "Sure. Here is a short overview:\n\n1. The gateway is running in fixture mode.\n2. Replies come from recorded fixtures.\n3. Nothing is sent to a model.",
+            1_800,
+        ), 0),
+        fixture("reply-hello", "gateway call", Some("hello"), reply_chunks("Hello! How can I help you today?", 900), 0),
+        fixture("error-auth", "gateway call", Some("fixture-error"), vec![FixtureChunk {
+            delay_ms: 600,
+            stderr: true,
+            text: r#"Error: 401 {"type":"error","error":{"type":"authentication_error","message":"invalid x-api-key"}}"#.into(),
+        }], 1),
+        // Outgoing HTTP: the key check lists models, telemetry posts batches
+        fixture("http-models", "http GET", Some("/models"), vec![chunk(300, r#"{"data": []}"#)], 0),
+        fixture("http-post", "http POST", None, vec![chunk(100, "{}")], 0),
+    ]
+}
+
+pub(crate) fn load_fixtures() -> Vec<Fixture> {
+    let dir = fixtures_dir();
+    let read = || -> Vec<Fixture> {
+        let Ok(entries) = fs::read_dir(&dir) else { return Vec::new() };
+        let mut paths: Vec<PathBuf> = entries.flatten().map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|e| e == "json"))
+            .collect();
+        paths.sort();
+        paths.iter()
+            .filter_map(|p| serde_json::from_str(&fs::read_to_string(p).ok()?).ok())
+            .collect()
+    };
+    let fixtures = read();
+    if !fixtures.is_empty() {
+        return fixtures;
+    }
+    for f in builtin_fixtures() {
+        save_fixture(&f).ok();
+    }
+    builtin_fixtures()
+}
+
+pub(crate) fn save_fixture(fixture: &Fixture) -> Result<(), AppError> {
+    fs::create_dir_all(fixtures_dir())?;
+    fs::write(fixtures_dir().join(format!("{}.json", fixture.name)), serde_json::to_string_pretty(fixture)?)?;
+    Ok(())
+}
+
+/// The most specific fixture for the command line: one whose `when_args_contain`
+/// matches, else the fallback for the command.
+pub(crate) fn find_fixture(args: &[String]) -> Option<Fixture> {
+    pick_fixture(load_fixtures(), args)
+}
+
+pub(crate) fn pick_fixture(fixtures: Vec<Fixture>, args: &[String]) -> Option<Fixture> {
+    let line = args.join(" ");
+    let fixtures: Vec<Fixture> = fixtures.into_iter().filter(|f| line.contains(&f.command)).collect();
+    let specific = fixtures.iter()
+        .find(|f| f.when_args_contain.as_deref().is_some_and(|w| line.to_lowercase().contains(&w.to_lowercase())));
+    specific.or_else(|| fixtures.iter().find(|f| f.when_args_contain.is_none())).cloned()
+}
+
+/// Plays the fixture's chunks with their recorded timing.
+pub(crate) async fn play_fixture(args: &[String]) -> Result<(Vec<u8>, Vec<u8>), String> {
+    let fixture = find_fixture(args).ok_or_else(|| format!("No fixture for: {}", args.join(" ")))?;
+    let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+    for c in &fixture.chunks {
+        tokio::time::sleep(std::time::Duration::from_millis(c.delay_ms)).await;
+        if !c.stderr {
+            with_call_timer(|t| t.first_byte());
+        }
+        let out = if c.stderr { &mut stderr } else { &mut stdout };
+        out.extend(c.text.as_bytes());
+        out.push(b'\n');
+    }
+    Ok((stdout, stderr))
+}
+
+/// Runs an OpenClaw subcommand, or replays its fixture in fixture mode.
+pub(crate) async fn run_openclaw(app: &tauri::AppHandle, rest: &[&str]) -> Result<(Vec<u8>, Vec<u8>), String> {
+    let args = openclaw_args(rest);
+    if fixture_mode() {
+        return play_fixture(&args).await;
+    }
+    let out = app.shell().command("cmd").args(&args).output().await.map_err(|e| e.to_string())?;
+    Ok((out.stdout, out.stderr))
+}
+
+/// Answers an outgoing HTTP request from its `http <METHOD>` fixture: status and body.
+/// Directories recorded before HTTP fixtures existed fall back to the built-in ones;
+/// a request without either fails rather than going out to the network.
+pub(crate) async fn play_http_fixture(method: &str, url: &str) -> Result<(u16, String), String> {
+    let args = [format!("http {} {}", method, url)];
+    let fixture = find_fixture(&args)
+        .or_else(|| pick_fixture(builtin_fixtures(), &args))
+        .ok_or_else(|| format!("No fixture for: {}", args[0]))?;
+    let mut body = Vec::new();
+    for c in &fixture.chunks {
+        tokio::time::sleep(std::time::Duration::from_millis(c.delay_ms)).await;
+        body.push(c.text.as_str());
+    }
+    Ok((fixture.status.unwrap_or(200), body.join("\n")))
+}
+
+pub(crate) fn health_says_ok(stdout: &[u8], stderr: &[u8]) -> bool {
+    String::from_utf8_lossy(stdout).to_lowercase().contains("ok")
+        || String::from_utf8_lossy(stderr).to_lowercase().contains("ok")
+}
+
+/// Fixture mode's launch: the recorded health answer stands in for the gateway.
+/// It needs no API key and leaves `~/.openclaw` alone.
+pub(crate) async fn start_fixture_gateway() -> Result<(), String> {
+    let (stdout, stderr) = play_fixture(&openclaw_args(&["gateway", "health"])).await?;
+    if health_says_ok(&stdout, &stderr) {
+        Ok(())
+    } else {
+        Err("The health fixture does not report the gateway as up".into())
+    }
+}
+
+/// Shareable: secrets and the home directory are taken out of every chunk.
+pub(crate) fn redact_fixture_text(text: &str) -> String {
+    let text = redact_text(text);
+    match dirs::home_dir() {
+        Some(home) => text.replace(&*home.to_string_lossy(), "~"),
+        None => text,
+    }
+}
+
+/// Saves the output of a real call, with timing, as a fixture.
+pub(crate) fn save_recording(name: &str, chunks: Vec<FixtureChunk>, exit_code: i32) {
+    let fixture = Fixture {
+        name: name.into(),
+        command: "gateway call".into(),
+        when_args_contain: None,
+        chunks: chunks.into_iter().map(|c| FixtureChunk { text: redact_fixture_text(&c.text), ..c }).collect(),
+        exit_code,
+        status: None,
+    };
+    match save_fixture(&fixture) {
+        Ok(()) => println!("[FIXTURES] recorded {}", name),
+        Err(e) => eprintln!("[FIXTURES ERR] {}: {}", name, e),
+    }
+}
+
+/// Records the next real gateway call as fixture `name`, redacted.
+#[tauri::command]
+pub(crate) fn record_fixture(state: tauri::State<AppState>, name: String) -> Result<(), AppError> {
+    ensure_writable()?;
+    if fixture_mode() {
+        return Err(AppError::InvalidInput("Fixture mode is on; recording needs the real gateway".into()));
+    }
+    validate_agent_id(&name).map_err(|_| AppError::InvalidInput(format!(
+        "Fixture name '{}' must be 1-64 characters of letters, digits, '-' or '_'", name
+    )))?;
+    *state.fixture_recording.lock().unwrap() = Some(name);
+    Ok(())
+}
+
+/// Takes effect immediately; `gateway_status` reports it so it can't ship on unnoticed.
+#[tauri::command]
+pub(crate) fn set_fixture_mode(enabled: bool) -> Result<(), AppError> {
+    ensure_writable()?;
+    let mut config = load_config();
+    config.fixture_mode = enabled;
+    save_config(&config)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixture_mode_starts_and_checks_a_key_offline() {
+        let rt = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+        rt.block_on(async {
+            // No key is read and nothing is spawned; the health fixture is the gateway
+            start_fixture_gateway().await.unwrap();
+            let (status, body) = play_http_fixture("GET", "https://api.anthropic.com/v1/models").await.unwrap();
+            assert_eq!(status, 200);
+            assert!(body.contains("data"));
+            // Unrecorded requests fail instead of reaching the network
+            assert!(play_http_fixture("GET", "https://example.com/other").await.is_err());
+        });
+    }
+}
@@ -0,0 +1,372 @@
+//! Who the running gateway belongs to.
+
+use crate::*;
+
+// ─── Gateway process identity ─────────────────────────────────────────────────
+
+#[derive(serde::Serialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ProcessIdentity {
+    /// `None` when the owner can't be read, which itself means another user or elevation
+    pub(crate) user: Option<String>,
+    pub(crate) elevated: Option<bool>,
+}
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UserMismatch {
+    /// `None` when the listener belongs to a user whose processes we can't inspect
+    pub(crate) gateway_pid: Option<u32>,
+    pub(crate) gateway: ProcessIdentity,
+    pub(crate) current: ProcessIdentity,
+    pub(crate) guidance: String,
+}
+
+/// The process listening on the gateway port, as far as we can see it.
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) struct Listener {
+    pub(crate) pid: Option<u32>,
+    /// Owner of the socket, known on Linux even when the process is hidden from us
+    pub(crate) uid: Option<String>,
+}
+
+/// True when an unmanaged gateway reads a different ~/.openclaw than we write to.
+pub(crate) fn identities_differ(current: &ProcessIdentity, gateway: &ProcessIdentity) -> bool {
+    let user_differs = match (&current.user, &gateway.user) {
+        (Some(a), Some(b)) => !a.eq_ignore_ascii_case(b),
+        // Unprivileged processes can't see the owner of elevated or foreign processes
+        (_, None) => current.elevated != Some(true),
+        (None, Some(_)) => false,
+    };
+    let elevation_differs = matches!((current.elevated, gateway.elevated), (Some(a), Some(b)) if a != b);
+    user_differs || elevation_differs
+}
+
+pub(crate) fn mismatch_guidance(pid: Option<u32>, gateway: &ProcessIdentity, current: &ProcessIdentity) -> String {
+    let who = |i: &ProcessIdentity| format!(
+        "{}{}",
+        i.user.as_deref().unwrap_or("an unknown user"),
+        if i.elevated == Some(true) { " (elevated)" } else { "" }
+    );
+    format!(
+        "A gateway{} is already running as {} while Clapp runs as {}. \
+         It reads a different ~/.openclaw, so settings from Clapp won't apply. \
+         Stop it from the terminal it was started in, or use \"Stop foreign gateway\" if you have permission.",
+        pid.map(|p| format!(" (PID {})", p)).unwrap_or_default(), who(gateway), who(current)
+    )
+}
+
+#[cfg(any(test, not(target_os = "linux")))]
+pub(crate) fn parse_netstat_listener(output: &str, port: u16) -> Option<u32> {
+    let suffix = format!(":{}", port);
+    output.lines().find_map(|l| {
+        let cols: Vec<&str> = l.split_whitespace().collect();
+        // Proto  Local Address  Foreign Address  State  PID
+        (cols.len() >= 5 && cols[1].ends_with(&suffix) && cols[3] == "LISTENING")
+            .then(|| cols[4].parse().ok())
+            .flatten()
+    })
+}
+
+/// Socket inode and owner uid of the listener on `port` in a /proc/net/tcp table.
+#[cfg(any(test, target_os = "linux"))]
+pub(crate) fn parse_proc_net_listener(table: &str, port: u16) -> Option<(String, String)> {
+    let hex_port = format!(":{:04X}", port);
+    table.lines().skip(1).find_map(|l| {
+        let cols: Vec<&str> = l.split_whitespace().collect();
+        // sl  local_address  rem_address  st  tx:rx  tr:when  retrnsmt  uid  timeout  inode; 0A = LISTEN
+        (cols.len() > 9 && cols[1].ends_with(&hex_port) && cols[3] == "0A").then(|| (cols[9].to_string(), cols[7].to_string()))
+    })
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn linux_listener(port: u16) -> Option<Listener> {
+    let (inode, uid) = ["/proc/net/tcp", "/proc/net/tcp6"].iter()
+        .find_map(|f| parse_proc_net_listener(&fs::read_to_string(f).ok()?, port))?;
+    let needle = format!("socket:[{}]", inode);
+    // Another user's fd tables are unreadable; the uid from the socket table still says who it is
+    let pid = fs::read_dir("/proc").ok()?.flatten().find_map(|p| {
+        let pid: u32 = p.file_name().to_str()?.parse().ok()?;
+        fs::read_dir(p.path().join("fd")).ok()?.flatten()
+            .any(|fd| fs::read_link(fd.path()).is_ok_and(|t| t.to_string_lossy() == needle))
+            .then_some(pid)
+    });
+    Some(Listener { pid, uid: Some(uid) })
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn linux_identity(pid: &str) -> ProcessIdentity {
+    let uid = fs::read_to_string(format!("/proc/{}/status", pid)).ok().and_then(|s| {
+        s.lines().find(|l| l.starts_with("Uid:"))?.split_whitespace().nth(1).map(String::from)
+    });
+    uid_identity(uid)
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn uid_identity(uid: Option<String>) -> ProcessIdentity {
+    ProcessIdentity { elevated: uid.as_deref().map(|u| u == "0"), user: uid }
+}
+
+/// Whether a process runs elevated, read from its token; `pid: None` is this process.
+/// `None` when the token can't be opened, as for an elevated process seen from an
+/// unelevated one.
+#[cfg(windows)]
+pub(crate) fn windows_elevated(pid: Option<u32>) -> Option<bool> {
+    use windows_sys::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows_sys::Win32::Security::{GetTokenInformation, TokenElevation, TOKEN_ELEVATION, TOKEN_QUERY};
+    use windows_sys::Win32::System::Threading::{GetCurrentProcess, OpenProcess, OpenProcessToken, PROCESS_QUERY_LIMITED_INFORMATION};
+
+    // SAFETY: every handle is checked before use and closed once; the buffer is
+    // a TOKEN_ELEVATION of the size passed
+    unsafe {
+        let process = match pid {
+            Some(pid) => OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid),
+            None => GetCurrentProcess(),
+        };
+        if process.is_null() {
+            return None;
+        }
+        let mut token: HANDLE = std::ptr::null_mut();
+        let opened = OpenProcessToken(process, TOKEN_QUERY, &mut token) != 0;
+        if pid.is_some() {
+            CloseHandle(process);
+        }
+        if !opened {
+            return None;
+        }
+        let mut elevation = TOKEN_ELEVATION { TokenIsElevated: 0 };
+        let mut len = 0u32;
+        let read = GetTokenInformation(
+            token,
+            TokenElevation,
+            &mut elevation as *mut TOKEN_ELEVATION as *mut std::ffi::c_void,
+            std::mem::size_of::<TOKEN_ELEVATION>() as u32,
+            &mut len,
+        ) != 0;
+        CloseHandle(token);
+        read.then_some(elevation.TokenIsElevated != 0)
+    }
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+pub(crate) fn windows_elevated(_pid: Option<u32>) -> Option<bool> {
+    None
+}
+
+/// Cheap enough for every status poll: no process spawns on Linux, one netstat elsewhere.
+pub(crate) async fn find_listener(app: &tauri::AppHandle, port: u16) -> Option<Listener> {
+    #[cfg(target_os = "linux")]
+    {
+        let _ = app;
+        linux_listener(port)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let netstat = app.shell().command("cmd").args(["/C", "netstat", "-ano", "-p", "TCP"]).output().await.ok()?;
+        let pid = parse_netstat_listener(&String::from_utf8_lossy(&netstat.stdout), port)?;
+        Some(Listener { pid: Some(pid), uid: None })
+    }
+}
+
+pub(crate) async fn listener_identity(app: &tauri::AppHandle, listener: &Listener) -> ProcessIdentity {
+    #[cfg(target_os = "linux")]
+    {
+        let _ = app;
+        match listener.pid {
+            Some(pid) => linux_identity(&pid.to_string()),
+            None => uid_identity(listener.uid.clone()),
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let Some(pid) = listener.pid else { return ProcessIdentity { user: None, elevated: None } };
+        let filter = format!("PID eq {}", pid);
+        let tasks = app.shell().command("cmd")
+            .args(["/C", "tasklist", "/V", "/FI", &filter, "/FO", "CSV", "/NH"])
+            .output()
+            .await
+            .ok();
+        // "Image Name","PID","Session Name","Session#","Mem Usage","Status","User Name",...
+        let line = tasks.map(|t| String::from_utf8_lossy(&t.stdout).lines().next().unwrap_or("").to_string()).unwrap_or_default();
+        let cols: Vec<&str> = line.trim_matches('"').split("\",\"").collect();
+        let user = cols.get(6).filter(|u| **u != "N/A" && !u.is_empty()).map(|u| u.to_string());
+        ProcessIdentity { user, elevated: windows_elevated(Some(pid)) }
+    }
+}
+
+pub(crate) async fn current_identity(app: &tauri::AppHandle) -> ProcessIdentity {
+    #[cfg(target_os = "linux")]
+    {
+        let _ = app;
+        linux_identity("self")
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let user = app.shell().command("cmd").args(["/C", "whoami"]).output().await.ok()
+            .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+            .filter(|u| !u.is_empty());
+        ProcessIdentity { user, elevated: windows_elevated(None) }
+    }
+}
+
+/// Who we run as doesn't change, and a listener's owner doesn't either, so the
+/// comparison is only redone when another process takes the port.
+pub(crate) async fn detect_user_mismatch(app: &tauri::AppHandle) -> Option<UserMismatch> {
+    let listener = find_listener(app, read_gateway_port()).await?;
+    let state = app.state::<AppState>();
+    let cached = state.identity_check.lock().unwrap().clone();
+    if let Some((_, mismatch)) = cached.filter(|(seen, _)| *seen == listener) {
+        return mismatch;
+    }
+    let gateway = listener_identity(app, &listener).await;
+    let current = state.own_identity.get_or_init(|| current_identity(app)).await.clone();
+    let mismatch = identities_differ(&current, &gateway).then(|| UserMismatch {
+        gateway_pid: listener.pid,
+        guidance: mismatch_guidance(listener.pid, &gateway, &current),
+        gateway,
+        current,
+    });
+    *state.identity_check.lock().unwrap() = Some((listener, mismatch.clone()));
+    mismatch
+}
+
+/// Force-stops a gateway that Clapp did not start. Fails if we lack permission.
+#[tauri::command]
+pub(crate) async fn stop_foreign_gateway(app: tauri::AppHandle) -> Result<(), AppError> {
+    ensure_writable()?;
+    let listener = find_listener(&app, read_gateway_port())
+        .await
+        .ok_or_else(|| AppError::NotFound("no gateway is listening".into()))?;
+    let pid = listener.pid.ok_or_else(|| AppError::Other(
+        "The gateway belongs to another user and its PID is hidden from Clapp. Stop it as that user.".into()
+    ))?;
+    let out = app.shell()
+        .command("cmd")
+        .args(["/C", "taskkill", "/PID", &pid.to_string(), "/T", "/F"])
+        .output()
+        .await
+        .map_err(|e| AppError::Other(e.to_string()))?;
+    if !out.status.success() {
+        return Err(AppError::Other(format!(
+            "Could not stop gateway (PID {}): {}. Stop it from an elevated terminal.",
+            pid,
+            String::from_utf8_lossy(&out.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+// ─── Gateway process memory ───────────────────────────────────────────────────
+
+/// Resident memory in KB.
+pub(crate) async fn process_memory_kb(app: &tauri::AppHandle, pid: u32) -> Result<u64, AppError> {
+    #[cfg(target_os = "linux")]
+    {
+        let _ = app;
+        let status = fs::read_to_string(format!("/proc/{}/status", pid))
+            .map_err(|_| AppError::NotFound(format!("process {}", pid)))?;
+        status.lines()
+            .find_map(|l| l.strip_prefix("VmRSS:"))
+            .and_then(|v| v.split_whitespace().next()?.parse().ok())
+            .ok_or_else(|| AppError::Other(format!("No memory usage for process {}", pid)))
+    }
+    #[cfg(target_os = "macos")]
+    {
+        let out = app.shell().command("ps").args(["-o", "rss=", "-p", &pid.to_string()])
+            .output()
+            .await
+            .map_err(|e| AppError::Other(e.to_string()))?;
+        String::from_utf8_lossy(&out.stdout).trim().parse()
+            .map_err(|_| AppError::NotFound(format!("process {}", pid)))
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    {
+        let filter = format!("PID eq {}", pid);
+        let out = app.shell().command("cmd")
+            .args(["/C", "tasklist", "/FI", &filter, "/FO", "CSV", "/NH"])
+            .output()
+            .await
+            .map_err(|e| AppError::Other(e.to_string()))?;
+        // Last column, e.g. "45,678 K"
+        let stdout = String::from_utf8_lossy(&out.stdout);
+        let line = stdout.lines().find(|l| l.contains(&format!("\"{}\"", pid)))
+            .ok_or_else(|| AppError::NotFound(format!("process {}", pid)))?;
+        let digits: String = line.rsplit("\",\"").next().unwrap_or("").chars().filter(|c| c.is_ascii_digit()).collect();
+        digits.parse().map_err(|_| AppError::Other(format!("No memory usage for process {}", pid)))
+    }
+}
+
+/// The gateway's own PID. What we spawn is often a wrapper (`cmd /C`, npx,
+/// docker), so prefer the PID the gateway writes to its pid file, and fall
+/// back to the spawned child only when that is missing or stale.
+async fn gateway_process_pid(app: &tauri::AppHandle) -> Option<u32> {
+    let child = app.state::<AppState>().process.lock().unwrap().as_ref().map(|c| c.pid())?;
+    let from_file = fs::read_to_string(gateway_pid_path()).ok()
+        .and_then(|s| s.trim().parse::<u32>().ok());
+    match from_file {
+        Some(pid) if pid_alive(app, pid).await => Some(pid),
+        _ => Some(child),
+    }
+}
+
+/// Resident memory of the gateway we launched, in MB. `None` when we don't have its PID.
+#[tauri::command]
+pub(crate) async fn get_gateway_process_memory_mb(app: tauri::AppHandle) -> Result<Option<f64>, AppError> {
+    let Some(pid) = gateway_process_pid(&app).await else { return Ok(None) };
+    let kb = process_memory_kb(&app, pid).await?;
+    Ok(Some(kb as f64 / 1024.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn who(user: Option<&str>, elevated: Option<bool>) -> ProcessIdentity {
+        ProcessIdentity { user: user.map(String::from), elevated }
+    }
+
+    #[test]
+    fn only_another_user_or_elevation_is_a_mismatch() {
+        let me = who(Some("DESK\\ana"), Some(false));
+        let cases = [
+            (who(Some("desk\\ANA"), Some(false)), false),
+            (who(Some("DESK\\ana"), None), false),
+            (who(Some("DESK\\bo"), Some(false)), true),
+            (who(Some("DESK\\ana"), Some(true)), true),
+            // An owner we can't read is someone else, or us elevated
+            (who(None, None), true),
+        ];
+        for (gateway, differ) in cases {
+            assert_eq!(identities_differ(&me, &gateway), differ, "{:?}", gateway);
+        }
+        // Elevated, we'd see any owner, so an unreadable one isn't a sign of anything
+        assert!(!identities_differ(&who(Some("DESK\\ana"), Some(true)), &who(None, None)));
+        assert!(!identities_differ(&who(None, None), &who(Some("DESK\\ana"), None)));
+    }
+
+    #[test]
+    fn netstat_finds_the_listening_pid_for_the_port() {
+        let output = "\
+Active Connections
+
+  Proto  Local Address          Foreign Address        State           PID
+  TCP    0.0.0.0:135            0.0.0.0:0              LISTENING       1024
+  TCP    127.0.0.1:18789        127.0.0.1:53122        ESTABLISHED     7310
+  TCP    127.0.0.1:187890       0.0.0.0:0              LISTENING       9999
+  TCP    127.0.0.1:18789        0.0.0.0:0              LISTENING       4242
+";
+        assert_eq!(parse_netstat_listener(output, 18789), Some(4242));
+        assert_eq!(parse_netstat_listener(output, 8080), None);
+    }
+
+    #[test]
+    fn proc_net_tcp_gives_the_listeners_inode_and_owner() {
+        let table = "\
+  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:4965 0100007F:D0C2 01 00000000:00000000 00:00000000 00000000  1000        0 55501 1
+   1: 0100007F:4965 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1001        0 55500 1
+";
+        assert_eq!(parse_proc_net_listener(table, 18789), Some(("55500".into(), "1001".into())));
+        assert_eq!(parse_proc_net_listener(table, 8080), None);
+    }
+}
@@ -0,0 +1,221 @@
+//! Every OpenClaw installation we can find, and the one the app runs.
+
+use crate::*;
+
+// ─── Installations ────────────────────────────────────────────────────────────
+
+#[derive(serde::Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum InstallSource {
+    /// `npm install -g`
+    Global,
+    /// A `node_modules` in the current directory or above; what npx picks first
+    Local,
+    Volta,
+    Nvm,
+    NpxCache,
+    /// `gateway_binary_path`
+    Configured,
+    /// Shipped next to the app executable
+    Sidecar,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct OpenClawInstall {
+    pub(crate) source: InstallSource,
+    /// Package directory, or the executable for configured and sidecar installs
+    pub(crate) path: String,
+    pub(crate) version: Option<String>,
+    pub(crate) selected: bool,
+}
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct NodeDiagnosis {
+    pub(crate) node_version: Option<String>,
+    pub(crate) npm_version: Option<String>,
+    pub(crate) installs: Vec<OpenClawInstall>,
+    pub(crate) selected: Option<String>,
+    /// What a plain `npx openclaw` resolves to from the app's working directory
+    pub(crate) npx_version: Option<String>,
+    pub(crate) warnings: Vec<String>,
+}
+
+pub(crate) fn package_json(dir: &std::path::Path) -> Option<serde_json::Value> {
+    serde_json::from_str(&fs::read_to_string(dir.join("package.json")).ok()?).ok()
+}
+
+/// The script `openclaw` runs, from the package's `bin` field.
+pub(crate) fn package_bin(dir: &std::path::Path) -> Option<PathBuf> {
+    let v = package_json(dir)?;
+    let bin = match &v["bin"] {
+        serde_json::Value::String(s) => s.clone(),
+        b => b["openclaw"].as_str()?.to_string(),
+    };
+    Some(dir.join(bin))
+}
+
+/// Program words that run the selected installation, or `npx openclaw` when none is chosen.
+pub(crate) fn openclaw_program(config: &AppConfig) -> Vec<String> {
+    let Some(path) = config.openclaw_install.as_deref() else {
+        return vec!["npx".into(), "openclaw".into()];
+    };
+    let p = std::path::Path::new(path);
+    match p.is_dir().then(|| package_bin(p)).flatten() {
+        Some(bin) => vec!["node".into(), bin.to_string_lossy().into_owned()],
+        None => vec![path.into()],
+    }
+}
+
+/// `cmd` arguments for an OpenClaw subcommand, e.g. `openclaw_args(&["gateway", "health"])`.
+pub(crate) fn openclaw_args(rest: &[&str]) -> Vec<String> {
+    let mut args = vec!["/C".to_string()];
+    args.extend(openclaw_program(&load_config()));
+    args.extend(rest.iter().map(|s| s.to_string()));
+    args
+}
+
+pub(crate) fn package_install(source: InstallSource, dir: PathBuf) -> Option<OpenClawInstall> {
+    let version = package_json(&dir)?["version"].as_str().map(String::from);
+    Some(OpenClawInstall { source, path: dir.to_string_lossy().into_owned(), version, selected: false })
+}
+
+/// `openclaw` packages in each of the given `node_modules` directories.
+pub(crate) fn packages_in(source: InstallSource, roots: impl IntoIterator<Item = PathBuf>) -> Vec<OpenClawInstall> {
+    roots.into_iter().filter_map(|r| package_install(source, r.join("openclaw"))).collect()
+}
+
+/// Subdirectories, for one-directory-per-version layouts like nvm and the npx cache.
+pub(crate) fn subdirs(dir: &std::path::Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|e| e.path()).filter(|p| p.is_dir()).collect())
+        .unwrap_or_default();
+    dirs.sort();
+    dirs
+}
+
+pub(crate) async fn executable_install(app: &tauri::AppHandle, source: InstallSource, path: &std::path::Path) -> Option<OpenClawInstall> {
+    if !is_executable(path) {
+        return None;
+    }
+    let path = path.to_string_lossy().into_owned();
+    let version = command_version(app, &["/C", &path, "--version"]).await;
+    Some(OpenClawInstall { source, path, version, selected: false })
+}
+
+/// Looks everywhere npm, npx and the version managers put packages. The same
+/// directory reached two ways is listed once, under the first source.
+pub(crate) async fn discover_openclaw_installs(app: &tauri::AppHandle) -> Vec<OpenClawInstall> {
+    let config = load_config();
+    let home = dirs::home_dir().unwrap_or_default();
+    let mut found = Vec::new();
+
+    if let Some(root) = command_version(app, &["/C", "npm", "root", "-g"]).await {
+        found.extend(packages_in(InstallSource::Global, [PathBuf::from(root)]));
+    }
+    if let Ok(cwd) = std::env::current_dir() {
+        found.extend(packages_in(InstallSource::Local, cwd.ancestors().map(|d| d.join("node_modules"))));
+    }
+
+    let volta_homes = [Some(home.join(".volta")), dirs::data_local_dir().map(|d| d.join("Volta"))];
+    for volta in volta_homes.into_iter().flatten() {
+        let package = volta.join("tools").join("image").join("packages").join("openclaw");
+        found.extend(packages_in(InstallSource::Volta, [package.join("lib").join("node_modules"), package.join("node_modules")]));
+    }
+
+    // nvm keeps versions under ~/.nvm/versions/node, nvm-windows under NVM_HOME
+    let nvm_unix = subdirs(&home.join(".nvm").join("versions").join("node"))
+        .into_iter()
+        .map(|v| v.join("lib").join("node_modules"));
+    let nvm_windows_home = std::env::var_os("NVM_HOME").map(PathBuf::from)
+        .or_else(|| dirs::config_dir().map(|d| d.join("nvm")));
+    let nvm_windows = nvm_windows_home.map(|h| subdirs(&h)).unwrap_or_default()
+        .into_iter()
+        .map(|v| v.join("node_modules"));
+    found.extend(packages_in(InstallSource::Nvm, nvm_unix.chain(nvm_windows)));
+
+    let cache = match command_version(app, &["/C", "npm", "config", "get", "cache"]).await {
+        Some(c) => PathBuf::from(c),
+        None if cfg!(windows) => dirs::data_local_dir().unwrap_or_default().join("npm-cache"),
+        None => home.join(".npm"),
+    };
+    found.extend(packages_in(InstallSource::NpxCache, subdirs(&cache.join("_npx")).into_iter().map(|d| d.join("node_modules"))));
+
+    if let Some(path) = config.gateway_binary_path.as_deref() {
+        found.extend(executable_install(app, InstallSource::Configured, std::path::Path::new(path)).await);
+    }
+    if let Some(dir) = std::env::current_exe().ok().and_then(|e| e.parent().map(|d| d.to_path_buf())) {
+        let name = if cfg!(windows) { "openclaw.exe" } else { "openclaw" };
+        found.extend(executable_install(app, InstallSource::Sidecar, &dir.join(name)).await);
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    found.retain(|i| seen.insert(fs::canonicalize(&i.path).unwrap_or_else(|_| PathBuf::from(&i.path))));
+    for install in &mut found {
+        install.selected = config.openclaw_install.as_deref() == Some(install.path.as_str());
+    }
+    found
+}
+
+/// Problems with the selected installation, each also emitted as an event:
+/// `openclaw-install-missing` when it is gone, `openclaw-version-mismatch` when
+/// a plain `npx openclaw` would run a different version.
+pub(crate) async fn check_selected_install(app: &tauri::AppHandle) -> Vec<String> {
+    let Some(path) = load_config().openclaw_install else { return Vec::new() };
+    if !std::path::Path::new(&path).exists() {
+        app.emit("openclaw-install-missing", serde_json::json!({ "path": path })).ok();
+        return vec![format!("The selected OpenClaw installation is gone: {}", path)];
+    }
+    let selected = command_version(app, &openclaw_args(&["--version"]).iter().map(String::as_str).collect::<Vec<_>>()).await;
+    let npx = command_version(app, &["/C", "npx", "openclaw", "--version"]).await;
+    if npx.is_some() && selected != npx {
+        app.emit("openclaw-version-mismatch", serde_json::json!({
+            "path": path,
+            "selectedVersion": selected,
+            "npxVersion": npx,
+        })).ok();
+        return vec![format!(
+            "npx runs OpenClaw {} but Clapp uses {} from {}; changes made from the command line may target a different version",
+            npx.unwrap_or_default(),
+            selected.unwrap_or_else(|| "an unknown version".into()),
+            path,
+        )];
+    }
+    Vec::new()
+}
+
+#[tauri::command]
+pub(crate) async fn diagnose_node_environment(app: tauri::AppHandle) -> NodeDiagnosis {
+    let installs = discover_openclaw_installs(&app).await;
+    let warnings = check_selected_install(&app).await;
+    NodeDiagnosis {
+        node_version: command_version(&app, &["/C", "node", "--version"]).await,
+        npm_version: command_version(&app, &["/C", "npm", "--version"]).await,
+        installs,
+        selected: load_config().openclaw_install,
+        npx_version: command_version(&app, &["/C", "npx", "openclaw", "--version"]).await,
+        warnings,
+    }
+}
+
+/// `None` goes back to plain `npx openclaw`. Only an installation that
+/// `diagnose_node_environment` lists can be chosen.
+#[tauri::command]
+pub(crate) async fn select_openclaw_install(app: tauri::AppHandle, path: Option<String>) -> Result<(), AppError> {
+    ensure_writable()?;
+    let path = path.map(|p| p.trim().to_string()).filter(|p| !p.is_empty());
+    if let Some(p) = &path {
+        if !discover_openclaw_installs(&app).await.iter().any(|i| &i.path == p) {
+            return Err(AppError::NotFound(format!("OpenClaw installation {}", p)));
+        }
+    }
+    let mut config = load_config();
+    config.openclaw_install = path.clone();
+    save_config(&config)?;
+    audit("openclaw_install_selected", serde_json::json!({ "path": path }));
+    // Another installation may keep ~/.openclaw in another shape; know before the next launch
+    let probe_app = app.clone();
+    run_storage_io(&app, move || refresh_data_format(&probe_app)).await?;
+    Ok(())
+}
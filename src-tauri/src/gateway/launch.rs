@@ -0,0 +1,338 @@
+//! How the gateway process is started: through npx, a prebuilt binary or Docker.
+
+use crate::*;
+
+// ─── Launch modes ─────────────────────────────────────────────────────────────
+
+#[derive(serde::Serialize, serde::Deserialize, Default, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum GatewayMode {
+    #[default]
+    Npx,
+    Binary,
+    Docker,
+}
+
+/// Program, arguments and environment for one gateway launch.
+pub(crate) struct GatewayLaunch {
+    pub(crate) program: String,
+    pub(crate) args: Vec<String>,
+    pub(crate) env: Vec<(String, String)>,
+}
+
+pub(crate) const GATEWAY_PORT: u16 = 18789;
+pub(crate) const GATEWAY_LOG_LEVELS: &[&str] = &["error", "warn", "info", "debug", "trace"];
+
+pub(crate) fn validate_log_level(level: &str) -> Result<(), String> {
+    if GATEWAY_LOG_LEVELS.contains(&level) {
+        Ok(())
+    } else {
+        Err(format!("Gateway log level '{}' must be one of {}", level, GATEWAY_LOG_LEVELS.join(", ")))
+    }
+}
+
+/// Arguments every mode passes to `openclaw gateway run`. `bind` is "loopback"
+/// except inside a container, where only the published port reaches the gateway.
+pub(crate) fn gateway_run_args(config: &AppConfig, bind: &str) -> Vec<String> {
+    let mut args: Vec<String> = ["gateway", "run", "--port", &GATEWAY_PORT.to_string(), "--bind", bind]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    if config.enable_cors {
+        args.push("--enable-cors".into());
+    }
+    // The gateway already defaults to info
+    if config.gateway_log_level != "info" {
+        args.extend(["--log-level".into(), config.gateway_log_level.clone()]);
+    }
+    args
+}
+
+pub(crate) fn provider_env(api_key: &str) -> Vec<(String, String)> {
+    vec![
+        ("ANTHROPIC_API_KEY".into(), api_key.into()),
+        ("OPENAI_API_KEY".into(), api_key.into()),
+    ]
+}
+
+pub(crate) fn npx_launch(config: &AppConfig, api_key: &str) -> GatewayLaunch {
+    let mut args = vec!["/C".to_string()];
+    args.extend(openclaw_program(config));
+    args.extend(gateway_run_args(config, "loopback"));
+    GatewayLaunch { program: "cmd".into(), args, env: provider_env(api_key) }
+}
+
+pub(crate) fn is_executable(path: &std::path::Path) -> bool {
+    let Ok(meta) = fs::metadata(path) else { return false };
+    if !meta.is_file() {
+        return false;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        meta.permissions().mode() & 0o111 != 0
+    }
+    #[cfg(not(unix))]
+    {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| ["exe", "cmd", "bat", "com"].contains(&e.to_ascii_lowercase().as_str()))
+    }
+}
+
+/// A prebuilt `openclaw` executable, started with the same arguments and keys as npx.
+pub(crate) fn binary_launch(config: &AppConfig, api_key: &str) -> Result<GatewayLaunch, String> {
+    let Some(path) = config.gateway_binary_path.as_deref() else {
+        return Err("Choose the OpenClaw executable for the binary gateway mode".into());
+    };
+    let p = std::path::Path::new(path);
+    if !p.exists() {
+        return Err(format!("Gateway executable not found: {}", path));
+    }
+    if !is_executable(p) {
+        return Err(format!("Gateway executable is not runnable: {}", path));
+    }
+    Ok(GatewayLaunch { program: path.to_string(), args: gateway_run_args(config, "loopback"), env: provider_env(api_key) })
+}
+
+pub(crate) fn docker_cid_path() -> PathBuf {
+    clapp_dir().join("gateway.cid")
+}
+
+/// Where ~/.openclaw is mounted in the container, so the gateway sees the same
+/// config, token and agents as the app.
+pub(crate) const DOCKER_OPENCLAW_DIR: &str = "/root/.openclaw";
+
+/// `docker run` stays attached so output forwarding works as in the other modes;
+/// the container id comes from `--cidfile`. Keys and the gateway token are passed
+/// by name so they never appear on the command line. The image runs
+/// `openclaw gateway run` with the same flags as the other modes, so it needs
+/// `openclaw` on its PATH.
+pub(crate) fn docker_launch(config: &AppConfig, api_key: &str, token: &str) -> Result<GatewayLaunch, String> {
+    let image = config.docker_image.trim();
+    if image.is_empty() {
+        return Err("Choose a Docker image for the docker gateway mode".into());
+    }
+    // docker refuses to start when the cidfile already exists
+    fs::remove_file(docker_cid_path()).ok();
+    let port = format!("127.0.0.1:{0}:{0}", GATEWAY_PORT);
+    let volume = format!("{}:{}", openclaw_dir().display(), DOCKER_OPENCLAW_DIR);
+    let mut args: Vec<String> = [
+        "run", "--rm",
+        "--cidfile", &docker_cid_path().to_string_lossy(),
+        "-p", &port,
+        "-v", &volume,
+        "-e", "ANTHROPIC_API_KEY",
+        "-e", "OPENAI_API_KEY",
+        "-e", "OPENCLAW_GATEWAY_TOKEN",
+        image,
+        "openclaw",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    // Published on the host's loopback only; inside, loopback would be unreachable
+    args.extend(gateway_run_args(config, "lan"));
+    let mut env = provider_env(api_key);
+    env.push(("OPENCLAW_GATEWAY_TOKEN".into(), token.into()));
+    Ok(GatewayLaunch { program: "docker".into(), args, env })
+}
+
+/// Waits for `docker run` to write the container id and remembers it for stop and status.
+pub(crate) async fn record_docker_container(app: tauri::AppHandle) {
+    for _ in 0..20 {
+        let id = fs::read_to_string(docker_cid_path()).unwrap_or_default().trim().to_string();
+        if !id.is_empty() {
+            *app.state::<AppState>().docker_container.lock().unwrap() = Some(id);
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    }
+    eprintln!("[DOCKER ERR] no container id in {}", docker_cid_path().display());
+}
+
+/// Stops the container we started, if any. `docker stop` gives it the usual grace period.
+pub(crate) async fn stop_agent_docker(app: &tauri::AppHandle) -> Result<bool, AppError> {
+    let id = app.state::<AppState>().docker_container.lock().unwrap().take();
+    let Some(id) = id else { return Ok(false) };
+    let out = app.shell()
+        .command("docker")
+        .args(["stop", &id])
+        .output()
+        .await
+        .map_err(|e| AppError::Other(e.to_string()))?;
+    fs::remove_file(docker_cid_path()).ok();
+    if !out.status.success() {
+        return Err(AppError::Other(String::from_utf8_lossy(&out.stderr).trim().to_string()));
+    }
+    Ok(true)
+}
+
+/// Whether our container is running, or None when no container is known.
+pub(crate) async fn gateway_status_docker(app: &tauri::AppHandle) -> Option<bool> {
+    let id = app.state::<AppState>().docker_container.lock().unwrap().clone()?;
+    let out = app.shell()
+        .command("docker")
+        .args(["inspect", "-f", "{{.State.Running}}", &id])
+        .output()
+        .await
+        .ok()?;
+    Some(out.status.success() && String::from_utf8_lossy(&out.stdout).trim() == "true")
+}
+
+/// Runs the configured startup command, if any. A non-zero exit aborts the launch.
+pub(crate) async fn run_startup_command(app: &tauri::AppHandle, config: &AppConfig) -> Result<(), AppError> {
+    let Some(cmd) = config.startup_command.as_deref().map(str::trim).filter(|c| !c.is_empty()) else {
+        return Ok(());
+    };
+    let out = shell_output(app, cmd).await.map_err(AppError::StartupCommandFailed)?;
+    if !out.status.success() {
+        let stderr = String::from_utf8_lossy(&out.stderr).trim().to_string();
+        let detail = if stderr.is_empty() { format!("exited with {:?}", out.status.code()) } else { stderr };
+        return Err(AppError::StartupCommandFailed(detail));
+    }
+    Ok(())
+}
+
+/// Runs the configured shutdown command in the background. The gateway is already
+/// stopped, so a failure is only reported through `shutdown-command-failed`.
+pub(crate) fn spawn_shutdown_command(app: &tauri::AppHandle) {
+    let Some(cmd) = load_config().shutdown_command.map(|c| c.trim().to_string()).filter(|c| !c.is_empty()) else {
+        return;
+    };
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let error = match shell_output(&app, &cmd).await {
+            Ok(out) if out.status.success() => return,
+            Ok(out) => {
+                let stderr = String::from_utf8_lossy(&out.stderr).trim().to_string();
+                if stderr.is_empty() { format!("exited with {:?}", out.status.code()) } else { stderr }
+            }
+            Err(e) => e,
+        };
+        eprintln!("[SHUTDOWN CMD ERR] {}", error);
+        app.emit("shutdown-command-failed", serde_json::json!({ "command": cmd, "error": error })).ok();
+    });
+}
+
+/// `gateway_args_file`: one argument per line, blank lines and `#` comments skipped.
+pub(crate) fn read_gateway_args_file(config: &AppConfig) -> Result<Vec<String>, String> {
+    let Some(path) = config.gateway_args_file.as_deref().filter(|p| !p.trim().is_empty()) else {
+        return Ok(Vec::new());
+    };
+    let content = fs::read_to_string(path).map_err(|e| format!("Gateway args file {}: {}", path, e))?;
+    Ok(content.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(String::from)
+        .collect())
+}
+
+pub(crate) fn gateway_launch(config: &AppConfig, api_key: &str, token: &str) -> Result<GatewayLaunch, String> {
+    validate_log_level(&config.gateway_log_level)?;
+    let mut launch = match config.gateway_mode {
+        GatewayMode::Npx => npx_launch(config, api_key),
+        GatewayMode::Binary => binary_launch(config, api_key)?,
+        GatewayMode::Docker => docker_launch(config, api_key, token)?,
+    };
+    launch.args.extend(read_gateway_args_file(config)?);
+    Ok(launch)
+}
+
+/// `port` or `gateway.port` in the JSON the CLI printed.
+pub(crate) fn parse_default_port(stdout: &str) -> Option<u16> {
+    let v: serde_json::Value = serde_json::from_str(stdout.trim()).ok()?;
+    let port = v["gateway"]["port"].as_u64().or(v["port"].as_u64())?;
+    u16::try_from(port).ok()
+}
+
+/// The port OpenClaw itself defaults to, from `openclaw gateway config --json`,
+/// so `GATEWAY_PORT` can be checked against the installed version.
+#[tauri::command]
+pub(crate) async fn get_openclaw_default_port(app: tauri::AppHandle) -> Result<u16, AppError> {
+    let (stdout, stderr) = run_openclaw(&app, &["gateway", "config", "--json"]).await.map_err(AppError::Other)?;
+    let stdout = String::from_utf8_lossy(&stdout);
+    parse_default_port(&stdout).ok_or_else(|| {
+        let stderr = String::from_utf8_lossy(&stderr).trim().to_string();
+        AppError::UnsupportedByGateway(format!(
+            "reading the default port ({})",
+            if stderr.is_empty() { "no port in the output of gateway config" } else { &stderr }
+        ))
+    })
+}
+
+/// Takes effect on the next gateway start.
+#[tauri::command]
+pub(crate) fn set_gateway_mode(mode: GatewayMode) -> Result<(), AppError> {
+    ensure_writable()?;
+    let mut config = load_config();
+    config.gateway_mode = mode;
+    save_config(&config)?;
+    Ok(())
+}
+
+/// For web apps that call the gateway directly. Takes effect on the next gateway start.
+#[tauri::command]
+pub(crate) fn set_gateway_cors(enabled: bool) -> Result<(), AppError> {
+    ensure_writable()?;
+    let mut config = load_config();
+    config.enable_cors = enabled;
+    save_config(&config)?;
+    Ok(())
+}
+
+/// Takes effect on the next gateway start.
+#[tauri::command]
+pub(crate) fn set_gateway_log_level(level: String) -> Result<(), AppError> {
+    ensure_writable()?;
+    let level = level.trim().to_ascii_lowercase();
+    validate_log_level(&level).map_err(AppError::InvalidInput)?;
+    let mut config = load_config();
+    config.gateway_log_level = level;
+    save_config(&config)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) fn set_startup_command(command: Option<String>) -> Result<(), AppError> {
+    ensure_writable()?;
+    let mut config = load_config();
+    config.startup_command = command.map(|c| c.trim().to_string()).filter(|c| !c.is_empty());
+    save_config(&config)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) fn set_shutdown_command(command: Option<String>) -> Result<(), AppError> {
+    ensure_writable()?;
+    let mut config = load_config();
+    config.shutdown_command = command.map(|c| c.trim().to_string()).filter(|c| !c.is_empty());
+    save_config(&config)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) fn set_docker_image(image: String) -> Result<(), AppError> {
+    ensure_writable()?;
+    let image = image.trim().to_string();
+    if image.starts_with('-') || image.chars().any(char::is_whitespace) {
+        return Err(AppError::InvalidInput(format!("'{}' is not a Docker image reference", image)));
+    }
+    let mut config = load_config();
+    config.docker_image = image;
+    save_config(&config)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) fn set_gateway_binary_path(path: Option<String>) -> Result<(), AppError> {
+    ensure_writable()?;
+    let path = match path.filter(|p| !p.trim().is_empty()) {
+        Some(p) => Some(validate_user_path(&p, PathIntent::Executable)?.to_string_lossy().into_owned()),
+        None => None,
+    };
+    let mut config = load_config();
+    config.gateway_binary_path = path;
+    save_config(&config)?;
+    Ok(())
+}
@@ -0,0 +1,449 @@
+//! Starting, stopping and pairing the gateway.
+
+use crate::*;
+
+// ─── Pairing: read token from config and call pair ────────────────────────
+
+pub(crate) async fn do_pairing(app: &tauri::AppHandle, token: &str) -> Result<(), AppError> {
+    let timeout_ms = load_config().pair_timeout_ms;
+    // Gateway auto-approves pairing on loopback — just call pair without --url
+    let pair = ["gateway", "pair", "--token", token];
+    let (stdout, stderr) = tokio::time::timeout(std::time::Duration::from_millis(timeout_ms), run_openclaw(app, &pair))
+        .await
+        .map_err(|_| AppError::Timeout(format!("pairing after {} ms", timeout_ms)))?
+        .map_err(AppError::Other)?;
+
+    let combined = format!(
+        "{}{}",
+        String::from_utf8_lossy(&stdout),
+        String::from_utf8_lossy(&stderr)
+    );
+    println!("[PAIR] {}", combined.trim());
+    Ok(()) // Not fatal in any case
+}
+
+// ─── Gateway start/stop/status ────────────────────────────────────────────────
+
+#[tauri::command]
+pub(crate) async fn start_agent(app: tauri::AppHandle) -> Result<String, String> {
+    ensure_writable().map_err(|e| e.to_string())?;
+    let result = launch_gateway(&app, false).await;
+    report_usage_event("gateway_start", HashMap::from([("ok".to_string(), result.is_ok().to_string())]));
+    result
+}
+
+/// Starts every non-archived agent. All agents share one gateway, so each start
+/// makes sure it is up and reports the status as seen by that agent.
+#[tauri::command]
+pub(crate) async fn start_all_agents(app: tauri::AppHandle) -> Result<HashMap<String, Result<GatewayStatus, AppError>>, AppError> {
+    ensure_writable()?;
+    let ids = run_storage_io(&app, || {
+        get_all_agent_ids()
+            .into_iter()
+            .filter(|id| !read_agent_config(id).archived)
+            .collect::<Vec<_>>()
+    }).await?;
+
+    let starts = ids.iter().map(|_| {
+        let app = app.clone();
+        async move {
+            start_agent(app.clone()).await?;
+            Ok(gateway_status(app).await?)
+        }
+    });
+    let results = futures::future::join_all(starts).await;
+    Ok(ids.into_iter().zip(results).collect())
+}
+
+/// `restarting` suppresses `gateway-starting`; a restart announces itself with `gateway-restarting`.
+/// A restart always spawns, so it always ends in `gateway-started` or an error.
+pub(crate) async fn launch_gateway(app: &tauri::AppHandle, restarting: bool) -> Result<String, String> {
+    let state = app.state::<AppState>();
+    let _guard = state.launch_lock.lock().await;
+    if is_shutting_down(app) {
+        return Err("Clapp is shutting down".into());
+    }
+    if fixture_mode() {
+        start_fixture_gateway().await?;
+        if restarting {
+            app.emit("gateway-started", serde_json::json!({
+                "port": read_gateway_port(),
+                "pid": null,
+                "token_present": false,
+            })).ok();
+        }
+        return Ok("running".into());
+    }
+    let api_key = load_api_key()?;
+
+    if api_key.trim().is_empty() {
+        return Err("Add an API key in the agent settings first".into());
+    }
+
+    let key = api_key.clone();
+    let probe_app = app.clone();
+    let token = run_storage_io(app, move || -> Result<String, String> {
+        // An upgraded OpenClaw may have left a new shape; check before writing to it
+        refresh_data_format(&probe_app);
+        let token = ensure_openclaw_config()?;
+        write_auth_profile("main", &key, "anthropic", None, OPENCLAW_AUTH_VERSION)?;
+        Ok(token)
+    }).await.map_err(|e| e.to_string())??;
+
+    let shell = app.shell();
+
+    // Already running?
+    let health_ok = run_openclaw(app, &["gateway", "health"])
+        .await
+        .map(|(stdout, stderr)| {
+            let s = String::from_utf8_lossy(&stdout).to_lowercase();
+            let e = String::from_utf8_lossy(&stderr).to_lowercase();
+            s.contains("ok") || e.contains("ok")
+        })
+        .unwrap_or(false);
+
+    // After a restart's stop, an answer can only be the old gateway on its way out
+    if health_ok && !restarting {
+        // Only adopt a gateway we started or one that runs as us
+        let managed = app.state::<AppState>().process.lock().unwrap().is_some();
+        if !managed {
+            if let Some(m) = detect_user_mismatch(app).await {
+                return Err(m.guidance);
+            }
+        }
+        return Ok("running".into());
+    }
+
+    if !restarting {
+        app.emit("gateway-starting", ()).ok();
+    }
+
+    // Start gateway
+    let config = load_config();
+    if let Some(path) = config.openclaw_install.as_deref().filter(|p| !std::path::Path::new(p).exists()) {
+        app.emit("openclaw-install-missing", serde_json::json!({ "path": path })).ok();
+        return Err(format!("The selected OpenClaw installation is gone: {}. Choose another one in settings.", path));
+    }
+    run_startup_command(app, &config).await.map_err(|e| e.to_string())?;
+    let launch = gateway_launch(&config, &api_key, &token)?;
+    let (rx, child) = shell
+        .command(&launch.program)
+        .args(&launch.args)
+        .envs(launch.env)
+        .spawn()
+        .map_err(|e| format!("Failed to start gateway: {}", e))?;
+
+    let pid = child.pid();
+    spawn_output_forwarding(app.clone(), rx, pid);
+    if config.gateway_mode == GatewayMode::Docker {
+        tauri::async_runtime::spawn(record_docker_container(app.clone()));
+    }
+
+    *app.state::<AppState>().process.lock().unwrap() = Some(child);
+    run_storage_io(app, move || update_heartbeat(|h| {
+        h.gateway_pid = Some(pid);
+        h.last_status = "running".into();
+    })).await.ok();
+
+    // Wait for gateway to spin up (up to 10 sec)
+    let mut gateway_up = false;
+    for _ in 0..20 {
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+        let alive = run_openclaw(app, &["gateway", "health"])
+            .await
+            .map(|(stdout, stderr)| {
+                let s = String::from_utf8_lossy(&stdout).to_lowercase();
+                let e = String::from_utf8_lossy(&stderr).to_lowercase();
+                s.contains("ok") || e.contains("ok")
+            })
+            .unwrap_or(false);
+
+        if alive {
+            gateway_up = true;
+            break;
+        }
+    }
+
+    if !gateway_up {
+        return Err("Gateway failed to start within 10 sec. Check: npm install -g openclaw".into());
+    }
+
+    // Perform pairing so this client can make calls
+    // Do not consider pairing error fatal — might already be paired
+    if let Err(e) = do_pairing(app, &token).await {
+        eprintln!("[PAIR ERR] {}", e);
+        log_error(app, ErrorSource::Pair, &e.to_string());
+    }
+    if load_config().warm_up {
+        tauri::async_runtime::spawn(warm_up_gateway(app.clone()));
+    }
+
+    // From the config the gateway runs with, which it may have rewritten on start
+    let token_present = matches!(run_storage_io(app, read_gateway_token).await, Ok(Ok(_)));
+    app.emit("gateway-started", serde_json::json!({
+        "port": read_gateway_port(),
+        "pid": pid,
+        "token_present": token_present,
+    })).ok();
+    announce(app, "gateway.started", AnnouncementSeverity::Info, &[]);
+
+    Ok("running".into())
+}
+
+/// Gateway session used by warm-up calls. Its transcript is hidden from the session list.
+pub(crate) const WARM_UP_SESSION: &str = "clapp-warm-up";
+
+/// One minimal call so module loading and the provider handshake happen before
+/// the user's first message. Its reply is discarded and a failure is only logged.
+pub(crate) async fn warm_up_gateway(app: tauri::AppHandle) {
+    let started = std::time::Instant::now();
+    let result = call_gateway_agent(&app, "main", "Reply with OK.", WARM_UP_SESSION, Some(WARM_UP_SESSION), None, None).await;
+    let duration_ms = started.elapsed().as_millis() as u64;
+    match &result {
+        Ok(_) => {
+            println!("[WARM-UP] {} ms", duration_ms);
+            *app.state::<AppState>().cold_start_ms.lock().unwrap() = Some(duration_ms);
+        }
+        Err(e) => eprintln!("[WARM-UP ERR] {}", e),
+    }
+    app.emit("gateway-warm", serde_json::json!({
+        "ok": result.is_ok(),
+        "durationMs": duration_ms,
+    })).ok();
+}
+
+#[tauri::command]
+pub(crate) fn set_gateway_warm_up(enabled: bool) -> Result<(), AppError> {
+    ensure_writable()?;
+    let mut config = load_config();
+    config.warm_up = enabled;
+    save_config(&config)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) fn stop_agent(app: tauri::AppHandle) -> Result<String, String> {
+    ensure_writable().map_err(|e| e.to_string())?;
+    let child = app.state::<AppState>().process.lock().unwrap().take();
+    // Killing the docker client leaves the container running
+    if app.state::<AppState>().docker_container.lock().unwrap().is_some() {
+        let handle = app.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = stop_agent_docker(&handle).await {
+                eprintln!("[DOCKER ERR] {}", e);
+            }
+        });
+    }
+    if let Some(child) = child {
+        child.kill().map_err(|e| e.to_string())?;
+        // The gateway serves the "main" agent
+        app.emit("gateway-stopped", "main").ok();
+        announce(&app, "gateway.stopped", AnnouncementSeverity::Info, &[]);
+        report_usage_event("gateway_stop", HashMap::new());
+        spawn_shutdown_command(&app);
+    }
+    Ok("stopped".into())
+}
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GatewayStatus {
+    /// "running", "stopped" or "user_mismatch"
+    pub(crate) state: String,
+    pub(crate) storage_available: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) user_mismatch: Option<UserMismatch>,
+    /// Compact environment fingerprint for support
+    pub(crate) environment: String,
+    /// Gateway output lines dropped by the rate limit since the app started
+    pub(crate) dropped_log_lines: u64,
+    /// Credential state of the gateway's agent; anything but ok needs the user
+    pub(crate) auth: AuthState,
+    /// Replies are recorded fixtures, not a real gateway
+    pub(crate) fixture_mode: bool,
+    /// Set when ~/.openclaw is newer than this Clapp: reads are best-effort, writes are blocked
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) data_format_mismatch: Option<DataFormat>,
+}
+
+pub(crate) const GRACEFUL_STOP_TIMEOUT_MS: u64 = 5_000;
+/// How long a killed gateway has to be seen exiting before a restart goes ahead anyway.
+pub(crate) const KILL_EXIT_WAIT_MS: u64 = 3_000;
+
+/// Waits until the output forwarder has seen `pid` terminate. False if it didn't within `timeout_ms`.
+pub(crate) async fn wait_for_gateway_exit(app: &tauri::AppHandle, pid: u32, timeout_ms: u64) -> bool {
+    let exited = || *app.state::<AppState>().gateway_exit.lock().unwrap() == Some(pid);
+    let deadline = std::time::Instant::now() + std::time::Duration::from_millis(timeout_ms);
+    while !exited() && std::time::Instant::now() < deadline {
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    exited()
+}
+
+/// Kills the gateway and waits, bounded, for it to be gone.
+pub(crate) async fn kill_gateway(app: &tauri::AppHandle, child: tauri_plugin_shell::process::CommandChild) -> Result<(), AppError> {
+    let pid = child.pid();
+    child.kill().map_err(|e| AppError::Other(e.to_string()))?;
+    if !wait_for_gateway_exit(app, pid, KILL_EXIT_WAIT_MS).await {
+        eprintln!("[GATEWAY ERR] pid {} not seen exiting {} ms after kill", pid, KILL_EXIT_WAIT_MS);
+    }
+    Ok(())
+}
+
+/// Asks the gateway to exit and waits for it, killing it if it is still alive after `timeout_ms`.
+pub(crate) async fn stop_gateway_graceful(app: &tauri::AppHandle, timeout_ms: Option<u64>, restarting: bool) -> Result<(), AppError> {
+    let child = app.state::<AppState>().process.lock().unwrap().take();
+    let Some(child) = child else { return Ok(()) };
+    let pid = child.pid();
+
+    if stop_agent_docker(app).await? {
+        kill_gateway(app, child).await.ok();
+        if !restarting {
+            app.emit("gateway-stopped", "main").ok();
+            announce(app, "gateway.stopped", AnnouncementSeverity::Info, &[]);
+        }
+        return Ok(());
+    }
+
+    // Without /F taskkill sends a close request instead of terminating
+    let asked = app.shell()
+        .command("cmd")
+        .args(["/C", "taskkill", "/PID", &pid.to_string(), "/T"])
+        .output()
+        .await
+        .map(|out| out.status.success())
+        .unwrap_or(false);
+
+    let exited = asked && wait_for_gateway_exit(app, pid, timeout_ms.unwrap_or(GRACEFUL_STOP_TIMEOUT_MS)).await;
+    if !exited {
+        // Waited for too, so a restart doesn't find the dying gateway still answering
+        kill_gateway(app, child).await?;
+    }
+
+    if !restarting {
+        app.emit("gateway-stopped", "main").ok();
+        announce(app, "gateway.stopped", AnnouncementSeverity::Info, &[]);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) async fn stop_agent_graceful(app: tauri::AppHandle, timeout_ms: Option<u64>) -> Result<String, AppError> {
+    ensure_writable()?;
+    let running = app.state::<AppState>().process.lock().unwrap().is_some();
+    stop_gateway_graceful(&app, timeout_ms, false).await?;
+    if running {
+        spawn_shutdown_command(&app);
+    }
+    Ok("stopped".into())
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct StopResult {
+    pub(crate) pid: u32,
+    pub(crate) graceful: bool,
+}
+
+/// Stops every process we manage, keyed by the agent it serves. Today that is
+/// the single gateway serving "main", so the map has at most one entry.
+#[tauri::command]
+pub(crate) async fn stop_all_agents(app: tauri::AppHandle, graceful: bool) -> HashMap<String, Result<StopResult, AppError>> {
+    let pid = app.state::<AppState>().process.lock().unwrap().as_ref().map(|c| c.pid());
+    let mut results = HashMap::new();
+    if let Some(pid) = pid {
+        let result = if graceful {
+            stop_agent_graceful(app.clone(), None).await.map(|_| StopResult { pid, graceful })
+        } else {
+            stop_agent(app.clone()).map(|_| StopResult { pid, graceful }).map_err(AppError::from)
+        };
+        results.insert("main".to_string(), result);
+    }
+    results
+}
+
+#[tauri::command]
+pub(crate) async fn graceful_restart_gateway(app: tauri::AppHandle, timeout_ms: Option<u64>) -> Result<GatewayStatus, AppError> {
+    ensure_writable()?;
+    // Stopping only reaches a gateway we launched; restarting next to someone else's
+    // would leave theirs serving and ours failing to bind
+    let managed = app.state::<AppState>().process.lock().unwrap().is_some();
+    if !managed && !fixture_mode() {
+        let answers = run_openclaw(&app, &["gateway", "health"]).await
+            .is_ok_and(|(stdout, stderr)| health_says_ok(&stdout, &stderr));
+        if answers {
+            return Err(AppError::GatewayNotManaged(read_gateway_port()));
+        }
+    }
+    app.emit("gateway-restarting", ()).ok();
+    stop_gateway_graceful(&app, timeout_ms, true).await?;
+    launch_gateway(&app, true).await?;
+    Ok(gateway_status(app).await?)
+}
+
+#[tauri::command]
+pub(crate) async fn gateway_status(app: tauri::AppHandle) -> Result<GatewayStatus, String> {
+    let (stdout, stderr) = run_openclaw(&app, &["gateway", "health"]).await?;
+
+    let s = String::from_utf8_lossy(&stdout).to_lowercase();
+    let e = String::from_utf8_lossy(&stderr).to_lowercase();
+
+    // A container that is gone means stopped, even if something else answers on the port
+    let running = (s.contains("ok") || e.contains("ok")) && gateway_status_docker(&app).await != Some(false);
+    let managed = app.state::<AppState>().process.lock().unwrap().is_some();
+    // Our process is there but doesn't answer, and not because it is still starting
+    let unhealthy = managed && !running && app.state::<AppState>().launch_lock.try_lock().is_ok();
+    let was_unhealthy = app.state::<AppState>().gateway_unhealthy.swap(unhealthy, std::sync::atomic::Ordering::Relaxed);
+    if unhealthy && !was_unhealthy {
+        announce(&app, "gateway.unhealthy", AnnouncementSeverity::Warning, &[]);
+    }
+    let fixtures = fixture_mode();
+    let user_mismatch = if running && !managed && !fixtures { detect_user_mismatch(&app).await } else { None };
+    let state = match (running, &user_mismatch) {
+        (true, Some(_)) => "user_mismatch",
+        (true, None) => "running",
+        (false, _) => "stopped",
+    };
+    let auth = run_storage_io(&app, || auth_state("main")).await.unwrap_or(AuthState::Ok);
+    let data_format_mismatch = cached_data_format().filter(DataFormat::mismatch);
+    Ok(GatewayStatus {
+        state: state.into(),
+        storage_available: app.state::<AppState>().storage_ok.load(std::sync::atomic::Ordering::Relaxed),
+        user_mismatch,
+        environment: compact_environment(&app.state::<AppState>().env_info.lock().unwrap()),
+        dropped_log_lines: app.state::<AppState>().dropped_log_lines.load(std::sync::atomic::Ordering::Relaxed),
+        auth,
+        fixture_mode: fixtures,
+        data_format_mismatch,
+    })
+}
+
+/// For external scripts that stop the gateway process directly.
+#[tauri::command]
+pub(crate) fn get_gateway_pid_file_path() -> String {
+    gateway_pid_path().to_string_lossy().into_owned()
+}
+
+#[tauri::command]
+pub(crate) async fn get_gateway_metrics(app: tauri::AppHandle) -> Result<HashMap<String, f64>, AppError> {
+    let token = run_storage_io(&app, read_gateway_token).await?.map_err(AppError::Other)?;
+
+    let (stdout, stderr) = run_openclaw(&app, &["gateway", "metrics", "--json", "--token", &token])
+        .await
+        .map_err(AppError::Other)?;
+
+    let stdout = String::from_utf8_lossy(&stdout);
+    let v: serde_json::Value = serde_json::from_str(stdout.trim()).map_err(|_| {
+        let stderr = String::from_utf8_lossy(&stderr).trim().to_string();
+        AppError::Other(if stderr.is_empty() { "Gateway returned no metrics".into() } else { stderr })
+    })?;
+
+    // Non-numeric entries (labels, build info) are skipped
+    let mut metrics: HashMap<String, f64> = v.as_object()
+        .map(|m| m.iter().filter_map(|(k, v)| v.as_f64().map(|n| (k.clone(), n))).collect())
+        .unwrap_or_default();
+    if let Some(ms) = *app.state::<AppState>().cold_start_ms.lock().unwrap() {
+        metrics.insert("coldStartLatencyMs".into(), ms as f64);
+    }
+    Ok(metrics)
+}
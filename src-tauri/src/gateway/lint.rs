@@ -0,0 +1,116 @@
+//! Pre-flight checks on outgoing prompts.
+
+use crate::*;
+
+// ─── Prompt lint ──────────────────────────────────────────────────────────────
+
+#[derive(serde::Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum LintSeverity {
+    /// Refused by `gateway_call` when linting is on
+    Error,
+    Warning,
+}
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct LintWarning {
+    pub(crate) code: String,
+    pub(crate) severity: LintSeverity,
+    pub(crate) message: String,
+    /// Char offsets into the message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) span: Option<(usize, usize)>,
+}
+
+/// Used when the agent doesn't pin a context window.
+pub(crate) const DEFAULT_CONTEXT_TOKENS: u64 = 200_000;
+/// Workspace files OpenClaw injects into every prompt.
+pub(crate) const INJECTED_CONTEXT_FILES: &[&str] = &[
+    "AGENTS.md", "SOUL.md", "TOOLS.md", "IDENTITY.md", "USER.md", "HEARTBEAT.md", "BOOTSTRAP.md", "MEMORY.md",
+];
+
+/// Rough count, ~4 chars per token. Errs high for non-Latin text, which is the safe side here.
+pub(crate) fn estimate_tokens(chars: u64) -> u64 {
+    chars.div_ceil(4)
+}
+
+pub(crate) fn looks_like_path(s: &str) -> bool {
+    let s = s.trim_matches(['"', '\'']);
+    if s.is_empty() || s.contains('\n') {
+        return false;
+    }
+    let bytes = s.as_bytes();
+    let absolute = s.starts_with('/')
+        || s.starts_with("~/")
+        || s.starts_with("\\\\")
+        || (bytes.len() > 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && matches!(bytes[2], b'\\' | b'/'));
+    if !absolute {
+        return false;
+    }
+    let has_extension = std::path::Path::new(s).extension().is_some();
+    let spaced = s.contains(' ');
+    (has_extension && !spaced) || PathBuf::from(s).exists()
+}
+
+pub(crate) fn lint_message(agent_id: &str, session_key: &str, message: &str) -> Vec<LintWarning> {
+    let mut warnings = Vec::new();
+    let trimmed = message.trim();
+    if trimmed.is_empty() {
+        warnings.push(LintWarning {
+            code: "empty".into(),
+            severity: LintSeverity::Error,
+            message: "Message is empty".into(),
+            span: None,
+        });
+        return warnings;
+    }
+
+    if looks_like_path(trimmed) {
+        let start = message.len() - message.trim_start().len();
+        let start = message[..start].chars().count();
+        warnings.push(LintWarning {
+            code: "path_only".into(),
+            severity: LintSeverity::Warning,
+            message: "Message is only a file path; did you mean to attach the file?".into(),
+            span: Some((start, start + trimmed.chars().count())),
+        });
+    }
+
+    for secret in find_secrets(message) {
+        warnings.push(LintWarning {
+            code: "secret".into(),
+            severity: LintSeverity::Warning,
+            message: format!("Looks like a credential ({})", secret.kind),
+            span: Some((secret.start, secret.end)),
+        });
+    }
+
+    // Sizes only; injected files and history are never read into memory here
+    let config = read_agent_config(agent_id);
+    let workspace = agent_workspace(&config);
+    let injected: u64 = INJECTED_CONTEXT_FILES.iter()
+        .filter_map(|f| fs::metadata(workspace.join(f)).ok())
+        .map(|m| m.len())
+        .sum();
+    let history: u64 = read_history(agent_id).iter()
+        .filter(|r| r.session_key == session_key && !r.superseded)
+        .map(|r| r.text.len() as u64)
+        .sum();
+    let used = estimate_tokens(message.len() as u64 + config.instructions.len() as u64 + injected + history);
+    let limit = config.context_window.unwrap_or(DEFAULT_CONTEXT_TOKENS);
+    if used > limit {
+        warnings.push(LintWarning {
+            code: "over_context".into(),
+            severity: LintSeverity::Error,
+            message: format!("About {} tokens with injected context, over the {} token window", used, limit),
+            span: None,
+        });
+    }
+    warnings
+}
+
+#[tauri::command]
+pub(crate) async fn lint_prompt(app: tauri::AppHandle, agent_id: String, session_key: String, message: String) -> Result<Vec<LintWarning>, AppError> {
+    run_storage_io(&app, move || lint_message(&agent_id, &session_key, &message)).await
+}
@@ -0,0 +1,37 @@
+//! The OpenClaw gateway: process lifecycle, calls and what we read from it.
+
+pub(crate) mod activity;
+pub(crate) mod cache;
+pub(crate) mod call;
+pub(crate) mod deferred;
+pub(crate) mod fixtures;
+pub(crate) mod health;
+pub(crate) mod installs;
+pub(crate) mod launch;
+pub(crate) mod lifecycle;
+pub(crate) mod lint;
+pub(crate) mod output;
+pub(crate) mod params;
+pub(crate) mod power;
+pub(crate) mod refusal;
+pub(crate) mod reply;
+pub(crate) mod sessions;
+pub(crate) mod timings;
+
+pub(crate) use activity::*;
+pub(crate) use cache::*;
+pub(crate) use call::*;
+pub(crate) use deferred::*;
+pub(crate) use fixtures::*;
+pub(crate) use health::*;
+pub(crate) use installs::*;
+pub(crate) use launch::*;
+pub(crate) use lifecycle::*;
+pub(crate) use lint::*;
+pub(crate) use output::*;
+pub(crate) use params::*;
+pub(crate) use power::*;
+pub(crate) use refusal::*;
+pub(crate) use reply::*;
+pub(crate) use sessions::*;
+pub(crate) use timings::*;
@@ -0,0 +1,283 @@
+//! Forwarding gateway stdout/stderr to the log and the frontend without unbounded buffering.
+
+use crate::*;
+
+// ─── Gateway output ───────────────────────────────────────────────────────────
+
+/// Lines waiting for the log writer. Past this the reader drops instead of queueing.
+pub(crate) const GATEWAY_LINE_QUEUE: usize = 1024;
+/// Above this many lines in a second, only every `EVENT_SAMPLE_EVERY`th line reaches the frontend.
+pub(crate) const EVENT_SAMPLE_THRESHOLD: u32 = 100;
+pub(crate) const EVENT_SAMPLE_EVERY: u32 = 50;
+
+pub(crate) struct GatewayLine {
+    pub(crate) text: String,
+    pub(crate) stderr: bool,
+    /// False when the line was sampled out of frontend events; it is still logged
+    pub(crate) forward: bool,
+}
+
+/// Per-second line budget for one gateway process.
+pub(crate) struct LineLimiter {
+    pub(crate) max_per_sec: u32,
+    pub(crate) window_start: std::time::Instant,
+    pub(crate) in_window: u32,
+    pub(crate) dropped_in_window: u64,
+}
+
+pub(crate) enum Admit {
+    Keep { forward: bool },
+    Drop,
+}
+
+impl LineLimiter {
+    pub(crate) fn new(max_per_sec: u32) -> Self {
+        Self { max_per_sec: max_per_sec.max(1), window_start: std::time::Instant::now(), in_window: 0, dropped_in_window: 0 }
+    }
+
+    /// Closes the window if a second has passed, returning how many lines it dropped.
+    pub(crate) fn roll(&mut self) -> u64 {
+        if self.window_start.elapsed() < std::time::Duration::from_secs(1) {
+            return 0;
+        }
+        self.window_start = std::time::Instant::now();
+        self.in_window = 0;
+        std::mem::take(&mut self.dropped_in_window)
+    }
+
+    pub(crate) fn admit(&mut self) -> Admit {
+        if self.in_window >= self.max_per_sec {
+            self.dropped_in_window += 1;
+            return Admit::Drop;
+        }
+        self.in_window += 1;
+        let forward = self.in_window <= EVENT_SAMPLE_THRESHOLD || self.in_window.is_multiple_of(EVENT_SAMPLE_EVERY);
+        Admit::Keep { forward }
+    }
+}
+
+/// Reader side of the queue: the rate limit, then the bounded queue. Dropped
+/// lines are reported by a marker sent through the queue, so it lands in the
+/// log between the lines it was dropped from.
+pub(crate) struct LinePump {
+    pub(crate) limiter: LineLimiter,
+    pub(crate) tx: tokio::sync::mpsc::Sender<GatewayLine>,
+    /// Dropped lines no queued marker reports yet
+    pub(crate) unreported: u64,
+}
+
+impl LinePump {
+    pub(crate) fn new(max_per_sec: u32, tx: tokio::sync::mpsc::Sender<GatewayLine>) -> Self {
+        Self { limiter: LineLimiter::new(max_per_sec), tx, unreported: 0 }
+    }
+
+    /// A full queue keeps the count for the next marker.
+    fn report(&mut self) {
+        if self.unreported > 0 && self.tx.try_send(marker_line(self.unreported)).is_ok() {
+            self.unreported = 0;
+        }
+    }
+
+    /// Returns how many lines it found dropped, for `count_dropped`.
+    pub(crate) fn push(&mut self, bytes: &[u8], stderr: bool) -> u64 {
+        let mut dropped = self.limiter.roll();
+        self.unreported += dropped;
+        self.report();
+        // Lines over the budget are counted when their window closes
+        if let Admit::Keep { forward } = self.limiter.admit() {
+            let line = GatewayLine { text: String::from_utf8_lossy(bytes).into_owned(), stderr, forward };
+            if self.tx.try_send(line).is_err() {
+                self.unreported += 1;
+                dropped += 1;
+            }
+        }
+        dropped
+    }
+
+    /// Reports what is left once the process is gone, waiting for room in the queue.
+    /// Returns how many lines it found dropped.
+    pub(crate) async fn finish(mut self) -> u64 {
+        let dropped = std::mem::take(&mut self.limiter.dropped_in_window);
+        self.unreported += dropped;
+        if self.unreported > 0 {
+            self.tx.send(marker_line(self.unreported)).await.ok();
+        }
+        dropped
+    }
+}
+
+pub(crate) fn dropped_marker(count: u64) -> String {
+    format!("[clapp] {} gateway output lines dropped\n", count)
+}
+
+/// Logged only; never an event or channel activity.
+pub(crate) fn marker_line(count: u64) -> GatewayLine {
+    GatewayLine { text: dropped_marker(count), stderr: false, forward: false }
+}
+
+pub(crate) fn count_dropped(app: &tauri::AppHandle, count: u64) {
+    app.state::<AppState>().dropped_log_lines.fetch_add(count, std::sync::atomic::Ordering::Relaxed);
+}
+
+/// Writer side of the queue: channel activity and the error log. Stored secrets,
+/// such as agent environment values a tool echoed, are redacted first; the
+/// redacted text is returned for the log file.
+pub(crate) fn handle_gateway_line(app: &tauri::AppHandle, line: &GatewayLine, emit: bool) -> String {
+    let text = redact_known_secrets(&line.text);
+    if line.stderr {
+        eprint!("[GW ERR] {}", text);
+    } else {
+        print!("[GW] {}", text);
+    }
+    if line.forward {
+        if emit {
+            emit_stream(app, "gateway-log", "gateway", &serde_json::json!({ "text": text, "stderr": line.stderr }));
+        }
+        record_channel_activity(app, &text);
+        if line.stderr {
+            log_error(app, ErrorSource::Gateway, &text);
+        }
+    }
+    text
+}
+
+/// Drains the shell plugin's unbounded receiver as fast as it fills, passing
+/// lines through a rate limit and a bounded queue to a separate writer task.
+pub(crate) fn spawn_output_forwarding(
+    app: tauri::AppHandle,
+    mut rx: tauri::async_runtime::Receiver<tauri_plugin_shell::process::CommandEvent>,
+    pid: u32,
+) {
+    use tauri_plugin_shell::process::CommandEvent;
+    let (tx, mut queue) = tokio::sync::mpsc::channel::<GatewayLine>(GATEWAY_LINE_QUEUE);
+
+    let writer = app.clone();
+    // Counted so shutdown can wait for queued lines to reach the log
+    writer.state::<AppState>().gateway_log_writers.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    let emit = writer.state::<AppState>().gateway_log_switch.subscribe();
+    tauri::async_runtime::spawn(async move {
+        while let Some(line) = queue.recv().await {
+            // Whatever else is queued goes to the log file in the same write
+            let mut text = handle_gateway_line(&writer, &line, *emit.borrow());
+            while let Ok(line) = queue.try_recv() {
+                text.push_str(&handle_gateway_line(&writer, &line, *emit.borrow()));
+            }
+            run_storage_io(&writer, move || append_gateway_log(&text)).await.ok();
+        }
+        writer.state::<AppState>().gateway_log_writers.fetch_sub(1, std::sync::atomic::Ordering::Relaxed);
+    });
+
+    tauri::async_runtime::spawn(async move {
+        let mut pump = LinePump::new(load_config().gateway_output_max_lines_per_sec, tx);
+        while let Some(ev) = rx.recv().await {
+            let (bytes, stderr) = match ev {
+                CommandEvent::Stdout(b) => (b, false),
+                CommandEvent::Stderr(b) => (b, true),
+                CommandEvent::Terminated(_) => {
+                    *app.state::<AppState>().gateway_exit.lock().unwrap() = Some(pid);
+                    // Every stop path takes the child first, so a child still held here died on its own
+                    let unexpected = app.state::<AppState>().process.lock().unwrap()
+                        .as_ref().is_some_and(|c| c.pid() == pid);
+                    if unexpected {
+                        let crashed_at = now_ms();
+                        run_storage_io(&app, move || update_heartbeat(|h| h.gateway_crashed_at = Some(crashed_at))).await.ok();
+                        announce(&app, "gateway.crashed", AnnouncementSeverity::Error, &[]);
+                    }
+                    continue;
+                }
+                _ => continue,
+            };
+            if stderr {
+                let text = String::from_utf8_lossy(&bytes);
+                if !text.trim().is_empty() {
+                    *app.state::<AppState>().last_gateway_error.lock().unwrap() = Some(redact_text(text.trim()));
+                }
+            }
+            let dropped = pump.push(&bytes, stderr);
+            if dropped > 0 {
+                count_dropped(&app, dropped);
+            }
+        }
+        let dropped = pump.finish().await;
+        if dropped > 0 {
+            count_dropped(&app, dropped);
+        }
+    });
+}
+
+#[tauri::command]
+pub(crate) fn set_emit_gateway_logs(app: tauri::AppHandle, enabled: bool) -> Result<(), AppError> {
+    ensure_writable()?;
+    let mut config = load_config();
+    config.emit_gateway_logs = enabled;
+    save_config(&config)?;
+    app.state::<AppState>().gateway_log_switch.send_replace(enabled);
+    Ok(())
+}
+
+/// Quick "what went wrong?": the last line the gateway wrote to stderr, if any.
+#[tauri::command]
+pub(crate) fn get_last_gateway_error(state: tauri::State<AppState>) -> Option<String> {
+    state.last_gateway_error.lock().unwrap().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The count a marker line reports, or `None` for a gateway line.
+    fn marker_count(line: &GatewayLine) -> Option<u64> {
+        line.text.strip_prefix("[clapp] ")?.split_whitespace().next()?.parse().ok()
+    }
+
+    #[test]
+    fn a_flood_stays_bounded_and_every_dropped_line_is_counted() {
+        const LINES: u64 = 300_000;
+        let (tx, mut queue) = tokio::sync::mpsc::channel(GATEWAY_LINE_QUEUE);
+        let mut pump = LinePump::new(20_000, tx);
+        let (mut kept, mut reported, mut counted) = (Vec::new(), 0u64, 0u64);
+        let take = |line: GatewayLine, kept: &mut Vec<u64>, reported: &mut u64| match marker_count(&line) {
+            Some(n) => *reported += n,
+            None => kept.push(line.text.trim_start_matches("line ").parse().unwrap()),
+        };
+        for i in 0..LINES {
+            counted += pump.push(format!("line {}", i).as_bytes(), false);
+            assert!(queue.len() <= GATEWAY_LINE_QUEUE);
+            // The writer keeps up with one line in ten
+            if i % 10 == 0 {
+                if let Ok(line) = queue.try_recv() {
+                    take(line, &mut kept, &mut reported);
+                }
+            }
+        }
+        while let Ok(line) = queue.try_recv() {
+            take(line, &mut kept, &mut reported);
+        }
+        counted += futures::executor::block_on(pump.finish());
+        while let Ok(line) = queue.try_recv() {
+            take(line, &mut kept, &mut reported);
+        }
+        assert!(counted > 0);
+        assert_eq!(reported, counted);
+        assert_eq!(kept.len() as u64 + counted, LINES);
+        // What got through is in order
+        assert!(kept.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn the_marker_lands_between_the_lines_it_was_dropped_from() {
+        let (tx, mut queue) = tokio::sync::mpsc::channel(2);
+        let mut pump = LinePump::new(1_000, tx);
+        pump.push(b"a", false);
+        pump.push(b"b", false);
+        // Queue full: dropped
+        assert_eq!(pump.push(b"c", false), 1);
+        assert_eq!(queue.try_recv().unwrap().text, "a");
+        assert_eq!(queue.try_recv().unwrap().text, "b");
+        pump.push(b"d", false);
+        let marker = queue.try_recv().unwrap();
+        assert_eq!(marker_count(&marker), Some(1));
+        assert!(!marker.forward);
+        assert_eq!(queue.try_recv().unwrap().text, "d");
+    }
+}
@@ -0,0 +1,211 @@
+//! Typed, versioned params for the gateway's `agent` method.
+
+use crate::*;
+
+// ─── Agent call params ────────────────────────────────────────────────────────
+
+/// Params of `openclaw gateway call agent`. Serialized field names are the wire names.
+#[derive(serde::Serialize, serde::Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AgentParams {
+    pub(crate) message: String,
+    pub(crate) session_key: String,
+    pub(crate) idempotency_key: String,
+    pub(crate) deliver: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) context_window: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) agent_id: Option<String>,
+}
+
+/// Which fields a gateway generation accepts for the `agent` method.
+pub(crate) struct AgentParamsSchema {
+    pub(crate) version: u32,
+    pub(crate) fields: &'static [&'static str],
+}
+
+pub(crate) const AGENT_PARAMS_V1: AgentParamsSchema = AgentParamsSchema {
+    version: 1,
+    fields: &["message", "sessionKey", "idempotencyKey", "deliver"],
+};
+
+pub(crate) const AGENT_PARAMS_V2: AgentParamsSchema = AgentParamsSchema {
+    version: 2,
+    fields: &["message", "sessionKey", "idempotencyKey", "deliver", "contextWindow", "agentId"],
+};
+
+impl AgentParamsSchema {
+    pub(crate) fn accepts(&self, field: &str) -> bool {
+        self.fields.contains(&field)
+    }
+}
+
+/// First OpenClaw release whose `agent` method takes `contextWindow` and `agentId`.
+pub(crate) const AGENT_PARAMS_V2_SINCE: [u32; 3] = [2026, 1, 0];
+
+/// The version in an `openclaw --version` line, e.g. "openclaw 2026.2.3" or "v2026.2.3-beta.1".
+/// Missing parts count as 0.
+pub(crate) fn parse_openclaw_version(line: &str) -> Option<[u32; 3]> {
+    let token = line.split_whitespace()
+        .map(|w| w.trim_start_matches('v'))
+        .find(|w| w.starts_with(|c: char| c.is_ascii_digit()))?;
+    let mut version = [0; 3];
+    let parts = token.split('.').map_while(|p| p.split(|c: char| !c.is_ascii_digit()).next()?.parse().ok());
+    for (slot, n) in version.iter_mut().zip(parts) {
+        *slot = n;
+    }
+    Some(version)
+}
+
+/// A gateway that rejected per-call routing is the older generation whatever its
+/// version says. Otherwise the version decides; an unknown one is assumed current.
+pub(crate) fn schema_for(openclaw_version: Option<&str>, routing: Option<bool>) -> &'static AgentParamsSchema {
+    if routing == Some(false) {
+        return &AGENT_PARAMS_V1;
+    }
+    match openclaw_version.and_then(parse_openclaw_version) {
+        Some(v) if v < AGENT_PARAMS_V2_SINCE => &AGENT_PARAMS_V1,
+        _ => &AGENT_PARAMS_V2,
+    }
+}
+
+/// From the version the environment probe found and what routed calls have shown.
+pub(crate) fn agent_params_schema(app: &tauri::AppHandle) -> &'static AgentParamsSchema {
+    let state = app.state::<AppState>();
+    let version = state.env_info.lock().unwrap().openclaw_version.clone();
+    let routing = *state.agent_routing.lock().unwrap();
+    schema_for(version.as_deref(), routing)
+}
+
+/// A params template must be an object and may not set any field Clapp sends
+/// itself, in any params version.
+pub(crate) fn validate_call_params_template(template: &serde_json::Value) -> Result<(), AppError> {
+    let obj = template.as_object()
+        .ok_or_else(|| AppError::InvalidParams("callParamsTemplate must be an object".into()))?;
+    match obj.keys().find(|k| AGENT_PARAMS_V2.fields.contains(&k.as_str())) {
+        Some(field) => Err(AppError::InvalidParams(format!("callParamsTemplate may not set {}; Clapp sets it per call", field))),
+        None => Ok(()),
+    }
+}
+
+impl AgentParams {
+    /// Checks the params and serializes them on top of `template`. A field set
+    /// here that `schema` doesn't accept fails with `InvalidParams` rather than
+    /// going missing. `extra` is merged last and is NOT validated: it exists for
+    /// trying out gateway fields before they get a typed counterpart here.
+    pub(crate) fn to_wire(
+        &self,
+        schema: &AgentParamsSchema,
+        template: Option<&serde_json::Value>,
+        extra: Option<&serde_json::Value>,
+    ) -> Result<String, AppError> {
+        for (field, value) in [("message", &self.message), ("sessionKey", &self.session_key), ("idempotencyKey", &self.idempotency_key)] {
+            if value.trim().is_empty() {
+                return Err(AppError::InvalidParams(format!("{} is required", field)));
+            }
+        }
+        if self.context_window == Some(0) {
+            return Err(AppError::InvalidParams("contextWindow must be positive".into()));
+        }
+        if self.agent_id.as_deref().is_some_and(|id| validate_agent_id(id).is_err()) {
+            return Err(AppError::InvalidParams("agentId is not a valid agent id".into()));
+        }
+
+        let own = serde_json::to_value(self)?;
+        let Some(own) = own.as_object() else { return Err(AppError::InvalidParams("params must be an object".into())) };
+        if let Some(field) = own.keys().find(|k| !schema.accepts(k)) {
+            return Err(AppError::InvalidParams(format!(
+                "{} is not supported by this gateway (agent params v{}); update OpenClaw or clear the setting",
+                field, schema.version
+            )));
+        }
+        // Checked again here: config.json may have been edited by hand
+        let mut wire = match template {
+            Some(t) => {
+                validate_call_params_template(t)?;
+                t.clone()
+            }
+            None => serde_json::json!({}),
+        };
+        for (k, v) in own {
+            wire[k] = v.clone();
+        }
+        if let Some(serde_json::Value::Object(extra)) = extra {
+            for (k, v) in extra {
+                wire[k] = v.clone();
+            }
+        } else if extra.is_some_and(|e| !e.is_null()) {
+            return Err(AppError::InvalidParams("extraParams must be an object".into()));
+        }
+        Ok(wire.to_string())
+    }
+}
+
+/// `None` removes the template.
+#[tauri::command]
+pub(crate) fn set_call_params_template(template: Option<serde_json::Value>) -> Result<(), AppError> {
+    ensure_writable()?;
+    let template = template.filter(|t| !t.is_null());
+    if let Some(t) = &template {
+        validate_call_params_template(t)?;
+    }
+    let mut config = load_config();
+    config.call_params_template = template;
+    save_config(&config)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `agent` params a gateway of each generation accepted, under tests/fixtures/params.
+    fn recorded(name: &str) -> serde_json::Value {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/params").join(name);
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    fn round_trip(name: &str, schema: &AgentParamsSchema) {
+        let accepted = recorded(name);
+        let params: AgentParams = serde_json::from_value(accepted.clone()).unwrap();
+        let wire: serde_json::Value = serde_json::from_str(&params.to_wire(schema, None, None).unwrap()).unwrap();
+        assert_eq!(wire, accepted);
+        assert!(wire.as_object().unwrap().keys().all(|k| schema.accepts(k)));
+    }
+
+    #[test]
+    fn round_trips_what_each_generation_accepted() {
+        round_trip("agent-v1.json", &AGENT_PARAMS_V1);
+        round_trip("agent-v2.json", &AGENT_PARAMS_V2);
+        // The current generation takes the older payload unchanged
+        round_trip("agent-v1.json", &AGENT_PARAMS_V2);
+    }
+
+    #[test]
+    fn fields_an_older_gateway_lacks_fail_by_name() {
+        let params: AgentParams = serde_json::from_value(recorded("agent-v2.json")).unwrap();
+        let Err(AppError::InvalidParams(e)) = params.to_wire(&AGENT_PARAMS_V1, None, None) else {
+            panic!("expected InvalidParams");
+        };
+        assert!(e.starts_with("contextWindow "), "{}", e);
+        let routed = AgentParams { context_window: None, ..params };
+        let Err(AppError::InvalidParams(e)) = routed.to_wire(&AGENT_PARAMS_V1, None, None) else {
+            panic!("expected InvalidParams");
+        };
+        assert!(e.starts_with("agentId "), "{}", e);
+    }
+
+    #[test]
+    fn schema_follows_the_detected_version() {
+        assert_eq!(parse_openclaw_version("openclaw 2026.2.3"), Some([2026, 2, 3]));
+        assert_eq!(parse_openclaw_version("v2025.11.30-beta.1"), Some([2025, 11, 30]));
+        assert_eq!(parse_openclaw_version("2026.1"), Some([2026, 1, 0]));
+        assert_eq!(parse_openclaw_version("openclaw"), None);
+        assert_eq!(schema_for(Some("openclaw 2025.12.9"), None).version, 1);
+        assert_eq!(schema_for(Some("openclaw 2026.1.0"), None).version, 2);
+        assert_eq!(schema_for(None, None).version, 2);
+        assert_eq!(schema_for(Some("garbled"), Some(true)).version, 2);
+        // A rejected routed call outweighs the version
+        assert_eq!(schema_for(Some("openclaw 2026.3.0"), Some(false)).version, 1);
+    }
+}
@@ -0,0 +1,126 @@
+//! Suspend and resume handling.
+
+use crate::*;
+
+// ─── Power events ─────────────────────────────────────────────────────────────
+
+#[derive(Default)]
+pub(crate) struct PowerState {
+    pub(crate) suspended: std::sync::atomic::AtomicBool,
+    /// We stopped our own gateway for sleep and should bring it back on wake
+    pub(crate) stopped_for_suspend: std::sync::atomic::AtomicBool,
+}
+
+// Windows gives suspend handlers about two seconds
+pub(crate) const SUSPEND_STOP_TIMEOUT_MS: u64 = 1_500;
+pub(crate) const CLOCK_JUMP_TICK_SECS: u64 = 5;
+pub(crate) const CLOCK_JUMP_THRESHOLD_SECS: u64 = 30;
+
+#[cfg(windows)]
+pub(crate) async fn on_suspend(app: &tauri::AppHandle) {
+    use std::sync::atomic::Ordering;
+    let app_state = app.state::<AppState>();
+    let power = &app_state.power;
+    power.suspended.store(true, Ordering::Relaxed);
+    app.emit("power-suspend", ()).ok();
+
+    let managed = app.state::<AppState>().process.lock().unwrap().is_some();
+    if managed && load_config().stop_gateway_on_suspend
+        && stop_gateway_graceful(app, Some(SUSPEND_STOP_TIMEOUT_MS), false).await.is_ok()
+    {
+        power.stopped_for_suspend.store(true, Ordering::Relaxed);
+    }
+}
+
+pub(crate) async fn on_resume(app: &tauri::AppHandle) {
+    use std::sync::atomic::Ordering;
+    let app_state = app.state::<AppState>();
+    let power = &app_state.power;
+    power.suspended.store(false, Ordering::Relaxed);
+    app.emit("power-resume", ()).ok();
+
+    if power.stopped_for_suspend.swap(false, Ordering::Relaxed) {
+        if let Err(e) = launch_gateway(app, false).await {
+            eprintln!("[POWER] restart after wake failed: {}", e);
+        }
+        return;
+    }
+
+    let managed = app.state::<AppState>().process.lock().unwrap().is_some();
+    if !managed {
+        return;
+    }
+    let healthy = matches!(gateway_status(app.clone()).await, Ok(s) if s.state == "running");
+    if healthy {
+        // Pairing may not survive the sleep
+        if let Ok(Ok(token)) = run_storage_io(app, read_gateway_token).await {
+            do_pairing(app, &token).await.ok();
+        }
+    } else if let Err(e) = graceful_restart_gateway(app.clone(), Some(SUSPEND_STOP_TIMEOUT_MS)).await {
+        eprintln!("[POWER] gateway wedged after wake, restart failed: {}", e);
+    }
+}
+
+#[cfg(windows)]
+pub(crate) fn register_power_notifications(app: tauri::AppHandle) -> bool {
+    use std::ffi::c_void;
+    use windows_sys::Win32::System::Power::{PowerRegisterSuspendResumeNotification, DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS};
+    use windows_sys::Win32::UI::WindowsAndMessaging::{DEVICE_NOTIFY_CALLBACK, PBT_APMRESUMEAUTOMATIC, PBT_APMSUSPEND};
+
+    unsafe extern "system" fn callback(context: *const c_void, kind: u32, _setting: *const c_void) -> u32 {
+        // SAFETY: context is the AppHandle leaked below and lives for the whole process
+        let app = unsafe { &*(context as *const tauri::AppHandle) };
+        // Runs on a system thread; blocking keeps the machine awake until we're done
+        match kind {
+            PBT_APMSUSPEND => tauri::async_runtime::block_on(on_suspend(app)),
+            PBT_APMRESUMEAUTOMATIC => {
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move { on_resume(&app).await });
+            }
+            _ => {}
+        }
+        0
+    }
+
+    // Registration lasts for the process lifetime, so both allocations are leaked on purpose
+    let context = Box::into_raw(Box::new(app)) as *mut c_void;
+    let params = Box::leak(Box::new(DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS { Callback: Some(callback), Context: context }));
+    let mut registration = std::ptr::null_mut();
+    // SAFETY: params and context outlive the registration
+    let rc = unsafe {
+        PowerRegisterSuspendResumeNotification(
+            DEVICE_NOTIFY_CALLBACK,
+            params as *mut DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS as *mut c_void,
+            &mut registration,
+        )
+    };
+    rc == 0
+}
+
+#[cfg(not(windows))]
+pub(crate) fn register_power_notifications(_app: tauri::AppHandle) -> bool {
+    false
+}
+
+/// Fallback without power notifications: a sleep shows up as the wall clock
+/// jumping far past our tick interval. Only the resume side can be observed.
+pub(crate) fn spawn_clock_jump_detector(app: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut last = std::time::SystemTime::now();
+        while !is_shutting_down(&app) {
+            tokio::time::sleep(std::time::Duration::from_secs(CLOCK_JUMP_TICK_SECS)).await;
+            let now = std::time::SystemTime::now();
+            let elapsed = now.duration_since(last).unwrap_or_default().as_secs();
+            last = now;
+            if elapsed > CLOCK_JUMP_TICK_SECS + CLOCK_JUMP_THRESHOLD_SECS {
+                on_resume(&app).await;
+            }
+        }
+    });
+}
+
+pub(crate) fn start_power_monitor(app: tauri::AppHandle) {
+    if !register_power_notifications(app.clone()) {
+        spawn_clock_jump_detector(app);
+    }
+}
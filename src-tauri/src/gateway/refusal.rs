@@ -0,0 +1,240 @@
+//! Recognizing refusals and provider content blocks.
+
+use crate::*;
+
+// ─── Refusal detection ────────────────────────────────────────────────────────
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum RefusalKind {
+    /// The provider blocked the request; taken from the error payload
+    ContentPolicy,
+    /// The model answered with a refusal; heuristic
+    ModelRefusal,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RefusalInfo {
+    pub(crate) kind: RefusalKind,
+    /// 1.0 for provider errors, lower for text heuristics
+    pub(crate) confidence: f64,
+    /// The pattern or error code that matched
+    pub(crate) matched: String,
+}
+
+/// Patterns are matched case-insensitively against the start of the reply.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub(crate) struct RefusalConfig {
+    pub(crate) enabled: bool,
+    pub(crate) patterns: Vec<String>,
+    /// Heuristic matches below this are treated as normal replies
+    pub(crate) min_confidence: f64,
+}
+
+impl Default for RefusalConfig {
+    fn default() -> Self {
+        let patterns = [
+            "i can't help with",
+            "i cannot help with",
+            "i can't assist with",
+            "i cannot assist with",
+            "i'm not able to help with",
+            "i won't be able to help with",
+            "i can't provide",
+            "i cannot provide",
+            "i'm sorry, but i can't",
+            "i'm sorry, but i cannot",
+            "я не могу помочь с",
+            "я не могу с этим помочь",
+            "я не могу выполнить",
+            "я не могу предоставить",
+            "извините, но я не могу",
+            "к сожалению, я не могу",
+        ];
+        Self {
+            enabled: true,
+            patterns: patterns.iter().map(|p| p.to_string()).collect(),
+            min_confidence: 0.8,
+        }
+    }
+}
+
+/// Error codes and types providers put on blocked requests, compared whole.
+pub(crate) const CONTENT_POLICY_CODES: &[&str] = &[
+    "content_policy_violation",
+    "content_policy",
+    "content_filter",
+    "policy_violation",
+    "moderation_blocked",
+    "safety",
+];
+
+fn content_policy_code(code: &str) -> Option<RefusalInfo> {
+    CONTENT_POLICY_CODES.iter().find(|c| code.eq_ignore_ascii_case(c)).map(|c| RefusalInfo {
+        kind: RefusalKind::ContentPolicy,
+        confidence: 1.0,
+        matched: c.to_string(),
+    })
+}
+
+/// A plain error message counts when one of its words is a code, so
+/// "safety_identifier" is not "safety".
+pub(crate) fn content_policy_error(error: &str) -> Option<RefusalInfo> {
+    error.split(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).find_map(content_policy_code)
+}
+
+/// A structured error counts by its `code` or `type`, on it or on a nested `error`.
+pub(crate) fn content_policy_payload(error: &serde_json::Value) -> Option<RefusalInfo> {
+    match error {
+        serde_json::Value::String(s) => content_policy_error(s),
+        serde_json::Value::Object(o) => ["code", "type"].iter()
+            .filter_map(|k| o.get(*k)?.as_str())
+            .find_map(content_policy_code)
+            .or_else(|| o.get("error").and_then(content_policy_payload)),
+        _ => None,
+    }
+}
+
+/// Conservative on purpose: a pattern only counts near the start of a short reply,
+/// since long answers often quote or discuss refusals without being one.
+pub(crate) fn model_refusal(text: &str, config: &RefusalConfig) -> Option<RefusalInfo> {
+    let text = text.trim().to_lowercase();
+    let chars = text.chars().count();
+    if chars == 0 || chars > 600 {
+        return None;
+    }
+    let (pattern, pos) = config.patterns.iter()
+        .filter(|p| !p.trim().is_empty())
+        .filter_map(|p| {
+            let p = p.trim().to_lowercase();
+            text.find(&p).map(|i| (p, text[..i].chars().count()))
+        })
+        .min_by_key(|(_, pos)| *pos)?;
+    let mut confidence: f64 = 0.5;
+    if pos <= 40 { confidence += 0.3; }
+    if chars <= 300 { confidence += 0.15; }
+    (confidence >= config.min_confidence).then_some(RefusalInfo {
+        kind: RefusalKind::ModelRefusal,
+        confidence,
+        matched: pattern,
+    })
+}
+
+/// Classifies a raw gateway response. Provider errors win over text heuristics.
+pub(crate) fn classify_refusal(raw: &str, config: &RefusalConfig) -> Option<RefusalInfo> {
+    if !config.enabled {
+        return None;
+    }
+    let v: serde_json::Value = serde_json::from_str(raw).ok()?;
+    match v.get("error") {
+        Some(serde_json::Value::Null) | None => {}
+        Some(e) => return content_policy_payload(e),
+    }
+    model_refusal(&reply_text(raw), config)
+}
+
+pub(crate) fn emit_refusal(app: &tauri::AppHandle, agent_id: &str, session_key: &str, refusal: &RefusalInfo) {
+    app.emit("gateway-refused", serde_json::json!({
+        "agentId": agent_id,
+        "sessionKey": session_key,
+        "refusal": refusal,
+    })).ok();
+}
+
+#[tauri::command]
+pub(crate) fn get_refusal_config() -> RefusalConfig {
+    load_config().refusal
+}
+
+#[tauri::command]
+pub(crate) fn set_refusal_config(refusal: RefusalConfig) -> Result<(), AppError> {
+    ensure_writable()?;
+    if !(0.0..=1.0).contains(&refusal.min_confidence) {
+        return Err(AppError::InvalidInput("minConfidence must be between 0 and 1".into()));
+    }
+    let mut config = load_config();
+    config.refusal = refusal;
+    save_config(&config)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(serde::Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct RefusalFixtures {
+        refusals: Vec<String>,
+        not_refusals: Vec<String>,
+    }
+
+    /// Real refusal texts and replies that only look like one, under tests/fixtures/refusals.
+    fn fixtures(lang: &str) -> RefusalFixtures {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/refusals").join(format!("{}.json", lang));
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    fn reply(text: &str) -> String {
+        serde_json::json!({ "status": "ok", "result": { "payloads": [{ "text": text }] } }).to_string()
+    }
+
+    fn check_language(lang: &str) {
+        let config = RefusalConfig::default();
+        let f = fixtures(lang);
+        for text in &f.refusals {
+            let r = classify_refusal(&reply(text), &config).unwrap_or_else(|| panic!("missed refusal: {}", text));
+            assert!(r.kind == RefusalKind::ModelRefusal && r.confidence >= config.min_confidence);
+        }
+        for text in &f.not_refusals {
+            assert!(classify_refusal(&reply(text), &config).is_none(), "false positive: {}", text);
+        }
+    }
+
+    #[test]
+    fn english_fixtures() {
+        check_language("en");
+    }
+
+    #[test]
+    fn russian_fixtures() {
+        check_language("ru");
+    }
+
+    #[test]
+    fn provider_blocks_come_from_the_error_payload() {
+        let config = RefusalConfig::default();
+        let blocked = r#"{"result":null,"error":{"code":"content_filter","message":"The response was filtered"}}"#;
+        let r = classify_refusal(blocked, &config).unwrap();
+        assert!(r.kind == RefusalKind::ContentPolicy && r.confidence == 1.0);
+        assert_eq!(r.matched, "content_filter");
+        assert!(content_policy_error("Error: 400 content_policy_violation: your request was rejected").is_some());
+        let nested = r#"{"error":{"type":"error","error":{"type":"SAFETY","message":"blocked"}}}"#;
+        assert_eq!(classify_refusal(nested, &config).unwrap().matched, "safety");
+        // Codes are compared whole, not found inside other fields or words
+        let unrelated = r#"{"result":null,"error":{"code":"invalid_safety_identifier","message":"bad safety_identifier"}}"#;
+        assert!(classify_refusal(unrelated, &config).is_none());
+        assert!(content_policy_error("Error: 400 unknown parameter safety_identifier").is_none());
+        assert!(content_policy_error("Error: 400 request blocked by moderation").is_none());
+        // Other errors are failures, not refusals, whatever the text says
+        let limited = r#"{"result":null,"error":{"type":"rate_limit_error","message":"I can't help with that right now"}}"#;
+        assert!(classify_refusal(limited, &config).is_none());
+        assert!(content_policy_error("Error: 401 invalid x-api-key").is_none());
+    }
+
+    #[test]
+    fn patterns_are_configurable_and_can_be_turned_off() {
+        let text = reply("Nope, not doing that one.");
+        let mut config = RefusalConfig::default();
+        assert!(classify_refusal(&text, &config).is_none());
+        config.patterns.push("  Nope, not doing  ".into());
+        assert_eq!(classify_refusal(&text, &config).unwrap().matched, "nope, not doing");
+        config.enabled = false;
+        assert!(classify_refusal(&text, &config).is_none());
+        // A blank pattern never matches everything
+        let blank = RefusalConfig { patterns: vec![" ".into()], ..RefusalConfig::default() };
+        assert!(model_refusal("Sure, here it is.", &blank).is_none());
+    }
+}
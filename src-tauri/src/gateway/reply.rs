@@ -0,0 +1,229 @@
+//! Turning raw `gateway call` output into one response document.
+
+use crate::*;
+
+// ─── Reply assembly ───────────────────────────────────────────────────────────
+
+/// Fields the gateway has used to number the parts of a split reply.
+pub(crate) const SEQUENCE_FIELDS: &[&str] = &["seq", "sequence", "part", "index"];
+
+pub(crate) fn reply_sequence(doc: &serde_json::Value) -> Option<u64> {
+    SEQUENCE_FIELDS.iter().find_map(|k| doc[k].as_u64().or(doc["result"][k].as_u64()))
+}
+
+/// Long replies can arrive as several JSON documents back to back, and output
+/// past the CLI's limit ends mid-document. A single complete document (or
+/// output that isn't JSON at all) is returned untouched. Several are stitched
+/// into the last one, which carries the final metadata, with payloads ordered
+/// by their sequence field. A cut-off document fails with `PartialResponse`
+/// holding the text that could be recovered. It isn't fetched again: the gateway
+/// only hands out replies through the CLI, and calling again would run the agent
+/// a second time.
+pub(crate) fn assemble_reply(stdout: &str) -> Result<String, AppError> {
+    let mut docs: Vec<serde_json::Value> = Vec::new();
+    for doc in serde_json::Deserializer::from_str(stdout).into_iter::<serde_json::Value>() {
+        match doc {
+            Ok(v) => docs.push(v),
+            Err(e) if e.is_eof() => {
+                let mut texts: Vec<String> = ordered(docs).iter().flat_map(payload_texts).collect();
+                texts.push(recover_texts(unfinished_document(stdout)));
+                let recovered = texts.into_iter().filter(|t| !t.trim().is_empty()).collect::<Vec<_>>().join("\n\n");
+                return Err(AppError::PartialResponse(recovered));
+            }
+            Err(_) => return Ok(stdout.to_string()),
+        }
+    }
+    if docs.len() < 2 {
+        return Ok(stdout.to_string());
+    }
+
+    let docs = ordered(docs);
+    let payloads: Vec<serde_json::Value> = docs.iter()
+        .flat_map(|d| d["result"]["payloads"].as_array().cloned().unwrap_or_default())
+        .collect();
+    let mut merged = docs.last().cloned().unwrap_or_default();
+    if merged["result"].is_object() {
+        merged["result"]["payloads"] = payloads.into();
+    }
+    Ok(merged.to_string())
+}
+
+/// Stable order by sequence field; parts without one keep their arrival position.
+fn ordered(docs: Vec<serde_json::Value>) -> Vec<serde_json::Value> {
+    let mut indexed: Vec<(u64, serde_json::Value)> = docs.into_iter()
+        .enumerate()
+        .map(|(i, d)| (reply_sequence(&d).unwrap_or(i as u64), d))
+        .collect();
+    indexed.sort_by_key(|(seq, _)| *seq);
+    indexed.into_iter().map(|(_, d)| d).collect()
+}
+
+fn payload_texts(doc: &serde_json::Value) -> Vec<String> {
+    doc["result"]["payloads"].as_array()
+        .map(|a| a.iter().filter_map(|p| p["text"].as_str()).map(String::from).collect())
+        .unwrap_or_default()
+}
+
+/// The trailing document that never closed: everything after the last complete one.
+fn unfinished_document(stdout: &str) -> &str {
+    let mut rest = stdout;
+    let mut stream = serde_json::Deserializer::from_str(stdout).into_iter::<serde::de::IgnoredAny>();
+    while let Some(Ok(_)) = stream.next() {
+        rest = &stdout[stream.byte_offset()..];
+    }
+    rest
+}
+
+/// Pulls every `"text": "..."` value out of truncated JSON, keeping a value cut
+/// off at the end. Half-written escapes and a character split by the cut are dropped.
+pub(crate) fn recover_texts(partial: &str) -> String {
+    let mut texts = Vec::new();
+    let mut rest = partial;
+    while let Some(at) = rest.find("\"text\"") {
+        rest = rest[at + 6..].trim_start();
+        let Some(after_colon) = rest.strip_prefix(':') else { continue };
+        let Some(body) = after_colon.trim_start().strip_prefix('"') else { continue };
+        let (text, consumed) = decode_json_string(body);
+        texts.push(text);
+        rest = &body[consumed..];
+    }
+    texts.into_iter().filter(|t| !t.trim().is_empty()).collect::<Vec<_>>().join("\n\n")
+}
+
+/// Decodes a JSON string body up to its closing quote or the end of input.
+/// Returns the text and how many bytes were consumed.
+fn decode_json_string(body: &str) -> (String, usize) {
+    let mut out = String::new();
+    let mut chars = body.char_indices();
+    let mut pending_high: Option<u16> = None;
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return (out, i + 1),
+            '\\' => {
+                let Some((_, esc)) = chars.next() else { break };
+                let decoded = match esc {
+                    'n' => '\n',
+                    't' => '\t',
+                    'r' => '\r',
+                    'b' => '\u{8}',
+                    'f' => '\u{c}',
+                    'u' => {
+                        let hex: String = chars.by_ref().take(4).map(|(_, h)| h).collect();
+                        let Some(unit) = (hex.len() == 4).then(|| u16::from_str_radix(&hex, 16).ok()).flatten() else { break };
+                        match (pending_high.take(), unit) {
+                            (None, 0xD800..=0xDBFF) => { pending_high = Some(unit); continue }
+                            (Some(high), 0xDC00..=0xDFFF) => {
+                                let code = 0x10000 + (((high as u32) - 0xD800) << 10) + ((unit as u32) - 0xDC00);
+                                char::from_u32(code).unwrap_or('\u{FFFD}')
+                            }
+                            (_, unit) => char::from_u32(unit as u32).unwrap_or('\u{FFFD}'),
+                        }
+                    }
+                    other => other,
+                };
+                out.push(decoded);
+            }
+            // Lossy decoding leaves a replacement char where a multi-byte character was cut
+            '\u{FFFD}' if body[i + c.len_utf8()..].is_empty() => break,
+            c => out.push(c),
+        }
+    }
+    (out, body.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Replies shaped like real long-reply sessions, under tests/fixtures/replies.
+    fn fixture(name: &str) -> Vec<u8> {
+        fs::read(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/replies").join(name)).unwrap()
+    }
+
+    fn expected(name: &str) -> String {
+        String::from_utf8(fixture(&format!("{}.expected.txt", name))).unwrap()
+    }
+
+    /// What `collect_call_output` makes of output that arrives line by line.
+    fn streamed<'a>(lines: impl IntoIterator<Item = &'a [u8]>) -> String {
+        let mut stdout = Vec::new();
+        for line in lines {
+            stdout.extend(line);
+            stdout.push(b'\n');
+        }
+        String::from_utf8_lossy(&stdout).trim().to_string()
+    }
+
+    /// The same output read in one piece.
+    fn buffered(bytes: &[u8]) -> String {
+        String::from_utf8_lossy(bytes).trim().to_string()
+    }
+
+    fn partial(stdout: &str) -> String {
+        match assemble_reply(stdout) {
+            Err(AppError::PartialResponse(text)) => text,
+            other => panic!("expected a partial response, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parts_are_stitched_in_sequence_order() {
+        let raw = assemble_reply(&buffered(&fixture("split-out-of-order.ndjson"))).unwrap();
+        let v: serde_json::Value = serde_json::from_str(&raw).unwrap();
+        // The last part carries the final metadata
+        assert_eq!(v["status"], "ok");
+        assert_eq!(v["result"]["stopReason"], "end_turn");
+        assert_eq!(reply_text(&raw), expected("split-out-of-order"));
+    }
+
+    #[test]
+    fn streaming_and_buffered_output_give_the_same_text() {
+        let bytes = fixture("split-out-of-order.ndjson");
+        let lines = bytes.split(|b| *b == b'\n').filter(|l| !l.is_empty());
+        let from_stream = assemble_reply(&streamed(lines)).unwrap();
+        let from_buffer = assemble_reply(&buffered(&bytes)).unwrap();
+        assert_eq!(reply_text(&from_stream), reply_text(&from_buffer));
+
+        // One long document, pretty-printed over many lines the way the fixture runner plays it
+        let text = expected("split-out-of-order");
+        let chunks = reply_chunks(&text, 0);
+        let from_stream = assemble_reply(&streamed(chunks.iter().map(|c| c.text.as_bytes()))).unwrap();
+        let doc = serde_json::json!({ "status": "ok", "result": { "payloads": [{ "text": text }] } });
+        let from_buffer = assemble_reply(&doc.to_string()).unwrap();
+        assert_eq!(reply_text(&from_stream), text);
+        assert_eq!(reply_text(&from_buffer), text);
+    }
+
+    #[test]
+    fn emoji_cut_in_its_utf8_bytes_is_dropped() {
+        let bytes = fixture("truncated-raw-emoji.txt");
+        let from_buffer = partial(&buffered(&bytes));
+        assert_eq!(from_buffer, expected("truncated-raw-emoji"));
+        assert!(!from_buffer.contains('\u{FFFD}'));
+        let from_stream = partial(&streamed(bytes.split(|b| *b == b'\n')));
+        assert_eq!(from_stream, from_buffer);
+    }
+
+    #[test]
+    fn emoji_cut_between_its_surrogates_is_dropped() {
+        let stdout = buffered(&fixture("truncated-escaped-emoji.ndjson"));
+        // The complete part before the cut is kept, with its own escaped emoji decoded
+        assert_eq!(partial(&stdout), expected("truncated-escaped-emoji"));
+    }
+
+    #[test]
+    fn decodes_up_to_the_cut() {
+        assert_eq!(decode_json_string(r#"a\"b" rest"#), ("a\"b".to_string(), 5));
+        assert_eq!(decode_json_string(r#"smile \ud83d\ude00"#).0, "smile \u{1F600}");
+        assert_eq!(decode_json_string(r#"cut \ud83d"#).0, "cut ");
+        assert_eq!(decode_json_string(r#"cut \u00"#).0, "cut ");
+        assert_eq!(decode_json_string("cut \\").0, "cut ");
+    }
+
+    #[test]
+    fn single_documents_and_plain_text_are_untouched() {
+        let doc = r#"{"status":"ok","result":{"payloads":[{"text":"hi"}]}}"#;
+        assert_eq!(assemble_reply(doc).unwrap(), doc);
+        assert_eq!(assemble_reply("Usage: openclaw gateway call").unwrap(), "Usage: openclaw gateway call");
+    }
+}
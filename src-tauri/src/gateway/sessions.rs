@@ -0,0 +1,220 @@
+//! Read-only access to the gateway's own session transcripts.
+
+use crate::*;
+
+// ─── Gateway sessions (read-only) ─────────────────────────────────────────────
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GatewaySessionInfo {
+    pub(crate) id: String,
+    pub(crate) agent_id: String,
+    pub(crate) format: String,
+    pub(crate) updated_at: u64,
+    pub(crate) size_bytes: u64,
+}
+
+/// Transcript layouts seen across OpenClaw versions.
+#[derive(PartialEq)]
+pub(crate) enum TranscriptFormat {
+    /// First line is a `{"type":"session","version":N}` header, messages are `{"type":"message","message":{..}}`
+    Versioned(u64),
+    /// Older builds write bare `{"role":..,"content":..}` lines
+    Legacy,
+}
+
+pub(crate) const SUPPORTED_TRANSCRIPT_VERSIONS: std::ops::RangeInclusive<u64> = 1..=3;
+
+pub(crate) fn sniff_transcript_format(first_line: &str) -> Result<TranscriptFormat, AppError> {
+    let v: serde_json::Value = serde_json::from_str(first_line)
+        .map_err(|_| AppError::UnsupportedFormat("not a JSONL transcript".into()))?;
+    if v["type"] == "session" {
+        let version = v["version"].as_u64().unwrap_or(1);
+        if !SUPPORTED_TRANSCRIPT_VERSIONS.contains(&version) {
+            return Err(AppError::UnsupportedFormat(format!("transcript version {}", version)));
+        }
+        return Ok(TranscriptFormat::Versioned(version));
+    }
+    if v.get("role").is_some() {
+        return Ok(TranscriptFormat::Legacy);
+    }
+    Err(AppError::UnsupportedFormat(format!(
+        "unknown transcript header (type {})", v["type"].as_str().unwrap_or("missing")
+    )))
+}
+
+pub(crate) fn format_label(f: &TranscriptFormat) -> String {
+    match f {
+        TranscriptFormat::Versioned(v) => format!("v{}", v),
+        TranscriptFormat::Legacy => "legacy".into(),
+    }
+}
+
+/// Transcript ids of our own warm-up sessions, looked up in the gateway's session store.
+pub(crate) fn warm_up_session_ids(sessions_dir: &std::path::Path) -> std::collections::HashSet<String> {
+    let store: serde_json::Value = fs::read_to_string(sessions_dir.join("sessions.json"))
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default();
+    store.as_object()
+        .map(|m| m.iter()
+            .filter(|(key, _)| key.ends_with(WARM_UP_SESSION))
+            .filter_map(|(_, entry)| entry["sessionId"].as_str().map(String::from))
+            .collect())
+        .unwrap_or_default()
+}
+
+/// Transcript id the gateway keeps for one of an agent's named sessions, e.g. "main".
+pub(crate) fn gateway_session_id(agent_id: &str, session: &str) -> Option<String> {
+    let store: serde_json::Value = fs::read_to_string(openclaw_agents_root().join(agent_id).join("sessions").join("sessions.json"))
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())?;
+    let suffix = format!(":{}", session);
+    store.as_object()?
+        .iter()
+        .find(|(key, _)| key.ends_with(&suffix))
+        .and_then(|(_, entry)| entry["sessionId"].as_str().map(String::from))
+}
+
+pub(crate) fn gateway_transcripts() -> Vec<(String, PathBuf)> {
+    let mut out = Vec::new();
+    let Ok(agents) = fs::read_dir(openclaw_agents_root()) else { return out };
+    for agent in agents.flatten() {
+        let agent_id = agent.file_name().to_string_lossy().into_owned();
+        let sessions_dir = agent.path().join("sessions");
+        let Ok(files) = fs::read_dir(&sessions_dir) else { continue };
+        let hidden = warm_up_session_ids(&sessions_dir);
+        for f in files.flatten() {
+            let id = f.path().file_stem().unwrap_or_default().to_string_lossy().into_owned();
+            if f.path().extension().is_some_and(|e| e == "jsonl") && !hidden.contains(&id) {
+                out.push((agent_id.clone(), f.path()));
+            }
+        }
+    }
+    out
+}
+
+pub(crate) fn find_gateway_transcript(id: &str) -> Result<(String, PathBuf), AppError> {
+    gateway_transcripts()
+        .into_iter()
+        .find(|(_, p)| p.file_stem().is_some_and(|s| s == id))
+        .ok_or_else(|| AppError::NotFound(format!("gateway session {}", id)))
+}
+
+pub(crate) fn content_text(content: &serde_json::Value) -> String {
+    match content {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Array(parts) => parts
+            .iter()
+            .filter(|p| p["type"] == "text")
+            .filter_map(|p| p["text"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+pub(crate) fn parse_transcript(id: &str, content: &str) -> Result<Vec<HistoryRecord>, AppError> {
+    let mut lines = content.lines().filter(|l| !l.trim().is_empty());
+    let Some(first) = lines.next() else { return Ok(Vec::new()) };
+    let format = sniff_transcript_format(first)?;
+
+    let all: Vec<&str> = match format {
+        TranscriptFormat::Legacy => std::iter::once(first).chain(lines).collect(),
+        TranscriptFormat::Versioned(_) => lines.collect(),
+    };
+
+    let mut records = Vec::new();
+    for (i, line) in all.into_iter().enumerate() {
+        let Ok(entry) = serde_json::from_str::<serde_json::Value>(line) else { continue };
+        let msg = match format {
+            TranscriptFormat::Legacy => &entry,
+            TranscriptFormat::Versioned(_) if entry["type"] == "message" => &entry["message"],
+            TranscriptFormat::Versioned(_) => continue,
+        };
+        let role = match msg["role"].as_str() {
+            Some("user") => "user",
+            Some("assistant") => "agent",
+            // Tool calls/results are not part of the chat view
+            _ => continue,
+        };
+        let text = content_text(&msg["content"]);
+        if text.trim().is_empty() {
+            continue;
+        }
+        let ts = entry["timestamp"].as_u64()
+            .or_else(|| entry["timestamp"].as_str().and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+                .map(|t| t.timestamp_millis() as u64))
+            .unwrap_or(0);
+        let line_id = entry["id"].as_str().map(String::from).unwrap_or_else(|| i.to_string());
+        records.push(HistoryRecord {
+            id: format!("gw-{}-{}", id, line_id),
+            session_key: format!("gateway:{}", id),
+            role: role.into(),
+            text,
+            ts,
+            source: Some("gateway".into()),
+            ..Default::default()
+        });
+    }
+    Ok(records)
+}
+
+#[tauri::command]
+pub(crate) async fn list_gateway_sessions(app: tauri::AppHandle) -> Result<Vec<GatewaySessionInfo>, AppError> {
+    run_storage_io(&app, || {
+        let mut sessions: Vec<GatewaySessionInfo> = gateway_transcripts()
+            .into_iter()
+            .map(|(agent_id, path)| {
+                let meta = fs::metadata(&path).ok();
+                // Only the first line is read; transcripts can be large
+                let first = fs::File::open(&path).ok()
+                    .and_then(|f| std::io::BufRead::lines(std::io::BufReader::new(f)).map_while(Result::ok).find(|l| !l.trim().is_empty()))
+                    .unwrap_or_default();
+                let format = match sniff_transcript_format(&first) {
+                    Ok(f) => format_label(&f),
+                    Err(_) => "unsupported".into(),
+                };
+                GatewaySessionInfo {
+                    id: path.file_stem().unwrap_or_default().to_string_lossy().into_owned(),
+                    agent_id,
+                    format,
+                    updated_at: meta.as_ref()
+                        .and_then(|m| m.modified().ok())
+                        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                        .map(|d| d.as_millis() as u64)
+                        .unwrap_or(0),
+                    size_bytes: meta.map(|m| m.len()).unwrap_or(0),
+                }
+            })
+            .collect();
+        sessions.sort_by_key(|s| std::cmp::Reverse(s.updated_at));
+        sessions
+    }).await
+}
+
+#[tauri::command]
+pub(crate) async fn read_gateway_session(app: tauri::AppHandle, id: String) -> Result<Vec<HistoryRecord>, AppError> {
+    run_storage_io(&app, move || {
+        let (_, path) = find_gateway_transcript(&id)?;
+        parse_transcript(&id, &fs::read_to_string(path)?)
+    }).await?
+}
+
+/// Copies a gateway session into local history, skipping records imported before.
+#[tauri::command]
+pub(crate) async fn import_gateway_session(app: tauri::AppHandle, id: String) -> Result<usize, AppError> {
+    ensure_writable()?;
+    let (agent_id, records) = run_storage_io(&app, move || -> Result<_, AppError> {
+        let (agent_id, path) = find_gateway_transcript(&id)?;
+        Ok((agent_id, parse_transcript(&id, &fs::read_to_string(path)?)?))
+    }).await??;
+
+    run_storage_io(&app, move || -> Result<usize, AppError> {
+        let existing: std::collections::HashSet<String> =
+            read_history(&agent_id).into_iter().map(|r| r.id).collect();
+        let fresh: Vec<HistoryRecord> = records.into_iter().filter(|r| !existing.contains(&r.id)).collect();
+        append_history(&agent_id, &fresh)?;
+        Ok(fresh.len())
+    }).await?
+}
@@ -0,0 +1,208 @@
+//! Where the time of a gateway call went, stage by stage.
+
+use crate::*;
+
+// ─── Call timings ─────────────────────────────────────────────────────────────
+
+pub(crate) const CALL_TIMINGS_CAPACITY: usize = 100;
+
+/// One run of the gateway CLI. Offsets are from the start of the attempt;
+/// a stage that was never reached stays `None`.
+#[derive(serde::Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AttemptTiming {
+    pub(crate) attempt: u32,
+    /// Until the process was running
+    pub(crate) spawn_ms: Option<u64>,
+    /// Until the first output: npx start-up, gateway queue and the model's first token
+    pub(crate) first_byte_ms: Option<u64>,
+    /// From the first output to the end of the process
+    pub(crate) streaming_ms: Option<u64>,
+    pub(crate) total_ms: Option<u64>,
+    pub(crate) ok: bool,
+}
+
+#[derive(serde::Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CallTiming {
+    pub(crate) agent_id: String,
+    pub(crate) session_key: String,
+    pub(crate) started_at: u64,
+    /// Time spent deferred, for calls that were queued
+    pub(crate) queue_wait_ms: Option<u64>,
+    /// Linting, duplicate checks and the workspace snapshot
+    pub(crate) preparation_ms: Option<u64>,
+    /// Each attempt separately, so a retried call isn't reported as one long one
+    pub(crate) attempts: Vec<AttemptTiming>,
+    /// Refusal checks, assets, workspace diff and history
+    pub(crate) post_processing_ms: Option<u64>,
+    pub(crate) total_ms: u64,
+    pub(crate) cached: bool,
+    pub(crate) ok: bool,
+    /// Stopped by its cancel token; `ok` is false but it did not fail
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) cancelled: bool,
+    /// For failed calls, the stage they were in: "preparation", "spawn", "gateway" or "streaming"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) failed_in: Option<String>,
+}
+
+#[derive(Default)]
+pub(crate) struct TimerMarks {
+    attempt_started: Option<std::time::Instant>,
+    first_byte: Option<std::time::Instant>,
+    attempts_done: Option<std::time::Instant>,
+}
+
+/// Collects a `CallTiming` while the call runs. Code deep in the call reaches
+/// it through `with_call_timer`, so the call path keeps its signatures.
+#[derive(Clone)]
+pub(crate) struct CallTimer {
+    started: std::time::Instant,
+    inner: std::sync::Arc<Mutex<(CallTiming, TimerMarks)>>,
+}
+
+tokio::task_local! {
+    pub(crate) static CALL_TIMER: CallTimer;
+}
+
+/// Runs `f` on the timer of the call in progress, if it is being timed.
+pub(crate) fn with_call_timer(f: impl FnOnce(&CallTimer)) {
+    CALL_TIMER.try_with(f).ok();
+}
+
+pub(crate) fn ms_since(t: std::time::Instant) -> u64 {
+    t.elapsed().as_millis() as u64
+}
+
+impl CallTimer {
+    pub(crate) fn start(agent_id: &str, session_key: &str, queue_wait_ms: Option<u64>) -> Self {
+        let timing = CallTiming {
+            agent_id: agent_id.into(),
+            session_key: session_key.into(),
+            started_at: now_ms(),
+            queue_wait_ms,
+            ..Default::default()
+        };
+        CallTimer { started: std::time::Instant::now(), inner: std::sync::Arc::new(Mutex::new((timing, TimerMarks::default()))) }
+    }
+
+    pub(crate) fn prepared(&self) {
+        self.inner.lock().unwrap().0.preparation_ms = Some(ms_since(self.started));
+    }
+
+    pub(crate) fn cached(&self) {
+        self.inner.lock().unwrap().0.cached = true;
+    }
+
+    pub(crate) fn begin_attempt(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        let attempt = inner.0.attempts.len() as u32 + 1;
+        inner.0.attempts.push(AttemptTiming { attempt, ..Default::default() });
+        inner.1 = TimerMarks { attempt_started: Some(std::time::Instant::now()), ..Default::default() };
+    }
+
+    pub(crate) fn spawned(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        let Some(at) = inner.1.attempt_started else { return };
+        if let Some(a) = inner.0.attempts.last_mut() {
+            a.spawn_ms = Some(ms_since(at));
+        }
+    }
+
+    /// Only the first call per attempt counts.
+    pub(crate) fn first_byte(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        let (Some(at), None) = (inner.1.attempt_started, inner.1.first_byte) else { return };
+        inner.1.first_byte = Some(std::time::Instant::now());
+        if let Some(a) = inner.0.attempts.last_mut() {
+            a.first_byte_ms = Some(ms_since(at));
+        }
+    }
+
+    pub(crate) fn end_attempt(&self, ok: bool) {
+        let mut inner = self.inner.lock().unwrap();
+        let marks = std::mem::take(&mut inner.1);
+        if let Some(a) = inner.0.attempts.last_mut() {
+            a.ok = ok;
+            a.total_ms = marks.attempt_started.map(ms_since);
+            a.streaming_ms = marks.first_byte.map(ms_since);
+        }
+        inner.1.attempts_done = Some(std::time::Instant::now());
+    }
+
+    pub(crate) fn cancelled(&self) {
+        self.inner.lock().unwrap().0.cancelled = true;
+    }
+
+    /// The timing as it stands once the call is over.
+    pub(crate) fn complete(&self, ok: bool) -> CallTiming {
+        let mut inner = self.inner.lock().unwrap();
+        let done = inner.1.attempts_done;
+        let t = &mut inner.0;
+        t.ok = ok;
+        t.total_ms = ms_since(self.started);
+        if ok {
+            t.post_processing_ms = done.map(ms_since);
+        } else {
+            t.failed_in = Some(match t.attempts.last() {
+                None if !t.cached => "preparation",
+                Some(a) if a.spawn_ms.is_none() => "spawn",
+                Some(a) if a.first_byte_ms.is_none() => "gateway",
+                _ => "streaming",
+            }.into());
+        }
+        t.clone()
+    }
+
+    /// Completes the timing and keeps it for `get_recent_call_timings`.
+    pub(crate) fn finish(&self, app: &tauri::AppHandle, ok: bool) -> CallTiming {
+        self.finish_in(&app.state::<AppState>(), ok)
+    }
+
+    pub(crate) fn finish_in(&self, state: &AppState, ok: bool) -> CallTiming {
+        let timing = self.complete(ok);
+        let mut timings = state.call_timings.lock().unwrap();
+        if timings.len() == CALL_TIMINGS_CAPACITY {
+            timings.pop_front();
+        }
+        timings.push_back(timing.clone());
+        timing
+    }
+}
+
+/// Newest first.
+#[tauri::command]
+pub(crate) fn get_recent_call_timings(state: tauri::State<AppState>) -> Vec<CallTiming> {
+    state.call_timings.lock().unwrap().iter().rev().cloned().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failed_in(timer: &CallTimer) -> Option<String> {
+        timer.complete(false).failed_in
+    }
+
+    #[test]
+    fn failures_name_the_stage_they_stopped_in() {
+        let timer = CallTimer::start("main", "s", None);
+        assert_eq!(failed_in(&timer).as_deref(), Some("preparation"));
+        timer.prepared();
+        timer.begin_attempt();
+        assert_eq!(failed_in(&timer).as_deref(), Some("spawn"));
+        timer.spawned();
+        assert_eq!(failed_in(&timer).as_deref(), Some("gateway"));
+        timer.first_byte();
+        assert_eq!(failed_in(&timer).as_deref(), Some("streaming"));
+    }
+
+    #[test]
+    fn replayed_responses_are_cached_successes() {
+        let timer = CallTimer::start("main", "s", None);
+        timer.cached();
+        let timing = timer.complete(true);
+        assert!(timing.ok && timing.cached && timing.failed_in.is_none());
+    }
+}
@@ -0,0 +1,606 @@
+//! Local chat history and usage stats built from it.
+
+use crate::*;
+
+// ─── Local history ────────────────────────────────────────────────────────────
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct HistoryRecord {
+    pub(crate) id: String,
+    pub(crate) session_key: String,
+    /// "user", "agent" or "system", same as the chat view
+    pub(crate) role: String,
+    pub(crate) text: String,
+    pub(crate) ts: u64,
+    /// Where the record came from when it did not originate in this app, e.g. "gateway"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) source: Option<String>,
+    /// Replaced by an edited resend; kept for the "edited" chip
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) superseded: bool,
+    /// Id of the user message this one was edited from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) supersedes: Option<String>,
+    /// Tokens the gateway reported for this reply
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) tokens: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) prompt_tokens: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) completion_tokens: Option<u64>,
+    /// Model the gateway says answered
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) model: Option<String>,
+    /// Tool the conversation was exported from, e.g. "chatgpt"; such sessions are read-only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) imported_from: Option<String>,
+}
+
+pub(crate) fn history_dir() -> PathBuf {
+    let observer = load_config().observer;
+    let p = observer.history_dir
+        .filter(|_| observer.enabled)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| clapp_dir().join("history"));
+    fs::create_dir_all(&p).ok();
+    p
+}
+
+pub(crate) fn history_path(agent_id: &str) -> PathBuf {
+    history_dir().join(format!("{}.jsonl", agent_id))
+}
+
+pub(crate) fn read_history(agent_id: &str) -> Vec<HistoryRecord> {
+    let _watch = SyncIoWatch::start(format_args!("history of {}", agent_id));
+    fs::read_to_string(history_path(agent_id))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|l| serde_json::from_str(l).ok())
+        .collect()
+}
+
+/// One lock per agent's history file, leaked since there are only ever a few agents.
+pub(crate) static HISTORY_LOCKS: std::sync::LazyLock<Mutex<HashMap<String, &'static Mutex<()>>>> =
+    std::sync::LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Serializes writes to an agent's history. Appends take it themselves; a
+/// read-modify-write holds it from the read to `write_history`, so records
+/// appended meanwhile are not lost.
+pub(crate) fn lock_history(agent_id: &str) -> std::sync::MutexGuard<'static, ()> {
+    let lock = *HISTORY_LOCKS.lock().unwrap()
+        .entry(agent_id.to_string())
+        .or_insert_with(|| Box::leak(Box::new(Mutex::new(()))));
+    lock.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+pub(crate) fn append_history(agent_id: &str, records: &[HistoryRecord]) -> Result<(), String> {
+    use std::io::Write;
+    let _lock = lock_history(agent_id);
+    let mut f = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(history_path(agent_id))
+        .map_err(|e| e.to_string())?;
+    for r in records {
+        writeln!(f, "{}", serde_json::to_string(r).unwrap()).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Extracts the reply text the same way the chat view does.
+pub(crate) fn reply_text(raw: &str) -> String {
+    let Ok(v) = serde_json::from_str::<serde_json::Value>(raw) else { return raw.to_string() };
+    let parts: Vec<&str> = v["result"]["payloads"]
+        .as_array()
+        .map(|a| a.iter().filter_map(|p| p["text"].as_str()).filter(|t| !t.trim().is_empty()).collect())
+        .unwrap_or_default();
+    if !parts.is_empty() {
+        return parts.join("\n\n");
+    }
+    v["result"]["summary"].as_str().or(v["error"].as_str()).unwrap_or("").to_string()
+}
+
+/// Writes the exchange off the async runtime; a write that runs out of time is logged and dropped.
+pub(crate) async fn record_exchange(app: &tauri::AppHandle, agent_id: &str, session_key: &str, message: &str, sent_at: u64, response: &str) {
+    let (agent_id, session_key, message, response) =
+        (agent_id.to_string(), session_key.to_string(), message.to_string(), response.to_string());
+    let write = move || record_exchange_with(&agent_id, &session_key, &message, sent_at, &response, None);
+    if let Err(e) = run_storage_io(app, write).await {
+        eprintln!("[HISTORY ERR] {}", e);
+    }
+}
+
+pub(crate) fn record_exchange_with(
+    agent_id: &str,
+    session_key: &str,
+    message: &str,
+    sent_at: u64,
+    response: &str,
+    supersedes: Option<&str>,
+) {
+    let agent = read_agent_config(agent_id);
+    if agent.session_mode == SessionMode::Ephemeral && agent.skip_history {
+        return;
+    }
+    let usage = response_usage(response);
+    // Both halves share the sequence number, which keeps ids unique if the clock steps back
+    let seq = next_seq();
+    let records = [
+        HistoryRecord {
+            id: format!("{}-{}-{}-u", session_key, sent_at, seq),
+            session_key: session_key.to_string(),
+            role: "user".into(),
+            text: message.to_string(),
+            ts: sent_at,
+            supersedes: supersedes.map(String::from),
+            ..Default::default()
+        },
+        HistoryRecord {
+            id: format!("{}-{}-{}-a", session_key, sent_at, seq),
+            session_key: session_key.to_string(),
+            role: "agent".into(),
+            text: reply_text(response),
+            ts: now_ms(),
+            tokens: usage.total,
+            prompt_tokens: usage.prompt,
+            completion_tokens: usage.completion,
+            model: usage.model,
+            ..Default::default()
+        },
+    ];
+    if let Err(e) = append_history(agent_id, &records) {
+        eprintln!("[HISTORY ERR] {}", e);
+    }
+}
+
+/// The message and a system record saying the reply was cancelled, so the
+/// session doesn't show a question that was never answered.
+pub(crate) fn cancelled_records(session_key: &str, message: &str, sent_at: u64) -> [HistoryRecord; 2] {
+    let seq = next_seq();
+    [
+        HistoryRecord {
+            id: format!("{}-{}-{}-u", session_key, sent_at, seq),
+            session_key: session_key.to_string(),
+            role: "user".into(),
+            text: message.to_string(),
+            ts: sent_at,
+            ..Default::default()
+        },
+        HistoryRecord {
+            id: format!("{}-{}-{}-c", session_key, sent_at, seq),
+            session_key: session_key.to_string(),
+            role: "system".into(),
+            text: "Cancelled before the reply finished".into(),
+            ts: now_ms(),
+            ..Default::default()
+        },
+    ]
+}
+
+pub(crate) async fn record_cancelled(app: &tauri::AppHandle, agent_id: &str, session_key: &str, message: &str, sent_at: u64) {
+    let (agent_id, session_key, message) = (agent_id.to_string(), session_key.to_string(), message.to_string());
+    let write = move || {
+        let agent = read_agent_config(&agent_id);
+        if agent.session_mode == SessionMode::Ephemeral && agent.skip_history {
+            return;
+        }
+        if let Err(e) = append_history(&agent_id, &cancelled_records(&session_key, &message, sent_at)) {
+            eprintln!("[HISTORY ERR] {}", e);
+        }
+    };
+    if let Err(e) = run_storage_io(app, write).await {
+        eprintln!("[HISTORY ERR] {}", e);
+    }
+}
+
+/// Drops sessions whose newest record is older than `cutoff` (ms). Returns how many were removed.
+/// Imported sessions keep the timestamps of the tool they came from, so they are
+/// never pruned by age; a re-import would only bring them back.
+pub(crate) fn prune_old_sessions(agent_id: &str, cutoff: u64) -> Result<usize, String> {
+    let _lock = lock_history(agent_id);
+    let records = read_history(agent_id);
+    let mut last_seen: HashMap<&str, u64> = HashMap::new();
+    for r in records.iter().filter(|r| r.imported_from.is_none()) {
+        let ts = last_seen.entry(r.session_key.as_str()).or_default();
+        *ts = (*ts).max(r.ts);
+    }
+    let stale: std::collections::HashSet<String> = last_seen.into_iter()
+        .filter(|(_, ts)| *ts < cutoff)
+        .map(|(k, _)| k.to_string())
+        .collect();
+    if stale.is_empty() {
+        return Ok(0);
+    }
+    let kept: Vec<HistoryRecord> = records.into_iter().filter(|r| !stale.contains(&r.session_key)).collect();
+    write_history(agent_id, &kept)?;
+    Ok(stale.len())
+}
+
+/// Runs from the storage health loop; does real work at most once a day.
+pub(crate) async fn auto_prune_history(app: &tauri::AppHandle) {
+    let config = load_config();
+    // An observer must not prune the history it shares with the observed machine
+    if config.observer.enabled {
+        return;
+    }
+    let Some(days) = config.data_retention_days else { return };
+    let now = now_ms();
+    if now.saturating_sub(config.last_auto_prune) < MS_PER_DAY {
+        return;
+    }
+    let cutoff = now.saturating_sub(days * MS_PER_DAY);
+    // A long job: it can take as long as it needs and is stopped by cancelling, not a deadline
+    let result = run_storage_operation(app, "history-retention", move |job| {
+        let Ok(files) = fs::read_dir(history_dir()) else { return Ok(0) };
+        let agents: Vec<String> = files.flatten()
+            .filter_map(|f| Some(f.path().file_stem()?.to_string_lossy().into_owned()))
+            .collect();
+        job.set_total(agents.len());
+        let mut pruned = 0;
+        for (i, agent_id) in agents.iter().enumerate() {
+            if job.is_cancelled() {
+                break;
+            }
+            job.progress("pruning", i, Some(agent_id));
+            let has_old = read_history(agent_id).iter().any(|r| r.ts < cutoff && r.imported_from.is_none());
+            if has_old {
+                pruned += prune_old_sessions(agent_id, cutoff).unwrap_or(0);
+            }
+        }
+        Ok(pruned)
+    }).await;
+    let pruned = result.unwrap_or(0);
+    // Settings may have changed during a long prune; only the timestamp is ours to write
+    run_storage_io(app, move || {
+        let mut config = load_config();
+        config.last_auto_prune = now;
+        save_config(&config)
+    }).await.ok();
+    if pruned > 0 {
+        app.emit("history-pruned", pruned).ok();
+    }
+}
+
+#[tauri::command]
+pub(crate) fn set_data_retention_days(days: Option<u64>) -> Result<(), AppError> {
+    ensure_writable()?;
+    if days == Some(0) {
+        return Err(AppError::InvalidInput("Retention must be at least one day".into()));
+    }
+    let mut config = load_config();
+    config.data_retention_days = days;
+    save_config(&config)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) fn get_history(agent_id: Option<String>, session_key: Option<String>) -> Vec<HistoryRecord> {
+    read_history(&resolve_agent_id(agent_id))
+        .into_iter()
+        .filter(|r| session_key.as_ref().is_none_or(|k| &r.session_key == k))
+        .collect()
+}
+
+/// Replaces the whole file. Callers hold `lock_history` since the read they changed.
+pub(crate) fn write_history(agent_id: &str, records: &[HistoryRecord]) -> Result<(), String> {
+    let _watch = SyncIoWatch::start(format_args!("history of {}", agent_id));
+    let mut out = String::new();
+    for r in records {
+        out.push_str(&serde_json::to_string(r).unwrap());
+        out.push('\n');
+    }
+    fs::write(history_path(agent_id), out).map_err(|e| e.to_string())
+}
+
+/// Finds which agent's history holds `message_id`.
+pub(crate) fn find_history_owner(message_id: &str) -> Option<String> {
+    fs::read_dir(history_dir()).ok()?.flatten().find_map(|e| {
+        let path = e.path();
+        let agent_id = path.file_stem()?.to_string_lossy().into_owned();
+        read_history(&agent_id).iter().any(|r| r.id == message_id).then_some(agent_id)
+    })
+}
+
+/// The agent whose local history holds the session.
+pub(crate) fn find_session_owner(session_key: &str) -> Option<String> {
+    fs::read_dir(history_dir()).ok()?.flatten().find_map(|e| {
+        let path = e.path();
+        let agent_id = path.file_stem()?.to_string_lossy().into_owned();
+        read_history(&agent_id).iter().any(|r| r.session_key == session_key).then_some(agent_id)
+    })
+}
+
+/// Every agent whose local history has a record in the session, sorted. More
+/// than one means the same key was reused across agents.
+#[tauri::command]
+pub(crate) async fn find_session_key_across_agents(
+    app: tauri::AppHandle,
+    session_key: String,
+) -> Result<Vec<String>, AppError> {
+    if session_key.trim().is_empty() {
+        return Err(AppError::InvalidInput("Session key is empty".into()));
+    }
+    run_storage_io(&app, move || {
+        let Ok(entries) = fs::read_dir(history_dir()) else { return Vec::new() };
+        let mut owners: Vec<String> = entries.flatten()
+            .filter_map(|e| {
+                let path = e.path();
+                let agent_id = path.file_stem()?.to_string_lossy().into_owned();
+                read_history(&agent_id).iter().any(|r| r.session_key == session_key).then_some(agent_id)
+            })
+            .collect();
+        owners.sort();
+        owners
+    }).await
+}
+
+/// Prepended to an edited resend, since the gateway session still contains the original attempt.
+pub(crate) const EDIT_PREAMBLE: &str = "[Correction: disregard my previous message and your reply to it; \
+it contained a mistake. The corrected message follows.]";
+
+#[tauri::command]
+pub(crate) async fn edit_and_resend(
+    app: tauri::AppHandle,
+    session_key: String,
+    message_id: String,
+    new_content: String,
+) -> Result<String, AppError> {
+    ensure_writable()?;
+    ensure_live_session(&session_key)?;
+    if new_content.trim().is_empty() {
+        return Err(AppError::InvalidInput("Message is empty".into()));
+    }
+    let (message, session) = (message_id.clone(), session_key.clone());
+    let agent_id = run_storage_io(&app, move || -> Result<String, AppError> {
+        let agent_id = find_history_owner(&message)
+            .ok_or_else(|| AppError::NotFound(format!("message {}", message)))?;
+        edited_message_position(&read_history(&agent_id), &message, &session)?;
+        Ok(agent_id)
+    }).await??;
+
+    let sent_at = now_ms();
+    let wire_message = format!("{}\n\n{}", EDIT_PREAMBLE, new_content);
+    // Held until the new reply is in history
+    let in_flight = InFlightCall::start(&app, &agent_id);
+    let result = execute_gateway_call(&app, &in_flight, &wire_message, &session_key, false, None, None).await;
+    let recorded = result.as_ref().ok().cloned();
+    // Rewrites the whole history file, which takes as long as the history is big
+    run_storage_operation(&app, "history-edit", move |_| {
+        finish_edit(&agent_id, &session_key, &message_id, &new_content, sent_at, recorded.as_deref())
+    }).await?;
+    result
+}
+
+pub(crate) fn edited_message_position(records: &[HistoryRecord], message_id: &str, session_key: &str) -> Result<usize, AppError> {
+    records.iter()
+        .position(|r| r.id == message_id && r.session_key == session_key && r.role == "user")
+        .ok_or_else(|| AppError::NotFound(format!("user message {} in session {}", message_id, session_key)))
+}
+
+/// Only a resend that got an answer replaces the original exchange: the old
+/// message and its reply are soft-flagged and the new pair is recorded. A
+/// failed or cancelled resend (`response` is `None`) leaves history as it was.
+pub(crate) fn finish_edit(
+    agent_id: &str,
+    session_key: &str,
+    message_id: &str,
+    new_content: &str,
+    sent_at: u64,
+    response: Option<&str>,
+) -> Result<(), AppError> {
+    let Some(response) = response else { return Ok(()) };
+    {
+        let _lock = lock_history(agent_id);
+        let mut records = read_history(agent_id);
+        let pos = edited_message_position(&records, message_id, session_key)?;
+        records[pos].superseded = true;
+        if let Some(reply) = records[pos + 1..].iter_mut()
+            .take_while(|r| r.role != "user" || r.session_key != session_key)
+            .find(|r| r.role == "agent" && r.session_key == session_key)
+        {
+            reply.superseded = true;
+        }
+        write_history(agent_id, &records)?;
+    }
+    record_exchange_with(agent_id, session_key, new_content, sent_at, response, Some(message_id));
+    Ok(())
+}
+
+/// Writes a session's history as a JSON array. Superseded exchanges are left out unless asked for.
+#[tauri::command]
+pub(crate) fn export_history(
+    agent_id: String,
+    session_key: Option<String>,
+    dest_path: String,
+    include_superseded: Option<bool>,
+) -> Result<usize, AppError> {
+    let dest = validate_user_path(&dest_path, PathIntent::Export)?;
+    let include_superseded = include_superseded.unwrap_or(false);
+    let records: Vec<HistoryRecord> = read_history(&agent_id)
+        .into_iter()
+        .filter(|r| session_key.as_ref().is_none_or(|k| &r.session_key == k))
+        .filter(|r| include_superseded || !r.superseded)
+        .collect();
+    fs::write(&dest, serde_json::to_string_pretty(&records)?)?;
+    Ok(records.len())
+}
+
+// ─── Session stats ────────────────────────────────────────────────────────────
+
+#[derive(serde::Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum StatsGranularity {
+    Day,
+    Week,
+    Month,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BucketStat {
+    /// Start of the UTC period, in ms
+    pub(crate) period_start_ts: u64,
+    pub(crate) call_count: u64,
+    pub(crate) total_tokens: u64,
+}
+
+/// UTC day, ISO week (Monday) or month containing `ts_ms`.
+pub(crate) fn period_start(ts_ms: u64, granularity: StatsGranularity) -> u64 {
+    use chrono::Datelike;
+    let Some(dt) = chrono::DateTime::<chrono::Utc>::from_timestamp_millis(ts_ms as i64) else { return 0 };
+    let date = dt.date_naive();
+    let start = match granularity {
+        StatsGranularity::Day => date,
+        StatsGranularity::Week => date - chrono::Days::new(date.weekday().num_days_from_monday() as u64),
+        StatsGranularity::Month => date.with_day(1).unwrap_or(date),
+    };
+    start.and_hms_opt(0, 0, 0)
+        .map(|t| t.and_utc().timestamp_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// What the gateway reported about the model call behind a reply.
+#[derive(Default)]
+pub(crate) struct ReplyUsage {
+    pub(crate) total: Option<u64>,
+    pub(crate) prompt: Option<u64>,
+    pub(crate) completion: Option<u64>,
+    pub(crate) model: Option<String>,
+}
+
+/// Usage and model reported anywhere in the response. The model is taken from
+/// the object carrying the usage, falling back to the first one found.
+pub(crate) fn response_usage(raw: &str) -> ReplyUsage {
+    fn find_usage(v: &serde_json::Value) -> Option<&serde_json::Map<String, serde_json::Value>> {
+        match v {
+            serde_json::Value::Object(m) if m.get("usage").is_some_and(|u| u.is_object()) => Some(m),
+            serde_json::Value::Object(m) => m.values().find_map(find_usage),
+            serde_json::Value::Array(a) => a.iter().find_map(find_usage),
+            _ => None,
+        }
+    }
+    fn find_model(v: &serde_json::Value) -> Option<&str> {
+        match v {
+            serde_json::Value::Object(m) => m.get("model").and_then(|m| m.as_str())
+                .or_else(|| m.values().find_map(find_model)),
+            serde_json::Value::Array(a) => a.iter().find_map(find_model),
+            _ => None,
+        }
+    }
+    let Ok(v) = serde_json::from_str::<serde_json::Value>(raw) else { return ReplyUsage::default() };
+    let model = find_usage(&v)
+        .and_then(|m| m.get("model").and_then(|m| m.as_str()))
+        .or_else(|| find_model(&v))
+        .filter(|m| !m.trim().is_empty())
+        .map(String::from);
+    let Some(u) = find_usage(&v).map(|m| &m["usage"]) else { return ReplyUsage { model, ..Default::default() } };
+    let prompt = u["input"].as_u64().or(u["inputTokens"].as_u64()).or(u["prompt_tokens"].as_u64());
+    let completion = u["output"].as_u64().or(u["outputTokens"].as_u64()).or(u["completion_tokens"].as_u64());
+    let split = prompt.unwrap_or(0) + completion.unwrap_or(0);
+    ReplyUsage {
+        total: u["total"].as_u64().or(u["totalTokens"].as_u64()).or((split > 0).then_some(split)),
+        prompt,
+        completion,
+        model,
+    }
+}
+
+/// Buckets every exchange in the agent's local history. Replies without reported
+/// usage are counted from their text length.
+#[tauri::command]
+pub(crate) async fn get_session_stats(
+    app: tauri::AppHandle,
+    agent_id: String,
+    granularity: StatsGranularity,
+) -> Result<Vec<BucketStat>, AppError> {
+    run_storage_io(&app, move || {
+        let records = read_history(&agent_id);
+        let prompts: HashMap<&str, usize> = records.iter()
+            .filter(|r| r.role == "user")
+            .map(|r| (r.id.trim_end_matches("-u"), r.text.len()))
+            .collect();
+        let mut buckets: std::collections::BTreeMap<u64, (u64, u64)> = std::collections::BTreeMap::new();
+        for r in records.iter().filter(|r| r.role == "agent") {
+            let tokens = r.tokens.unwrap_or_else(|| {
+                let prompt = prompts.get(r.id.trim_end_matches("-a")).copied().unwrap_or(0);
+                estimate_tokens((prompt + r.text.len()) as u64)
+            });
+            let bucket = buckets.entry(period_start(r.ts, granularity)).or_default();
+            bucket.0 += 1;
+            bucket.1 += tokens;
+        }
+        buckets.into_iter()
+            .map(|(period_start_ts, (call_count, total_tokens))| BucketStat { period_start_ts, call_count, total_tokens })
+            .collect()
+    }).await
+}
+
+#[derive(serde::Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TokenUsage {
+    pub(crate) prompt_tokens: u64,
+    pub(crate) completion_tokens: u64,
+    pub(crate) calls: u64,
+}
+
+/// Reported usage per model for the cost panel. Replies without a recorded model
+/// go under "unknown"; ones recorded before the prompt/completion split count
+/// their total as completion.
+#[tauri::command]
+pub(crate) async fn get_token_usage_breakdown(
+    app: tauri::AppHandle,
+    agent_id: String,
+) -> Result<HashMap<String, TokenUsage>, AppError> {
+    run_storage_io(&app, move || {
+        let mut by_model: HashMap<String, TokenUsage> = HashMap::new();
+        for r in read_history(&agent_id).into_iter().filter(|r| r.role == "agent") {
+            let usage = by_model.entry(r.model.unwrap_or_else(|| "unknown".into())).or_default();
+            usage.calls += 1;
+            if r.prompt_tokens.is_none() && r.completion_tokens.is_none() {
+                usage.completion_tokens += r.tokens.unwrap_or(0);
+            } else {
+                usage.prompt_tokens += r.prompt_tokens.unwrap_or(0);
+                usage.completion_tokens += r.completion_tokens.unwrap_or(0);
+            }
+        }
+        by_model
+    }).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(id: &str, role: &str, text: &str) -> HistoryRecord {
+        HistoryRecord { id: id.into(), session_key: "edit-session".into(), role: role.into(), text: text.into(), ..Default::default() }
+    }
+
+    fn seed(agent_id: &str) {
+        fs::remove_file(history_path(agent_id)).ok();
+        append_history(agent_id, &[record("m1", "user", "2 + 2?"), record("r1", "agent", "5")]).unwrap();
+    }
+
+    #[test]
+    fn a_failed_resend_leaves_the_original_exchange_visible() {
+        let agent = "edit-failed";
+        seed(agent);
+        finish_edit(agent, "edit-session", "m1", "2 + 3?", now_ms(), None).unwrap();
+        let records = read_history(agent);
+        assert_eq!(records.len(), 2);
+        assert!(records.iter().all(|r| !r.superseded));
+    }
+
+    #[test]
+    fn an_answered_resend_supersedes_the_original_exchange() {
+        let agent = "edit-answered";
+        seed(agent);
+        let answered = serde_json::json!({ "reply": "5" }).to_string();
+        finish_edit(agent, "edit-session", "m1", "2 + 3?", now_ms(), Some(&answered)).unwrap();
+        let records = read_history(agent);
+        assert_eq!(records.len(), 4);
+        assert!(records[0].superseded && records[1].superseded);
+        assert_eq!(records[2].text, "2 + 3?");
+        assert_eq!(records[2].supersedes.as_deref(), Some("m1"));
+    }
+}
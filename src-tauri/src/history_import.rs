@@ -0,0 +1,453 @@
+//! Conversations exported from ChatGPT and Claude, imported into local history
+//! as read-only sessions.
+
+use crate::*;
+
+// ─── External history import ──────────────────────────────────────────────────
+
+/// Session keys of imported conversations; calls into them are refused.
+pub(crate) const IMPORTED_SESSION_PREFIX: &str = "imported-";
+/// Reasons kept in the report; later failures are only counted
+pub(crate) const IMPORT_MAX_FAILURE_REASONS: usize = 500;
+/// Transcript handed back by `continue_imported_session`, newest messages kept
+pub(crate) const FORK_CONTEXT_MAX_CHARS: usize = 32 * 1024;
+
+#[derive(serde::Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ExternalFormat {
+    /// conversations.json from a ChatGPT data export
+    Chatgpt,
+    /// conversations.json from a Claude data export
+    Claude,
+}
+
+impl ExternalFormat {
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            ExternalFormat::Chatgpt => "chatgpt",
+            ExternalFormat::Claude => "claude",
+        }
+    }
+}
+
+/// One conversation in the shape both formats are reduced to.
+pub(crate) struct ExternalConversation {
+    pub(crate) title: String,
+    pub(crate) created_at: u64,
+    /// (role, text, ts) with roles as in `HistoryRecord`
+    pub(crate) messages: Vec<(&'static str, String, u64)>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ImportFailure {
+    /// Position in the export's array
+    pub(crate) index: usize,
+    pub(crate) id: Option<String>,
+    pub(crate) reason: String,
+}
+
+#[derive(serde::Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ExternalImportReport {
+    pub(crate) imported: usize,
+    /// Already imported before, by content
+    pub(crate) skipped: usize,
+    pub(crate) failed: usize,
+    /// The first `IMPORT_MAX_FAILURE_REASONS` failures
+    pub(crate) failures: Vec<ImportFailure>,
+    pub(crate) cancelled: bool,
+}
+
+impl ExternalImportReport {
+    pub(crate) fn fail(&mut self, index: usize, id: Option<String>, reason: String) {
+        self.failed += 1;
+        if self.failures.len() < IMPORT_MAX_FAILURE_REASONS {
+            self.failures.push(ImportFailure { index, id, reason });
+        }
+    }
+}
+
+pub(crate) fn iso_ms(v: &serde_json::Value) -> Option<u64> {
+    chrono::DateTime::parse_from_rfc3339(v.as_str()?).ok().map(|t| t.timestamp_millis() as u64)
+}
+
+/// ChatGPT stores seconds as floats.
+pub(crate) fn secs_ms(v: &serde_json::Value) -> Option<u64> {
+    v.as_f64().filter(|s| *s > 0.0).map(|s| (s * 1000.0) as u64)
+}
+
+/// ChatGPT keeps a tree of message nodes; the conversation as last shown is the
+/// path from `current_node` up to the root.
+pub(crate) fn parse_chatgpt(v: &serde_json::Value) -> Result<ExternalConversation, String> {
+    let mapping = v["mapping"].as_object().ok_or("no message mapping")?;
+    let created_at = secs_ms(&v["create_time"]).unwrap_or(0);
+    let mut node = v["current_node"].as_str().ok_or("no current_node")?;
+    let mut messages = Vec::new();
+    for _ in 0..mapping.len() {
+        let entry = mapping.get(node).ok_or_else(|| format!("node {} is missing", node))?;
+        let message = &entry["message"];
+        let role = match message["author"]["role"].as_str() {
+            Some("user") => Some("user"),
+            Some("assistant") => Some("agent"),
+            // System prompts and tool output are not part of the chat view
+            _ => None,
+        };
+        let content = &message["content"];
+        let text = match content["parts"].as_array() {
+            Some(parts) => parts.iter().filter_map(|p| p.as_str()).collect::<Vec<_>>().join("\n"),
+            None => content["text"].as_str().unwrap_or("").to_string(),
+        };
+        if let Some(role) = role.filter(|_| !text.trim().is_empty()) {
+            messages.push((role, text, secs_ms(&message["create_time"]).unwrap_or(created_at)));
+        }
+        match entry["parent"].as_str() {
+            Some(parent) => node = parent,
+            None => break,
+        }
+    }
+    messages.reverse();
+    Ok(ExternalConversation {
+        title: v["title"].as_str().unwrap_or("").to_string(),
+        created_at,
+        messages,
+    })
+}
+
+pub(crate) fn parse_claude(v: &serde_json::Value) -> Result<ExternalConversation, String> {
+    let chat = v["chat_messages"].as_array().ok_or("no chat_messages")?;
+    let created_at = iso_ms(&v["created_at"]).unwrap_or(0);
+    let mut messages = Vec::new();
+    for m in chat {
+        let role = match m["sender"].as_str() {
+            Some("human") => "user",
+            Some("assistant") => "agent",
+            other => return Err(format!("unknown sender {:?}", other.unwrap_or(""))),
+        };
+        let text = match m["text"].as_str().filter(|t| !t.trim().is_empty()) {
+            Some(t) => t.to_string(),
+            None => m["content"].as_array()
+                .map(|c| c.iter().filter(|p| p["type"] == "text").filter_map(|p| p["text"].as_str()).collect::<Vec<_>>().join("\n"))
+                .unwrap_or_default(),
+        };
+        if !text.trim().is_empty() {
+            messages.push((role, text, iso_ms(&m["created_at"]).unwrap_or(created_at)));
+        }
+    }
+    Ok(ExternalConversation {
+        title: v["name"].as_str().unwrap_or("").to_string(),
+        created_at,
+        messages,
+    })
+}
+
+/// Same content, same key: re-importing an export finds what is already there.
+pub(crate) fn imported_session_key(format: ExternalFormat, conversation: &ExternalConversation) -> String {
+    use sha2::Digest;
+    let mut h = sha2::Sha256::new();
+    h.update(format.label().as_bytes());
+    for (role, text, ts) in &conversation.messages {
+        h.update(format!("\0{}\0{}\0{}", role, ts, text).as_bytes());
+    }
+    format!("{}{}-{}", IMPORTED_SESSION_PREFIX, format.label(), &format!("{:x}", h.finalize())[..16])
+}
+
+pub(crate) fn imported_records(format: ExternalFormat, session_key: &str, conversation: ExternalConversation) -> Vec<HistoryRecord> {
+    let imported_from = Some(format.label().to_string());
+    let title = if conversation.title.is_empty() { "untitled" } else { &conversation.title };
+    let header = HistoryRecord {
+        id: format!("{}-title", session_key),
+        session_key: session_key.into(),
+        role: "system".into(),
+        text: format!("Imported from {}: {}", format.label(), title),
+        ts: conversation.created_at,
+        imported_from: imported_from.clone(),
+        ..Default::default()
+    };
+    std::iter::once(header)
+        .chain(conversation.messages.into_iter().enumerate().map(|(i, (role, text, ts))| HistoryRecord {
+            id: format!("{}-{}", session_key, i),
+            session_key: session_key.into(),
+            role: role.into(),
+            text,
+            ts,
+            imported_from: imported_from.clone(),
+            ..Default::default()
+        }))
+        .collect()
+}
+
+/// Hands each element of a top-level JSON array to `f` as it is read, so the
+/// file is never held whole. `f` returns false to stop.
+struct EachElement<F>(F);
+
+impl<'de, F: FnMut(serde_json::Value) -> bool> serde::de::Visitor<'de> for EachElement<F> {
+    type Value = bool;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("an array of conversations")
+    }
+
+    fn visit_seq<A: serde::de::SeqAccess<'de>>(mut self, mut seq: A) -> Result<bool, A::Error> {
+        while let Some(element) = seq.next_element::<serde_json::Value>()? {
+            if !(self.0)(element) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Reads the export from disk; see `import_external_reader`.
+pub(crate) fn import_external_file(
+    op: &Operation,
+    format: ExternalFormat,
+    path: &std::path::Path,
+    agent_id: &str,
+) -> Result<ExternalImportReport, AppError> {
+    let reader = std::io::BufReader::new(fs::File::open(path)?);
+    import_external_reader(Some(op), format, reader, agent_id)
+}
+
+/// Streams the export into the agent's history. A JSON syntax error ends the run
+/// as one failure; everything imported before it stays.
+pub(crate) fn import_external_reader(
+    op: Option<&Operation>,
+    format: ExternalFormat,
+    reader: impl std::io::Read,
+    agent_id: &str,
+) -> Result<ExternalImportReport, AppError> {
+    use serde::Deserializer;
+    let mut known: std::collections::HashSet<String> = read_history(agent_id).into_iter()
+        .filter(|r| r.session_key.starts_with(IMPORTED_SESSION_PREFIX))
+        .map(|r| r.session_key)
+        .collect();
+    let mut report = ExternalImportReport::default();
+    let mut index = 0;
+    let mut write_error = None;
+    let mut de = serde_json::Deserializer::from_reader(reader);
+    let visitor = EachElement(|element: serde_json::Value| {
+        if op.is_some_and(Operation::is_cancelled) {
+            return false;
+        }
+        let id = element["conversation_id"].as_str().or(element["id"].as_str()).or(element["uuid"].as_str()).map(String::from);
+        let parsed = match format {
+            ExternalFormat::Chatgpt => parse_chatgpt(&element),
+            ExternalFormat::Claude => parse_claude(&element),
+        };
+        match parsed {
+            Err(reason) => report.fail(index, id, reason),
+            Ok(c) if c.messages.is_empty() => report.fail(index, id, "no messages".into()),
+            Ok(c) => {
+                if let Some(op) = op {
+                    op.progress("importing", index, Some(&c.title));
+                }
+                let session_key = imported_session_key(format, &c);
+                if !known.insert(session_key.clone()) {
+                    report.skipped += 1;
+                } else if let Err(e) = append_history(agent_id, &imported_records(format, &session_key, c)) {
+                    write_error = Some(e);
+                    return false;
+                } else {
+                    report.imported += 1;
+                }
+            }
+        }
+        index += 1;
+        true
+    });
+    let finished = de.deserialize_seq(visitor);
+    if let Some(e) = write_error {
+        return Err(AppError::Other(format!("History write failed after {} conversations: {}", report.imported, e)));
+    }
+    match finished {
+        Ok(true) => de.end().map_err(|e| AppError::InvalidInput(format!("Unexpected data after the conversations: {}", e)))?,
+        // Cancelled; the rest of the file is left unread
+        Ok(false) => report.cancelled = true,
+        Err(e) => report.fail(index, None, format!("the file is not valid JSON from here on, so the rest was not read: {}", e)),
+    }
+    Ok(report)
+}
+
+/// Imports a ChatGPT or Claude `conversations.json` (`source_format` "chatgpt" or
+/// "claude") into the agent's history. Conversations imported before are skipped.
+/// The export's .zip itself is refused with `UnsupportedFormat`; conversations.json
+/// has to be extracted from it first.
+#[tauri::command]
+pub(crate) async fn import_external_history(
+    app: tauri::AppHandle,
+    source_format: ExternalFormat,
+    path: String,
+    agent_id: Option<String>,
+) -> Result<ExternalImportReport, AppError> {
+    ensure_writable()?;
+    let src = validate_user_path(&path, PathIntent::Import)?;
+    if src.extension().is_some_and(|e| e.eq_ignore_ascii_case("zip")) {
+        return Err(AppError::UnsupportedFormat("zip archives; extract conversations.json from the export first".into()));
+    }
+    let agent_id = resolve_agent_id(agent_id);
+    validate_agent_id(&agent_id)?;
+    let result = run_storage_operation(&app, "history-import", move |op| {
+        import_external_file(op, source_format, &src, &agent_id)
+    }).await;
+    if let Ok(report) = &result {
+        audit("history_imported", serde_json::json!({
+            "format": source_format.label(), "imported": report.imported, "skipped": report.skipped, "failed": report.failed,
+        }));
+    }
+    result
+}
+
+/// Calls into imported sessions are refused until they are continued as a fork.
+pub(crate) fn ensure_live_session(session_key: &str) -> Result<(), AppError> {
+    if session_key.starts_with(IMPORTED_SESSION_PREFIX) {
+        return Err(AppError::InvalidInput(format!(
+            "{} is an imported conversation and read-only; continue_imported_session makes a live copy", session_key
+        )));
+    }
+    Ok(())
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ForkedSession {
+    pub(crate) session_key: String,
+    /// The conversation so far, to send first: the gateway has never seen it
+    pub(crate) context: String,
+}
+
+/// Copies an imported conversation into a new live session of the same agent.
+#[tauri::command]
+pub(crate) async fn continue_imported_session(
+    app: tauri::AppHandle,
+    agent_id: Option<String>,
+    session_key: String,
+) -> Result<ForkedSession, AppError> {
+    ensure_writable()?;
+    if !session_key.starts_with(IMPORTED_SESSION_PREFIX) {
+        return Err(AppError::InvalidInput(format!("{} is not an imported conversation", session_key)));
+    }
+    let agent_id = resolve_agent_id(agent_id);
+    validate_agent_id(&agent_id)?;
+    run_storage_operation(&app, "history-fork", move |_| -> Result<ForkedSession, AppError> {
+        let records: Vec<HistoryRecord> = read_history(&agent_id).into_iter()
+            .filter(|r| r.session_key == session_key)
+            .collect();
+        if records.is_empty() {
+            return Err(AppError::NotFound(format!("imported conversation {}", session_key)));
+        }
+        let fork = format!("clapp-fork-{}-{}", now_ms(), next_seq());
+        let mut copies: Vec<HistoryRecord> = records.iter()
+            .filter(|r| r.role != "system")
+            .enumerate()
+            .map(|(i, r)| HistoryRecord {
+                id: format!("{}-{}", fork, i),
+                session_key: fork.clone(),
+                role: r.role.clone(),
+                text: r.text.clone(),
+                ts: r.ts,
+                ..Default::default()
+            })
+            .collect();
+        copies.push(HistoryRecord {
+            id: format!("{}-forked", fork),
+            session_key: fork.clone(),
+            role: "system".into(),
+            text: format!("Continued from imported conversation {}", session_key),
+            ts: now_ms(),
+            ..Default::default()
+        });
+        append_history(&agent_id, &copies)?;
+
+        let mut context = String::new();
+        for r in copies.iter().rev().filter(|r| r.role != "system") {
+            let speaker = if r.role == "user" { "User" } else { "Assistant" };
+            let turn = format!("{}: {}\n\n", speaker, r.text);
+            if context.len() + turn.len() > FORK_CONTEXT_MAX_CHARS {
+                break;
+            }
+            context.insert_str(0, &turn);
+        }
+        audit("imported_session_continued", serde_json::json!({ "agentId": agent_id, "from": session_key, "to": fork }));
+        Ok(ForkedSession {
+            session_key: fork,
+            context: format!("Earlier conversation, for context:\n\n{}", context.trim_end()),
+        })
+    }).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn import(agent_id: &str, format: ExternalFormat, json: &str) -> ExternalImportReport {
+        import_external_reader(None, format, json.as_bytes(), agent_id).unwrap()
+    }
+
+    fn texts(agent_id: &str) -> Vec<(String, String)> {
+        read_history(agent_id).into_iter().map(|r| (r.role, r.text)).collect()
+    }
+
+    /// A regenerated answer leaves two children under the question; the one shown is on
+    /// the path to `current_node`.
+    const BRANCHED_CHATGPT: &str = r#"[{
+        "title": "Branches", "create_time": 1700000000.5, "current_node": "b2",
+        "mapping": {
+            "root": { "message": null, "parent": null },
+            "sys": { "message": { "author": { "role": "system" }, "content": { "parts": [""] } }, "parent": "root" },
+            "q": { "message": { "author": { "role": "user" }, "content": { "parts": ["Which one?"] }, "create_time": 1700000001.0 }, "parent": "sys" },
+            "b1": { "message": { "author": { "role": "assistant" }, "content": { "parts": ["First try"] }, "create_time": 1700000002.0 }, "parent": "q" },
+            "b2": { "message": { "author": { "role": "assistant" }, "content": { "parts": ["Second", "try"] }, "create_time": 1700000003.0 }, "parent": "q" }
+        }
+    }]"#;
+
+    #[test]
+    fn a_branched_chatgpt_tree_imports_the_branch_last_shown() {
+        let report = import("import-branched", ExternalFormat::Chatgpt, BRANCHED_CHATGPT);
+        assert_eq!((report.imported, report.failed), (1, 0));
+        let records = texts("import-branched");
+        assert_eq!(records[0], ("system".into(), "Imported from chatgpt: Branches".into()));
+        assert_eq!(records[1..], [("user".into(), "Which one?".into()), ("agent".into(), "Second\ntry".into())]);
+    }
+
+    #[test]
+    fn claude_content_parts_stand_in_for_empty_text() {
+        let export = r#"[{
+            "name": "Parts", "created_at": "2024-05-01T10:00:00Z",
+            "chat_messages": [
+                { "sender": "human", "text": "Hi", "created_at": "2024-05-01T10:00:01Z" },
+                { "sender": "assistant", "text": "", "created_at": "2024-05-01T10:00:02Z", "content": [
+                    { "type": "text", "text": "Hello" },
+                    { "type": "tool_use", "name": "search", "input": {} },
+                    { "type": "text", "text": "there" }
+                ] }
+            ]
+        }]"#;
+        let report = import("import-claude-parts", ExternalFormat::Claude, export);
+        assert_eq!((report.imported, report.failed), (1, 0));
+        assert_eq!(texts("import-claude-parts")[1..], [("user".into(), "Hi".into()), ("agent".into(), "Hello\nthere".into())]);
+    }
+
+    #[test]
+    fn importing_the_same_export_again_skips_what_is_there() {
+        let first = import("import-twice", ExternalFormat::Chatgpt, BRANCHED_CHATGPT);
+        let second = import("import-twice", ExternalFormat::Chatgpt, BRANCHED_CHATGPT);
+        assert_eq!((first.imported, first.skipped), (1, 0));
+        assert_eq!((second.imported, second.skipped), (0, 1));
+        assert_eq!(read_history("import-twice").len(), 3);
+    }
+
+    #[test]
+    fn a_bad_element_fails_alone_and_a_syntax_error_ends_the_run() {
+        let conversation = BRANCHED_CHATGPT.trim().trim_start_matches('[').trim_end_matches(']');
+        let other = conversation.replace("Which one?", "Another question");
+        // Valid JSON that isn't a conversation, then a file cut off mid-element
+        let export = format!(r#"[{}, {{"title": "no mapping"}}, {}, {{"title": "cut"#, conversation, other);
+        let report = import("import-broken", ExternalFormat::Chatgpt, &export);
+        assert_eq!((report.imported, report.failed), (2, 2));
+        assert_eq!(report.failures[0].index, 1);
+        assert!(report.failures[0].reason.contains("mapping"));
+        assert!(report.failures[1].reason.contains("not valid JSON"), "{}", report.failures[1].reason);
+        assert_eq!(read_history("import-broken").len(), 6);
+    }
+}
@@ -0,0 +1,330 @@
+//! Optional loopback HTTP API for editor plugins and scripts.
+
+use crate::*;
+
+// ─── Local HTTP API ───────────────────────────────────────────────────────────
+
+/// Calls accepted per minute; the editor plugin sends one per prompt.
+pub(crate) const HTTP_API_CALLS_PER_MIN: usize = 30;
+pub(crate) const HTTP_API_MAX_BODY: u64 = 1024 * 1024;
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub(crate) struct HttpApiConfig {
+    pub(crate) enabled: bool,
+}
+
+#[derive(serde::Deserialize)]
+pub(crate) struct HttpCallBody {
+    pub(crate) agent: Option<String>,
+    pub(crate) message: String,
+    pub(crate) session: Option<String>,
+}
+
+/// Tells clients where to connect. The token is kept in its own file.
+pub(crate) fn http_api_discovery_path() -> PathBuf {
+    clapp_dir().join("http-api.json")
+}
+
+pub(crate) fn http_api_token_path() -> PathBuf {
+    clapp_dir().join("http-api-token")
+}
+
+/// Random and unrelated to the gateway token, so handing it to a script never exposes the gateway.
+pub(crate) fn new_http_api_token() -> Result<String, AppError> {
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes).map_err(|e| AppError::Other(e.to_string()))?;
+    let token: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    write_private(&http_api_token_path(), token.as_bytes())?;
+    Ok(token)
+}
+
+pub(crate) fn http_api_token() -> Result<String, AppError> {
+    match fs::read_to_string(http_api_token_path()) {
+        Ok(t) if !t.trim().is_empty() => Ok(t.trim().to_string()),
+        _ => new_http_api_token(),
+    }
+}
+
+/// Compares without stopping at the first differing byte.
+pub(crate) fn token_matches(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given.bytes().zip(expected.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+pub(crate) type HttpResponse = tiny_http::Response<std::io::Cursor<Vec<u8>>>;
+
+pub(crate) fn json_response(status: u16, body: serde_json::Value) -> HttpResponse {
+    tiny_http::Response::from_string(body.to_string())
+        .with_status_code(status)
+        .with_header(tiny_http::Header::from_bytes("Content-Type", "application/json").unwrap())
+}
+
+pub(crate) fn error_response(status: u16, message: &str) -> HttpResponse {
+    json_response(status, serde_json::json!({ "error": message }))
+}
+
+pub(crate) fn header<'a>(request: &'a tiny_http::Request, name: &'static str) -> Option<&'a str> {
+    request.headers().iter().find(|h| h.field.equiv(name)).map(|h| h.value.as_str())
+}
+
+/// Sliding one-minute window of accepted calls, on the monotonic clock.
+pub(crate) fn allow_http_call(app: &tauri::AppHandle) -> bool {
+    let app_state = app.state::<AppState>();
+    let mut recent = app_state.http_api_calls.lock().unwrap();
+    let now = monotonic_ms();
+    recent.retain(|t| now.saturating_sub(*t) < 60_000);
+    if recent.len() >= HTTP_API_CALLS_PER_MIN {
+        return false;
+    }
+    recent.push(now);
+    true
+}
+
+/// A request that passed every check not needing the app.
+pub(crate) enum HttpRoute {
+    Status,
+    Sessions,
+    Call { agent_id: String, body: HttpCallBody },
+}
+
+/// Everything short of running the route: the API switch, browser requests, the
+/// token, method and path, and a call's body. Calls are refused in observer mode.
+pub(crate) fn check_http_request(config: &AppConfig, expected_token: &str, request: &mut tiny_http::Request) -> Result<HttpRoute, HttpResponse> {
+    if !config.http_api.enabled {
+        return Err(error_response(503, "The HTTP API is disabled"));
+    }
+    // Not for browsers: any cross-origin request is refused and no CORS headers are ever sent
+    if header(request, "Origin").is_some() {
+        return Err(error_response(403, "Browser requests are not allowed"));
+    }
+    let authorized = header(request, "Authorization")
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .is_some_and(|t| token_matches(t.trim(), expected_token));
+    if !authorized {
+        return Err(error_response(401, "Missing or wrong bearer token"));
+    }
+
+    let method = request.method().clone();
+    let path = request.url().split('?').next().unwrap_or("").to_string();
+    match (method, path.as_str()) {
+        (tiny_http::Method::Get, "/status") => Ok(HttpRoute::Status),
+        (tiny_http::Method::Get, "/sessions") => Ok(HttpRoute::Sessions),
+        (tiny_http::Method::Post, "/call") => {
+            use std::io::Read;
+            if config.observer.enabled {
+                return Err(error_response(403, &AppError::ReadOnlyMode.to_string()));
+            }
+            let mut raw = String::new();
+            if request.as_reader().take(HTTP_API_MAX_BODY).read_to_string(&mut raw).is_err() {
+                return Err(error_response(400, "Body must be UTF-8 JSON"));
+            }
+            let body: HttpCallBody = serde_json::from_str(&raw).map_err(|e| error_response(400, &format!("Invalid body: {}", e)))?;
+            let agent_id = body.agent.clone().unwrap_or_else(|| config.default_agent_id.clone());
+            validate_agent_id(&agent_id).map_err(|e| error_response(400, &e.to_string()))?;
+            if body.message.trim().is_empty() {
+                return Err(error_response(400, "message is required"));
+            }
+            Ok(HttpRoute::Call { agent_id, body })
+        }
+        (_, "/status" | "/sessions" | "/call") => Err(error_response(405, "Method not allowed")),
+        _ => Err(error_response(404, "Not found")),
+    }
+}
+
+pub(crate) async fn handle_http_request(app: &tauri::AppHandle, request: &mut tiny_http::Request) -> HttpResponse {
+    let expected = match http_api_token() {
+        Ok(t) => t,
+        Err(e) => return error_response(500, &e.to_string()),
+    };
+    let route = match check_http_request(&load_config(), &expected, request) {
+        Ok(route) => route,
+        Err(response) => return response,
+    };
+    match route {
+        HttpRoute::Status => match gateway_status(app.clone()).await {
+            Ok(status) => json_response(200, serde_json::to_value(status).unwrap()),
+            Err(e) => error_response(502, &e),
+        },
+        HttpRoute::Sessions => match list_gateway_sessions(app.clone()).await {
+            Ok(sessions) => json_response(200, serde_json::to_value(sessions).unwrap()),
+            Err(e) => error_response(500, &e.to_string()),
+        },
+        HttpRoute::Call { agent_id, body } => {
+            if !allow_http_call(app) {
+                return error_response(429, "Too many calls; try again in a minute");
+            }
+            let session_key = body.session.unwrap_or_else(|| "http-api".into());
+            audit("http_api_call", serde_json::json!({ "agentId": agent_id, "sessionKey": session_key }));
+            match gateway_call(app.clone(), agent_id, body.message, session_key, None, None).await {
+                Ok(response) => match serde_json::from_str::<serde_json::Value>(&response) {
+                    Ok(v) => json_response(200, v),
+                    Err(_) => json_response(200, serde_json::json!({ "raw": response })),
+                },
+                Err(e) => error_response(502, &e),
+            }
+        }
+    }
+}
+
+/// Binds a random loopback port, writes the discovery file and serves until stopped.
+pub(crate) fn start_http_api(app: &tauri::AppHandle) -> Result<u16, AppError> {
+    if let Some(server) = app.state::<AppState>().http_api.lock().unwrap().as_ref() {
+        return Ok(server.server_addr().to_ip().map_or(0, |a| a.port()));
+    }
+    // Rewritten owner-only in case it dates from before the file was private
+    let token = http_api_token()?;
+    write_private(&http_api_token_path(), token.as_bytes())?;
+    let server = tiny_http::Server::http("127.0.0.1:0").map_err(|e| AppError::Other(e.to_string()))?;
+    let port = server.server_addr().to_ip().map_or(0, |a| a.port());
+    fs::write(http_api_discovery_path(), serde_json::to_string_pretty(&serde_json::json!({
+        "url": format!("http://127.0.0.1:{}", port),
+        "port": port,
+        "pid": std::process::id(),
+        "tokenFile": http_api_token_path(),
+    }))?)?;
+
+    let server = std::sync::Arc::new(server);
+    *app.state::<AppState>().http_api.lock().unwrap() = Some(server.clone());
+    let app = app.clone();
+    std::thread::spawn(move || {
+        for mut request in server.incoming_requests() {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                let response = handle_http_request(&app, &mut request).await;
+                request.respond(response).ok();
+            });
+        }
+    });
+    println!("[HTTP API] listening on 127.0.0.1:{}", port);
+    Ok(port)
+}
+
+pub(crate) fn stop_http_api(app: &tauri::AppHandle) {
+    if let Some(server) = app.state::<AppState>().http_api.lock().unwrap().take() {
+        server.unblock();
+    }
+    fs::remove_file(http_api_discovery_path()).ok();
+}
+
+/// Returns the port when enabled.
+#[tauri::command]
+pub(crate) fn set_http_api_enabled(app: tauri::AppHandle, enabled: bool) -> Result<Option<u16>, AppError> {
+    ensure_writable()?;
+    let mut config = load_config();
+    config.http_api.enabled = enabled;
+    save_config(&config)?;
+    if enabled {
+        start_http_api(&app).map(Some)
+    } else {
+        stop_http_api(&app);
+        Ok(None)
+    }
+}
+
+/// Invalidates the old token immediately; clients re-read the token file.
+#[tauri::command]
+pub(crate) fn rotate_http_api_token() -> Result<(), AppError> {
+    ensure_writable()?;
+    new_http_api_token()?;
+    audit("http_api_token_rotated", serde_json::Value::Null);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: &str = "0123456789abcdef";
+
+    fn enabled() -> AppConfig {
+        let mut config = AppConfig::default();
+        config.http_api.enabled = true;
+        config
+    }
+
+    /// Serves on a loopback port with the real checks, answering with the route a
+    /// request would run instead of running it, and sends `requests` through reqwest.
+    fn exchange(config: AppConfig, requests: &[(&str, &str, Option<&str>, &str)]) -> Vec<(u16, serde_json::Value)> {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://127.0.0.1:{}", server.server_addr().to_ip().unwrap().port());
+        let count = requests.len();
+        let serving = std::thread::spawn(move || {
+            for mut request in server.incoming_requests().take(count) {
+                let response = match check_http_request(&config, TOKEN, &mut request) {
+                    Ok(HttpRoute::Status) => json_response(200, serde_json::json!({ "route": "status" })),
+                    Ok(HttpRoute::Sessions) => json_response(200, serde_json::json!({ "route": "sessions" })),
+                    Ok(HttpRoute::Call { agent_id, body }) => {
+                        json_response(200, serde_json::json!({ "route": "call", "agent": agent_id, "message": body.message }))
+                    }
+                    Err(response) => response,
+                };
+                request.respond(response).unwrap();
+            }
+        });
+        let client = reqwest::Client::builder().no_proxy().build().unwrap();
+        let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        let replies = rt.block_on(async {
+            let mut replies = Vec::new();
+            for (method, path, auth, body) in requests {
+                let mut request = client.request(method.parse().unwrap(), format!("{}{}", url, path)).body(body.to_string());
+                if let Some(auth) = auth {
+                    request = request.header("Authorization", *auth);
+                }
+                let response = request.send().await.unwrap();
+                replies.push((response.status().as_u16(), response.json().await.unwrap()));
+            }
+            replies
+        });
+        serving.join().unwrap();
+        replies
+    }
+
+    #[test]
+    fn requests_need_the_token() {
+        let bearer = format!("Bearer {}", TOKEN);
+        let replies = exchange(enabled(), &[
+            ("GET", "/status", None, ""),
+            ("GET", "/status", Some("Bearer 0123456789abcdeX"), ""),
+            ("GET", "/status", Some(TOKEN), ""),
+            ("GET", "/status", Some(bearer.as_str()), ""),
+            ("POST", "/status", Some(bearer.as_str()), ""),
+            ("GET", "/nowhere", Some(bearer.as_str()), ""),
+        ]);
+        let statuses: Vec<u16> = replies.iter().map(|(s, _)| *s).collect();
+        assert_eq!(statuses, [401, 401, 401, 200, 405, 404]);
+        assert_eq!(replies[0].1["error"], "Missing or wrong bearer token");
+        assert_eq!(replies[3].1["route"], "status");
+    }
+
+    #[test]
+    fn calls_are_checked_before_they_run() {
+        let bearer = format!("Bearer {}", TOKEN);
+        let replies = exchange(enabled(), &[
+            ("POST", "/call", Some(bearer.as_str()), r#"{"message": "hi"}"#),
+            ("POST", "/call", Some(bearer.as_str()), r#"{"agent": "../etc", "message": "hi"}"#),
+            ("POST", "/call", Some(bearer.as_str()), r#"{"message": "  "}"#),
+            ("POST", "/call", Some(bearer.as_str()), "not json"),
+        ]);
+        assert_eq!(replies[0], (200, serde_json::json!({ "route": "call", "agent": "main", "message": "hi" })));
+        assert!(replies[1..].iter().all(|(s, _)| *s == 400), "{:?}", replies);
+        assert_eq!(exchange(AppConfig::default(), &[("GET", "/status", Some(bearer.as_str()), "")])[0].0, 503);
+    }
+
+    #[test]
+    fn observer_mode_refuses_calls_but_answers_reads() {
+        let mut config = enabled();
+        config.observer.enabled = true;
+        let bearer = format!("Bearer {}", TOKEN);
+        let replies = exchange(config, &[
+            ("POST", "/call", Some(bearer.as_str()), r#"{"message": "hi"}"#),
+            ("GET", "/sessions", Some(bearer.as_str()), ""),
+            ("POST", "/call", None, r#"{"message": "hi"}"#),
+        ]);
+        assert_eq!(replies[0].0, 403);
+        assert_eq!(replies[0].1["error"], AppError::ReadOnlyMode.to_string());
+        assert_eq!(replies[1], (200, serde_json::json!({ "route": "sessions" })));
+        // The token is still checked first
+        assert_eq!(replies[2].0, 401);
+    }
+}
@@ -1,502 +1,165 @@
-use tauri::Manager;
+use tauri::{Emitter, Manager};
 use tauri_plugin_shell::ShellExt;
+use std::collections::HashMap;
 use std::sync::Mutex;
 use std::fs;
 use std::path::PathBuf;
 
-struct AgentProcess(Mutex<Option<tauri_plugin_shell::process::CommandChild>>);
+pub mod commands;
+mod agents;
+mod announce;
+mod audit;
+mod auth_expiry;
+mod cancel;
+mod checkpoints;
+mod clock;
+mod config;
+mod conflicts;
+mod crash;
+mod data_format;
+mod credentials;
+mod environment;
+mod error;
+mod error_log;
+mod gateway;
+mod history;
+mod history_import;
+mod http_api;
+mod operations;
+mod paths;
+mod quick_actions;
+mod reply_assets;
+mod resync;
+mod safe_mode;
+mod secrets;
+mod self_test;
+mod shutdown;
+mod state;
+mod storage;
+#[cfg(feature = "telemetry")]
+mod telemetry;
+#[cfg(not(feature = "telemetry"))]
+#[path = "telemetry_off.rs"]
+mod telemetry;
+mod terminal;
+mod trash;
+mod workspace;
+
+use agents::*;
+use announce::*;
+use audit::*;
+use auth_expiry::*;
+use cancel::*;
+use clock::*;
+use config::*;
+use conflicts::*;
+use crash::*;
+use data_format::*;
+use credentials::*;
+use environment::*;
+use error::*;
+use error_log::*;
+use gateway::*;
+use history::*;
+use history_import::*;
+use http_api::*;
+use operations::*;
+use paths::*;
+use reply_assets::*;
+use resync::*;
+use safe_mode::*;
+use secrets::*;
+use self_test::*;
+use shutdown::*;
+use state::*;
+use storage::*;
+use telemetry::*;
+use terminal::*;
+use trash::*;
+use workspace::*;
 
-// ─── Paths ────────────────────────────────────────────────────────────────────
-
-fn config_path() -> PathBuf {
-    let mut p = dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
-    p.push("clapp");
-    fs::create_dir_all(&p).ok();
-    p.push("config.json");
-    p
-}
-
-fn openclaw_dir() -> PathBuf {
-    dirs::home_dir().unwrap_or_default().join(".openclaw")
-}
-
-fn openclaw_config_path() -> PathBuf {
-    openclaw_dir().join("openclaw.json")
-}
-
-fn openclaw_agents_root() -> PathBuf {
-    openclaw_dir().join("agents")
-}
-
-// ─── API key ──────────────────────────────────────────────────────────────────
-
-#[tauri::command]
-fn save_api_key(key: String) -> Result<(), String> {
-    let json = serde_json::json!({ "api_key": key });
-    fs::write(config_path(), serde_json::to_string_pretty(&json).unwrap())
-        .map_err(|e| e.to_string())
-}
-
-#[tauri::command]
-fn load_api_key() -> Result<String, String> {
-    let p = config_path();
-    if !p.exists() { return Ok("".into()); }
-    let v: serde_json::Value = serde_json::from_str(&fs::read_to_string(p).unwrap_or_default())
-        .unwrap_or_default();
-    Ok(v["api_key"].as_str().unwrap_or("").to_string())
-}
-
-// ─── Auth profile ─────────────────────────────────────────────────────────────
-
-fn write_auth_profile(agent_id: &str, api_key: &str, provider: &str, base_url: Option<&str>) -> Result<(), String> {
-    let mut dir = openclaw_agents_root();
-    dir.push(agent_id);
-    dir.push("agent");
-    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
-    dir.push("auth-profiles.json");
-
-    // Normalize provider name for openclaw
-    let provider_id = match provider {
-        "openai" | "groq" | "together" | "custom" => "openai", // OpenAI-compatible
-        _ => "anthropic",
-    };
-
-    let profile_key = format!("{}:default", provider_id);
-
-    let mut profile_obj = serde_json::json!({
-        "type": "api_key",
-        "provider": provider_id,
-        "key": api_key
-    });
-
-    // Add baseUrl for OpenAI-compatible providers
-    if let Some(url) = base_url.filter(|u| !u.trim().is_empty()) {
-        profile_obj["baseUrl"] = serde_json::Value::String(url.to_string());
-    }
-    // Groq
-    if provider == "groq" {
-        profile_obj["baseUrl"] = serde_json::Value::String("https://api.groq.com/openai/v1".into());
-    }
-    // Together
-    if provider == "together" {
-        profile_obj["baseUrl"] = serde_json::Value::String("https://api.together.xyz/v1".into());
-    }
-    // Ollama — no key, only URL
-    if provider == "ollama" {
-        let url = base_url.unwrap_or("http://localhost:11434");
-        let profile = serde_json::json!({
-            "version": 1,
-            "profiles": {
-                "openai:default": {
-                    "type": "api_key",
-                    "provider": "openai",
-                    "key": "ollama",
-                    "baseUrl": format!("{}/v1", url.trim_end_matches("/"))
-                }
-            },
-            "lastGood": { "openai": "openai:default" },
-            "usageStats": {}
-        });
-        return fs::write(&dir, serde_json::to_string_pretty(&profile).unwrap())
-            .map_err(|e| e.to_string());
-    }
-
-    let profile = serde_json::json!({
-        "version": 1,
-        "profiles": {
-            (profile_key.clone()): profile_obj
-        },
-        "lastGood": { (provider_id): (profile_key) },
-        "usageStats": {}
-    });
-    fs::write(&dir, serde_json::to_string_pretty(&profile).unwrap())
-        .map_err(|e| e.to_string())
-}
-
-fn write_agent_config(agent_id: &str, name: &str, system_prompt: &str) -> Result<(), String> {
-    let mut dir = openclaw_agents_root();
-    dir.push(agent_id);
-    dir.push("agent");
-    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
-    dir.push("agent.json");
-
-    let config = serde_json::json!({
-        "name": name,
-        "instructions": system_prompt
-    });
-    fs::write(&dir, serde_json::to_string_pretty(&config).unwrap())
-        .map_err(|e| e.to_string())
-}
-
-#[tauri::command]
-fn sync_agent_auth(
-    agent_id: String,
-    api_key: String,
-    agent_name: String,
-    system_prompt: String,
-    provider: String,
-    base_url: Option<String>,
-) -> Result<(), String> {
-    // Ollama doesn't require a key, others do
-    if provider != "ollama" && api_key.trim().is_empty() {
-        return Err("API key is empty".into());
-    }
-    let url = base_url.as_deref();
-    write_auth_profile(&agent_id, &api_key, &provider, url)?;
-    write_agent_config(&agent_id, &agent_name, &system_prompt)?;
-    write_auth_profile("main", &api_key, &provider, url)?;
-    write_agent_config("main", &agent_name, &system_prompt)
-}
-
-// ─── openclaw.json ────────────────────────────────────────────────────────────
-
-fn generate_token() -> String {
-    let t = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_nanos();
-    format!("local-{:x}-{:x}", t, std::process::id())
-}
-
-fn ensure_openclaw_config() -> Result<String, String> {
-    let dir = openclaw_dir();
-    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+// ─── Entry ────────────────────────────────────────────────────────────────────
 
-    let config_file = openclaw_config_path();
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    let safe_mode = detect_safe_mode();
+    fs::write(startup_sentinel_path(), b"").ok();
+    let previous_run = begin_heartbeat();
 
-    if config_file.exists() {
-        if let Ok(content) = fs::read_to_string(&config_file) {
-            if let Ok(mut v) = serde_json::from_str::<serde_json::Value>(&content) {
-                // Remove keys that openclaw does not accept
-                if let Some(obj) = v.as_object_mut() {
-                    obj.remove("providers");
-                    obj.remove("version");
-                }
-                let token = v["gateway"]["auth"]["token"].as_str().unwrap_or("").to_string();
-                if !token.is_empty() {
-                    // Rewrite without garbage
-                    fs::write(&config_file, serde_json::to_string_pretty(&v).unwrap())
-                        .map_err(|e| e.to_string())?;
-                    return Ok(token);
-                }
+    let builder = tauri::Builder::default()
+        .manage(AppState::new(safe_mode.clone()))
+        .on_page_load(|webview, payload| on_webview_load(webview.app_handle(), payload.event()))
+        .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_shell::init())
+        .register_uri_scheme_protocol(ASSET_SCHEME, |_ctx, request| serve_cached_asset(&request))
+        .setup(move |app| {
+            spawn_storage_monitor(app.handle().clone());
+            spawn_heartbeat(app.handle().clone());
+            spawn_state_flusher(app.handle().clone());
+            tauri::async_runtime::spawn(collect_environment_info(app.handle().clone()));
+            CONFLICT_EVENTS.set(app.handle().clone()).ok();
+            CLOCK_EVENTS.set(app.handle().clone()).ok();
+            spawn_telemetry_loop(app.handle().clone());
+            spawn_config_watcher(app.handle().clone());
+            tauri::async_runtime::spawn_blocking(agents::env::learn_agent_env_secrets);
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn_blocking(move || refresh_data_format(&handle));
+            if fixture_mode() {
+                println!("[FIXTURES] fixture mode: replaying {}", fixtures_dir().display());
             }
-        }
-    }
-
-    // Create a minimal valid config
-    let token = generate_token();
-    let config = serde_json::json!({
-        "gateway": {
-            "mode": "local",
-            "port": 18789,
-            "bind": "loopback",
-            "auth": {
-                "token": token
+            if let Some(previous) = previous_run {
+                tauri::async_runtime::spawn(report_previous_crash(app.handle().clone(), previous));
             }
-        }
-    });
-
-    fs::write(&config_file, serde_json::to_string_pretty(&config).unwrap())
-        .map_err(|e| e.to_string())?;
-
-    Ok(token)
-}
-
-// ─── Pairing: read token from config and call pair ────────────────────────
-
-async fn do_pairing(app: &tauri::AppHandle, token: &str) -> Result<(), String> {
-    // Gateway auto-approves pairing on loopback — just call pair without --url
-    let out = app.shell()
-        .command("cmd")
-        .args(["/C", "npx", "openclaw", "gateway", "pair", "--token", token])
-        .output()
-        .await
-        .map_err(|e| e.to_string())?;
-
-    let combined = format!(
-        "{}{}",
-        String::from_utf8_lossy(&out.stdout),
-        String::from_utf8_lossy(&out.stderr)
-    );
-    println!("[PAIR] {}", combined.trim());
-    Ok(()) // Not fatal in any case
-}
-
-// ─── Gateway token ────────────────────────────────────────────────────────────
-
-fn read_gateway_token() -> Result<String, String> {
-    let p = openclaw_config_path();
-    if !p.exists() { return Err("openclaw.json not found".into()); }
-    let v: serde_json::Value = serde_json::from_str(&fs::read_to_string(p).unwrap_or_default())
-        .map_err(|_| "openclaw.json is corrupted".to_string())?;
-    let token = v["gateway"]["auth"]["token"].as_str().unwrap_or("").to_string();
-    if token.is_empty() { return Err("Token is empty".into()); }
-    Ok(token)
-}
-
-// ─── Gateway start/stop/status ────────────────────────────────────────────────
-
-#[tauri::command]
-async fn start_agent(app: tauri::AppHandle) -> Result<String, String> {
-    let api_key = load_api_key()?;
-
-    if api_key.trim().is_empty() {
-        return Err("Add an API key in the agent settings first".into());
-    }
-
-    let token = ensure_openclaw_config()?;
-    write_auth_profile("main", &api_key, "anthropic", None)?;
-
-    let shell = app.shell();
-
-    // Already running?
-    let health_ok = shell
-        .command("cmd")
-        .args(["/C", "npx", "openclaw", "gateway", "health"])
-        .output()
-        .await
-        .map(|out| {
-            let s = String::from_utf8_lossy(&out.stdout).to_lowercase();
-            let e = String::from_utf8_lossy(&out.stderr).to_lowercase();
-            s.contains("ok") || e.contains("ok")
-        })
-        .unwrap_or(false);
-
-    if health_ok {
-        return Ok("running".into());
-    }
-
-    // Start gateway
-    let (mut rx, child) = shell
-        .command("cmd")
-        .args([
-            "/C", "npx", "openclaw", "gateway", "run",
-            "--port", "18789",
-            "--bind", "loopback",
-        ])
-        .env("ANTHROPIC_API_KEY", &api_key)
-        .env("OPENAI_API_KEY", &api_key)
-        .spawn()
-        .map_err(|e| format!("Failed to start gateway: {}", e))?;
-
-    tauri::async_runtime::spawn(async move {
-        use tauri_plugin_shell::process::CommandEvent;
-        while let Some(ev) = rx.recv().await {
-            match ev {
-                CommandEvent::Stdout(b) => print!("[GW] {}", String::from_utf8_lossy(&b)),
-                CommandEvent::Stderr(b) => eprint!("[GW ERR] {}", String::from_utf8_lossy(&b)),
-                _ => {}
+            if let Some(reason) = app.state::<AppState>().safe_mode.clone() {
+                app.emit("safe-mode", reason).ok();
+            } else {
+                spawn_deferred_drain_loop(app.handle().clone());
+                tauri::async_runtime::spawn_blocking(backup_main_before_policy);
+                tauri::async_runtime::spawn_blocking(remove_stale_self_test_agents);
+                spawn_daily_maintenance(app.handle().clone());
+                spawn_auth_expiry_monitor(app.handle().clone());
+                agents::skills::spawn_skills_watcher(app.handle().clone());
+                start_power_monitor(app.handle().clone());
+                if load_config().http_api.enabled {
+                    if let Err(e) = start_http_api(app.handle()) {
+                        eprintln!("[HTTP API ERR] {}", e);
+                    }
+                }
             }
-        }
-    });
-
-    *app.state::<AgentProcess>().0.lock().unwrap() = Some(child);
-
-    // Wait for gateway to spin up (up to 10 sec)
-    let mut gateway_up = false;
-    for _ in 0..20 {
-        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
-        let alive = app.shell()
-            .command("cmd")
-            .args(["/C", "npx", "openclaw", "gateway", "health"])
-            .output()
-            .await
-            .map(|out| {
-                let s = String::from_utf8_lossy(&out.stdout).to_lowercase();
-                let e = String::from_utf8_lossy(&out.stderr).to_lowercase();
-                s.contains("ok") || e.contains("ok")
-            })
-            .unwrap_or(false);
-
-        if alive {
-            gateway_up = true;
-            break;
-        }
-    }
-
-    if !gateway_up {
-        return Err("Gateway failed to start within 10 sec. Check: npm install -g openclaw".into());
-    }
-
-    // Perform pairing so this client can make calls
-    // Do not consider pairing error fatal — might already be paired
-    if let Err(e) = do_pairing(&app, &token).await {
-        eprintln!("[PAIR ERR] {}", e);
-    }
-
-    Ok("running".into())
-}
-
-#[tauri::command]
-fn stop_agent(app: tauri::AppHandle) -> Result<String, String> {
-    if let Some(child) = app.state::<AgentProcess>().0.lock().unwrap().take() {
-        child.kill().map_err(|e| e.to_string())?;
-    }
-    Ok("stopped".into())
-}
-
-#[tauri::command]
-async fn gateway_status(app: tauri::AppHandle) -> Result<String, String> {
-    let out = app.shell()
-        .command("cmd")
-        .args(["/C", "npx", "openclaw", "gateway", "health"])
-        .output()
-        .await
-        .map_err(|e| e.to_string())?;
-
-    let s = String::from_utf8_lossy(&out.stdout).to_lowercase();
-    let e = String::from_utf8_lossy(&out.stderr).to_lowercase();
-
-    if s.contains("ok") || e.contains("ok") {
-        Ok("running".into())
-    } else {
-        Ok("stopped".into())
-    }
-}
-
-// ─── Gateway call ─────────────────────────────────────────────────────────────
-
-#[tauri::command]
-async fn gateway_call(
-    app: tauri::AppHandle,
-    _agent_id: String,
-    message: String,
-    session_key: String,
-    _system_prompt: Option<String>,
-) -> Result<String, String> {
-    let token = read_gateway_token().unwrap_or_default();
-
-    let ikey = format!("{}-{}", session_key,
-        std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis());
-
-    let params = serde_json::json!({
-        "message": message,
-        "sessionKey": "main",
-        "idempotencyKey": ikey,
-        "deliver": false
-    });
-
-    let params_str = params.to_string();
-
-    let mut args: Vec<&str> = vec![
-        "/C", "npx", "openclaw", "gateway", "call",
-        "agent",
-        "--json",
-        "--expect-final",
-        "--timeout", "130000",
-        "--params", &params_str,
-    ];
-
-    if !token.is_empty() {
-        args.push("--token");
-        args.push(&token);
-    }
-
-    let output = app.shell()
-        .command("cmd")
-        .args(&args)
-        .output()
-        .await
-        .map_err(|e| e.to_string())?;
-
-    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
-    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            tauri::async_runtime::spawn(async {
+                tokio::time::sleep(std::time::Duration::from_secs(STARTUP_GRACE_SECS)).await;
+                fs::remove_file(startup_sentinel_path()).ok();
+            });
+            Ok(())
+        });
 
-    if stdout.is_empty() {
-        Err(if stderr.is_empty() { "Empty response from gateway".into() } else { stderr })
+    let builder = if safe_mode.is_some() {
+        builder.invoke_handler(commands::safe_mode_handler())
     } else {
-        Ok(stdout)
-    }
-}
-
-// ─── Environment check ───────────────────────────────────────────────────────
-
-#[derive(serde::Serialize)]
-pub struct EnvCheck {
-    node: bool,
-    node_version: String,
-    openclaw: bool,
-    openclaw_version: String,
-}
-
-#[tauri::command]
-async fn check_environment(app: tauri::AppHandle) -> Result<EnvCheck, String> {
-    let shell = app.shell();
-
-    // Check Node.js
-    let node_out = shell
-        .command("cmd")
-        .args(["/C", "node", "--version"])
-        .output()
-        .await;
-
-    let (node, node_version) = match node_out {
-        Ok(out) if out.status.success() => {
-            let v = String::from_utf8_lossy(&out.stdout).trim().to_string();
-            (true, v)
-        }
-        _ => (false, String::new()),
+        builder.invoke_handler(commands::handler())
     };
 
-    // Check openclaw
-    let openclaw_out = shell
-        .command("cmd")
-        .args(["/C", "npx", "openclaw", "--version"])
-        .output()
-        .await;
-
-    let (openclaw, openclaw_version) = match openclaw_out {
-        Ok(out) => {
-            let stdout = String::from_utf8_lossy(&out.stdout).trim().to_string();
-            let stderr = String::from_utf8_lossy(&out.stderr).trim().to_string();
-            let v = if !stdout.is_empty() { stdout } else { stderr };
-            let ok = out.status.success() || v.contains(".");
-            (ok, if ok { v } else { String::new() })
-        }
-        _ => (false, String::new()),
-    };
-
-    Ok(EnvCheck { node, node_version, openclaw, openclaw_version })
-}
-
-// ─── Terminal ─────────────────────────────────────────────────────────────────
-
-#[tauri::command]
-async fn run_command(app: tauri::AppHandle, cmd: String) -> Result<String, String> {
-    let out = app.shell()
-        .command("cmd")
-        .args(["/C", &format!("chcp 65001 >nul && {}", cmd)])
-        .output()
-        .await
-        .map_err(|e| e.to_string())?;
-
-    let stdout = String::from_utf8_lossy(&out.stdout).to_string();
-    let stderr = String::from_utf8_lossy(&out.stderr).to_string();
-    Ok(if stdout.is_empty() { stderr } else { stdout })
+    builder
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| match event {
+            tauri::RunEvent::ExitRequested { api, .. } => {
+                // Held until the ordered shutdown has run, then released by app.exit
+                let finished = app.state::<AppState>().shutdown_lock.try_lock().is_ok_and(|done| *done);
+                if !finished {
+                    api.prevent_exit();
+                    let app = app.clone();
+                    tauri::async_runtime::spawn(async move {
+                        run_shutdown(&app).await;
+                        app.exit(0);
+                    });
+                }
+            }
+            // Last resort if the process exits without going through ExitRequested
+            tauri::RunEvent::Exit => {
+                fs::remove_file(http_api_discovery_path()).ok();
+                mark_clean_shutdown();
+            }
+            _ => {}
+        });
 }
-
-// ─── Entry ────────────────────────────────────────────────────────────────────
-
-#[cfg_attr(mobile, tauri::mobile_entry_point)]
-pub fn run() {
-    tauri::Builder::default()
-        .manage(AgentProcess(Mutex::new(None)))
-        .plugin(tauri_plugin_opener::init())
-        .plugin(tauri_plugin_shell::init())
-        .invoke_handler(tauri::generate_handler![
-            start_agent,
-            stop_agent,
-            gateway_status,
-            gateway_call,
-            sync_agent_auth,
-            save_api_key,
-            load_api_key,
-            run_command,
-            check_environment,
-        ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
-}
\ No newline at end of file
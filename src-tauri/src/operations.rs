@@ -0,0 +1,143 @@
+//! Progress, listing and cancellation for long-running operations.
+
+use crate::*;
+
+// ─── Operations ───────────────────────────────────────────────────────────────
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct OperationProgress {
+    pub(crate) id: String,
+    /// e.g. "export", "import", "trash-purge"
+    pub(crate) kind: String,
+    pub(crate) phase: String,
+    pub(crate) done: usize,
+    pub(crate) total: Option<usize>,
+    /// Item being worked on, redacted
+    pub(crate) current: Option<String>,
+    pub(crate) started_at: u64,
+    /// Extrapolated from the items done so far
+    pub(crate) remaining_ms: Option<u64>,
+    /// "running", "done", "cancelled" or "failed"
+    pub(crate) status: String,
+}
+
+/// Operations in flight, so a reopened window can find them again.
+pub(crate) static OPERATIONS: std::sync::LazyLock<Mutex<HashMap<String, (OperationProgress, CancelToken)>>> =
+    std::sync::LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// One registered operation. Cloneable into storage jobs; dropping the last
+/// clone without `finish` reports it as failed. Every report goes through
+/// `emit_stream`, so with batching on the final status still arrives after
+/// the last progress.
+#[derive(Clone)]
+pub(crate) struct Operation {
+    app: tauri::AppHandle,
+    id: String,
+    cancel: CancelToken,
+    _guard: std::sync::Arc<OperationGuard>,
+}
+
+pub(crate) struct OperationGuard {
+    app: tauri::AppHandle,
+    id: String,
+}
+
+impl Drop for OperationGuard {
+    fn drop(&mut self) {
+        if let Some((mut progress, _)) = OPERATIONS.lock().unwrap().remove(&self.id) {
+            progress.status = "failed".into();
+            progress.remaining_ms = None;
+            emit_stream(&self.app, "operation-progress", &self.id, &progress);
+        }
+    }
+}
+
+impl Operation {
+    pub(crate) fn start(app: &tauri::AppHandle, kind: &str, total: Option<usize>) -> Self {
+        let id = format!("{}-{}", kind, next_seq());
+        let cancel = ROOT_CANCEL.child();
+        let progress = OperationProgress {
+            id: id.clone(),
+            kind: kind.into(),
+            phase: "starting".into(),
+            done: 0,
+            total,
+            current: None,
+            started_at: now_ms(),
+            remaining_ms: None,
+            status: "running".into(),
+        };
+        OPERATIONS.lock().unwrap().insert(id.clone(), (progress.clone(), cancel.clone()));
+        emit_stream(app, "operation-progress", &id, &progress);
+        let guard = std::sync::Arc::new(OperationGuard { app: app.clone(), id: id.clone() });
+        Operation { app: app.clone(), id, cancel, _guard: guard }
+    }
+
+    pub(crate) fn set_total(&self, total: usize) {
+        self.update(|p| p.total = Some(total));
+    }
+
+    /// Reports `done` items finished in `phase`, now working on `current`.
+    pub(crate) fn progress(&self, phase: &str, done: usize, current: Option<&str>) {
+        self.update(|p| {
+            p.phase = phase.into();
+            p.done = done;
+            p.current = current.map(redact_text);
+            let elapsed = now_ms().saturating_sub(p.started_at);
+            p.remaining_ms = p.total
+                .filter(|_| done > 0)
+                .map(|total| elapsed / done as u64 * total.saturating_sub(done) as u64);
+        });
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    /// For item boundaries: fails with `Cancelled` once cancellation was asked for.
+    pub(crate) fn check(&self, done_so_far: &str) -> Result<(), AppError> {
+        self.cancel.check(done_so_far)
+    }
+
+    /// Unregisters the operation and reports how it ended.
+    pub(crate) fn finish<T>(&self, result: &Result<T, AppError>) {
+        let status = match result {
+            Ok(_) if self.is_cancelled() => "cancelled",
+            Ok(_) => "done",
+            Err(AppError::Cancelled(_)) => "cancelled",
+            Err(_) => "failed",
+        };
+        if let Some((mut progress, _)) = OPERATIONS.lock().unwrap().remove(&self.id) {
+            progress.status = status.into();
+            progress.remaining_ms = None;
+            emit_stream(&self.app, "operation-progress", &self.id, &progress);
+        }
+    }
+
+    pub(crate) fn update(&self, f: impl FnOnce(&mut OperationProgress)) {
+        let progress = {
+            let mut ops = OPERATIONS.lock().unwrap();
+            let Some((progress, _)) = ops.get_mut(&self.id) else { return };
+            f(progress);
+            progress.clone()
+        };
+        emit_stream(&self.app, "operation-progress", &self.id, &progress);
+    }
+}
+
+#[tauri::command]
+pub(crate) fn list_operations() -> Vec<OperationProgress> {
+    let mut ops: Vec<OperationProgress> = OPERATIONS.lock().unwrap().values().map(|(p, _)| p.clone()).collect();
+    ops.sort_by_key(|p| p.started_at);
+    ops
+}
+
+/// Asks the operation to stop at its next item boundary.
+#[tauri::command]
+pub(crate) fn cancel_operation(id: String) -> Result<(), AppError> {
+    let ops = OPERATIONS.lock().unwrap();
+    let (_, cancel) = ops.get(&id).ok_or_else(|| AppError::NotFound(format!("operation {}", id)))?;
+    cancel.cancel();
+    Ok(())
+}
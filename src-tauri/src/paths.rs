@@ -0,0 +1,326 @@
+//! Locations of app and OpenClaw files.
+
+use crate::*;
+
+// ─── Paths ────────────────────────────────────────────────────────────────────
+
+/// Tests get their own home and config dir, shared by the whole test run.
+#[cfg(test)]
+pub(crate) static TEST_ROOT: std::sync::LazyLock<PathBuf> = std::sync::LazyLock::new(|| {
+    let root = std::env::temp_dir().join(format!("clapp-test-{}", std::process::id()));
+    fs::remove_dir_all(&root).ok();
+    root
+});
+
+fn home_dir() -> PathBuf {
+    #[cfg(test)]
+    return TEST_ROOT.join("home");
+    #[cfg(not(test))]
+    dirs::home_dir().unwrap_or_default()
+}
+
+fn config_dir() -> PathBuf {
+    #[cfg(test)]
+    return TEST_ROOT.join("config");
+    #[cfg(not(test))]
+    dirs::config_dir().unwrap_or_else(|| PathBuf::from("."))
+}
+
+pub(crate) fn clapp_dir() -> PathBuf {
+    let mut p = config_dir();
+    p.push("clapp");
+    fs::create_dir_all(&p).ok();
+    p
+}
+
+pub(crate) fn config_path() -> PathBuf {
+    clapp_dir().join("config.json")
+}
+
+pub(crate) fn startup_sentinel_path() -> PathBuf {
+    clapp_dir().join("startup.sentinel")
+}
+
+pub(crate) fn openclaw_dir() -> PathBuf {
+    home_dir().join(".openclaw")
+}
+
+pub(crate) fn openclaw_config_path() -> PathBuf {
+    openclaw_dir().join("openclaw.json")
+}
+
+pub(crate) fn gateway_pid_path() -> PathBuf {
+    openclaw_dir().join("gateway.pid")
+}
+
+pub(crate) fn openclaw_agents_root() -> PathBuf {
+    openclaw_dir().join("agents")
+}
+
+pub(crate) fn agent_dir(agent_id: &str) -> PathBuf {
+    openclaw_agents_root().join(agent_id).join("agent")
+}
+
+// ─── User-supplied paths ──────────────────────────────────────────────────────
+
+/// Longest path accepted from the webview, in characters.
+pub(crate) const MAX_USER_PATH_LEN: usize = 1024;
+
+/// Names Windows maps to devices in any directory, with or without an extension.
+pub(crate) const WINDOWS_RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL",
+    "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9",
+    "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// What a path from the webview is going to be used for; each has its own rules.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum PathIntent<'a> {
+    /// A file we create or overwrite: anywhere, but its directory must exist and
+    /// the file itself must not be a symlink
+    Export,
+    /// A file we read: must exist and be a regular file
+    Import,
+    /// A file read on an agent's behalf: must resolve, symlinks included, to inside
+    /// one of the roots. Relative paths are taken from the first root.
+    WorkspaceRead(&'a [PathBuf]),
+    /// A program we run: an existing executable file
+    Executable,
+}
+
+impl PathIntent<'_> {
+    /// Whether the path may be a symlink. Where it may, the rules apply to its target.
+    pub(crate) fn follows_symlinks(&self) -> bool {
+        !matches!(self, PathIntent::Export)
+    }
+}
+
+pub(crate) fn path_rejected(path: &str, rule: &str) -> AppError {
+    AppError::PathRejected { path: path.to_string(), rule: rule.to_string() }
+}
+
+/// `fs::canonicalize` gives `\\?\C:\...` on Windows, which many tools refuse.
+pub(crate) fn strip_verbatim(path: PathBuf) -> PathBuf {
+    match path.to_str().and_then(|s| s.strip_prefix(r"\\?\")) {
+        Some(rest) if rest.as_bytes().get(1) == Some(&b':') => PathBuf::from(rest),
+        _ => path,
+    }
+}
+
+/// `starts_with` that ignores case where the filesystem does.
+pub(crate) fn path_inside(path: &std::path::Path, root: &std::path::Path) -> bool {
+    if cfg!(windows) {
+        let lower = |p: &std::path::Path| PathBuf::from(p.to_string_lossy().to_lowercase());
+        lower(path).starts_with(lower(root))
+    } else {
+        path.starts_with(root)
+    }
+}
+
+/// Rules that don't depend on the filesystem: length, NUL bytes, and on Windows
+/// device paths, UNC shares, reserved names and components ending in a dot or
+/// space, which Windows silently strips.
+pub(crate) fn check_path_syntax(raw: &str) -> Result<(), AppError> {
+    if raw.trim().is_empty() {
+        return Err(path_rejected(raw, "the path is empty"));
+    }
+    if raw.chars().count() > MAX_USER_PATH_LEN {
+        return Err(path_rejected(raw, &format!("longer than {} characters", MAX_USER_PATH_LEN)));
+    }
+    if raw.contains('\0') {
+        return Err(path_rejected(raw, "contains a NUL character"));
+    }
+    if !cfg!(windows) {
+        return Ok(());
+    }
+    if raw.starts_with(r"\\?\") || raw.starts_with(r"\\.\") || raw.starts_with("//?/") || raw.starts_with("//./") {
+        return Err(path_rejected(raw, "device paths are not accepted"));
+    }
+    if raw.starts_with(r"\\") || raw.starts_with("//") {
+        return Err(path_rejected(raw, "network (UNC) paths are not accepted"));
+    }
+    for part in raw.split(['\\', '/']).filter(|p| !p.is_empty() && *p != "." && *p != "..") {
+        if part.ends_with('.') || part.ends_with(' ') {
+            return Err(path_rejected(raw, &format!("'{}' ends in a dot or space", part)));
+        }
+        let stem = part.split('.').next().unwrap_or("").trim_end();
+        if WINDOWS_RESERVED_NAMES.iter().any(|n| n.eq_ignore_ascii_case(stem)) {
+            return Err(path_rejected(raw, &format!("'{}' is a reserved device name", part)));
+        }
+    }
+    Ok(())
+}
+
+/// The one check every path from the webview goes through before it is touched.
+/// Returns the resolved path to use instead of the raw one.
+pub(crate) fn validate_user_path(raw: &str, intent: PathIntent) -> Result<PathBuf, AppError> {
+    check_path_syntax(raw)?;
+    let path = std::path::Path::new(raw.trim());
+    let is_link = fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink());
+    if is_link && !intent.follows_symlinks() {
+        return Err(path_rejected(raw, "is a symbolic link"));
+    }
+
+    match intent {
+        PathIntent::Export => {
+            if !path.is_absolute() {
+                return Err(path_rejected(raw, "must be an absolute path"));
+            }
+            let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+                return Err(path_rejected(raw, "has no file name"));
+            };
+            let dir = fs::canonicalize(dir).map_err(|_| path_rejected(raw, "its folder does not exist"))?;
+            if path.is_dir() {
+                return Err(path_rejected(raw, "is a folder"));
+            }
+            Ok(strip_verbatim(dir).join(name))
+        }
+        PathIntent::Import | PathIntent::Executable => {
+            if !path.is_absolute() {
+                return Err(path_rejected(raw, "must be an absolute path"));
+            }
+            let resolved = fs::canonicalize(path).map_err(|_| path_rejected(raw, "does not exist"))?;
+            if !resolved.is_file() {
+                return Err(path_rejected(raw, "is not a regular file"));
+            }
+            if matches!(intent, PathIntent::Executable) && !is_executable(&resolved) {
+                return Err(path_rejected(raw, "is not executable"));
+            }
+            Ok(strip_verbatim(resolved))
+        }
+        PathIntent::WorkspaceRead(roots) => {
+            let candidate = match roots.first() {
+                Some(root) if path.is_relative() => root.join(path),
+                _ => path.to_path_buf(),
+            };
+            let resolved = fs::canonicalize(&candidate).map_err(|_| path_rejected(raw, "does not exist"))?;
+            // Canonical on both sides, so `..` and symlinks can't step outside
+            let inside = roots.iter()
+                .filter_map(|root| fs::canonicalize(root).ok())
+                .any(|root| path_inside(&resolved, &root));
+            if !inside {
+                return Err(path_rejected(raw, "is outside the folders this agent may read"));
+            }
+            if !resolved.is_file() {
+                return Err(path_rejected(raw, "is not a regular file"));
+            }
+            Ok(strip_verbatim(resolved))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `root/{file.txt, folder/, run.sh, ws/{inside.txt, escape -> ../file.txt}}`
+    /// plus `link.txt -> file.txt`.
+    fn tree() -> PathBuf {
+        let root = TEST_ROOT.join("user-paths");
+        fs::create_dir_all(root.join("folder")).unwrap();
+        fs::create_dir_all(root.join("ws")).unwrap();
+        fs::write(root.join("file.txt"), "x").unwrap();
+        fs::write(root.join("ws").join("inside.txt"), "x").unwrap();
+        fs::write(root.join("run.sh"), "#!/bin/sh\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(root.join("run.sh"), fs::Permissions::from_mode(0o755)).unwrap();
+            std::os::unix::fs::symlink(root.join("file.txt"), root.join("link.txt")).ok();
+            std::os::unix::fs::symlink(root.join("file.txt"), root.join("ws").join("escape")).ok();
+        }
+        root
+    }
+
+    fn check(raw: &str, intent: PathIntent) -> Result<(), String> {
+        match validate_user_path(raw, intent) {
+            Ok(_) => Ok(()),
+            Err(AppError::PathRejected { rule, .. }) => Err(rule),
+            Err(e) => panic!("{}: unexpected error {}", raw, e),
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_paths_for_every_intent() {
+        let long = format!("/{}", "a".repeat(MAX_USER_PATH_LEN));
+        let roots = [tree()];
+        let intents = [PathIntent::Export, PathIntent::Import, PathIntent::Executable, PathIntent::WorkspaceRead(&roots)];
+        let cases = [("", "the path is empty"), ("   ", "the path is empty"), (long.as_str(), "longer than"), ("/tmp/a\0b", "NUL")];
+        for intent in intents {
+            for (raw, rule) in cases {
+                let err = check(raw, intent).unwrap_err();
+                assert!(err.contains(rule), "{:?} {:?}: {}", raw, intent, err);
+            }
+        }
+    }
+
+    #[test]
+    fn applies_each_intents_rules() {
+        let root = tree();
+        let p = |name: &str| root.join(name).to_string_lossy().into_owned();
+        let roots = [root.join("ws")];
+        let ws = PathIntent::WorkspaceRead(&roots);
+        let mut cases: Vec<(String, PathIntent, Result<(), &str>)> = vec![
+            (p("new.txt"), PathIntent::Export, Ok(())),
+            (p("file.txt"), PathIntent::Export, Ok(())),
+            ("new.txt".into(), PathIntent::Export, Err("must be an absolute path")),
+            (p("missing/new.txt"), PathIntent::Export, Err("its folder does not exist")),
+            (p("folder"), PathIntent::Export, Err("is a folder")),
+            (p("file.txt"), PathIntent::Import, Ok(())),
+            ("file.txt".into(), PathIntent::Import, Err("must be an absolute path")),
+            (p("missing.txt"), PathIntent::Import, Err("does not exist")),
+            (p("folder"), PathIntent::Import, Err("is not a regular file")),
+            (p("folder"), PathIntent::Executable, Err("is not a regular file")),
+            ("inside.txt".into(), ws, Ok(())),
+            (p("ws/inside.txt"), ws, Ok(())),
+            ("../file.txt".into(), ws, Err("is outside the folders")),
+            (p("file.txt"), ws, Err("is outside the folders")),
+            ("missing.txt".into(), ws, Err("does not exist")),
+        ];
+        #[cfg(unix)]
+        cases.extend([
+            (p("link.txt"), PathIntent::Export, Err("is a symbolic link")),
+            (p("link.txt"), PathIntent::Import, Ok(())),
+            (p("run.sh"), PathIntent::Executable, Ok(())),
+            (p("file.txt"), PathIntent::Executable, Err("is not executable")),
+            ("escape".into(), ws, Err("is outside the folders")),
+        ]);
+        for (raw, intent, expected) in cases {
+            let got = check(&raw, intent);
+            match expected {
+                Ok(()) => assert!(got.is_ok(), "{} {:?}: {:?}", raw, intent, got),
+                Err(rule) => {
+                    let err = got.expect_err(&format!("{} {:?} was accepted", raw, intent));
+                    assert!(err.contains(rule), "{} {:?}: {}", raw, intent, err);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn export_resolves_to_the_canonical_folder() {
+        let root = tree();
+        let raw = root.join("folder").join("..").join("out.txt");
+        let resolved = validate_user_path(&raw.to_string_lossy(), PathIntent::Export).unwrap();
+        assert_eq!(resolved, fs::canonicalize(&root).unwrap().join("out.txt"));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn rejects_windows_only_syntax() {
+        let cases = [
+            (r"\\?\C:\x.txt", "device paths"),
+            (r"\\.\PhysicalDrive0", "device paths"),
+            (r"\\server\share\x.txt", "UNC"),
+            (r"C:\data\CON", "reserved device name"),
+            (r"C:\data\nul.txt", "reserved device name"),
+            (r"C:\data\name.", "ends in a dot or space"),
+            (r"C:\data\name \x.txt", "ends in a dot or space"),
+        ];
+        for (raw, rule) in cases {
+            let err = check(raw, PathIntent::Import).unwrap_err();
+            assert!(err.contains(rule), "{}: {}", raw, err);
+        }
+        assert!(check_path_syntax(r"C:\data\console.txt").is_ok());
+    }
+}
@@ -0,0 +1,261 @@
+//! One-click actions on a file, folder or diff: a prompt template plus context
+//! gathered here, sent through `gateway_call`.
+
+use crate::*;
+
+// ─── Quick actions ────────────────────────────────────────────────────────────
+
+/// Larger files and diffs are refused rather than cut, so a review never misses the end
+pub(crate) const QUICK_FILE_MAX_BYTES: u64 = 256 * 1024;
+pub(crate) const QUICK_DIFF_MAX_BYTES: usize = 256 * 1024;
+/// Folders with more files than this are refused
+pub(crate) const QUICK_FOLDER_MAX_FILES: usize = 2_000;
+pub(crate) const QUICK_FOLDER_SAMPLE_FILES: usize = 12;
+pub(crate) const QUICK_FOLDER_SAMPLE_BYTES: usize = 4 * 1024;
+
+/// What a quick action gathers before it calls the agent.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum QuickContext {
+    /// A file's text, or the target itself when it isn't a path
+    File,
+    /// `git diff` of the repository the target is in, or the target when it is a diff
+    Diff,
+    /// The folder's file list and the start of a few of its files
+    Folder,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct QuickAction {
+    /// Sent before the context; `{target}` is replaced by the path
+    pub(crate) prompt: String,
+    pub(crate) context: QuickContext,
+    /// Agent template whose system prompt goes first, from templates.json
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) template: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct QuickActionInfo {
+    pub(crate) name: String,
+    pub(crate) context: QuickContext,
+    pub(crate) builtin: bool,
+}
+
+pub(crate) fn quick_actions_path() -> PathBuf {
+    clapp_dir().join("quick-actions.json")
+}
+
+pub(crate) fn builtin_quick_actions() -> std::collections::BTreeMap<String, QuickAction> {
+    let a = |prompt: &str, context: QuickContext| QuickAction { prompt: prompt.into(), context, template: None };
+    [
+        ("explain_file", a("Explain what {target} does, how it is structured and anything surprising in it.", QuickContext::File)),
+        ("review_diff", a("Review this diff of {target}. List bugs, risky changes and missing tests, most important first.", QuickContext::Diff)),
+        ("summarize_folder", a("Summarize what the project in {target} is and how it is organized.", QuickContext::Folder)),
+        ("write_tests_for", a("Write tests for {target}, in the test framework it already uses if you can tell.", QuickContext::File)),
+    ]
+    .into_iter()
+    .map(|(name, action)| (name.to_string(), action))
+    .collect()
+}
+
+/// Built-in actions plus the user's from quick-actions.json, which may replace them.
+pub(crate) fn load_quick_actions() -> Result<std::collections::BTreeMap<String, QuickAction>, AppError> {
+    let mut actions = builtin_quick_actions();
+    match fs::read_to_string(quick_actions_path()) {
+        Ok(content) => {
+            let user: std::collections::BTreeMap<String, QuickAction> = serde_json::from_str(&content)
+                .map_err(|e| AppError::InvalidInput(format!("quick-actions.json is invalid: {}", e)))?;
+            actions.extend(user);
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+    Ok(actions)
+}
+
+pub(crate) fn looks_binary(bytes: &[u8]) -> bool {
+    bytes.iter().take(8 * 1024).any(|b| *b == 0) || std::str::from_utf8(bytes).is_err()
+}
+
+pub(crate) fn read_text_file(path: &std::path::Path) -> Result<String, AppError> {
+    let size = fs::metadata(path)?.len();
+    if size > QUICK_FILE_MAX_BYTES {
+        return Err(AppError::InvalidInput(format!(
+            "{} is {} KB; quick actions take files up to {} KB", path.display(), size / 1024, QUICK_FILE_MAX_BYTES / 1024
+        )));
+    }
+    let bytes = fs::read(path)?;
+    if looks_binary(&bytes) {
+        return Err(AppError::UnsupportedFormat(format!("{} is a binary file", path.display())));
+    }
+    Ok(String::from_utf8(bytes).unwrap_or_default())
+}
+
+pub(crate) fn resolve_folder(raw: &str) -> Result<PathBuf, AppError> {
+    check_path_syntax(raw)?;
+    let path = std::path::Path::new(raw.trim());
+    if !path.is_absolute() {
+        return Err(path_rejected(raw, "must be an absolute path"));
+    }
+    let resolved = fs::canonicalize(path).map_err(|_| path_rejected(raw, "does not exist"))?;
+    if !resolved.is_dir() {
+        return Err(path_rejected(raw, "is not a folder"));
+    }
+    Ok(strip_verbatim(resolved))
+}
+
+/// The file list and the start of the first few text files, READMEs first.
+pub(crate) fn sample_folder(root: &std::path::Path) -> Result<String, AppError> {
+    let mut files = Vec::new();
+    let mut stack = vec![root.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else { continue };
+        for entry in entries.flatten() {
+            let Ok(meta) = entry.metadata() else { continue };
+            if meta.is_dir() {
+                if !WORKSPACE_IGNORE.iter().any(|i| entry.file_name() == *i) {
+                    stack.push(entry.path());
+                }
+                continue;
+            }
+            if files.len() >= QUICK_FOLDER_MAX_FILES {
+                return Err(AppError::InvalidInput(format!(
+                    "{} has more than {} files; pick a smaller folder", root.display(), QUICK_FOLDER_MAX_FILES
+                )));
+            }
+            files.push((entry.path(), meta.len()));
+        }
+    }
+    if files.is_empty() {
+        return Err(AppError::InvalidInput(format!("{} has no files", root.display())));
+    }
+    let relative = |p: &std::path::Path| p.strip_prefix(root).unwrap_or(p).display().to_string();
+    files.sort_by_key(|(p, _)| (!relative(p).to_lowercase().starts_with("readme"), relative(p)));
+
+    let mut out = format!("Files ({}):\n", files.len());
+    for (path, size) in &files {
+        out.push_str(&format!("{} ({} bytes)\n", relative(path), size));
+    }
+    let samples = files.iter()
+        .filter(|(_, size)| *size <= QUICK_FILE_MAX_BYTES)
+        .filter_map(|(path, _)| Some((path, fs::read(path).ok().filter(|b| !looks_binary(b))?)))
+        .take(QUICK_FOLDER_SAMPLE_FILES);
+    for (path, bytes) in samples {
+        let text = String::from_utf8_lossy(&bytes);
+        let mut end = text.len().min(QUICK_FOLDER_SAMPLE_BYTES);
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        out.push_str(&format!("\n--- {} ---\n{}\n", relative(path), &text[..end]));
+    }
+    Ok(out)
+}
+
+/// `git <args>` in `dir`, run directly so the path needs no quoting.
+pub(crate) async fn run_git(app: &tauri::AppHandle, dir: &std::path::Path, args: &[&str]) -> Result<tauri_plugin_shell::process::Output, AppError> {
+    let dir = dir.to_string_lossy().into_owned();
+    app.shell()
+        .command("git")
+        .args(["-C", dir.as_str()])
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| AppError::Other(format!("Could not run git: {}", e)))
+}
+
+/// True when git failed because the repository has no commit for HEAD to name.
+pub(crate) fn is_unborn_head(stderr: &str) -> bool {
+    ["unknown revision", "bad revision", "ambiguous argument 'HEAD'"].iter().any(|m| stderr.contains(m))
+}
+
+/// `git diff HEAD` of the repository containing `raw`. Before the first commit
+/// there is no HEAD, so the staged changes stand in for it.
+pub(crate) async fn git_diff(app: &tauri::AppHandle, raw: &str) -> Result<String, AppError> {
+    check_path_syntax(raw)?;
+    let path = fs::canonicalize(raw.trim()).map_err(|_| path_rejected(raw, "does not exist"))?;
+    let path = strip_verbatim(path);
+    let dir = if path.is_dir() { path.clone() } else { path.parent().map(PathBuf::from).unwrap_or_default() };
+    let mut out = run_git(app, &dir, &["diff", "HEAD"]).await?;
+    if !out.status.success() && is_unborn_head(&String::from_utf8_lossy(&out.stderr)) {
+        out = run_git(app, &dir, &["diff", "--cached"]).await?;
+    }
+    if !out.status.success() {
+        let stderr = String::from_utf8_lossy(&out.stderr).to_string();
+        if stderr.contains("not a git repository") {
+            return Err(AppError::NotFound(format!("git repository at {}", dir.display())));
+        }
+        return Err(AppError::Other(format!("git diff failed: {}", stderr.trim())));
+    }
+    let diff = String::from_utf8_lossy(&out.stdout).to_string();
+    if diff.trim().is_empty() {
+        return Err(AppError::InvalidInput(format!("{} has no uncommitted changes", dir.display())));
+    }
+    if diff.len() > QUICK_DIFF_MAX_BYTES {
+        return Err(AppError::InvalidInput(format!(
+            "The diff is {} KB; quick actions take diffs up to {} KB", diff.len() / 1024, QUICK_DIFF_MAX_BYTES / 1024
+        )));
+    }
+    Ok(diff)
+}
+
+/// Reads what the action needs. Targets that aren't an existing path are taken as text.
+pub(crate) async fn quick_action_context(app: &tauri::AppHandle, context: QuickContext, target: &str) -> Result<String, AppError> {
+    let is_path = std::path::Path::new(target.trim()).exists();
+    match context {
+        QuickContext::File if is_path => {
+            let path = validate_user_path(target, PathIntent::Import)?;
+            run_storage_io(app, move || read_text_file(&path)).await?
+        }
+        QuickContext::Diff if is_path => git_diff(app, target).await,
+        QuickContext::File | QuickContext::Diff => Ok(target.to_string()),
+        QuickContext::Folder => {
+            let root = resolve_folder(target)?;
+            run_storage_io(app, move || sample_folder(&root)).await?
+        }
+    }
+}
+
+#[tauri::command]
+pub(crate) fn list_quick_actions() -> Result<Vec<QuickActionInfo>, AppError> {
+    let builtin = builtin_quick_actions();
+    Ok(load_quick_actions()?.into_iter()
+        .map(|(name, action)| QuickActionInfo { builtin: builtin.contains_key(&name), name, context: action.context })
+        .collect())
+}
+
+/// Runs `action` on a path or pasted text. Without `session_key` it gets a
+/// fresh session. Returns the normal `gateway_call` response.
+#[tauri::command]
+pub(crate) async fn quick_action(
+    app: tauri::AppHandle,
+    agent_id: String,
+    action: String,
+    target_path_or_text: String,
+    session_key: Option<String>,
+) -> Result<String, AppError> {
+    ensure_writable()?;
+    if target_path_or_text.trim().is_empty() {
+        return Err(AppError::InvalidInput("Nothing to run the quick action on".into()));
+    }
+    let definition = load_quick_actions()?
+        .remove(&action)
+        .ok_or_else(|| AppError::NotFound(format!("quick action {}", action)))?;
+    let preamble = match &definition.template {
+        Some(name) => {
+            let template = agents::templates::load_templates()?
+                .remove(name)
+                .ok_or_else(|| AppError::NotFound(format!("template {}", name)))?;
+            format!("{}\n\n", template.system_prompt)
+        }
+        None => String::new(),
+    };
+    let context = quick_action_context(&app, definition.context, &target_path_or_text).await?;
+    let target = if std::path::Path::new(target_path_or_text.trim()).exists() { target_path_or_text.trim() } else { "the text below" };
+    let message = format!("{}{}\n\n---\n\n{}", preamble, definition.prompt.replace("{target}", target), context);
+    let session_key = session_key.filter(|k| !k.trim().is_empty())
+        .unwrap_or_else(|| format!("clapp-quick-{}", now_ms()));
+    gateway_call(app, agent_id, message, session_key, None, None).await.map_err(AppError::Other)
+}
@@ -0,0 +1,208 @@
+//! Images in agent replies, copied into a cache the webview can load.
+
+use crate::*;
+
+// ─── Reply assets ─────────────────────────────────────────────────────────────
+
+/// Served by our own URI scheme, which only ever reads from `asset_cache_dir`.
+pub(crate) const ASSET_SCHEME: &str = "clapp-asset";
+pub(crate) const ASSET_CACHE_MAX_BYTES: u64 = 256 * 1024 * 1024;
+pub(crate) const ASSET_MAX_BYTES: u64 = 32 * 1024 * 1024;
+
+/// Formats the webview can show in an `<img>`, by extension.
+pub(crate) const IMAGE_TYPES: &[(&str, &str)] = &[
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("avif", "image/avif"),
+    ("bmp", "image/bmp"),
+    ("ico", "image/x-icon"),
+    ("svg", "image/svg+xml"),
+];
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ResolvedAsset {
+    pub(crate) url: String,
+    pub(crate) mime: String,
+    pub(crate) bytes: u64,
+}
+
+/// One image reference found in a reply. `error` is set instead of `url` when it
+/// could not be resolved; `unsupported` means the file exists but can only be downloaded.
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ReplyAsset {
+    pub(crate) source: String,
+    #[serde(flatten)]
+    pub(crate) resolved: Option<ResolvedAsset>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) error: Option<String>,
+    pub(crate) unsupported: bool,
+}
+
+pub(crate) fn asset_cache_dir() -> PathBuf {
+    clapp_dir().join("asset-cache")
+}
+
+/// Where the gateway's tools (browser screenshots, generated files) put media.
+pub(crate) fn gateway_media_dir() -> PathBuf {
+    openclaw_dir().join("media")
+}
+
+pub(crate) fn asset_url(name: &str) -> String {
+    // Custom schemes are served from http://<scheme>.localhost on Windows
+    if cfg!(windows) {
+        format!("http://{}.localhost/{}", ASSET_SCHEME, name)
+    } else {
+        format!("{}://localhost/{}", ASSET_SCHEME, name)
+    }
+}
+
+pub(crate) fn image_mime(path: &std::path::Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    IMAGE_TYPES.iter().find(|(e, _)| *e == ext).map(|(_, m)| *m)
+}
+
+/// Canonical path of `raw` if it lies inside the agent workspace or the gateway
+/// media folder. Relative paths are taken from the workspace.
+pub(crate) fn allowed_asset_path(agent_id: &str, raw: &str) -> Result<PathBuf, AppError> {
+    let roots = [agent_workspace(&read_agent_config(agent_id)), gateway_media_dir()];
+    validate_user_path(raw.trim().trim_start_matches("file://"), PathIntent::WorkspaceRead(&roots))
+}
+
+/// Touches a cached copy, or writes one, then trims the cache to its size limit.
+pub(crate) fn resolve_asset(agent_id: &str, raw: &str) -> Result<ResolvedAsset, AppError> {
+    use std::hash::{Hash, Hasher};
+    let path = allowed_asset_path(agent_id, raw)?;
+    let Some(mime) = image_mime(&path) else {
+        let ext = path.extension().map(|e| e.to_string_lossy().into_owned()).unwrap_or_default();
+        return Err(AppError::UnsupportedFormat(format!("'{}' files can't be shown inline", ext)));
+    };
+    let meta = fs::metadata(&path)?;
+    if meta.len() > ASSET_MAX_BYTES {
+        return Err(AppError::InvalidInput(format!("{} is larger than {} MB", raw, ASSET_MAX_BYTES / 1024 / 1024)));
+    }
+
+    // Named by source and version, so an overwritten file gets a new URL
+    let mut h = std::collections::hash_map::DefaultHasher::new();
+    (&path, meta.len(), meta.modified().ok()).hash(&mut h);
+    let ext = path.extension().unwrap_or_default().to_string_lossy().to_ascii_lowercase();
+    let name = format!("{:016x}.{}", h.finish(), ext);
+    let cached = asset_cache_dir().join(&name);
+    if cached.exists() {
+        fs::File::options().write(true).open(&cached)?.set_modified(std::time::SystemTime::now())?;
+    } else {
+        fs::create_dir_all(asset_cache_dir())?;
+        let tmp = cached.with_extension("tmp");
+        fs::copy(&path, &tmp)?;
+        fs::rename(&tmp, &cached)?;
+        evict_asset_cache(&cached);
+    }
+    Ok(ResolvedAsset { url: asset_url(&name), mime: mime.into(), bytes: meta.len() })
+}
+
+/// Least recently resolved first, never the file just added.
+pub(crate) fn evict_asset_cache(keep: &std::path::Path) {
+    let Ok(entries) = fs::read_dir(asset_cache_dir()) else { return };
+    let mut files: Vec<(std::time::SystemTime, u64, PathBuf)> = entries.flatten()
+        .filter_map(|e| {
+            let meta = e.metadata().ok()?;
+            Some((meta.modified().ok()?, meta.len(), e.path()))
+        })
+        .collect();
+    let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+    files.sort();
+    for (_, len, path) in files {
+        if total <= ASSET_CACHE_MAX_BYTES {
+            break;
+        }
+        if path != keep && fs::remove_file(&path).is_ok() {
+            total -= len;
+        }
+    }
+}
+
+/// Handler for `ASSET_SCHEME`. Only bare file names in the cache are served.
+pub(crate) fn serve_cached_asset(request: &tauri::http::Request<Vec<u8>>) -> tauri::http::Response<Vec<u8>> {
+    let name = request.uri().path().trim_start_matches('/');
+    let plain = !name.is_empty() && !name.starts_with('.') && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '.');
+    let path = asset_cache_dir().join(name);
+    let (Some(mime), Ok(bytes)) = (image_mime(&path).filter(|_| plain), fs::read(&path)) else {
+        return tauri::http::Response::builder().status(404).body(Vec::new()).unwrap();
+    };
+    tauri::http::Response::builder()
+        .header("Content-Type", mime)
+        // SVG can carry script; it is only ever an image here
+        .header("Content-Security-Policy", "default-src 'none'; style-src 'unsafe-inline'")
+        .body(bytes)
+        .unwrap()
+}
+
+/// Markdown image targets, and bare paths into the gateway media folder.
+pub(crate) fn find_image_refs(text: &str) -> Vec<String> {
+    let mut refs = Vec::new();
+    let mut rest = text;
+    while let Some(at) = rest.find("![") {
+        rest = &rest[at + 2..];
+        let Some(open) = rest.find("](") else { break };
+        let Some(close) = rest[open + 2..].find(')') else { break };
+        let target = rest[open + 2..open + 2 + close].trim();
+        // Drop an optional title: ![alt](path "title")
+        let target = target.split(" \"").next().unwrap_or("").trim_matches(|c| c == '<' || c == '>');
+        if !target.is_empty() && (!target.contains("://") || target.starts_with("file://")) {
+            refs.push(target.to_string());
+        }
+        rest = &rest[open + 2 + close..];
+    }
+    let media = gateway_media_dir().to_string_lossy().into_owned();
+    for word in text.split_whitespace() {
+        let word = word.trim_matches(|c: char| "`'\"()[]<>,;".contains(c));
+        if word.starts_with(&media) && !refs.iter().any(|r| r == word) {
+            refs.push(word.to_string());
+        }
+    }
+    refs
+}
+
+/// Adds `assets` to the response for every image the reply refers to.
+pub(crate) async fn attach_reply_assets(app: &tauri::AppHandle, agent_id: &str, response: String) -> String {
+    let Ok(mut v) = serde_json::from_str::<serde_json::Value>(&response) else { return response };
+    let refs = find_image_refs(&reply_text(&response));
+    if refs.is_empty() {
+        return response;
+    }
+    let agent_id = agent_id.to_string();
+    let assets = run_storage_io(app, move || {
+        refs.into_iter()
+            .map(|source| match resolve_asset(&agent_id, &source) {
+                Ok(resolved) => ReplyAsset { source, resolved: Some(resolved), error: None, unsupported: false },
+                Err(e) => ReplyAsset {
+                    source,
+                    resolved: None,
+                    unsupported: matches!(e, AppError::UnsupportedFormat(_)),
+                    error: Some(e.to_string()),
+                },
+            })
+            .collect::<Vec<_>>()
+    }).await;
+    match assets {
+        Ok(assets) => {
+            v["assets"] = serde_json::to_value(assets).unwrap_or_default();
+            v.to_string()
+        }
+        Err(_) => response,
+    }
+}
+
+/// For references the response didn't pre-resolve. Fails with `UnsupportedFormat`
+/// for files the webview can't show, so the UI can offer a download instead.
+#[tauri::command]
+pub(crate) async fn resolve_reply_asset(app: tauri::AppHandle, session_key: String, path: String) -> Result<ResolvedAsset, AppError> {
+    run_storage_io(&app, move || {
+        let owner = find_session_owner(&session_key).ok_or_else(|| AppError::NotFound(format!("session {}", session_key)))?;
+        resolve_asset(&owner, &path)
+    }).await?
+}
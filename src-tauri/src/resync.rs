@@ -0,0 +1,220 @@
+//! Catching a reloaded webview up with work that kept running while it was gone.
+
+use crate::*;
+
+// ─── Stream tails ─────────────────────────────────────────────────────────────
+
+/// Events kept per consumer for a frontend that reloads; older ones are dropped.
+pub(crate) const STREAM_TAIL_MAX: usize = 200;
+/// Consumers kept at once; the one that went longest without an event goes first.
+pub(crate) const STREAM_CONSUMERS_MAX: usize = 100;
+/// A reloaded page that never calls `resync` gets events again after this long.
+pub(crate) const RESYNC_TIMEOUT_MS: u64 = 10_000;
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct StreamEvent {
+    /// Increases across all streams, so a frontend can ask for what it hasn't seen
+    pub(crate) seq: u64,
+    pub(crate) event: &'static str,
+    /// What the events are about: an operation id, "gateway" for its log, a channel
+    pub(crate) consumer: String,
+    pub(crate) at: u64,
+    pub(crate) payload: serde_json::Value,
+}
+
+/// The recent events of every `emit_stream` stream, per consumer. While a
+/// reloaded frontend has not called `resync`, events are only kept, not emitted.
+#[derive(Default)]
+pub(crate) struct StreamTails {
+    pub(crate) seq: std::sync::atomic::AtomicU64,
+    pub(crate) paused: std::sync::atomic::AtomicBool,
+    pub(crate) page_loads: std::sync::atomic::AtomicU64,
+    pub(crate) tails: Mutex<HashMap<String, std::collections::VecDeque<StreamEvent>>>,
+}
+
+impl StreamTails {
+    /// Keeps the event; returns false while emission is paused for a reload.
+    pub(crate) fn record(&self, event: &'static str, consumer: &str, payload: &serde_json::Value) -> bool {
+        // Numbered and checked under the lock, so `resync` can't miss an event in between
+        let mut tails = self.tails.lock().unwrap();
+        let seq = self.seq.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+        if !tails.contains_key(consumer) && tails.len() >= STREAM_CONSUMERS_MAX {
+            let stalest = tails.iter()
+                .min_by_key(|(_, tail)| tail.back().map_or(0, |e| e.seq))
+                .map(|(consumer, _)| consumer.clone());
+            if let Some(stalest) = stalest {
+                tails.remove(&stalest);
+            }
+        }
+        let tail = tails.entry(consumer.to_string()).or_default();
+        if tail.len() >= STREAM_TAIL_MAX {
+            tail.pop_front();
+        }
+        tail.push_back(StreamEvent { seq, event, consumer: consumer.to_string(), at: now_ms(), payload: payload.clone() });
+        !self.paused.load(std::sync::atomic::Ordering::SeqCst)
+    }
+
+    /// Pauses emission for a reload. Returns the page load it paused for.
+    pub(crate) fn pause(&self) -> u64 {
+        let load = self.page_loads.load(std::sync::atomic::Ordering::SeqCst);
+        self.paused.store(true, std::sync::atomic::Ordering::SeqCst);
+        load
+    }
+
+    /// Resumes emission unless a later reload paused it again.
+    pub(crate) fn resume_after(&self, load: u64) {
+        if self.page_loads.load(std::sync::atomic::Ordering::SeqCst) == load {
+            self.paused.store(false, std::sync::atomic::Ordering::SeqCst);
+        }
+    }
+
+    /// Kept events of `consumer_kinds` (event names, all when empty) after
+    /// `since`, by consumer. Resumes emission.
+    pub(crate) fn take_since(&self, consumer_kinds: &[String], since: u64) -> (HashMap<String, Vec<StreamEvent>>, u64) {
+        let kept = self.tails.lock().unwrap();
+        let tails = kept.iter()
+            .map(|(consumer, tail)| {
+                let events: Vec<StreamEvent> = tail.iter()
+                    .filter(|e| e.seq > since)
+                    .filter(|e| consumer_kinds.is_empty() || consumer_kinds.iter().any(|k| k == e.event))
+                    .cloned()
+                    .collect();
+                (consumer.clone(), events)
+            })
+            .filter(|(_, events)| !events.is_empty())
+            .collect();
+        let last_seq = self.seq.load(std::sync::atomic::Ordering::SeqCst);
+        // Everything after this is emitted again
+        self.paused.store(false, std::sync::atomic::Ordering::SeqCst);
+        (tails, last_seq)
+    }
+}
+
+/// Every load after the first is a reload: the old page's listeners are gone, so
+/// streams pause until the new page resyncs, or `RESYNC_TIMEOUT_MS` passes for a
+/// page that doesn't. Nothing running is cancelled.
+pub(crate) fn on_webview_load(app: &tauri::AppHandle, event: tauri::webview::PageLoadEvent) {
+    if event != tauri::webview::PageLoadEvent::Started {
+        return;
+    }
+    let app_state = app.state::<AppState>();
+    let streams = &app_state.stream_tails;
+    if streams.page_loads.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 0 {
+        return;
+    }
+    let load = streams.pause();
+    println!("[RESYNC] webview reloaded; stream events held until resync");
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(RESYNC_TIMEOUT_MS)).await;
+        let app_state = app.state::<AppState>();
+        if app_state.stream_tails.paused.load(std::sync::atomic::Ordering::SeqCst) {
+            app_state.stream_tails.resume_after(load);
+            println!("[RESYNC] no resync after reload; stream events resumed");
+        }
+    });
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ResyncState {
+    /// Gateway calls running now, by agent id
+    pub(crate) calls_in_flight: HashMap<String, usize>,
+    pub(crate) pending_calls: Vec<PendingCall>,
+    pub(crate) operations: Vec<OperationProgress>,
+    /// Kept events of the requested streams by consumer, oldest first
+    pub(crate) tails: HashMap<String, Vec<StreamEvent>>,
+    /// Pass back as `since_seq` to get only newer events next time
+    pub(crate) last_seq: u64,
+}
+
+/// What is running and the kept events of `consumer_kinds` (stream event names,
+/// all when empty) after `since_seq`. Resumes emission.
+#[tauri::command]
+pub(crate) fn resync(
+    state: tauri::State<AppState>,
+    consumer_kinds: Vec<String>,
+    since_seq: Option<u64>,
+) -> ResyncState {
+    let (tails, last_seq) = state.stream_tails.take_since(&consumer_kinds, since_seq.unwrap_or(0));
+    ResyncState {
+        calls_in_flight: state.calls_in_flight.by_agent(),
+        pending_calls: state.pending_calls.lock().unwrap().clone(),
+        operations: list_operations(),
+        tails,
+        last_seq,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload(n: usize) -> serde_json::Value {
+        serde_json::json!({ "n": n })
+    }
+
+    #[test]
+    fn consumer_that_reloads_gets_every_event_in_the_window() {
+        let streams = StreamTails::default();
+        assert!(streams.record("operation-progress", "op-1", &payload(0)));
+        let (_, seen) = streams.take_since(&[], 0);
+        // The page goes away; work keeps producing
+        streams.page_loads.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        streams.pause();
+        for n in 1..=50 {
+            assert!(!streams.record("operation-progress", "op-1", &payload(n)));
+            assert!(!streams.record("gateway-log", "gateway", &payload(n)));
+        }
+        let (tails, last) = streams.take_since(&["operation-progress".to_string()], seen);
+        let op: Vec<u64> = tails["op-1"].iter().map(|e| e.payload["n"].as_u64().unwrap()).collect();
+        assert_eq!(op, (1..=50).collect::<Vec<_>>());
+        assert!(!tails.contains_key("gateway"));
+        assert_eq!(last, 101);
+        // Resynced: emitted again
+        assert!(streams.record("operation-progress", "op-1", &payload(51)));
+    }
+
+    #[test]
+    fn consumers_keep_their_own_tail() {
+        let streams = StreamTails::default();
+        for n in 0..STREAM_TAIL_MAX * 2 {
+            streams.record("gateway-log", "gateway", &payload(n));
+        }
+        streams.record("operation-progress", "op-1", &payload(0));
+        let (tails, _) = streams.take_since(&[], 0);
+        // A chatty stream never pushes out a quiet one's events
+        assert_eq!(tails["op-1"].len(), 1);
+        assert_eq!(tails["gateway"].len(), STREAM_TAIL_MAX);
+        assert_eq!(tails["gateway"][0].payload["n"], STREAM_TAIL_MAX);
+    }
+
+    #[test]
+    fn memory_stays_bounded_past_the_window() {
+        let streams = StreamTails::default();
+        for c in 0..STREAM_CONSUMERS_MAX * 3 {
+            for n in 0..10 {
+                streams.record("operation-progress", &format!("op-{}", c), &payload(n));
+            }
+        }
+        let tails = streams.tails.lock().unwrap();
+        assert_eq!(tails.len(), STREAM_CONSUMERS_MAX);
+        // The most recent consumers are the ones kept
+        assert!(tails.contains_key(&format!("op-{}", STREAM_CONSUMERS_MAX * 3 - 1)));
+        assert!(!tails.contains_key("op-0"));
+    }
+
+    #[test]
+    fn resume_timeout_only_ends_its_own_pause() {
+        let streams = StreamTails::default();
+        streams.page_loads.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        let first = streams.pause();
+        streams.page_loads.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        streams.pause();
+        streams.resume_after(first);
+        assert!(streams.paused.load(std::sync::atomic::Ordering::SeqCst));
+        streams.resume_after(first + 1);
+        assert!(!streams.paused.load(std::sync::atomic::Ordering::SeqCst));
+    }
+}
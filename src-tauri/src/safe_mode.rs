@@ -0,0 +1,100 @@
+//! Minimal startup after a crash or on request.
+
+use crate::*;
+
+// ─── Safe mode ────────────────────────────────────────────────────────────────
+
+pub(crate) const SAFE_MODE_FLAG: &str = "--safe-mode";
+
+// A startup that survives this long is considered successful
+pub(crate) const STARTUP_GRACE_SECS: u64 = 15;
+
+pub(crate) fn detect_safe_mode() -> Option<String> {
+    if std::env::args().any(|a| a == SAFE_MODE_FLAG) {
+        return Some("flag".into());
+    }
+    // The sentinel is only left behind if the previous startup never finished
+    if startup_sentinel_path().exists() {
+        return Some("crash".into());
+    }
+    None
+}
+
+pub(crate) fn validate_configs() -> Result<(), AppError> {
+    read_config().map_err(AppError::InvalidInput)?;
+    read_gateway_token().map_err(AppError::InvalidInput)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) fn get_safe_mode(state: tauri::State<AppState>) -> Option<String> {
+    state.safe_mode.clone()
+}
+
+#[tauri::command]
+pub(crate) async fn repair_config(app: tauri::AppHandle) -> Result<(), AppError> {
+    ensure_writable()?;
+    let p = config_path();
+    if p.exists() && read_config().is_err() {
+        fs::remove_file(&p)?;
+    }
+    // Drops unknown keys and regenerates the file if it has no usable token
+    run_storage_io(&app, ensure_openclaw_config).await??;
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) async fn factory_reset(app: tauri::AppHandle, scopes: Vec<String>) -> Result<(), AppError> {
+    ensure_writable()?;
+    run_storage_io(&app, move || {
+        flush_pending_writes()?;
+        reset_scopes(&scopes)
+    }).await?
+}
+
+pub(crate) fn reset_scopes(scopes: &[String]) -> Result<(), AppError> {
+    for scope in scopes {
+        let target = match scope.as_str() {
+            "app-config" => config_path(),
+            "openclaw-config" => openclaw_config_path(),
+            "agents" => openclaw_agents_root(),
+            other => return Err(AppError::InvalidInput(format!("Unknown reset scope: {}", other))),
+        };
+        if target.is_dir() {
+            fs::remove_dir_all(&target)?;
+        } else if target.exists() {
+            fs::remove_file(&target)?;
+        }
+    }
+    Ok(())
+}
+
+/// This start's arguments without the flag that asked for safe mode.
+pub(crate) fn relaunch_args(args: impl Iterator<Item = String>) -> Vec<String> {
+    args.skip(1).filter(|a| a != SAFE_MODE_FLAG).collect()
+}
+
+#[tauri::command]
+pub(crate) fn leave_safe_mode(app: tauri::AppHandle) -> Result<(), AppError> {
+    validate_configs()?;
+    fs::remove_file(startup_sentinel_path()).ok();
+    mark_clean_shutdown();
+    // Full command set is only registered on a normal start. `app.restart()` would
+    // keep the flag and come straight back up in safe mode.
+    std::process::Command::new(std::env::current_exe()?)
+        .args(relaunch_args(std::env::args()))
+        .spawn()?;
+    app.exit(0);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relaunching_drops_only_the_safe_mode_flag() {
+        let args = ["clapp", "--safe-mode", "--minimized", "--safe-mode"].map(String::from);
+        assert_eq!(relaunch_args(args.into_iter()), vec!["--minimized".to_string()]);
+    }
+}
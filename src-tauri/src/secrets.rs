@@ -0,0 +1,113 @@
+//! Detection and redaction of credential-looking text.
+
+// ─── Secret detection ─────────────────────────────────────────────────────────
+
+/// Token prefixes of well-known credential formats. Shared by export redaction and prompt linting.
+pub(crate) const SECRET_PREFIXES: &[(&str, &str)] = &[
+    ("sk-ant-", "anthropic_key"),
+    ("sk-proj-", "openai_key"),
+    ("sk-", "api_key"),
+    ("gsk_", "groq_key"),
+    ("ghp_", "github_token"),
+    ("gho_", "github_token"),
+    ("github_pat_", "github_token"),
+    ("glpat-", "gitlab_token"),
+    ("xoxb-", "slack_token"),
+    ("xoxp-", "slack_token"),
+    ("AKIA", "aws_key"),
+    ("AIza", "google_key"),
+];
+pub(crate) const SECRET_MIN_LEN: usize = 20;
+pub(crate) const REDACTED: &str = "<redacted>";
+/// Lowercased labels after which the next value is treated as a password.
+pub(crate) const PASSWORD_LABELS: &[&str] = &["password", "passwd", "pwd", "пароль"];
+
+/// A secret-looking token, in char offsets.
+pub(crate) struct SecretMatch {
+    pub(crate) kind: &'static str,
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+}
+
+pub(crate) fn is_token_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '-' | '_' | '.')
+}
+
+/// Single pass over the text; no backtracking, so large pastes stay cheap.
+pub(crate) fn find_secrets(text: &str) -> Vec<SecretMatch> {
+    let mut found = Vec::new();
+    let mut tokens: Vec<(usize, usize, usize)> = Vec::new(); // (char start, byte start, byte end)
+    let mut current: Option<(usize, usize)> = None;
+    for (chars, (byte, c)) in text.char_indices().enumerate() {
+        if is_token_char(c) {
+            current.get_or_insert((chars, byte));
+        } else if let Some((cs, bs)) = current.take() {
+            tokens.push((cs, bs, byte));
+        }
+    }
+    if let Some((cs, bs)) = current {
+        tokens.push((cs, bs, text.len()));
+    }
+
+    let mut after_label = false;
+    for &(cs, bs, be) in &tokens {
+        let token = &text[bs..be];
+        let len = token.chars().count();
+        if after_label && len >= 4 {
+            found.push(SecretMatch { kind: "password", start: cs, end: cs + len });
+            after_label = false;
+            continue;
+        }
+        let lower = token.to_lowercase();
+        after_label = PASSWORD_LABELS.iter().any(|l| lower == *l)
+            && text[be..].trim_start().starts_with([':', '=']);
+        if len < SECRET_MIN_LEN {
+            continue;
+        }
+        if let Some((_, kind)) = SECRET_PREFIXES.iter().find(|(p, _)| token.starts_with(p)) {
+            found.push(SecretMatch { kind, start: cs, end: cs + len });
+        }
+    }
+    found
+}
+
+pub(crate) fn contains_secret(text: &str) -> bool {
+    !find_secrets(text).is_empty()
+}
+
+/// The text with each secret, detected or known, replaced by `REDACTED`.
+pub(crate) fn redact_text(text: &str) -> String {
+    let text = &redact_known_secrets(text);
+    let secrets = find_secrets(text);
+    let mut out = String::with_capacity(text.len());
+    for (i, c) in text.chars().enumerate() {
+        match secrets.iter().find(|s| (s.start..s.end).contains(&i)) {
+            Some(s) if s.start == i => out.push_str(REDACTED),
+            Some(_) => {}
+            None => out.push(c),
+        }
+    }
+    out
+}
+
+// ─── Known secrets ────────────────────────────────────────────────────────────
+
+/// Values the user stored as secrets, e.g. agent environment variables.
+/// Redacted wherever they appear, whatever they look like.
+pub(crate) static KNOWN_SECRETS: std::sync::LazyLock<std::sync::RwLock<std::collections::BTreeSet<String>>> =
+    std::sync::LazyLock::new(Default::default);
+/// Shorter values would redact ordinary words
+pub(crate) const KNOWN_SECRET_MIN_LEN: usize = 6;
+
+pub(crate) fn learn_secrets<'a>(values: impl IntoIterator<Item = &'a String>) {
+    let mut known = KNOWN_SECRETS.write().unwrap();
+    known.extend(values.into_iter().filter(|v| v.chars().count() >= KNOWN_SECRET_MIN_LEN).cloned());
+}
+
+pub(crate) fn redact_known_secrets(text: &str) -> String {
+    let known = KNOWN_SECRETS.read().unwrap();
+    // Longest first, so a value containing another is replaced whole
+    let mut values: Vec<&String> = known.iter().filter(|v| text.contains(v.as_str())).collect();
+    values.sort_by_key(|v| std::cmp::Reverse(v.len()));
+    values.into_iter().fold(text.to_string(), |t, v| t.replace(v.as_str(), REDACTED))
+}
@@ -0,0 +1,210 @@
+//! End-to-end check of the call path.
+
+use crate::*;
+
+// ─── Self-test ────────────────────────────────────────────────────────────────
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SelfTestStep {
+    pub(crate) name: String,
+    /// "pass", "fail" or "skipped"
+    pub(crate) status: String,
+    pub(crate) duration_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) detail: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SelfTestReport {
+    pub(crate) ok: bool,
+    pub(crate) steps: Vec<SelfTestStep>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) first_failure: Option<String>,
+}
+
+pub(crate) const SELF_TEST_BUDGET_MS: u64 = 15_000;
+// Never recorded to history, so usage from the self-test stays out of normal stats
+pub(crate) const SELF_TEST_AGENT: &str = "__selftest";
+/// Reserved for the routing step's throwaway agents in ~/.openclaw
+pub(crate) const SELF_TEST_AGENT_PREFIX: &str = "__selftest-";
+
+/// Throwaway agents of the routing step. Removed on drop, so they go even when
+/// the step runs out of budget and its future is dropped mid-call.
+pub(crate) struct SelfTestAgents(pub(crate) Vec<&'static str>);
+
+impl Drop for SelfTestAgents {
+    fn drop(&mut self) {
+        let ids = std::mem::take(&mut self.0);
+        tauri::async_runtime::spawn_blocking(move || {
+            for id in ids {
+                fs::remove_dir_all(openclaw_agents_root().join(id)).ok();
+            }
+        });
+    }
+}
+
+/// Run at startup for agents left behind by a self-test the app never finished.
+pub(crate) fn remove_stale_self_test_agents() {
+    let Ok(dirs) = fs::read_dir(openclaw_agents_root()) else { return };
+    for dir in dirs.flatten() {
+        if dir.file_name().to_string_lossy().starts_with(SELF_TEST_AGENT_PREFIX) {
+            fs::remove_dir_all(dir.path()).ok();
+        }
+    }
+}
+
+pub(crate) fn diagnostics_dir() -> PathBuf {
+    let p = clapp_dir().join("diagnostics");
+    fs::create_dir_all(&p).ok();
+    p
+}
+
+pub(crate) async fn self_test_step(app: &tauri::AppHandle, step: &str, token: &mut String) -> Result<(), String> {
+    match step {
+        "config" => {
+            run_storage_io(app, || -> Result<(), String> {
+                let p = config_path();
+                if p.exists() {
+                    serde_json::from_str::<serde_json::Value>(&fs::read_to_string(p).map_err(|e| e.to_string())?)
+                        .map_err(|e| format!("config.json: {}", e))?;
+                }
+                let raw = fs::read_to_string(openclaw_config_path()).map_err(|e| format!("openclaw.json: {}", e))?;
+                serde_json::from_str::<serde_json::Value>(&raw).map_err(|e| format!("openclaw.json: {}", e))?;
+                Ok(())
+            }).await.map_err(|e| e.to_string())?
+        }
+        "token" => {
+            *token = run_storage_io(app, read_gateway_token).await.map_err(|e| e.to_string())??;
+            Ok(())
+        }
+        "health" => match gateway_status(app.clone()).await?.state.as_str() {
+            "running" => Ok(()),
+            other => Err(format!("gateway is {}", other)),
+        },
+        "pairing" => do_pairing(app, token.as_str()).await.map_err(|e| e.to_string()),
+        "call" => {
+            let session = format!("clapp-selftest-{}", now_ms());
+            let raw = call_gateway_agent(app, "main", "Reply with the single word OK.", &session, Some(&session), None, None)
+                .await
+                .map_err(|e| e.to_string())?;
+            let v: serde_json::Value = serde_json::from_str(&raw).map_err(|_| format!("non-JSON reply: {}", raw))?;
+            match v.get("error").filter(|e| !e.is_null()) {
+                Some(e) => Err(format!("gateway error: {}", e)),
+                None => Ok(()),
+            }
+        }
+        "routing" => {
+            // Two throwaway agents that can only be told apart by their instructions
+            // Ids under SELF_TEST_AGENT_PREFIX, swept at startup if the app quit mid-step
+            let agents = [("__selftest-alpha", "ALPHA"), ("__selftest-bravo", "BRAVO")];
+            let _cleanup = SelfTestAgents(agents.iter().map(|(id, _)| *id).collect());
+            run_storage_io(app, move || -> Result<(), String> {
+                let main_auth = agent_dir("main").join("auth-profiles.json");
+                for (id, word) in agents {
+                    fs::remove_dir_all(openclaw_agents_root().join(id)).ok();
+                    fs::create_dir_all(agent_dir(id)).map_err(|e| e.to_string())?;
+                    fs::copy(&main_auth, agent_dir(id).join("auth-profiles.json"))
+                        .map_err(|e| format!("copying main's auth profile: {}", e))?;
+                    write_agent_config(id, id, &format!("Whatever you are asked, reply with only the word {}.", word))?;
+                }
+                Ok(())
+            }).await.map_err(|e| e.to_string())??;
+            // Both at once, so the step costs one round trip of the budget rather than two
+            let checks = agents.map(|(id, word)| async move {
+                let session = format!("clapp-selftest-{}-{}", now_ms(), next_seq());
+                let raw = call_gateway_agent(app, id, "Say your word.", &session, Some(&session), None, None)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                let reply = reply_text(&raw).to_uppercase();
+                let other = agents.iter().find(|(other, _)| *other != id).map(|(_, w)| *w).unwrap_or_default();
+                if !reply.contains(word) || reply.contains(other) {
+                    return Err(format!("{} answered \"{}\"; calls are not reaching the intended agent", id, reply.trim()));
+                }
+                Ok(())
+            });
+            futures::future::try_join_all(checks).await.map(|_| ())
+        }
+        "history" => run_storage_io(app, || -> Result<(), String> {
+            let record = HistoryRecord {
+                id: format!("selftest-{}", now_ms()),
+                session_key: "selftest".into(),
+                role: "system".into(),
+                text: "self-test".into(),
+                ts: now_ms(),
+                ..Default::default()
+            };
+            append_history(SELF_TEST_AGENT, std::slice::from_ref(&record))?;
+            let found = read_history(SELF_TEST_AGENT).iter().any(|r| r.id == record.id);
+            fs::remove_file(history_path(SELF_TEST_AGENT)).ok();
+            if found { Ok(()) } else { Err("record not found after write".into()) }
+        }).await.map_err(|e| e.to_string())?,
+        _ => unreachable!(),
+    }
+}
+
+/// End-to-end check of the call path, one step at a time. Steps after the first failure are skipped.
+#[tauri::command]
+pub(crate) async fn run_self_test(app: tauri::AppHandle) -> Result<SelfTestReport, AppError> {
+    ensure_writable()?;
+    let started = std::time::Instant::now();
+    let mut steps = Vec::new();
+    let mut first_failure = None;
+    let mut token = String::new();
+
+    // Mirrored agents all answer as "main", so there is nothing to tell apart
+    let mirrored = load_config().mirror_to_main;
+
+    for name in ["config", "token", "health", "pairing", "call", "routing", "history"] {
+        if first_failure.is_some() || (name == "routing" && mirrored) {
+            let detail = first_failure.is_none().then(|| "mirroring to main is on".to_string());
+            steps.push(SelfTestStep { name: name.into(), status: "skipped".into(), duration_ms: 0, detail });
+            continue;
+        }
+        let remaining = SELF_TEST_BUDGET_MS.saturating_sub(started.elapsed().as_millis() as u64);
+        let t0 = std::time::Instant::now();
+        let result = tokio::time::timeout(
+            std::time::Duration::from_millis(remaining),
+            self_test_step(&app, name, &mut token),
+        )
+        .await
+        .unwrap_or_else(|_| Err("self-test time budget exhausted".into()));
+
+        let detail = result.err();
+        if let Some(d) = &detail {
+            first_failure = Some(format!("{}: {}", name, d));
+        }
+        steps.push(SelfTestStep {
+            name: name.into(),
+            status: if detail.is_none() { "pass" } else { "fail" }.into(),
+            duration_ms: t0.elapsed().as_millis() as u64,
+            detail,
+        });
+    }
+
+    let report = SelfTestReport { ok: first_failure.is_none(), steps, first_failure };
+    if !report.ok {
+        // Picked up by the diagnostics bundle
+        let json = serde_json::to_string_pretty(&report)?;
+        run_storage_io(&app, move || fs::write(diagnostics_dir().join("self-test.json"), json)).await??;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn agents_left_by_a_crashed_self_test_are_swept() {
+        let root = openclaw_agents_root();
+        for id in ["__selftest-sweep", "selftest-sweep-keeper"] {
+            fs::create_dir_all(root.join(id).join("agent")).unwrap();
+        }
+        remove_stale_self_test_agents();
+        assert!(!root.join("__selftest-sweep").exists());
+        assert!(root.join("selftest-sweep-keeper").exists());
+        fs::remove_dir_all(root.join("selftest-sweep-keeper")).ok();
+    }
+}
@@ -0,0 +1,111 @@
+//! Ordered teardown when the app quits.
+
+use crate::*;
+
+// ─── Shutdown ─────────────────────────────────────────────────────────────────
+
+pub(crate) const SHUTDOWN_STOP_GATEWAY_MS: u64 = 5_000;
+pub(crate) const SHUTDOWN_PHASE_MS: u64 = 2_000;
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ShutdownProgress {
+    pub(crate) phase: String,
+    pub(crate) label: String,
+    /// "running", "done" or "skipped"
+    pub(crate) status: String,
+}
+
+pub(crate) fn is_shutting_down(app: &tauri::AppHandle) -> bool {
+    app.state::<AppState>().shutting_down.load(std::sync::atomic::Ordering::Relaxed)
+}
+
+/// Runs one phase within its time limit. A phase that fails or overruns is
+/// logged and skipped so the rest of the shutdown still happens.
+pub(crate) async fn shutdown_phase<F>(app: &tauri::AppHandle, phase: &str, label: &str, limit_ms: u64, work: F)
+where
+    F: std::future::Future<Output = Result<(), String>>,
+{
+    let progress = |status: &str| ShutdownProgress { phase: phase.into(), label: label.into(), status: status.into() };
+    app.emit("shutdown-progress", progress("running")).ok();
+    let status = match tokio::time::timeout(std::time::Duration::from_millis(limit_ms), work).await {
+        Ok(Ok(())) => "done",
+        Ok(Err(e)) => {
+            eprintln!("[SHUTDOWN WARN] {}: {}", phase, e);
+            "skipped"
+        }
+        Err(_) => {
+            eprintln!("[SHUTDOWN WARN] {}: gave up after {}ms", phase, limit_ms);
+            "skipped"
+        }
+    };
+    app.emit("shutdown-progress", progress(status)).ok();
+}
+
+/// Tears the app down in order. Safe to call more than once: later calls wait
+/// for the first to finish and then return.
+pub(crate) async fn run_shutdown(app: &tauri::AppHandle) {
+    use std::sync::atomic::Ordering;
+    let app_state = app.state::<AppState>();
+    let mut done = app_state.shutdown_lock.lock().await;
+    if *done {
+        return;
+    }
+    // New calls, queued calls and background loops all check this
+    app_state.shutting_down.store(true, Ordering::Relaxed);
+
+    shutdown_phase(app, "services", "Stopping background services…", SHUTDOWN_PHASE_MS, async {
+        stop_http_api(app);
+        Ok(())
+    }).await;
+
+    shutdown_phase(app, "work", "Stopping running calls…", SHUTDOWN_PHASE_MS, async {
+        // Each call, job and operation cleans up after itself on its way out
+        ROOT_CANCEL.cancel();
+        while app.state::<AppState>().calls_in_flight.total() > 0 {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        Ok(())
+    }).await;
+
+    // After the calls have stopped: a deferred call cancelled mid-run is still in
+    // the queue and is saved with it, to run again on the next start
+    shutdown_phase(app, "queue", "Saving queued calls…", SHUTDOWN_PHASE_MS, async {
+        let calls = app.state::<AppState>().pending_calls.lock().unwrap().clone();
+        tauri::async_runtime::spawn_blocking(move || persist_pending_calls(&calls))
+            .await
+            .map_err(|e| e.to_string())?
+    }).await;
+
+    shutdown_phase(app, "gateway", "Stopping agent…", SHUTDOWN_STOP_GATEWAY_MS, async {
+        stop_gateway_graceful(app, Some(SHUTDOWN_STOP_GATEWAY_MS - 500), false).await.map_err(|e| e.to_string())
+    }).await;
+
+    shutdown_phase(app, "logs", "Writing logs…", SHUTDOWN_PHASE_MS, async {
+        // The output writer exits once the stopped gateway's queue is drained
+        while app.state::<AppState>().gateway_log_writers.load(Ordering::Relaxed) > 0 {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        use std::io::Write;
+        std::io::stdout().flush().ok();
+        std::io::stderr().flush().ok();
+        Ok(())
+    }).await;
+
+    shutdown_phase(app, "state", "Saving state…", SHUTDOWN_PHASE_MS, async {
+        tauri::async_runtime::spawn_blocking(|| {
+            flush_pending_writes().ok();
+            fs::remove_file(startup_sentinel_path()).ok();
+            mark_clean_shutdown();
+        }).await.map_err(|e| e.to_string())
+    }).await;
+
+    *done = true;
+}
+
+/// For the tray: quits through the same ordered shutdown as closing the window.
+#[tauri::command]
+pub(crate) async fn quit_app(app: tauri::AppHandle) {
+    run_shutdown(&app).await;
+    app.exit(0);
+}
@@ -0,0 +1,299 @@
+//! In-memory state shared by commands.
+
+use crate::*;
+
+/// Everything the app keeps in memory between commands, managed as one piece of Tauri state.
+pub(crate) struct AppState {
+    /// The gateway process we launched, if any
+    pub(crate) process: Mutex<Option<tauri_plugin_shell::process::CommandChild>>,
+    /// Serializes gateway launches so concurrent starts don't spawn twice
+    pub(crate) launch_lock: tokio::sync::Mutex<()>,
+    /// PID of the last gateway process that was seen terminating
+    pub(crate) gateway_exit: Mutex<Option<u32>>,
+    /// The gateway we launched stopped answering health checks; announced once per episode
+    pub(crate) gateway_unhealthy: std::sync::atomic::AtomicBool,
+    /// Why the app was started in safe mode, `None` for a normal start.
+    /// Auto-start, schedules, watchers and tray actions must check this before running.
+    pub(crate) safe_mode: Option<String>,
+    pub(crate) pending_calls: Mutex<Vec<PendingCall>>,
+    /// Ids of queued calls a drain is running; they stay in `pending_calls` until done.
+    /// Locked after `pending_calls` when both are needed.
+    pub(crate) deferred_running: Mutex<std::collections::HashSet<String>>,
+    pub(crate) prompt_cache: PromptCache,
+    pub(crate) response_cache: ResponseCache,
+    pub(crate) workspace_diffs: WorkspaceDiffs,
+    pub(crate) power: PowerState,
+    /// Whether ~/.openclaw was reachable on the last probe. A network share that
+    /// drops makes fs calls block for tens of seconds, so once it is known to be
+    /// down commands fail fast until the monitor sees it come back.
+    pub(crate) storage_ok: std::sync::atomic::AtomicBool,
+    pub(crate) last_crash: Mutex<Option<CrashReport>>,
+    pub(crate) env_info: Mutex<EnvironmentInfo>,
+    /// Duration of the last warm-up call, in ms
+    pub(crate) cold_start_ms: Mutex<Option<u64>>,
+    /// Learned from the first routed call: does the gateway accept `agentId`?
+    pub(crate) agent_routing: Mutex<Option<bool>>,
+    /// Learned from `gateway call --help`: can params come from a file?
+    pub(crate) params_file_support: Mutex<Option<bool>>,
+    pub(crate) error_log: Mutex<std::collections::VecDeque<ErrorLogEntry>>,
+    /// Stage timings of the last `CALL_TIMINGS_CAPACITY` calls
+    pub(crate) call_timings: Mutex<std::collections::VecDeque<CallTiming>>,
+    pub(crate) dropped_log_lines: std::sync::atomic::AtomicU64,
+    /// Container started by the docker gateway mode
+    pub(crate) docker_container: Mutex<Option<String>>,
+    pub(crate) http_api: Mutex<Option<std::sync::Arc<tiny_http::Server>>>,
+    /// When recent HTTP API calls were accepted, for rate limiting
+    pub(crate) http_api_calls: Mutex<Vec<u64>>,
+    /// Agents distilled from a session, waiting to be applied or cancelled
+    pub(crate) agent_drafts: Mutex<HashMap<String, agents::distill::AgentDraft>>,
+    /// Set when shutdown starts; refuses new calls and ends background loops
+    pub(crate) shutting_down: std::sync::atomic::AtomicBool,
+    /// Held for the whole shutdown; true once it has finished
+    pub(crate) shutdown_lock: tokio::sync::Mutex<bool>,
+    /// Gateway output writer tasks that still have lines to write
+    pub(crate) gateway_log_writers: std::sync::atomic::AtomicUsize,
+    /// Gateway calls currently running
+    pub(crate) calls_in_flight: CallsInFlight,
+    /// Credential expiry and state already warned about, by agent
+    pub(crate) auth_warned: Mutex<HashMap<String, (u64, AuthState)>>,
+    /// Fixture name the next real gateway call is recorded as
+    pub(crate) fixture_recording: Mutex<Option<String>>,
+    /// Recent stream events for a frontend that reloads
+    pub(crate) stream_tails: StreamTails,
+    /// Stream events collected in reduced-events mode, by event name
+    pub(crate) event_batches: Mutex<HashMap<&'static str, Vec<serde_json::Value>>>,
+    /// Last unmanaged gateway listener compared with us, and the result
+    pub(crate) identity_check: Mutex<Option<(Listener, Option<UserMismatch>)>>,
+    /// Who Clapp runs as, read once
+    pub(crate) own_identity: tokio::sync::OnceCell<ProcessIdentity>,
+    pub(crate) http_client: HttpClient,
+    /// Live `emit_gateway_logs`, so running writer tasks see a change immediately
+    pub(crate) gateway_log_switch: tokio::sync::watch::Sender<bool>,
+    /// The gateway's latest stderr line, kept even when the line itself is dropped
+    pub(crate) last_gateway_error: Mutex<Option<String>>,
+    /// When each session+message hash was last sent, for `enable_request_deduplication`
+    pub(crate) recent_call_hashes: Mutex<HashMap<String, u64>>,
+}
+
+impl AppState {
+    pub(crate) fn new(safe_mode: Option<String>) -> Self {
+        let config = load_config();
+        Self {
+            process: Mutex::new(None),
+            launch_lock: tokio::sync::Mutex::new(()),
+            gateway_exit: Mutex::new(None),
+            gateway_unhealthy: std::sync::atomic::AtomicBool::new(false),
+            env_info: Mutex::new(initial_environment_info(safe_mode.clone())),
+            safe_mode,
+            pending_calls: Mutex::new(load_pending_calls()),
+            deferred_running: Mutex::new(std::collections::HashSet::new()),
+            prompt_cache: PromptCache::default(),
+            response_cache: ResponseCache::default(),
+            workspace_diffs: WorkspaceDiffs::default(),
+            power: PowerState::default(),
+            storage_ok: std::sync::atomic::AtomicBool::new(true),
+            last_crash: Mutex::new(None),
+            cold_start_ms: Mutex::new(None),
+            agent_routing: Mutex::new(None),
+            params_file_support: Mutex::new(None),
+            error_log: Mutex::new(std::collections::VecDeque::with_capacity(ERROR_LOG_CAPACITY)),
+            call_timings: Mutex::new(std::collections::VecDeque::with_capacity(CALL_TIMINGS_CAPACITY)),
+            dropped_log_lines: std::sync::atomic::AtomicU64::new(0),
+            docker_container: Mutex::new(None),
+            http_api: Mutex::new(None),
+            http_api_calls: Mutex::new(Vec::new()),
+            agent_drafts: Mutex::new(HashMap::new()),
+            shutting_down: std::sync::atomic::AtomicBool::new(false),
+            shutdown_lock: tokio::sync::Mutex::new(false),
+            gateway_log_writers: std::sync::atomic::AtomicUsize::new(0),
+            calls_in_flight: CallsInFlight::default(),
+            auth_warned: Mutex::new(HashMap::new()),
+            fixture_recording: Mutex::new(None),
+            stream_tails: StreamTails::default(),
+            event_batches: Mutex::new(HashMap::new()),
+            identity_check: Mutex::new(None),
+            own_identity: tokio::sync::OnceCell::new(),
+            http_client: HttpClient::new(config.http_client_timeout_ms),
+            gateway_log_switch: tokio::sync::watch::Sender::new(config.emit_gateway_logs),
+            last_gateway_error: Mutex::new(None),
+            recent_call_hashes: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+/// One HTTP client for every outgoing request, so connections are pooled.
+#[derive(Clone)]
+pub(crate) struct HttpClient(pub(crate) reqwest::Client);
+
+impl HttpClient {
+    pub(crate) fn new(timeout_ms: u64) -> Self {
+        let timeout = std::time::Duration::from_millis(timeout_ms);
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .or_else(|e| {
+                // Usually the system proxy settings; keep the timeout either way
+                // so a hung endpoint can't stall a caller forever.
+                eprintln!("[HTTP ERR] client setup failed, retrying without proxy: {}", e);
+                reqwest::Client::builder().timeout(timeout).no_proxy().build()
+            })
+            .expect("HTTP client without proxy");
+        Self(client)
+    }
+
+    /// Sends the request and returns status and body. In fixture mode the
+    /// answer comes from the fixtures and nothing goes out.
+    pub(crate) async fn send(&self, request: reqwest::RequestBuilder) -> Result<(u16, String), String> {
+        let request = request.build().map_err(|e| e.to_string())?;
+        if fixture_mode() {
+            return play_http_fixture(request.method().as_str(), request.url().as_str()).await;
+        }
+        let response = self.0.execute(request).await.map_err(|e| e.to_string())?;
+        let status = response.status().as_u16();
+        Ok((status, response.text().await.map_err(|e| e.to_string())?))
+    }
+}
+
+// ─── Write-behind persistence ─────────────────────────────────────────────────
+//
+// Small state files that change on every call (the prompt cache) are kept here
+// and written at most every `state_flush_secs`, the latest content per file
+// only. A crash loses up to that much of them, which is fine for caches and
+// counters. Anything that must survive a crash writes directly instead: the
+// audit log, the call queue, the heartbeat and user edits.
+
+/// Content waiting to be written, by path. Process-wide because the writers
+/// run inside blocking storage jobs.
+pub(crate) static PENDING_WRITES: std::sync::LazyLock<Mutex<HashMap<PathBuf, String>>> =
+    std::sync::LazyLock::new(|| Mutex::new(HashMap::new()));
+/// Held for a whole flush and by `discard_behind`. Without it, a flush that
+/// drained older content could write it after a newer flush, or bring back a
+/// file that was discarded while it ran.
+pub(crate) static FLUSH_LOCK: Mutex<()> = Mutex::new(());
+/// Files actually written by flushes, for diagnostics
+pub(crate) static STATE_WRITES: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+/// Queues `content` for `path`, replacing whatever was queued before.
+pub(crate) fn write_behind(path: PathBuf, content: String) {
+    PENDING_WRITES.lock().unwrap().insert(path, content);
+}
+
+/// Queued content if there is any, else the file on disk.
+pub(crate) fn read_behind(path: &std::path::Path) -> Option<String> {
+    if let Some(content) = PENDING_WRITES.lock().unwrap().get(path) {
+        return Some(content.clone());
+    }
+    fs::read_to_string(path).ok()
+}
+
+/// Drops anything queued for `path` and removes the file.
+pub(crate) fn discard_behind(path: &std::path::Path) -> std::io::Result<()> {
+    let _flush = FLUSH_LOCK.lock().unwrap();
+    PENDING_WRITES.lock().unwrap().remove(path);
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Writes every queued file through a temp file and rename, so a crash mid-flush
+/// leaves the old content rather than half a file. Failed files stay queued.
+pub(crate) fn flush_pending_writes() -> Result<usize, AppError> {
+    let _flush = FLUSH_LOCK.lock().unwrap();
+    let pending: Vec<(PathBuf, String)> = PENDING_WRITES.lock().unwrap().drain().collect();
+    let mut written = 0;
+    let mut first_error = None;
+    for (path, content) in pending {
+        let tmp = path.with_extension("tmp");
+        let result = path.parent().map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&tmp, &content))
+            .and_then(|_| fs::rename(&tmp, &path));
+        match result {
+            Ok(()) => written += 1,
+            Err(e) => {
+                eprintln!("[STATE ERR] {}: {}", path.display(), e);
+                // Keep it unless something newer was queued meanwhile
+                PENDING_WRITES.lock().unwrap().entry(path).or_insert(content);
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    STATE_WRITES.fetch_add(written as u64, std::sync::atomic::Ordering::Relaxed);
+    match first_error {
+        Some(e) => Err(e.into()),
+        None => Ok(written),
+    }
+}
+
+pub(crate) fn spawn_state_flusher(app: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        while !is_shutting_down(&app) {
+            let secs = load_config().state_flush_secs.max(1);
+            tokio::time::sleep(std::time::Duration::from_secs(secs)).await;
+            if !PENDING_WRITES.lock().unwrap().is_empty() {
+                run_storage_io(&app, flush_pending_writes).await.ok();
+            }
+        }
+    });
+}
+
+/// Writes all queued state now. Returns how many files were written.
+#[tauri::command]
+pub(crate) async fn flush_state_now(app: tauri::AppHandle) -> Result<usize, AppError> {
+    run_storage_io(&app, flush_pending_writes).await?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_burst_of_updates_is_one_write_of_the_last_content() {
+        let path = clapp_dir().join("burst-index.json");
+        for i in 0..1000 {
+            write_behind(path.clone(), format!("{{\"bumps\":{}}}", i));
+        }
+        assert_eq!(read_behind(&path).as_deref(), Some("{\"bumps\":999}"));
+        let writes = flush_pending_writes().unwrap();
+        assert!((1..=3).contains(&writes), "{} writes", writes);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{\"bumps\":999}");
+        assert!(!PENDING_WRITES.lock().unwrap().contains_key(&path));
+    }
+
+    #[test]
+    fn concurrent_flushes_never_write_stale_content() {
+        let path = clapp_dir().join("racing-index.json");
+        let writer = {
+            let path = path.clone();
+            std::thread::spawn(move || {
+                for i in 0..1000 {
+                    write_behind(path.clone(), i.to_string());
+                    if i % 50 == 0 {
+                        flush_pending_writes().unwrap();
+                    }
+                }
+            })
+        };
+        let flusher = std::thread::spawn(|| {
+            for _ in 0..200 {
+                flush_pending_writes().unwrap();
+            }
+        });
+        writer.join().unwrap();
+        flusher.join().unwrap();
+        flush_pending_writes().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "999");
+    }
+
+    #[test]
+    fn discarded_files_stay_gone() {
+        let path = clapp_dir().join("discarded-index.json");
+        write_behind(path.clone(), "1".into());
+        flush_pending_writes().unwrap();
+        write_behind(path.clone(), "2".into());
+        discard_behind(&path).unwrap();
+        flush_pending_writes().unwrap();
+        assert!(!path.exists());
+        assert_eq!(read_behind(&path), None);
+    }
+}
@@ -0,0 +1,260 @@
+//! Reachability of ~/.openclaw and the deadline wrapper for its IO.
+
+use crate::*;
+
+// ─── Storage health ───────────────────────────────────────────────────────────
+
+pub(crate) const STORAGE_CHECK_SECS: u64 = 30;
+
+pub(crate) fn is_unreachable_volume(e: &std::io::Error) -> bool {
+    match e.raw_os_error() {
+        // ERROR_BAD_NETPATH, ERROR_NETNAME_DELETED, ERROR_BAD_NET_NAME, ERROR_NETWORK_UNREACHABLE
+        #[cfg(windows)]
+        Some(53) | Some(64) | Some(67) | Some(1231) => true,
+        // ENOTCONN, EHOSTDOWN, EHOSTUNREACH, ESTALE
+        #[cfg(unix)]
+        Some(107) | Some(112) | Some(113) | Some(116) => true,
+        _ => false,
+    }
+}
+
+/// Sync IO on a runtime worker taking longer than this fails a debug assertion.
+pub(crate) const SLOW_SYNC_IO_MS: u128 = 50;
+
+thread_local! {
+    static IN_STORAGE_IO: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+/// Debug-build check for blocking file IO that stalls the async runtime. Held
+/// across one file operation; on drop it asserts the operation was not slow on
+/// a runtime worker outside `run_storage_io`. Sync commands run on the main
+/// thread and are not flagged.
+pub(crate) struct SyncIoWatch {
+    #[cfg(debug_assertions)]
+    what: String,
+    #[cfg(debug_assertions)]
+    started: std::time::Instant,
+}
+
+impl SyncIoWatch {
+    pub(crate) fn start(_what: impl std::fmt::Display) -> Self {
+        Self {
+            #[cfg(debug_assertions)]
+            what: _what.to_string(),
+            #[cfg(debug_assertions)]
+            started: std::time::Instant::now(),
+        }
+    }
+}
+
+#[cfg(debug_assertions)]
+impl Drop for SyncIoWatch {
+    fn drop(&mut self) {
+        let elapsed = self.started.elapsed().as_millis();
+        let on_runtime = tokio::runtime::Handle::try_current().is_ok() && !IN_STORAGE_IO.with(|c| c.get());
+        debug_assert!(
+            !(on_runtime && elapsed > SLOW_SYNC_IO_MS),
+            "{} blocked the async runtime for {}ms; move it into run_storage_io",
+            self.what,
+            elapsed,
+        );
+    }
+}
+
+pub(crate) fn storage_deadline() -> std::time::Duration {
+    std::time::Duration::from_millis(load_config().storage_timeout_ms)
+}
+
+fn mark_storage_io<T>(f: impl FnOnce() -> T) -> T {
+    IN_STORAGE_IO.with(|c| c.set(true));
+    let out = f();
+    IN_STORAGE_IO.with(|c| c.set(false));
+    out
+}
+
+fn ensure_storage_ok(app: &tauri::AppHandle) -> Result<(), AppError> {
+    use std::sync::atomic::Ordering;
+    if app.state::<AppState>().storage_ok.load(Ordering::Relaxed) {
+        Ok(())
+    } else {
+        Err(AppError::StorageUnavailable(openclaw_dir().display().to_string()))
+    }
+}
+
+/// Runs small blocking filesystem work for openclaw paths off the async runtime
+/// within the configured storage deadline.
+pub(crate) async fn run_storage_io<T, F>(app: &tauri::AppHandle, f: F) -> Result<T, AppError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    run_storage_io_within(app, storage_deadline(), f).await
+}
+
+/// `run_storage_io` with its own deadline. A job that runs out of time is not
+/// by itself a sign of a dead volume: storage is only marked unhealthy when a
+/// probe of ~/.openclaw times out as well. The work itself can't be stopped
+/// and finishes in the background.
+pub(crate) async fn run_storage_io_within<T, F>(app: &tauri::AppHandle, deadline: std::time::Duration, f: F) -> Result<T, AppError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    use std::sync::atomic::Ordering;
+    ensure_storage_ok(app)?;
+    match tokio::time::timeout(deadline, tauri::async_runtime::spawn_blocking(move || mark_storage_io(f))).await {
+        Ok(Ok(v)) => Ok(v),
+        Ok(Err(e)) => Err(AppError::Other(e.to_string())),
+        Err(_) if probe_storage().await => {
+            Err(AppError::Timeout(format!("storage work took longer than {}ms", deadline.as_millis())))
+        }
+        Err(_) => {
+            app.state::<AppState>().storage_ok.store(false, Ordering::Relaxed);
+            app.emit("storage-health", false).ok();
+            Err(AppError::StorageUnavailable(openclaw_dir().display().to_string()))
+        }
+    }
+}
+
+/// Blocking work of unbounded size (trash, checkpoint copies, imports) run
+/// without a deadline. The job gets the operation to check at item boundaries,
+/// so cancelling it stops the writes instead of leaving them running unseen.
+pub(crate) async fn run_storage_job<T, F>(app: &tauri::AppHandle, op: &Operation, f: F) -> Result<T, AppError>
+where
+    T: Send + 'static,
+    F: FnOnce(&Operation) -> Result<T, AppError> + Send + 'static,
+{
+    ensure_storage_ok(app)?;
+    let job = op.clone();
+    tauri::async_runtime::spawn_blocking(move || mark_storage_io(|| f(&job)))
+        .await
+        .map_err(|e| AppError::Other(e.to_string()))?
+}
+
+/// `run_storage_job` under a fresh operation of `kind`, finished with its result.
+pub(crate) async fn run_storage_operation<T, F>(app: &tauri::AppHandle, kind: &str, f: F) -> Result<T, AppError>
+where
+    T: Send + 'static,
+    F: FnOnce(&Operation) -> Result<T, AppError> + Send + 'static,
+{
+    let op = Operation::start(app, kind, None);
+    let result = run_storage_job(app, &op, f).await;
+    op.finish(&result);
+    result
+}
+
+pub(crate) async fn probe_storage() -> bool {
+    let dir = openclaw_dir();
+    let deadline = storage_deadline();
+    let probe = tauri::async_runtime::spawn_blocking(move || fs::metadata(dir));
+    match tokio::time::timeout(deadline, probe).await {
+        // A missing directory is fine, it is created on first write
+        Ok(Ok(Err(e))) => !is_unreachable_volume(&e),
+        Ok(Ok(Ok(_))) => true,
+        Ok(Err(_)) => true,
+        Err(_) => false,
+    }
+}
+
+pub(crate) fn spawn_storage_monitor(app: tauri::AppHandle) {
+    use std::sync::atomic::Ordering;
+    tauri::async_runtime::spawn(async move {
+        while !is_shutting_down(&app) {
+            tokio::time::sleep(std::time::Duration::from_secs(STORAGE_CHECK_SECS)).await;
+            let ok = probe_storage().await;
+            if app.state::<AppState>().storage_ok.swap(ok, Ordering::Relaxed) != ok {
+                app.emit("storage-health", ok).ok();
+            }
+            if ok {
+                auto_prune_history(&app).await;
+            }
+        }
+    });
+}
+
+// ─── Private files ────────────────────────────────────────────────────────────
+
+/// Writes a file only the current user can read, through a temp file and
+/// rename so a crash leaves the old content. The temp file is restricted
+/// before anything is written to it: mode 0600 on Unix, on Windows an ACL
+/// without inherited entries that grants the user alone.
+pub(crate) fn write_private(path: &std::path::Path, content: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("private.tmp");
+    fs::remove_file(&tmp).ok();
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&tmp)?;
+    let written = restrict_to_owner(&tmp)
+        .and_then(|_| file.write_all(content))
+        .and_then(|_| file.sync_all());
+    drop(file);
+    if let Err(e) = written.and_then(|_| fs::rename(&tmp, path)) {
+        fs::remove_file(&tmp).ok();
+        return Err(e);
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+pub(crate) fn restrict_to_owner(_path: &std::path::Path) -> std::io::Result<()> {
+    // Created 0600 already
+    Ok(())
+}
+
+#[cfg(windows)]
+pub(crate) fn restrict_to_owner(path: &std::path::Path) -> std::io::Result<()> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    let user = std::env::var("USERNAME").map_err(|_| std::io::Error::other("USERNAME is not set"))?;
+    let status = std::process::Command::new("icacls")
+        .arg(path)
+        .args(["/inheritance:r", "/grant:r"])
+        .arg(format!("{}:F", user))
+        .creation_flags(CREATE_NO_WINDOW)
+        .status()?;
+    if !status.success() {
+        return Err(std::io::Error::other(format!("icacls could not restrict {}", path.display())));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slow_io() {
+        std::thread::sleep(std::time::Duration::from_millis(SLOW_SYNC_IO_MS as u64 + 20));
+    }
+
+    #[test]
+    #[should_panic(expected = "blocked the async runtime")]
+    fn slow_io_on_a_runtime_worker_fails_the_watch() {
+        let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        rt.block_on(async {
+            let _watch = SyncIoWatch::start("test file");
+            slow_io();
+        });
+    }
+
+    #[test]
+    fn slow_io_inside_storage_io_or_off_the_runtime_passes() {
+        let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        rt.block_on(async {
+            mark_storage_io(|| {
+                let _watch = SyncIoWatch::start("test file");
+                slow_io();
+            });
+        });
+        let _watch = SyncIoWatch::start("test file");
+        slow_io();
+    }
+}
@@ -0,0 +1,215 @@
+//! Opt-in anonymized usage telemetry: counts and timings aggregated locally,
+//! sent in periodic batches the user can preview. Builds without the
+//! `telemetry` feature use telemetry_off.rs instead.
+
+use crate::*;
+
+// ─── Telemetry ────────────────────────────────────────────────────────────────
+
+pub(crate) const TELEMETRY_BATCH_SECS: u64 = 60 * 60;
+/// Batches kept while offline; older ones are dropped first
+pub(crate) const TELEMETRY_QUEUE_MAX: usize = 24;
+/// Timings kept per event between batches, for the percentiles
+pub(crate) const TELEMETRY_MAX_SAMPLES: usize = 1_000;
+
+/// Display prefixes of `AppError` and their codes. Anything else counts as
+/// "other", so error text itself never leaves the machine.
+pub(crate) const ERROR_CODES: &[(&str, &str)] = &[
+    ("I/O error", "io"),
+    ("Invalid input", "invalid_input"),
+    ("Invalid gateway params", "invalid_params"),
+    ("Not found", "not_found"),
+    ("Already exists", "already_exists"),
+    ("Timed out", "timeout"),
+    ("Expired", "expired"),
+    ("Unsupported format", "unsupported_format"),
+    ("Not supported by this OpenClaw gateway", "unsupported_by_gateway"),
+    ("Storage unavailable", "storage_unavailable"),
+    ("Conflict", "conflict"),
+    ("Partial response", "partial_response"),
+    ("Startup command failed", "startup_command_failed"),
+    ("Auth expired", "auth_expired"),
+    ("Auth invalid", "auth_invalid"),
+    ("Agent limit reached", "agent_limit_reached"),
+    ("Response is not JSON", "response_not_json"),
+    ("Duplicate request", "duplicate_request"),
+    ("Path rejected", "path_rejected"),
+    ("Main agent is locked", "main_agent_locked"),
+    ("Cancelled", "cancelled"),
+    ("Invalid API key", "invalid_api_key"),
+    ("Data format mismatch", "data_format_mismatch"),
+    ("Read-only", "read_only"),
+];
+
+/// What happened since the last batch. Keys are event names and error codes only.
+#[derive(Default)]
+pub(crate) struct TelemetryAggregate {
+    pub(crate) since: u64,
+    pub(crate) events: std::collections::BTreeMap<String, u64>,
+    pub(crate) errors: std::collections::BTreeMap<String, u64>,
+    pub(crate) durations: std::collections::BTreeMap<String, Vec<u64>>,
+}
+
+pub(crate) static TELEMETRY: std::sync::LazyLock<Mutex<TelemetryAggregate>> =
+    std::sync::LazyLock::new(|| Mutex::new(TelemetryAggregate { since: now_ms(), ..Default::default() }));
+
+pub(crate) fn telemetry_queue_path() -> PathBuf {
+    clapp_dir().join("telemetry-queue.json")
+}
+
+pub(crate) fn error_code(message: &str) -> &'static str {
+    ERROR_CODES.iter().find(|(prefix, _)| message.starts_with(prefix)).map_or("other", |(_, code)| code)
+}
+
+/// Counts one event when the user opted in. `durationMs` feeds the timing
+/// percentiles and `error` is reduced to its code; other props are ignored.
+pub(crate) fn report_usage_event(event: &str, props: HashMap<String, String>) {
+    if !load_config().telemetry {
+        return;
+    }
+    let mut agg = TELEMETRY.lock().unwrap();
+    *agg.events.entry(event.to_string()).or_default() += 1;
+    if props.get("ok").is_some_and(|ok| ok == "false") {
+        *agg.events.entry(format!("{}.failed", event)).or_default() += 1;
+    }
+    if let Some(error) = props.get("error") {
+        *agg.errors.entry(error_code(error).to_string()).or_default() += 1;
+    }
+    if let Some(ms) = props.get("durationMs").and_then(|d| d.parse().ok()) {
+        let samples = agg.durations.entry(event.to_string()).or_default();
+        if samples.len() < TELEMETRY_MAX_SAMPLES {
+            samples.push(ms);
+        }
+    }
+}
+
+pub(crate) fn percentile(sorted: &[u64], p: f64) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    sorted[((sorted.len() - 1) as f64 * p / 100.0).round() as usize]
+}
+
+/// The batch for everything aggregated so far, passed through the secret
+/// redaction even though it should hold nothing to redact.
+pub(crate) fn build_batch(agg: &TelemetryAggregate) -> serde_json::Value {
+    let timings: serde_json::Map<String, serde_json::Value> = agg.durations.iter()
+        .map(|(event, samples)| {
+            let mut sorted = samples.clone();
+            sorted.sort_unstable();
+            (event.clone(), serde_json::json!({
+                "count": sorted.len(),
+                "p50": percentile(&sorted, 50.0),
+                "p90": percentile(&sorted, 90.0),
+                "p99": percentile(&sorted, 99.0),
+            }))
+        })
+        .collect();
+    let batch = serde_json::json!({
+        "from": agg.since,
+        "to": now_ms(),
+        "appVersion": env!("CARGO_PKG_VERSION"),
+        "os": std::env::consts::OS,
+        "events": agg.events,
+        "errors": agg.errors,
+        "timingsMs": timings,
+    });
+    serde_json::from_str(&redact_text(&batch.to_string())).unwrap_or(serde_json::Value::Null)
+}
+
+pub(crate) fn load_telemetry_queue() -> Vec<serde_json::Value> {
+    fs::read_to_string(telemetry_queue_path()).ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+pub(crate) fn save_telemetry_queue(queue: &[serde_json::Value]) {
+    fs::write(telemetry_queue_path(), serde_json::to_string(queue).unwrap()).ok();
+}
+
+/// Moves the aggregate into the queue, unless nothing happened.
+pub(crate) fn enqueue_batch() {
+    let batch = {
+        let mut agg = TELEMETRY.lock().unwrap();
+        if agg.events.is_empty() && agg.errors.is_empty() {
+            return;
+        }
+        let batch = build_batch(&agg);
+        *agg = TelemetryAggregate { since: now_ms(), ..Default::default() };
+        batch
+    };
+    let mut queue = load_telemetry_queue();
+    queue.push(batch);
+    let excess = queue.len().saturating_sub(TELEMETRY_QUEUE_MAX);
+    queue.drain(..excess);
+    save_telemetry_queue(&queue);
+}
+
+/// Sends queued batches oldest first and stops at the first failure; the rest
+/// are retried on the next round.
+pub(crate) async fn send_telemetry_queue(app: &tauri::AppHandle) {
+    let config = load_config();
+    let Some(endpoint) = config.telemetry_endpoint.filter(|u| u.starts_with("https://")) else { return };
+    let queue = run_storage_io(app, load_telemetry_queue).await.unwrap_or_default();
+    let client = app.state::<AppState>().http_client.clone();
+    let mut sent = 0;
+    for batch in &queue {
+        match client.send(client.0.post(&endpoint).json(batch)).await {
+            Ok((200..=299, _)) => sent += 1,
+            Ok((status, _)) => {
+                eprintln!("[TELEMETRY ERR] {} answered {}", endpoint, status);
+                break;
+            }
+            Err(e) => {
+                eprintln!("[TELEMETRY ERR] {}", e);
+                break;
+            }
+        }
+    }
+    if sent > 0 {
+        // Re-read: consent may have been withdrawn while sending
+        run_storage_io(app, move || {
+            let mut queue = load_telemetry_queue();
+            queue.drain(..sent.min(queue.len()));
+            save_telemetry_queue(&queue);
+        }).await.ok();
+    }
+}
+
+pub(crate) fn spawn_telemetry_loop(app: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        while !is_shutting_down(&app) {
+            tokio::time::sleep(std::time::Duration::from_secs(TELEMETRY_BATCH_SECS)).await;
+            if !load_config().telemetry {
+                continue;
+            }
+            run_storage_io(&app, enqueue_batch).await.ok();
+            send_telemetry_queue(&app).await;
+        }
+    });
+}
+
+/// Turning it off also throws away everything collected and queued.
+#[tauri::command]
+pub(crate) fn set_telemetry_consent(enabled: bool) -> Result<(), AppError> {
+    ensure_writable()?;
+    let mut config = load_config();
+    config.telemetry = enabled;
+    save_config(&config)?;
+    if !enabled {
+        *TELEMETRY.lock().unwrap() = TelemetryAggregate { since: now_ms(), ..Default::default() };
+        fs::remove_file(telemetry_queue_path()).ok();
+    }
+    Ok(())
+}
+
+/// Exactly what would be sent: the queued batches and the one being collected.
+#[tauri::command]
+pub(crate) fn preview_telemetry_payload() -> serde_json::Value {
+    serde_json::json!({
+        "enabled": load_config().telemetry,
+        "endpoint": load_config().telemetry_endpoint,
+        "queued": load_telemetry_queue(),
+        "current": build_batch(&TELEMETRY.lock().unwrap()),
+    })
+}
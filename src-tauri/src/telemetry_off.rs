@@ -0,0 +1,24 @@
+//! Stand-in for telemetry.rs in builds without the `telemetry` feature:
+//! nothing is collected and nothing can be sent.
+
+use crate::*;
+
+// ─── Telemetry ────────────────────────────────────────────────────────────────
+
+pub(crate) fn report_usage_event(_event: &str, _props: HashMap<String, String>) {}
+
+pub(crate) fn spawn_telemetry_loop(_app: tauri::AppHandle) {}
+
+#[tauri::command]
+pub(crate) fn set_telemetry_consent(enabled: bool) -> Result<(), AppError> {
+    ensure_writable()?;
+    if enabled {
+        return Err(AppError::InvalidInput("This build has no telemetry".into()));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) fn preview_telemetry_payload() -> serde_json::Value {
+    serde_json::json!({ "enabled": false, "compiledOut": true })
+}
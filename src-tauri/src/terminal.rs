@@ -0,0 +1,53 @@
+//! The built-in terminal.
+
+use crate::*;
+
+// ─── Terminal ─────────────────────────────────────────────────────────────────
+
+/// Runs `cmd` through the shell with UTF-8 output.
+pub(crate) async fn shell_output(app: &tauri::AppHandle, cmd: &str) -> Result<tauri_plugin_shell::process::Output, String> {
+    app.shell()
+        .command("cmd")
+        .args(["/C", &format!("chcp 65001 >nul && {}", cmd)])
+        .output()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// With a `job_id`, `cancel_job` can stop it: the process is killed and the
+/// result is a `Cancelled` error with the output so far.
+#[tauri::command]
+pub(crate) async fn run_command(app: tauri::AppHandle, cmd: String, job_id: Option<String>) -> Result<String, String> {
+    use tauri_plugin_shell::process::CommandEvent;
+    ensure_writable().map_err(|e| e.to_string())?;
+    let job_id = job_id.filter(|id| !id.trim().is_empty()).unwrap_or_else(|| format!("job-{}-{}", now_ms(), next_seq()));
+    let job = register_cancel("job", &job_id).map_err(|e| e.to_string())?;
+    let (mut rx, child) = app.shell()
+        .command("cmd")
+        .args(["/C", &format!("chcp 65001 >nul && {}", cmd)])
+        .spawn()
+        .map_err(|e| e.to_string())?;
+
+    let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+    loop {
+        match until_cancelled(&job.token, rx.recv()).await {
+            Some(Some(CommandEvent::Stdout(b))) => stdout.extend(b),
+            Some(Some(CommandEvent::Stderr(b))) => stderr.extend(b),
+            Some(Some(_)) => {}
+            Some(None) => break,
+            None => {
+                child.kill().ok();
+                let so_far = String::from_utf8_lossy(if stdout.is_empty() { &stderr } else { &stdout }).to_string();
+                return Err(AppError::Cancelled(format!("job {} was stopped. Output so far:\n{}", job_id, so_far)).to_string());
+            }
+        }
+    }
+    let stdout = String::from_utf8_lossy(&stdout).to_string();
+    let stderr = String::from_utf8_lossy(&stderr).to_string();
+    Ok(if stdout.is_empty() { stderr } else { stdout })
+}
+
+#[tauri::command]
+pub(crate) fn cancel_job(job_id: String) -> Result<(), AppError> {
+    cancel_registered("job", &job_id)
+}
@@ -0,0 +1,367 @@
+//! Recoverable deletes.
+
+use crate::*;
+
+// ─── Trash ────────────────────────────────────────────────────────────────────
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum TrashKind {
+    Agent,
+    Session,
+    History,
+}
+
+/// Stored as trash/{id}/manifest.json next to the moved content in trash/{id}/payload.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TrashManifest {
+    pub(crate) id: String,
+    pub(crate) kind: TrashKind,
+    pub(crate) agent_id: String,
+    /// Gateway session id, or the history session key for a partial history delete
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) session_id: Option<String>,
+    pub(crate) original_path: String,
+    pub(crate) deleted_at: u64,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TrashEntry {
+    #[serde(flatten)]
+    pub(crate) manifest: TrashManifest,
+    pub(crate) size_bytes: u64,
+}
+
+pub(crate) const MS_PER_DAY: u64 = 86_400_000;
+pub(crate) const MAINTENANCE_SECS: u64 = 24 * 60 * 60;
+
+pub(crate) fn trash_dir() -> PathBuf {
+    let p = clapp_dir().join("trash");
+    fs::create_dir_all(&p).ok();
+    p
+}
+
+pub(crate) fn path_size(p: &std::path::Path) -> u64 {
+    match fs::metadata(p) {
+        Ok(m) if m.is_dir() => fs::read_dir(p)
+            .map(|entries| entries.flatten().map(|e| path_size(&e.path())).sum())
+            .unwrap_or(0),
+        Ok(m) => m.len(),
+        Err(_) => 0,
+    }
+}
+
+pub(crate) fn copy_all(src: &std::path::Path, dst: &std::path::Path) -> std::io::Result<()> {
+    if src.is_dir() {
+        fs::create_dir_all(dst)?;
+        for entry in fs::read_dir(src)?.flatten() {
+            copy_all(&entry.path(), &dst.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else {
+        fs::copy(src, dst).map(|_| ())
+    }
+}
+
+/// Rename, falling back to copy + delete when the trash is on another volume.
+pub(crate) fn move_path(src: &std::path::Path, dst: &std::path::Path) -> std::io::Result<()> {
+    if let Some(parent) = dst.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if fs::rename(src, dst).is_ok() {
+        return Ok(());
+    }
+    copy_all(src, dst)?;
+    if src.is_dir() { fs::remove_dir_all(src) } else { fs::remove_file(src) }
+}
+
+pub(crate) fn gateway_session_path(agent_id: &str, session_id: &str) -> PathBuf {
+    openclaw_agents_root().join(agent_id).join("sessions").join(format!("{}.jsonl", session_id))
+}
+
+/// Where an entry belongs, derived from validated ids only; the manifest's stored path
+/// is just checked against it, never used.
+pub(crate) fn trash_target(kind: TrashKind, agent_id: &str, session_id: Option<&str>) -> Result<PathBuf, AppError> {
+    validate_agent_id(agent_id)?;
+    Ok(match (kind, session_id) {
+        (TrashKind::Agent, _) => openclaw_agents_root().join(agent_id),
+        (TrashKind::Session, Some(s)) => {
+            validate_agent_id(s).map_err(|_| AppError::InvalidInput(format!("Bad session id '{}'", s)))?;
+            gateway_session_path(agent_id, s)
+        }
+        (TrashKind::Session, None) => return Err(AppError::InvalidInput("Session entry without a session id".into())),
+        (TrashKind::History, _) => history_path(agent_id),
+    })
+}
+
+pub(crate) fn new_trash_id(kind: TrashKind) -> String {
+    let prefix = match kind {
+        TrashKind::Agent => "agent",
+        TrashKind::Session => "session",
+        TrashKind::History => "history",
+    };
+    let mut ts = now_ms();
+    while trash_dir().join(format!("{}-{}", prefix, ts)).exists() {
+        ts += 1;
+    }
+    format!("{}-{}", prefix, ts)
+}
+
+/// Moves `src` into a new trash entry, or writes `content` as the payload when given.
+pub(crate) fn move_to_trash(
+    kind: TrashKind,
+    agent_id: &str,
+    session_id: Option<&str>,
+    src: &std::path::Path,
+    content: Option<&str>,
+) -> Result<TrashManifest, AppError> {
+    let manifest = TrashManifest {
+        id: new_trash_id(kind),
+        kind,
+        agent_id: agent_id.to_string(),
+        session_id: session_id.map(String::from),
+        original_path: src.display().to_string(),
+        deleted_at: now_ms(),
+    };
+    let entry = trash_dir().join(&manifest.id);
+    fs::create_dir_all(&entry)?;
+    match content {
+        Some(c) => fs::write(entry.join("payload"), c)?,
+        None => move_path(src, &entry.join("payload"))?,
+    }
+    fs::write(entry.join("manifest.json"), serde_json::to_string_pretty(&manifest)?)?;
+    audit("trash", serde_json::to_value(&manifest)?);
+    Ok(manifest)
+}
+
+pub(crate) fn read_trash_manifest(id: &str) -> Result<TrashManifest, AppError> {
+    validate_agent_id(id).map_err(|_| AppError::InvalidInput(format!("Bad trash id '{}'", id)))?;
+    let path = trash_dir().join(id).join("manifest.json");
+    let content = fs::read_to_string(&path).map_err(|_| AppError::NotFound(format!("trash entry {}", id)))?;
+    let manifest: TrashManifest = serde_json::from_str(&content)?;
+    if manifest.id != id {
+        return Err(AppError::InvalidInput(format!("Trash entry {} has a mismatched manifest", id)));
+    }
+    Ok(manifest)
+}
+
+#[tauri::command]
+pub(crate) async fn delete_agent(app: tauri::AppHandle, agent_id: String) -> Result<TrashManifest, AppError> {
+    ensure_writable()?;
+    validate_agent_id(&agent_id)?;
+    if agent_id == "main" {
+        return Err(AppError::InvalidInput("The main agent backs the gateway and can't be deleted".into()));
+    }
+    if agent_is_running(&app, &agent_id) {
+        return Err(AppError::InvalidInput(format!("Agent {} has a call running; cancel it or wait before deleting", agent_id)));
+    }
+    run_storage_operation(&app, "trash-move", move |_| {
+        if !agent_exists(&agent_id) {
+            return Err(AppError::NotFound(format!("agent {}", agent_id)));
+        }
+        let manifest = move_to_trash(TrashKind::Agent, &agent_id, None, &openclaw_agents_root().join(&agent_id), None)?;
+        // Secrets are not kept for a restore from the trash
+        agents::env::purge_agent_env(&agent_id);
+        Ok(manifest)
+    }).await
+}
+
+/// Agent folders whose agent.json is missing or unparseable. Their contents go to
+/// the trash like a deleted agent; agents with a call running are left alone.
+/// Returns the ids removed, or that would be with `dry_run`.
+#[tauri::command]
+pub(crate) async fn cleanup_orphan_agents(app: tauri::AppHandle, dry_run: bool) -> Result<Vec<String>, AppError> {
+    if !dry_run {
+        ensure_writable()?;
+    }
+    let orphans = run_storage_io(&app, || -> Result<Vec<String>, AppError> {
+        let entries = match fs::read_dir(openclaw_agents_root()) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut orphans: Vec<String> = entries.flatten()
+            .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+            .map(|e| e.file_name().to_string_lossy().into_owned())
+            // Folders that can't be agent ids were not made by Clapp
+            .filter(|id| validate_agent_id(id).is_ok())
+            .filter(|id| {
+                fs::read_to_string(agent_config_path(id)).ok()
+                    .and_then(|c| serde_json::from_str::<AgentConfig>(&c).ok())
+                    .is_none()
+            })
+            .collect();
+        orphans.sort();
+        Ok(orphans)
+    }).await??;
+    let orphans: Vec<String> = orphans.into_iter().filter(|id| !agent_is_running(&app, id)).collect();
+    if dry_run {
+        return Ok(orphans);
+    }
+    run_storage_operation(&app, "trash-move", move |op| {
+        let mut removed = Vec::new();
+        for id in orphans {
+            if op.is_cancelled() {
+                break;
+            }
+            match move_to_trash(TrashKind::Agent, &id, None, &openclaw_agents_root().join(&id), None) {
+                Ok(_) => {
+                    agents::env::purge_agent_env(&id);
+                    removed.push(id);
+                }
+                Err(e) => eprintln!("[CLEANUP ERR] {}: {}", id, e),
+            }
+        }
+        if !removed.is_empty() {
+            audit("orphan_agents_removed", serde_json::json!({ "agentIds": removed }));
+        }
+        Ok(removed)
+    }).await
+}
+
+#[tauri::command]
+pub(crate) async fn delete_session(app: tauri::AppHandle, id: String) -> Result<TrashManifest, AppError> {
+    ensure_writable()?;
+    run_storage_operation(&app, "trash-move", move |_| {
+        let (agent_id, path) = find_gateway_transcript(&id)?;
+        move_to_trash(TrashKind::Session, &agent_id, Some(&id), &path, None)
+    }).await
+}
+
+/// Deletes an agent's local history, or only one session of it.
+#[tauri::command]
+pub(crate) async fn delete_history(app: tauri::AppHandle, agent_id: String, session_key: Option<String>) -> Result<TrashManifest, AppError> {
+    ensure_writable()?;
+    validate_agent_id(&agent_id)?;
+    run_storage_io(&app, move || {
+        let path = history_path(&agent_id);
+        if !path.exists() {
+            return Err(AppError::NotFound(format!("history of {}", agent_id)));
+        }
+        let Some(key) = session_key else {
+            return move_to_trash(TrashKind::History, &agent_id, None, &path, None);
+        };
+        let _lock = lock_history(&agent_id);
+        let (removed, kept): (Vec<HistoryRecord>, Vec<HistoryRecord>) =
+            read_history(&agent_id).into_iter().partition(|r| r.session_key == key);
+        if removed.is_empty() {
+            return Err(AppError::NotFound(format!("session {} in history of {}", key, agent_id)));
+        }
+        let payload: String = removed.iter().map(|r| serde_json::to_string(r).unwrap() + "\n").collect();
+        let manifest = move_to_trash(TrashKind::History, &agent_id, Some(&key), &path, Some(&payload))?;
+        write_history(&agent_id, &kept)?;
+        Ok(manifest)
+    }).await?
+}
+
+#[tauri::command]
+pub(crate) fn list_trash() -> Vec<TrashEntry> {
+    let mut entries: Vec<TrashEntry> = fs::read_dir(trash_dir())
+        .map(|dirs| {
+            dirs.flatten()
+                .filter_map(|d| {
+                    let manifest = read_trash_manifest(&d.file_name().to_string_lossy()).ok()?;
+                    Some(TrashEntry { manifest, size_bytes: path_size(&d.path().join("payload")) })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    entries.sort_by_key(|e| std::cmp::Reverse(e.manifest.deleted_at));
+    entries
+}
+
+/// Puts an entry back. If its original id is taken again, pass `rename_to` to restore under another id.
+#[tauri::command]
+pub(crate) async fn restore_trash_entry(app: tauri::AppHandle, id: String, rename_to: Option<String>) -> Result<String, AppError> {
+    ensure_writable()?;
+    run_storage_operation(&app, "trash-restore", move |_| -> Result<String, AppError> {
+        let manifest = read_trash_manifest(&id)?;
+        let original = trash_target(manifest.kind, &manifest.agent_id, manifest.session_id.as_deref())?;
+        if std::path::Path::new(&manifest.original_path) != original {
+            return Err(AppError::InvalidInput(format!("Trash entry {} points outside its original location", id)));
+        }
+        let payload = trash_dir().join(&id).join("payload");
+
+        // A partial history delete goes back into the (possibly newer) history file
+        if let (TrashKind::History, Some(_)) = (manifest.kind, &manifest.session_id) {
+            let agent_id = rename_to.unwrap_or(manifest.agent_id.clone());
+            validate_agent_id(&agent_id)?;
+            let existing: std::collections::HashSet<String> = read_history(&agent_id).into_iter().map(|r| r.id).collect();
+            let records: Vec<HistoryRecord> = fs::read_to_string(&payload)?
+                .lines()
+                .filter_map(|l| serde_json::from_str::<HistoryRecord>(l).ok())
+                .filter(|r| !existing.contains(&r.id))
+                .collect();
+            append_history(&agent_id, &records)?;
+            fs::remove_dir_all(trash_dir().join(&id))?;
+            audit("restore", serde_json::json!({ "id": id, "target": agent_id }));
+            return Ok(agent_id);
+        }
+
+        let (target, restored_id) = match &rename_to {
+            None => (original, manifest.session_id.clone().unwrap_or(manifest.agent_id.clone())),
+            Some(new_id) => match manifest.kind {
+                TrashKind::Session => (trash_target(manifest.kind, &manifest.agent_id, Some(new_id))?, new_id.clone()),
+                _ => (trash_target(manifest.kind, new_id, None)?, new_id.clone()),
+            },
+        };
+        if target.exists() {
+            return Err(AppError::AlreadyExists(format!("{} exists; restore it under another name", restored_id)));
+        }
+        move_path(&payload, &target)?;
+        fs::remove_dir_all(trash_dir().join(&id))?;
+        audit("restore", serde_json::json!({ "id": id, "target": target.display().to_string() }));
+        Ok(restored_id)
+    }).await
+}
+
+/// Reports each entry to `op` and stops between entries once it is cancelled.
+pub(crate) fn purge_trash(older_than_days: Option<u64>, op: &Operation) -> usize {
+    let cutoff = older_than_days.map(|d| now_ms().saturating_sub(d * MS_PER_DAY));
+    let entries: Vec<TrashEntry> = list_trash().into_iter()
+        .filter(|e| cutoff.is_none_or(|c| e.manifest.deleted_at <= c))
+        .collect();
+    op.set_total(entries.len());
+    let mut purged = 0;
+    for (i, entry) in entries.into_iter().enumerate() {
+        if op.is_cancelled() {
+            break;
+        }
+        op.progress("purging", i, Some(&entry.manifest.id));
+        if fs::remove_dir_all(trash_dir().join(&entry.manifest.id)).is_ok() {
+            audit("purge", serde_json::json!({ "id": entry.manifest.id }));
+            purged += 1;
+        }
+    }
+    purged
+}
+
+/// Purges everything, or only entries older than `older_than_days`.
+#[tauri::command]
+pub(crate) async fn empty_trash(app: tauri::AppHandle, older_than_days: Option<u64>) -> Result<usize, AppError> {
+    ensure_writable()?;
+    let op = Operation::start(&app, "trash-purge", None);
+    let result = run_storage_job(&app, &op, move |job| Ok(purge_trash(older_than_days, job))).await;
+    op.finish(&result);
+    result
+}
+
+/// Once-a-day housekeeping.
+pub(crate) fn spawn_daily_maintenance(app: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        while !is_shutting_down(&app) {
+            let config = load_config();
+            if let Some(days) = config.trash_retention_days {
+                let op = Operation::start(&app, "trash-retention", None);
+                let result = run_storage_job(&app, &op, move |job| Ok(purge_trash(Some(days), job))).await;
+                op.finish(&result);
+                if let Ok(purged @ 1..) = result {
+                    app.emit("trash-purged", purged).ok();
+                }
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(MAINTENANCE_SECS)).await;
+        }
+    });
+}
@@ -0,0 +1,282 @@
+//! Files changed in an agent workspace during a call.
+
+use crate::*;
+
+// ─── Workspace changes ────────────────────────────────────────────────────────
+
+pub(crate) const WRITE_CAPABLE_TOOLS: &[&str] = &["exec", "write", "edit", "apply_patch", "bash"];
+pub(crate) const WORKSPACE_IGNORE: &[&str] = &[".git", "node_modules", "target", ".venv", "__pycache__", "dist", "build"];
+pub(crate) const WORKSPACE_MAX_FILES: usize = 5_000;
+pub(crate) const WORKSPACE_HASH_MAX_BYTES: u64 = 1024 * 1024;
+// Past this much hashed per scan, the rest is compared by size and mtime only
+pub(crate) const WORKSPACE_HASH_TOTAL_BYTES: u64 = 64 * 1024 * 1024;
+// Old contents are kept only for small text files so `get_file_diff` has something to diff against
+pub(crate) const WORKSPACE_KEEP_TEXT_BYTES: u64 = 128 * 1024;
+pub(crate) const WORKSPACE_KEEP_TOTAL_BYTES: usize = 16 * 1024 * 1024;
+
+#[derive(Clone, PartialEq)]
+pub(crate) struct FileState {
+    pub(crate) size: u64,
+    pub(crate) mtime: u64,
+    pub(crate) hash: Option<u64>,
+}
+
+#[derive(Default)]
+pub(crate) struct WorkspaceSnapshot {
+    pub(crate) root: PathBuf,
+    pub(crate) files: HashMap<PathBuf, FileState>,
+    /// Pre-call text of small text files
+    pub(crate) texts: HashMap<PathBuf, String>,
+    pub(crate) truncated: bool,
+}
+
+#[derive(serde::Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WorkspaceDiff {
+    pub(crate) root: String,
+    pub(crate) created: Vec<String>,
+    pub(crate) modified: Vec<String>,
+    pub(crate) deleted: Vec<String>,
+    /// The file-count cap was hit, so changes beyond it are not listed
+    pub(crate) truncated: bool,
+}
+
+#[derive(Default)]
+pub(crate) struct WorkspaceDiffs {
+    pub(crate) by_session: Mutex<HashMap<String, WorkspaceDiff>>,
+    /// Pre-call text of files that changed in each session's last diff, keyed by absolute path;
+    /// empty for created files
+    pub(crate) originals: Mutex<HashMap<String, HashMap<PathBuf, String>>>,
+}
+
+pub(crate) fn agent_workspace(config: &AgentConfig) -> PathBuf {
+    config.workspace.as_ref().map(PathBuf::from).unwrap_or_else(|| openclaw_dir().join("workspace"))
+}
+
+/// The first 8 bytes of the SHA-256. Checkpoint manifests keep it, so unlike
+/// `DefaultHasher` it has to come out the same in every build.
+pub(crate) fn content_hash(bytes: &[u8]) -> u64 {
+    use sha2::Digest;
+    let digest = sha2::Sha256::digest(bytes);
+    u64::from_le_bytes(digest[..8].try_into().unwrap())
+}
+
+pub(crate) fn scan_workspace(root: PathBuf, keep_texts: bool) -> WorkspaceSnapshot {
+    let mut snap = WorkspaceSnapshot { root: root.clone(), ..Default::default() };
+    let mut kept = 0usize;
+    let mut hashed = 0u64;
+    let mut stack = vec![root];
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else { continue };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            // Symlinks are skipped, not followed: they can point outside the workspace or loop
+            let Ok(meta) = fs::symlink_metadata(&path) else { continue };
+            if meta.file_type().is_symlink() {
+                continue;
+            }
+            if meta.is_dir() {
+                if !WORKSPACE_IGNORE.iter().any(|i| entry.file_name() == *i) {
+                    stack.push(path);
+                }
+                continue;
+            }
+            if snap.files.len() >= WORKSPACE_MAX_FILES {
+                snap.truncated = true;
+                return snap;
+            }
+            let mtime = meta.modified().ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0);
+            let hashable = meta.len() <= WORKSPACE_HASH_MAX_BYTES && hashed + meta.len() <= WORKSPACE_HASH_TOTAL_BYTES;
+            let keepable = keep_texts && meta.len() <= WORKSPACE_KEEP_TEXT_BYTES && kept < WORKSPACE_KEEP_TOTAL_BYTES;
+            // One read serves both the hash and the kept text
+            let bytes = if hashable || keepable { fs::read(&path).ok() } else { None };
+            let mut hash = None;
+            if let Some(bytes) = bytes {
+                if hashable {
+                    hashed += bytes.len() as u64;
+                    hash = Some(content_hash(&bytes));
+                }
+                if keepable {
+                    if let Ok(text) = String::from_utf8(bytes) {
+                        kept += text.len();
+                        snap.texts.insert(path.clone(), text);
+                    }
+                }
+            }
+            snap.files.insert(path, FileState { size: meta.len(), mtime, hash });
+        }
+    }
+    snap
+}
+
+/// Paths a truncated scan didn't reach are unknown, not created or deleted, and
+/// a file hashed on one side only is compared by size and mtime.
+pub(crate) fn diff_snapshots(before: &WorkspaceSnapshot, after: &WorkspaceSnapshot) -> WorkspaceDiff {
+    let rel = |p: &PathBuf| p.strip_prefix(&before.root).unwrap_or(p).to_string_lossy().into_owned();
+    let mut diff = WorkspaceDiff {
+        root: before.root.to_string_lossy().into_owned(),
+        truncated: before.truncated || after.truncated,
+        ..Default::default()
+    };
+    for (path, state) in &after.files {
+        match before.files.get(path) {
+            None if !before.truncated => diff.created.push(rel(path)),
+            None => {}
+            Some(old) => {
+                let changed = match (old.hash, state.hash) {
+                    // Same content with a touched mtime is not a change
+                    (Some(a), Some(b)) => a != b,
+                    _ => old.size != state.size || old.mtime != state.mtime,
+                };
+                if changed {
+                    diff.modified.push(rel(path));
+                }
+            }
+        }
+    }
+    if !after.truncated {
+        for path in before.files.keys() {
+            if !after.files.contains_key(path) {
+                diff.deleted.push(rel(path));
+            }
+        }
+    }
+    diff.created.sort();
+    diff.modified.sort();
+    diff.deleted.sort();
+    diff
+}
+
+/// Snapshots the agent's workspace before a call, but only for agents whose tools can write to it.
+pub(crate) async fn snapshot_agent_workspace(app: &tauri::AppHandle, agent_id: &str) -> Option<WorkspaceSnapshot> {
+    let id = agent_id.to_string();
+    let config = run_storage_io(app, move || read_agent_config(&id)).await.ok()?;
+    if !config.tools.iter().any(|t| WRITE_CAPABLE_TOOLS.contains(&t.as_str())) {
+        return None;
+    }
+    let root = agent_workspace(&config);
+    tauri::async_runtime::spawn_blocking(move || scan_workspace(root, true)).await.ok()
+}
+
+pub(crate) async fn attach_workspace_diff(
+    app: &tauri::AppHandle,
+    session_key: &str,
+    before: WorkspaceSnapshot,
+    response: String,
+) -> String {
+    let root = before.root.clone();
+    let Ok(after) = tauri::async_runtime::spawn_blocking(move || scan_workspace(root, false)).await else {
+        return response;
+    };
+    let diff = diff_snapshots(&before, &after);
+
+    let app_state = app.state::<AppState>();
+    let state = &app_state.workspace_diffs;
+    {
+        // Only this session's last call is replaced; other sessions keep theirs.
+        // Created files are diffed against nothing.
+        let originals: HashMap<PathBuf, String> = diff.modified.iter().chain(&diff.deleted)
+            .map(|rel| before.root.join(rel))
+            .filter_map(|path| before.texts.get(&path).cloned().map(|text| (path, text)))
+            .chain(diff.created.iter().map(|rel| (before.root.join(rel), String::new())))
+            .collect();
+        state.originals.lock().unwrap().insert(session_key.to_string(), originals);
+    }
+    state.by_session.lock().unwrap().insert(session_key.to_string(), diff.clone());
+
+    if diff.created.is_empty() && diff.modified.is_empty() && diff.deleted.is_empty() {
+        return response;
+    }
+    app.emit("workspace-changed", serde_json::json!({ "sessionKey": session_key, "diff": diff })).ok();
+    match serde_json::from_str::<serde_json::Value>(&response) {
+        Ok(mut v) => {
+            v["workspaceChanges"] = serde_json::to_value(&diff).unwrap_or_default();
+            v.to_string()
+        }
+        Err(_) => response,
+    }
+}
+
+#[tauri::command]
+pub(crate) fn get_last_workspace_diff(state: tauri::State<AppState>, session_key: String) -> Option<WorkspaceDiff> {
+    state.workspace_diffs.by_session.lock().unwrap().get(&session_key).cloned()
+}
+
+/// Unified diff of a file changed by the session's last call; created files diff against
+/// an empty one. Binary files (or files too large to have been kept) return `None`: they
+/// are listed as changed without content.
+#[tauri::command]
+pub(crate) fn get_file_diff(
+    state: tauri::State<AppState>,
+    session_key: String,
+    path: String,
+) -> Result<Option<String>, AppError> {
+    let path = PathBuf::from(path);
+    let originals = state.workspace_diffs.originals.lock().unwrap();
+    let Some(old) = originals.get(&session_key).and_then(|o| o.get(&path)) else { return Ok(None) };
+    let new = match fs::read(&path) {
+        Ok(bytes) => match String::from_utf8(bytes) {
+            Ok(text) => text,
+            Err(_) => return Ok(None),
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let name = path.to_string_lossy();
+    Ok(Some(
+        similar::TextDiff::from_lines(old.as_str(), new.as_str())
+            .unified_diff()
+            .header(&format!("a/{}", name), &format!("b/{}", name))
+            .to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(files: &[(&str, u64, u64, Option<u64>)], truncated: bool) -> WorkspaceSnapshot {
+        WorkspaceSnapshot {
+            root: PathBuf::from("/ws"),
+            files: files.iter()
+                .map(|(p, size, mtime, hash)| (PathBuf::from("/ws").join(p), FileState { size: *size, mtime: *mtime, hash: *hash }))
+                .collect(),
+            truncated,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn files_a_truncated_scan_missed_are_not_created_or_deleted() {
+        let full = snapshot(&[("a.txt", 1, 1, Some(1)), ("b.txt", 1, 1, Some(2))], false);
+        let cut = snapshot(&[("a.txt", 1, 1, Some(1)), ("c.txt", 1, 1, Some(3))], true);
+
+        let diff = diff_snapshots(&full, &cut);
+        assert!(diff.deleted.is_empty() && diff.truncated);
+        assert_eq!(diff.created, ["c.txt"]);
+
+        let diff = diff_snapshots(&cut, &full);
+        assert!(diff.created.is_empty());
+        assert_eq!(diff.deleted, ["c.txt"]);
+    }
+
+    #[test]
+    fn a_file_past_the_hash_budget_is_compared_by_size_and_mtime() {
+        let hashed = snapshot(&[("big.bin", 10, 5, Some(7))], false);
+        // The budget ran out before this file in the second scan
+        let same = snapshot(&[("big.bin", 10, 5, None)], false);
+        let grown = snapshot(&[("big.bin", 11, 6, None)], false);
+
+        assert!(diff_snapshots(&hashed, &same).modified.is_empty());
+        assert!(diff_snapshots(&same, &hashed).modified.is_empty());
+        assert_eq!(diff_snapshots(&hashed, &grown).modified, ["big.bin"]);
+        // Both hashed: a touched mtime alone is no change, new content is
+        let touched = snapshot(&[("big.bin", 10, 9, Some(7))], false);
+        let rewritten = snapshot(&[("big.bin", 10, 5, Some(8))], false);
+        assert!(diff_snapshots(&hashed, &touched).modified.is_empty());
+        assert_eq!(diff_snapshots(&hashed, &rewritten).modified, ["big.bin"]);
+    }
+}
@@ -333,8 +333,8 @@ export default function App() {
     async function poll() {
       if (!alive) return;
       try {
-        const s = await invoke<string>('gateway_status');
-        if (alive) setGwStatus(s === 'running' ? 'running' : prev => prev === 'starting' ? 'starting' : 'stopped');
+        const s = await invoke<{ state: string; storageAvailable: boolean }>('gateway_status');
+        if (alive) setGwStatus(s.state === 'running' ? 'running' : prev => prev === 'starting' ? 'starting' : 'stopped');
       } catch {}
       if (alive) setTimeout(poll, 5000);
     }